@@ -0,0 +1,345 @@
+use core::fmt;
+use std::{error::Error, path::Path};
+
+use crate::elf_file::ElfFile;
+use crate::elf_types::SHN_ABS;
+use crate::elf_utils::{st_bind_to_str, st_type_to_str};
+use crate::symbols::{
+    STB_GLOBAL, STB_GNU_UNIQUE, STB_WEAK, STT_OBJECT, STT_TLS, STV_DEFAULT, STV_PROTECTED,
+};
+use crate::versions::VER_FLG_BASE;
+
+/// Whether consumers built against the old library keep working with the new one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Compatibility {
+    Compatible,
+    Breaking,
+}
+
+impl fmt::Display for Compatibility {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Compatibility::Compatible => "COMPATIBLE",
+            Compatibility::Breaking => "BREAKING",
+        })
+    }
+}
+
+/// A dynamic symbol other objects can bind to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AbiSymbol {
+    pub name: String,
+    pub version: Option<String>,
+    /// `false` for a non-default `sym@VER` version kept for old binaries
+    pub default: bool,
+    pub sym_type: u8,
+    pub bind: u8,
+    pub size: u64,
+}
+
+impl fmt::Display for AbiSymbol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.version {
+            Some(v) => write!(
+                f,
+                "{}{}{}",
+                self.name,
+                if self.default { "@@" } else { "@" },
+                v
+            ),
+            None => f.write_str(&self.name),
+        }
+    }
+}
+
+/// A difference between the interfaces of two versions of a shared library
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AbiChange {
+    SonameChanged {
+        old: Option<String>,
+        new: Option<String>,
+    },
+    NeededAdded(String),
+    NeededRemoved(String),
+    VersionAdded(String),
+    VersionRemoved(String),
+    SymbolAdded(AbiSymbol),
+    SymbolRemoved(AbiSymbol),
+    /// E.g. a function turned into a variable
+    TypeChanged {
+        symbol: AbiSymbol,
+        old: u8,
+    },
+    /// Size change of a data object, which breaks copy relocations in executables
+    SizeChanged {
+        symbol: AbiSymbol,
+        old: u64,
+    },
+    BindingChanged {
+        symbol: AbiSymbol,
+        old: u8,
+    },
+}
+
+impl AbiChange {
+    pub fn compatibility(&self) -> Compatibility {
+        match self {
+            AbiChange::SonameChanged { .. }
+            | AbiChange::VersionRemoved(_)
+            | AbiChange::SymbolRemoved(_)
+            | AbiChange::TypeChanged { .. }
+            | AbiChange::SizeChanged { .. } => Compatibility::Breaking,
+            AbiChange::NeededAdded(_)
+            | AbiChange::NeededRemoved(_)
+            | AbiChange::VersionAdded(_)
+            | AbiChange::SymbolAdded(_)
+            | AbiChange::BindingChanged { .. } => Compatibility::Compatible,
+        }
+    }
+}
+
+impl fmt::Display for AbiChange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[{}] ", self.compatibility())?;
+        let opt = |s: &Option<String>| s.clone().unwrap_or_else(|| "none".to_string());
+        match self {
+            AbiChange::SonameChanged { old, new } => {
+                write!(f, "SONAME changed: {} -> {}", opt(old), opt(new))
+            }
+            AbiChange::NeededAdded(n) => write!(f, "needed library added: {}", n),
+            AbiChange::NeededRemoved(n) => write!(f, "needed library removed: {}", n),
+            AbiChange::VersionAdded(v) => write!(f, "version added: {}", v),
+            AbiChange::VersionRemoved(v) => write!(f, "version removed: {}", v),
+            AbiChange::SymbolAdded(s) => write!(f, "symbol added: {}", s),
+            AbiChange::SymbolRemoved(s) => write!(f, "symbol removed: {}", s),
+            AbiChange::TypeChanged { symbol, old } => write!(
+                f,
+                "type of {} changed: {} -> {}",
+                symbol,
+                st_type_to_str(*old),
+                st_type_to_str(symbol.sym_type)
+            ),
+            AbiChange::SizeChanged { symbol, old } => {
+                write!(f, "size of {} changed: {} -> {}", symbol, old, symbol.size)
+            }
+            AbiChange::BindingChanged { symbol, old } => write!(
+                f,
+                "binding of {} changed: {} -> {}",
+                symbol,
+                st_bind_to_str(*old),
+                st_bind_to_str(symbol.bind)
+            ),
+        }
+    }
+}
+
+/// The result of [`ElfFile::check_abi`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AbiReport {
+    pub changes: Vec<AbiChange>,
+}
+
+impl AbiReport {
+    pub fn is_compatible(&self) -> bool {
+        self.breaking().next().is_none()
+    }
+
+    pub fn breaking(&self) -> impl Iterator<Item = &AbiChange> {
+        self.changes
+            .iter()
+            .filter(|c| c.compatibility() == Compatibility::Breaking)
+    }
+}
+
+impl fmt::Display for AbiReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for change in &self.changes {
+            writeln!(f, "{}", change)?;
+        }
+        Ok(())
+    }
+}
+
+/// Attempts to parse two versions of a shared library and compares their ABI.
+/// The **caller** is responsible for handling the return value properly.
+pub fn check_abi<P: AsRef<Path>>(old_path: P, new_path: P) -> Result<AbiReport, Box<dyn Error>> {
+    Ok(ElfFile::open(old_path)?.check_abi(&ElfFile::open(new_path)?))
+}
+
+impl ElfFile {
+    /// Returns the defined `.dynsym` symbols with default or protected visibility
+    pub fn abi_symbols(&self) -> Vec<AbiSymbol> {
+        let version_names: Vec<String> = self
+            .version_definitions()
+            .into_iter()
+            .map(|d| d.name)
+            .collect();
+        let versions = self.dynsym_versions();
+        self.dynsym()
+            .into_iter()
+            .enumerate()
+            .filter(|(_, s)| {
+                !s.name.is_empty()
+                    && matches!(s.bind(), STB_GLOBAL | STB_WEAK | STB_GNU_UNIQUE)
+                    && matches!(s.visibility(), STV_DEFAULT | STV_PROTECTED)
+                    // Version definitions show up as absolute symbols named after the version
+                    && (s.is_defined()
+                        || (s.shndx == SHN_ABS as u32 && !version_names.contains(&s.name)))
+            })
+            .map(|(i, s)| {
+                let version = versions.get(i).cloned().flatten();
+                AbiSymbol {
+                    sym_type: s.sym_type(),
+                    bind: s.bind(),
+                    size: s.size,
+                    default: version.as_ref().is_none_or(|v| v.default),
+                    version: version.map(|v| v.name),
+                    name: s.name,
+                }
+            })
+            .collect()
+    }
+
+    /// Compares this library (the old version) against `new` the way the dynamic linker
+    /// would resolve references of existing consumers
+    pub fn check_abi(&self, new: &ElfFile) -> AbiReport {
+        let mut changes = Vec::new();
+        let (old_soname, new_soname) = (self.soname(), new.soname());
+        if old_soname != new_soname {
+            changes.push(AbiChange::SonameChanged {
+                old: old_soname,
+                new: new_soname,
+            });
+        }
+
+        let (old_needed, new_needed) = (self.needed(), new.needed());
+        for n in new_needed.iter().filter(|n| !old_needed.contains(n)) {
+            changes.push(AbiChange::NeededAdded(n.clone()));
+        }
+        for n in old_needed.iter().filter(|n| !new_needed.contains(n)) {
+            changes.push(AbiChange::NeededRemoved(n.clone()));
+        }
+
+        // The base definition only repeats the SONAME
+        let versions = |elf: &ElfFile| -> Vec<String> {
+            elf.version_definitions()
+                .into_iter()
+                .filter(|d| d.flags & VER_FLG_BASE == 0)
+                .map(|d| d.name)
+                .collect()
+        };
+        let (old_versions, new_versions) = (versions(self), versions(new));
+        for v in new_versions.iter().filter(|v| !old_versions.contains(v)) {
+            changes.push(AbiChange::VersionAdded(v.clone()));
+        }
+        for v in old_versions.iter().filter(|v| !new_versions.contains(v)) {
+            changes.push(AbiChange::VersionRemoved(v.clone()));
+        }
+
+        let old_symbols = self.abi_symbols();
+        let new_symbols = new.abi_symbols();
+        // Without version information on either side the dynamic linker matches by name
+        let versioned = !old_versions.is_empty() && !new_versions.is_empty();
+        let find = |sym: &AbiSymbol, candidates: &[AbiSymbol]| -> Option<usize> {
+            candidates
+                .iter()
+                .position(|c| c.name == sym.name && c.version == sym.version)
+                .or_else(|| {
+                    if versioned && sym.version.is_some() {
+                        return None;
+                    }
+                    candidates
+                        .iter()
+                        .position(|c| c.name == sym.name && c.default)
+                })
+        };
+
+        let mut matched = vec![false; new_symbols.len()];
+        for old in &old_symbols {
+            let Some(i) = find(old, &new_symbols) else {
+                changes.push(AbiChange::SymbolRemoved(old.clone()));
+                continue;
+            };
+            matched[i] = true;
+            let new = &new_symbols[i];
+            if new.sym_type != old.sym_type {
+                changes.push(AbiChange::TypeChanged {
+                    symbol: new.clone(),
+                    old: old.sym_type,
+                });
+            } else if new.size != old.size && matches!(new.sym_type, STT_OBJECT | STT_TLS) {
+                changes.push(AbiChange::SizeChanged {
+                    symbol: new.clone(),
+                    old: old.size,
+                });
+            }
+            if new.bind != old.bind {
+                changes.push(AbiChange::BindingChanged {
+                    symbol: new.clone(),
+                    old: old.bind,
+                });
+            }
+        }
+        for (new, _) in new_symbols.iter().zip(matched).filter(|(_, m)| !m) {
+            changes.push(AbiChange::SymbolAdded(new.clone()));
+        }
+        AbiReport { changes }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dynamic::{DT_DEBUG, DT_SONAME};
+
+    #[test]
+    fn test_check_abi() {
+        let report = check_abi("tests/bin/libdemo.v1.so", "tests/bin/libdemo.v2.so").unwrap();
+        let lines: Vec<String> = report.changes.iter().map(|c| c.to_string()).collect();
+        assert_eq!(
+            lines,
+            [
+                "[COMPATIBLE] needed library added: libm.so.6",
+                "[COMPATIBLE] version added: LIBDEMO_2.0",
+                "[BREAKING] symbol removed: api_old@@LIBDEMO_1.0",
+                "[BREAKING] type of api_legacy@@LIBDEMO_1.0 changed: FUNC -> OBJECT",
+                "[BREAKING] size of table@@LIBDEMO_1.0 changed: 16 -> 32",
+                "[COMPATIBLE] symbol added: api_add@@LIBDEMO_2.0",
+                "[COMPATIBLE] symbol added: api_cos@@LIBDEMO_2.0",
+                "[COMPATIBLE] symbol added: api_new@@LIBDEMO_2.0",
+            ]
+        );
+        assert!(!report.is_compatible());
+        assert_eq!(report.breaking().count(), 3);
+
+        // The old api_add is kept as api_add@LIBDEMO_1.0, so going back loses only additions
+        // and the new version
+        let back = check_abi("tests/bin/libdemo.v2.so", "tests/bin/libdemo.v1.so").unwrap();
+        assert!(back
+            .changes
+            .contains(&AbiChange::VersionRemoved("LIBDEMO_2.0".to_string())));
+        assert!(!back.changes.iter().any(
+            |c| matches!(c, AbiChange::SymbolRemoved(s) if s.name == "api_add" && !s.default)
+        ));
+
+        let elf = ElfFile::open("tests/bin/libdemo.v1.so").unwrap();
+        assert!(elf.check_abi(&elf).changes.is_empty());
+        // Hide DT_SONAME by retagging it
+        let mut data = elf.data().to_vec();
+        let dynamic = elf.section_by_name(".dynamic").unwrap().sh_offset as usize;
+        let index = elf
+            .dynamic()
+            .iter()
+            .position(|d| d.d_tag == DT_SONAME)
+            .unwrap();
+        data[dynamic + index * 16] = DT_DEBUG as u8;
+        let report = elf.check_abi(&ElfFile::parse(data).unwrap());
+        assert_eq!(
+            report.changes,
+            [AbiChange::SonameChanged {
+                old: Some("libdemo.so.1".to_string()),
+                new: None
+            }]
+        );
+    }
+}
@@ -0,0 +1,307 @@
+use std::{error::Error, fs, path::Path};
+
+use crate::elf_file::ElfFile;
+
+pub const AR_MAGIC: &[u8; 8] = b"!<arch>\n";
+pub const AR_THIN_MAGIC: &[u8; 8] = b"!<thin>\n";
+pub const SIZEOF_AR_HDR: usize = 60;
+const AR_FMAG: &[u8; 2] = b"`\n";
+
+/// A member of an `ar` archive with its name resolved
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ArchiveMember {
+    pub name: String,
+    /// Offset of the member header, as referenced by the symbol index
+    pub header_offset: usize,
+    /// Offset of the member contents
+    pub offset: usize,
+    pub size: usize,
+    pub mtime: u64,
+    pub uid: u32,
+    pub gid: u32,
+    pub mode: u32,
+}
+
+/// A System V / GNU `ar` archive such as a static library.
+///
+/// The `/` and `/SYM64/` symbol indices and the `//` long name table are consumed while
+/// parsing, [`Archive::members`] only lists regular members. Thin archives are rejected.
+#[derive(Debug, Clone)]
+pub struct Archive {
+    data: Vec<u8>,
+    pub members: Vec<ArchiveMember>,
+    /// Symbol index entries as `(symbol, index into members)`
+    pub symbols: Vec<(String, usize)>,
+}
+
+/// Raw header fields of one archive member
+struct RawMember<'a> {
+    name: &'a str,
+    header_offset: usize,
+    offset: usize,
+    size: usize,
+    mtime: u64,
+    uid: u32,
+    gid: u32,
+    mode: u32,
+}
+
+impl Archive {
+    /// Reads and parses the archive at the given path
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Archive, Box<dyn Error>> {
+        Archive::parse(fs::read(path)?)
+    }
+
+    /// Returns `true` if `data` starts with the `ar` magic
+    pub fn is_archive(data: &[u8]) -> bool {
+        data.starts_with(AR_MAGIC) || data.starts_with(AR_THIN_MAGIC)
+    }
+
+    /// Parses an archive from an owned byte buffer
+    pub fn parse(data: Vec<u8>) -> Result<Archive, Box<dyn Error>> {
+        if data.starts_with(AR_THIN_MAGIC) {
+            return Err("thin archives are not supported".into());
+        }
+        if !data.starts_with(AR_MAGIC) {
+            return Err("not an ar archive (bad magic)".into());
+        }
+
+        let mut raw = Vec::new();
+        let mut pos = AR_MAGIC.len();
+        while pos < data.len() {
+            let member = read_member(&data, pos)?;
+            // Members are aligned to an even offset
+            pos = member.offset + member.size + (member.size & 1);
+            raw.push(member);
+        }
+
+        let mut long_names: &[u8] = &[];
+        let mut index: Vec<(String, usize)> = Vec::new();
+        let mut members = Vec::new();
+        for m in raw {
+            let contents = &data[m.offset..m.offset + m.size];
+            match m.name {
+                "/" | "/SYM64/" => index = parse_symbol_index(contents, m.name == "/SYM64/")?,
+                "//" => long_names = contents,
+                name => {
+                    let name = match name.strip_prefix('/') {
+                        Some(off) => long_name(long_names, off)?,
+                        None => name.strip_suffix('/').unwrap_or(name).to_string(),
+                    };
+                    members.push(ArchiveMember {
+                        name,
+                        header_offset: m.header_offset,
+                        offset: m.offset,
+                        size: m.size,
+                        mtime: m.mtime,
+                        uid: m.uid,
+                        gid: m.gid,
+                        mode: m.mode,
+                    });
+                }
+            }
+        }
+
+        let symbols = index
+            .into_iter()
+            .filter_map(|(name, header)| {
+                let i = members.iter().position(|m| m.header_offset == header)?;
+                Some((name, i))
+            })
+            .collect();
+        Ok(Archive {
+            data,
+            members,
+            symbols,
+        })
+    }
+
+    /// Returns the contents of a member
+    pub fn member_data(&self, member: &ArchiveMember) -> &[u8] {
+        &self.data[member.offset..member.offset + member.size]
+    }
+
+    /// Parses a member as an ELF file
+    pub fn member_elf(&self, member: &ArchiveMember) -> Result<ElfFile, Box<dyn Error>> {
+        ElfFile::parse(self.member_data(member).to_vec())
+            .map_err(|e| format!("{}: {}", member.name, e).into())
+    }
+
+    /// Iterates over all members together with their parsed ELF view
+    pub fn elf_members(
+        &self,
+    ) -> impl Iterator<Item = (&ArchiveMember, Result<ElfFile, Box<dyn Error>>)> {
+        self.members.iter().map(|m| (m, self.member_elf(m)))
+    }
+
+    /// Returns the member defining `symbol` according to the symbol index
+    pub fn member_for_symbol(&self, symbol: &str) -> Option<&ArchiveMember> {
+        self.symbols
+            .iter()
+            .find(|(name, _)| name == symbol)
+            .map(|&(_, i)| &self.members[i])
+    }
+}
+
+fn read_member(data: &[u8], pos: usize) -> Result<RawMember<'_>, Box<dyn Error>> {
+    let hdr = data
+        .get(pos..pos + SIZEOF_AR_HDR)
+        .ok_or_else(|| format!("truncated archive member header at {:#x}", pos))?;
+    if &hdr[58..60] != AR_FMAG {
+        return Err(format!("bad archive member header at {:#x}", pos).into());
+    }
+    let field = |range: std::ops::Range<usize>| {
+        std::str::from_utf8(&hdr[range])
+            .map(str::trim_end)
+            .map_err(|_| format!("non ASCII archive member header at {:#x}", pos))
+    };
+    let number = |range: std::ops::Range<usize>, radix: u32| -> Result<u64, String> {
+        let s = field(range)?;
+        if s.is_empty() {
+            return Ok(0);
+        }
+        u64::from_str_radix(s, radix)
+            .map_err(|_| format!("bad number {:?} in archive member header at {:#x}", s, pos))
+    };
+    let size = number(48..58, 10)? as usize;
+    let offset = pos + SIZEOF_AR_HDR;
+    if offset.checked_add(size).is_none_or(|end| end > data.len()) {
+        return Err(format!("archive member at {:#x} exceeds the file", pos).into());
+    }
+    Ok(RawMember {
+        name: field(0..16)?,
+        header_offset: pos,
+        offset,
+        size,
+        mtime: number(16..28, 10)?,
+        uid: number(28..34, 10)? as u32,
+        gid: number(34..40, 10)? as u32,
+        mode: number(40..48, 8)? as u32,
+    })
+}
+
+/// Parses the GNU symbol index: a big endian count, that many member header offsets and
+/// the NUL terminated symbol names
+fn parse_symbol_index(data: &[u8], sym64: bool) -> Result<Vec<(String, usize)>, Box<dyn Error>> {
+    let width = if sym64 { 8 } else { 4 };
+    let word = |i: usize| -> Option<u64> {
+        let b = data.get(i * width..(i + 1) * width)?;
+        Some(b.iter().fold(0u64, |acc, &x| acc << 8 | x as u64))
+    };
+    let truncated = || "truncated archive symbol index";
+    let count = word(0).ok_or_else(truncated)? as usize;
+    let strings_at = count
+        .checked_add(1)
+        .and_then(|n| n.checked_mul(width))
+        .filter(|&n| n <= data.len())
+        .ok_or_else(truncated)?;
+    let mut names = data[strings_at..].split(|&b| b == 0);
+    (1..=count)
+        .map(|i| {
+            let name = names.next().ok_or_else(truncated)?;
+            Ok((
+                String::from_utf8_lossy(name).into_owned(),
+                word(i).ok_or_else(truncated)? as usize,
+            ))
+        })
+        .collect()
+}
+
+/// Resolves a `/<offset>` name through the `//` long name table
+fn long_name(table: &[u8], offset: &str) -> Result<String, Box<dyn Error>> {
+    let start: usize = offset
+        .parse()
+        .map_err(|_| format!("bad long name reference /{}", offset))?;
+    let rest = table
+        .get(start..)
+        .ok_or_else(|| format!("long name offset {} outside the name table", start))?;
+    let end = rest
+        .windows(2)
+        .position(|w| w == b"/\n")
+        .or_else(|| rest.iter().position(|&b| b == b'\n'))
+        .unwrap_or(rest.len());
+    Ok(String::from_utf8_lossy(&rest[..end]).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn member(name: &str, data: &[u8]) -> Vec<u8> {
+        let mut out = format!(
+            "{:<16}{:<12}{:<6}{:<6}{:<8}{:<10}`\n",
+            name,
+            0,
+            0,
+            0,
+            644,
+            data.len()
+        )
+        .into_bytes();
+        out.extend_from_slice(data);
+        if data.len() % 2 == 1 {
+            out.push(b'\n');
+        }
+        out
+    }
+
+    #[test]
+    fn test_gnu_archive() {
+        let ar = Archive::open("tests/bin/libmix.a").unwrap();
+        let names: Vec<&str> = ar.members.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "debug.o",
+                "a_rather_long_object_name_for_the_table.o",
+                "h.o"
+            ]
+        );
+        let plain = std::fs::read("tests/bin/debug.o").unwrap();
+        assert_eq!(ar.member_data(&ar.members[1]), &plain[..]);
+        for (_, elf) in ar.elf_members() {
+            assert!(!elf.unwrap().symtab().is_empty());
+        }
+        assert_eq!(ar.member_for_symbol("helper_fn").unwrap().name, "h.o");
+        assert_eq!(ar.symbols.len(), 6);
+        assert!(ElfFile::open("tests/bin/libmix.a")
+            .unwrap_err()
+            .to_string()
+            .contains("ar archive"));
+    }
+
+    #[test]
+    fn test_sym64_index() {
+        let obj = std::fs::read("tests/bin/debug.o").unwrap();
+        let names = b"very_long_member_name.o/\n";
+        let strings = b"add\0main\0";
+        let index_len = 3 * 8 + strings.len();
+        // The index points to the header of the first regular member
+        let first = AR_MAGIC.len()
+            + SIZEOF_AR_HDR
+            + index_len
+            + index_len % 2
+            + SIZEOF_AR_HDR
+            + names.len()
+            + names.len() % 2;
+        let mut index = Vec::new();
+        index.extend_from_slice(&2u64.to_be_bytes());
+        index.extend_from_slice(&(first as u64).to_be_bytes());
+        index.extend_from_slice(&(first as u64).to_be_bytes());
+        index.extend_from_slice(strings);
+
+        let mut data = AR_MAGIC.to_vec();
+        data.extend(member("/SYM64/", &index));
+        data.extend(member("//", names));
+        data.extend(member("/0", &obj));
+        let ar = Archive::parse(data).unwrap();
+        assert_eq!(ar.members.len(), 1);
+        assert_eq!(ar.members[0].name, "very_long_member_name.o");
+        assert_eq!(ar.members[0].mode, 0o644);
+        assert_eq!(
+            ar.symbols,
+            [("add".to_string(), 0), ("main".to_string(), 0)]
+        );
+        assert!(ar.member_elf(&ar.members[0]).is_ok());
+    }
+}
@@ -0,0 +1,385 @@
+use std::error::Error;
+
+use crate::btf::{Btf, BtfExt, BtfKind};
+use crate::cstr_at;
+use crate::elf_file::ElfFile;
+use crate::elf_types::{Machine, SHT_PROGBITS};
+use crate::symbols::{STT_FUNC, STT_OBJECT};
+use crate::SectionFlags;
+
+/// Size of a BPF instruction
+pub const BPF_INSN_SIZE: u64 = 8;
+
+/// A BPF program: a function placed in its own section, the section name selecting the
+/// program type and attach point
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BpfProgram {
+    pub name: String,
+    pub section: String,
+    /// Program type as derived by libbpf from the section name, e.g. `kprobe`
+    pub prog_type: Option<&'static str>,
+    /// Offset of the program within its section
+    pub offset: u64,
+    pub size: u64,
+}
+
+impl BpfProgram {
+    pub fn insn_count(&self) -> u64 {
+        self.size / BPF_INSN_SIZE
+    }
+
+    /// Returns the attach target following the section prefix, e.g. `do_sys_open` for
+    /// `kprobe/do_sys_open`
+    pub fn attach_target(&self) -> Option<&str> {
+        self.section.split_once('/').map(|(_, target)| target)
+    }
+}
+
+/// A map definition from the legacy `maps` section or the BTF based `.maps` section
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BpfMap {
+    pub name: String,
+    pub section: String,
+    pub map_type: u32,
+    pub key_size: u32,
+    pub value_size: u32,
+    pub max_entries: u32,
+    pub map_flags: u32,
+    /// BTF types of key and value, only known for `.maps` definitions
+    pub key_type: Option<u32>,
+    pub value_type: Option<u32>,
+}
+
+impl BpfMap {
+    pub fn map_type_name(&self) -> Option<&'static str> {
+        map_type_to_str(self.map_type)
+    }
+}
+
+/// The contents of a BPF ELF object as loaded by libbpf
+#[derive(Debug, Clone)]
+pub struct BpfObject {
+    pub programs: Vec<BpfProgram>,
+    pub maps: Vec<BpfMap>,
+    pub license: Option<String>,
+    /// Kernel version from the `version` section required by old kprobe programs
+    pub kern_version: Option<u32>,
+    pub btf: Option<Btf>,
+    pub btf_ext: Option<BtfExt>,
+}
+
+/// Maps a program section name to the program type libbpf derives from it
+pub fn section_prog_type(section: &str) -> Option<&'static str> {
+    // Suffixes such as `.s` (sleepable), `.multi` or `.frags` keep the program type
+    let prefix = section.split('/').next()?;
+    let prefix = prefix.split('.').next()?;
+    Some(match prefix {
+        "socket" => "socket_filter",
+        "kprobe" | "kretprobe" | "uprobe" | "uretprobe" | "ksyscall" | "kretsyscall" | "usdt" => {
+            "kprobe"
+        }
+        "tc" | "tcx" | "classifier" | "netkit" => "sched_cls",
+        "action" => "sched_act",
+        "tracepoint" | "tp" => "tracepoint",
+        "raw_tracepoint" | "raw_tp" => "raw_tracepoint",
+        "raw_tracepoint_w" | "raw_tp_w" => "raw_tracepoint_writable",
+        "tp_btf" | "fentry" | "fexit" | "fmod_ret" | "freplace" | "iter" => "tracing",
+        "xdp" => "xdp",
+        "perf_event" => "perf_event",
+        "lwt_in" | "lwt_out" | "lwt_xmit" | "lwt_seg6local" => "lwt",
+        "sockops" => "sock_ops",
+        "sk_skb" => "sk_skb",
+        "sk_msg" => "sk_msg",
+        "lirc_mode2" => "lirc_mode2",
+        "flow_dissector" => "flow_dissector",
+        "cgroup_skb" => "cgroup_skb",
+        "cgroup" if section.starts_with("cgroup/dev") => "cgroup_device",
+        "cgroup" if section.starts_with("cgroup/sysctl") => "cgroup_sysctl",
+        "cgroup" if section.starts_with("cgroup/skb") => "cgroup_skb",
+        "cgroup" if section.starts_with("cgroup/getsockopt") => "cgroup_sockopt",
+        "cgroup" if section.starts_with("cgroup/setsockopt") => "cgroup_sockopt",
+        "cgroup" if section.starts_with("cgroup/sock") => "cgroup_sock",
+        "cgroup" => "cgroup_sock_addr",
+        "sk_reuseport" => "sk_reuseport",
+        "sk_lookup" => "sk_lookup",
+        "lsm" | "lsm_cgroup" => "lsm",
+        "struct_ops" => "struct_ops",
+        "syscall" => "syscall",
+        "netfilter" => "netfilter",
+        _ => return None,
+    })
+}
+
+/// Returns the name of a `BPF_MAP_TYPE_*` value
+pub fn map_type_to_str(map_type: u32) -> Option<&'static str> {
+    const NAMES: [&str; 34] = [
+        "unspec",
+        "hash",
+        "array",
+        "prog_array",
+        "perf_event_array",
+        "percpu_hash",
+        "percpu_array",
+        "stack_trace",
+        "cgroup_array",
+        "lru_hash",
+        "lru_percpu_hash",
+        "lpm_trie",
+        "array_of_maps",
+        "hash_of_maps",
+        "devmap",
+        "sockmap",
+        "cpumap",
+        "xskmap",
+        "sockhash",
+        "cgroup_storage",
+        "reuseport_sockarray",
+        "percpu_cgroup_storage",
+        "queue",
+        "stack",
+        "sk_storage",
+        "devmap_hash",
+        "struct_ops",
+        "ringbuf",
+        "inode_storage",
+        "task_storage",
+        "bloom_filter",
+        "user_ringbuf",
+        "cgrp_storage",
+        "arena",
+    ];
+    NAMES.get(map_type as usize).copied()
+}
+
+/// Decodes a `__uint(name, value)` member, encoded as a pointer to an array of `value`
+/// elements
+fn btf_map_uint(btf: &Btf, type_id: u32) -> Option<u32> {
+    let BtfKind::Ptr(target) = btf.get(btf.resolve(type_id))?.kind else {
+        return None;
+    };
+    match btf.get(btf.resolve(target))?.kind {
+        BtfKind::Array { nelems, .. } => Some(nelems),
+        _ => None,
+    }
+}
+
+/// Decodes a `__type(name, type)` member, encoded as a pointer to `type`
+fn btf_map_type(btf: &Btf, type_id: u32) -> Option<u32> {
+    match btf.get(btf.resolve(type_id))?.kind {
+        BtfKind::Ptr(target) => Some(target),
+        _ => None,
+    }
+}
+
+/// Decodes the map definitions of the `.maps` data section from their BTF description
+fn btf_maps(btf: &Btf) -> Vec<BpfMap> {
+    let vars = btf.types.iter().find_map(|t| match &t.kind {
+        BtfKind::Datasec { vars, .. } if t.name == ".maps" => Some(vars),
+        _ => None,
+    });
+    let mut maps = Vec::new();
+    for var in vars.into_iter().flatten() {
+        let Some(var_type) = btf.get(var.type_id) else {
+            continue;
+        };
+        let BtfKind::Var { type_id, .. } = var_type.kind else {
+            continue;
+        };
+        let Some(BtfKind::Struct { members, .. }) = btf.get(btf.resolve(type_id)).map(|t| &t.kind)
+        else {
+            continue;
+        };
+        let mut map = BpfMap {
+            name: var_type.name.clone(),
+            section: ".maps".to_string(),
+            ..Default::default()
+        };
+        for m in members {
+            let uint = || btf_map_uint(btf, m.type_id).unwrap_or_default();
+            match m.name.as_str() {
+                "type" => map.map_type = uint(),
+                "max_entries" => map.max_entries = uint(),
+                "map_flags" => map.map_flags = uint(),
+                "key_size" => map.key_size = uint(),
+                "value_size" => map.value_size = uint(),
+                "key" => {
+                    map.key_type = btf_map_type(btf, m.type_id);
+                    map.key_size = map
+                        .key_type
+                        .and_then(|t| btf.type_size(t))
+                        .unwrap_or_default() as u32;
+                }
+                "value" => {
+                    map.value_type = btf_map_type(btf, m.type_id);
+                    map.value_size = map
+                        .value_type
+                        .and_then(|t| btf.type_size(t))
+                        .unwrap_or_default() as u32;
+                }
+                _ => {}
+            }
+        }
+        maps.push(map);
+    }
+    maps
+}
+
+impl ElfFile {
+    /// Returns `true` for files targeting the BPF virtual machine
+    pub fn is_bpf(&self) -> bool {
+        self.header.machine() == Ok(Machine::Bpf)
+    }
+
+    /// Lists the programs, maps, license and BTF information of a BPF object file.
+    ///
+    /// Every function symbol in an executable section other than `.text` is a program,
+    /// functions in `.text` are subprograms callable from them.
+    pub fn bpf_object(&self) -> Result<BpfObject, Box<dyn Error>> {
+        if !self.is_bpf() {
+            return Err("not a BPF object".into());
+        }
+        let btf = match self.section_by_name(".BTF") {
+            Some(_) => Some(self.btf()?),
+            None => None,
+        };
+        let btf_ext = match (&btf, self.section_by_name(".BTF.ext")) {
+            (Some(_), Some(_)) => Some(self.btf_ext()?),
+            _ => None,
+        };
+
+        let symtab = self.symtab();
+        let mut programs = Vec::new();
+        let mut maps = Vec::new();
+        for (i, sh) in self.section_headers.iter().enumerate() {
+            let name = self.section_name(sh);
+            let in_section = |sym_type: u8| {
+                symtab
+                    .iter()
+                    .filter(move |s| s.shndx as usize == i && s.sym_type() == sym_type)
+            };
+            if sh.sh_type == SHT_PROGBITS
+                && SectionFlags::from(sh.sh_flags).contains(SectionFlags::EXECINSTR)
+                && name != ".text"
+            {
+                programs.extend(in_section(STT_FUNC).map(|s| BpfProgram {
+                    name: s.name.clone(),
+                    section: name.to_string(),
+                    prog_type: section_prog_type(name),
+                    offset: s.value,
+                    size: s.size,
+                }));
+            } else if name == "maps" || name.starts_with("maps/") {
+                // struct bpf_map_def: type, key_size, value_size, max_entries, map_flags
+                let data = self.section_data(sh).unwrap_or_default();
+                maps.extend(in_section(STT_OBJECT).map(|s| {
+                    let field = |n: u64| {
+                        if (n + 1) * 4 > s.size {
+                            return 0;
+                        }
+                        self.read_u32(data, (s.value + n * 4) as usize)
+                            .unwrap_or_default()
+                    };
+                    BpfMap {
+                        name: s.name.clone(),
+                        section: name.to_string(),
+                        map_type: field(0),
+                        key_size: field(1),
+                        value_size: field(2),
+                        max_entries: field(3),
+                        map_flags: field(4),
+                        ..Default::default()
+                    }
+                }));
+            }
+        }
+        if let Some(btf) = &btf {
+            maps.extend(btf_maps(btf));
+        }
+
+        let section = |name: &str| {
+            self.section_by_name(name)
+                .and_then(|sh| self.section_data(sh))
+        };
+        Ok(BpfObject {
+            programs,
+            maps,
+            license: section("license").map(|d| cstr_at(d, 0).to_string()),
+            kern_version: section("version").and_then(|d| self.read_u32(d, 0)),
+            btf,
+            btf_ext,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bpf_object() {
+        for path in ["tests/bin/prog.bpf.o", "tests/bin/prog.bpfeb.o"] {
+            let elf = ElfFile::open(path).unwrap();
+            assert!(elf.is_bpf());
+            let obj = elf.bpf_object().unwrap();
+            let programs: Vec<(&str, &str, Option<&str>, u64)> = obj
+                .programs
+                .iter()
+                .map(|p| {
+                    (
+                        p.name.as_str(),
+                        p.section.as_str(),
+                        p.prog_type,
+                        p.insn_count(),
+                    )
+                })
+                .collect();
+            assert_eq!(
+                programs,
+                [
+                    ("trace_open", "kprobe/do_sys_open", Some("kprobe"), 4),
+                    ("xdp_pass", "xdp", Some("xdp"), 2)
+                ]
+            );
+            assert_eq!(obj.programs[0].attach_target(), Some("do_sys_open"));
+            assert_eq!(obj.license.as_deref(), Some("GPL"));
+            assert_eq!(obj.kern_version, None);
+
+            let legacy = obj.maps.iter().find(|m| m.name == "legacy").unwrap();
+            assert_eq!(
+                (
+                    legacy.map_type,
+                    legacy.key_size,
+                    legacy.value_size,
+                    legacy.max_entries
+                ),
+                (2, 4, 8, 16)
+            );
+            assert_eq!(legacy.map_type_name(), Some("array"));
+            let counts = obj.maps.iter().find(|m| m.name == "counts").unwrap();
+            assert_eq!(counts.section, ".maps");
+            assert_eq!(counts.map_type_name(), Some("hash"));
+            assert_eq!(
+                (counts.key_size, counts.value_size, counts.max_entries),
+                (4, 8, 1024)
+            );
+            let btf = obj.btf.as_ref().unwrap();
+            assert_eq!(btf.type_name(counts.value_type.unwrap()), "long");
+        }
+        assert!(ElfFile::open("/bin/ls").unwrap().bpf_object().is_err());
+    }
+
+    #[test]
+    fn test_section_prog_type() {
+        assert_eq!(
+            section_prog_type("tp/syscalls/sys_enter_open"),
+            Some("tracepoint")
+        );
+        assert_eq!(section_prog_type("fentry.s/do_exit"), Some("tracing"));
+        assert_eq!(section_prog_type("xdp.frags"), Some("xdp"));
+        assert_eq!(
+            section_prog_type("cgroup/connect4"),
+            Some("cgroup_sock_addr")
+        );
+        assert_eq!(section_prog_type(".text"), None);
+    }
+}
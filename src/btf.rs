@@ -0,0 +1,705 @@
+use std::error::Error;
+
+use crate::cstr_at;
+use crate::dwarf::Reader;
+use crate::elf_file::ElfFile;
+
+pub const BTF_MAGIC: u16 = 0xeb9f;
+pub const SIZEOF_BTF_HEADER: usize = 24;
+pub const SIZEOF_BTF_EXT_HEADER: usize = 32;
+
+pub const BTF_KIND_INT: u8 = 1;
+pub const BTF_KIND_PTR: u8 = 2;
+pub const BTF_KIND_ARRAY: u8 = 3;
+pub const BTF_KIND_STRUCT: u8 = 4;
+pub const BTF_KIND_UNION: u8 = 5;
+pub const BTF_KIND_ENUM: u8 = 6;
+pub const BTF_KIND_FWD: u8 = 7;
+pub const BTF_KIND_TYPEDEF: u8 = 8;
+pub const BTF_KIND_VOLATILE: u8 = 9;
+pub const BTF_KIND_CONST: u8 = 10;
+pub const BTF_KIND_RESTRICT: u8 = 11;
+pub const BTF_KIND_FUNC: u8 = 12;
+pub const BTF_KIND_FUNC_PROTO: u8 = 13;
+pub const BTF_KIND_VAR: u8 = 14;
+pub const BTF_KIND_DATASEC: u8 = 15;
+pub const BTF_KIND_FLOAT: u8 = 16;
+pub const BTF_KIND_DECL_TAG: u8 = 17;
+pub const BTF_KIND_TYPE_TAG: u8 = 18;
+pub const BTF_KIND_ENUM64: u8 = 19;
+
+pub const BTF_INT_SIGNED: u8 = 1 << 0;
+pub const BTF_INT_CHAR: u8 = 1 << 1;
+pub const BTF_INT_BOOL: u8 = 1 << 2;
+
+pub const BPF_CORE_FIELD_BYTE_OFFSET: u32 = 0;
+pub const BPF_CORE_FIELD_BYTE_SIZE: u32 = 1;
+pub const BPF_CORE_FIELD_EXISTS: u32 = 2;
+pub const BPF_CORE_FIELD_SIGNED: u32 = 3;
+pub const BPF_CORE_FIELD_LSHIFT_U64: u32 = 4;
+pub const BPF_CORE_FIELD_RSHIFT_U64: u32 = 5;
+pub const BPF_CORE_TYPE_ID_LOCAL: u32 = 6;
+pub const BPF_CORE_TYPE_ID_TARGET: u32 = 7;
+pub const BPF_CORE_TYPE_EXISTS: u32 = 8;
+pub const BPF_CORE_TYPE_SIZE: u32 = 9;
+pub const BPF_CORE_ENUMVAL_EXISTS: u32 = 10;
+pub const BPF_CORE_ENUMVAL_VALUE: u32 = 11;
+pub const BPF_CORE_TYPE_MATCHES: u32 = 12;
+
+/// A struct or union member. `bitfield_size` is 0 for regular members.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BtfMember {
+    pub name: String,
+    pub type_id: u32,
+    pub bit_offset: u32,
+    pub bitfield_size: u8,
+}
+
+/// A variable placed in a data section: `type_id` refers to a `Var`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BtfVarSecinfo {
+    pub type_id: u32,
+    pub offset: u32,
+    pub size: u32,
+}
+
+/// The kind specific part of a BTF type
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BtfKind {
+    Int {
+        size: u32,
+        /// `BTF_INT_*` flags
+        encoding: u8,
+        offset: u8,
+        bits: u8,
+    },
+    Ptr(u32),
+    Array {
+        elem_type: u32,
+        index_type: u32,
+        nelems: u32,
+    },
+    Struct {
+        size: u32,
+        members: Vec<BtfMember>,
+    },
+    Union {
+        size: u32,
+        members: Vec<BtfMember>,
+    },
+    Enum {
+        size: u32,
+        signed: bool,
+        values: Vec<(String, i64)>,
+    },
+    Fwd {
+        union: bool,
+    },
+    Typedef(u32),
+    Volatile(u32),
+    Const(u32),
+    Restrict(u32),
+    Func {
+        /// The `FuncProto` describing the signature
+        type_id: u32,
+        linkage: u16,
+    },
+    FuncProto {
+        return_type: u32,
+        params: Vec<(String, u32)>,
+    },
+    Var {
+        type_id: u32,
+        linkage: u32,
+    },
+    Datasec {
+        size: u32,
+        vars: Vec<BtfVarSecinfo>,
+    },
+    Float {
+        size: u32,
+    },
+    DeclTag {
+        type_id: u32,
+        /// Member or parameter index the tag applies to, -1 for the type itself
+        component_idx: i32,
+    },
+    TypeTag(u32),
+    Enum64 {
+        size: u32,
+        signed: bool,
+        values: Vec<(String, i64)>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BtfType {
+    pub name: String,
+    pub kind: BtfKind,
+}
+
+/// The BPF Type Format type information of a `.BTF` section or a raw BTF blob such as
+/// `/sys/kernel/btf/vmlinux`
+#[derive(Debug, Clone)]
+pub struct Btf {
+    pub version: u8,
+    pub flags: u8,
+    /// Types in id order, type id `n` is stored at index `n - 1` as id 0 is `void`
+    pub types: Vec<BtfType>,
+    strings: Vec<u8>,
+}
+
+/// A `.BTF.ext` func info record: the BTF `Func` of the function starting at `insn_off`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BtfFuncInfo {
+    pub section: String,
+    /// Byte offset of the first instruction within `section`
+    pub insn_off: u32,
+    pub type_id: u32,
+}
+
+/// A `.BTF.ext` line info record mapping an instruction to a source location
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BtfLineInfo {
+    pub section: String,
+    pub insn_off: u32,
+    pub file: String,
+    /// The source line itself, empty if the compiler did not have the source
+    pub source: String,
+    pub line: u32,
+    pub column: u32,
+}
+
+/// A `.BTF.ext` CO-RE relocation applied to the instruction at `insn_off`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoreRelo {
+    pub section: String,
+    pub insn_off: u32,
+    /// Root type of the access
+    pub type_id: u32,
+    /// Access specifier such as `0:1:2`, see [`CoreRelo::access_spec`]
+    pub access: String,
+    /// One of the `BPF_CORE_*` relocation kinds
+    pub kind: u32,
+}
+
+/// The function, line and CO-RE relocation records of a `.BTF.ext` section
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BtfExt {
+    pub func_info: Vec<BtfFuncInfo>,
+    pub line_info: Vec<BtfLineInfo>,
+    pub core_relos: Vec<CoreRelo>,
+}
+
+/// Detects the byte order of a BTF blob from its magic
+fn btf_byte_order(data: &[u8], what: &str) -> Result<bool, Box<dyn Error>> {
+    match data.get(..2) {
+        Some([0x9f, 0xeb]) => Ok(false),
+        Some([0xeb, 0x9f]) => Ok(true),
+        _ => Err(format!("not {} data (bad magic)", what).into()),
+    }
+}
+
+/// Returns `len` bytes at `off` past the header, as addressed by the BTF headers
+fn btf_subsection(data: &[u8], hdr_len: usize, off: u32, len: u32) -> Option<&[u8]> {
+    let start = hdr_len.checked_add(off as usize)?;
+    data.get(start..start.checked_add(len as usize)?)
+}
+
+impl Btf {
+    /// Parses a BTF blob in either byte order
+    pub fn parse(data: &[u8]) -> Result<Btf, Box<dyn Error>> {
+        let big_endian = btf_byte_order(data, "BTF")?;
+        let mut r = Reader::new(data, 2, big_endian);
+        let truncated = || "truncated BTF header";
+        let version = r.u8().ok_or_else(truncated)?;
+        let flags = r.u8().ok_or_else(truncated)?;
+        let mut fields = [0u32; 5];
+        for f in fields.iter_mut() {
+            *f = r.u32().ok_or_else(truncated)?;
+        }
+        let [hdr_len, type_off, type_len, str_off, str_len] = fields;
+        let strings = btf_subsection(data, hdr_len as usize, str_off, str_len)
+            .ok_or("BTF string section exceeds the data")?
+            .to_vec();
+        let type_data = btf_subsection(data, hdr_len as usize, type_off, type_len)
+            .ok_or("BTF type section exceeds the data")?;
+
+        let mut types = Vec::new();
+        let mut r = Reader::new(type_data, 0, big_endian);
+        while !r.is_empty() {
+            let ty = read_type(&mut r, &strings)
+                .ok_or_else(|| format!("malformed BTF type {}", types.len() + 1))?;
+            types.push(ty);
+        }
+        Ok(Btf {
+            version,
+            flags,
+            types,
+            strings,
+        })
+    }
+
+    /// Returns the string at `off` in the BTF string section
+    pub fn string(&self, off: u32) -> &str {
+        cstr_at(&self.strings, off as usize)
+    }
+
+    /// Returns the type with the given id, `None` for `void` (id 0) and invalid ids
+    pub fn get(&self, id: u32) -> Option<&BtfType> {
+        self.types.get((id as usize).checked_sub(1)?)
+    }
+
+    /// Returns the id of the first type named `name`
+    pub fn find(&self, name: &str) -> Option<u32> {
+        self.types
+            .iter()
+            .position(|t| t.name == name)
+            .map(|i| i as u32 + 1)
+    }
+
+    /// Skips typedefs, type tags and `const`/`volatile`/`restrict` qualifiers
+    pub fn resolve(&self, mut id: u32) -> u32 {
+        for _ in 0..self.types.len() {
+            match self.get(id).map(|t| &t.kind) {
+                Some(
+                    BtfKind::Typedef(t)
+                    | BtfKind::Volatile(t)
+                    | BtfKind::Const(t)
+                    | BtfKind::Restrict(t)
+                    | BtfKind::TypeTag(t),
+                ) => id = *t,
+                _ => break,
+            }
+        }
+        id
+    }
+
+    /// Returns the size of a type in bytes. Pointers are taken to be 8 bytes as in BPF.
+    pub fn type_size(&self, id: u32) -> Option<u64> {
+        self.type_size_depth(id, 0)
+    }
+
+    fn type_size_depth(&self, id: u32, depth: usize) -> Option<u64> {
+        if depth > 32 {
+            return None;
+        }
+        let id = self.resolve(id);
+        Some(match &self.get(id)?.kind {
+            BtfKind::Int { size, .. }
+            | BtfKind::Struct { size, .. }
+            | BtfKind::Union { size, .. }
+            | BtfKind::Enum { size, .. }
+            | BtfKind::Enum64 { size, .. }
+            | BtfKind::Datasec { size, .. }
+            | BtfKind::Float { size } => *size as u64,
+            BtfKind::Ptr(_) => 8,
+            BtfKind::Array {
+                elem_type, nelems, ..
+            } => (*nelems as u64).checked_mul(self.type_size_depth(*elem_type, depth + 1)?)?,
+            BtfKind::Var { type_id, .. } => self.type_size_depth(*type_id, depth + 1)?,
+            _ => return None,
+        })
+    }
+
+    /// Renders a type as it would be spelled in C, e.g. `const char *` or `struct task`
+    pub fn type_name(&self, id: u32) -> String {
+        self.type_name_depth(id, 0)
+    }
+
+    fn type_name_depth(&self, id: u32, depth: usize) -> String {
+        let Some(ty) = self.get(id).filter(|_| depth <= 32) else {
+            return if id == 0 { "void" } else { "?" }.to_string();
+        };
+        let inner = |t: u32| self.type_name_depth(t, depth + 1);
+        let named = |prefix: &str| match ty.name.as_str() {
+            "" => format!("{} {{...}}", prefix),
+            name => format!("{} {}", prefix, name),
+        };
+        match &ty.kind {
+            BtfKind::Ptr(t) => format!("{} *", inner(*t)),
+            BtfKind::Array {
+                elem_type, nelems, ..
+            } => format!("{}[{}]", inner(*elem_type), nelems),
+            BtfKind::Struct { .. } => named("struct"),
+            BtfKind::Union { .. } => named("union"),
+            BtfKind::Enum { .. } | BtfKind::Enum64 { .. } => named("enum"),
+            BtfKind::Fwd { union } => named(if *union { "union" } else { "struct" }),
+            BtfKind::Volatile(t) => format!("volatile {}", inner(*t)),
+            BtfKind::Const(t) => format!("const {}", inner(*t)),
+            BtfKind::Restrict(t) => format!("{} restrict", inner(*t)),
+            BtfKind::TypeTag(t) => format!(
+                "{} __attribute__((btf_type_tag(\"{}\")))",
+                inner(*t),
+                ty.name
+            ),
+            BtfKind::FuncProto {
+                return_type,
+                params,
+            } => {
+                let params: Vec<String> = params.iter().map(|(_, t)| inner(*t)).collect();
+                format!("{} (*)({})", inner(*return_type), params.join(", "))
+            }
+            _ => ty.name.clone(),
+        }
+    }
+}
+
+fn read_type(r: &mut Reader, strings: &[u8]) -> Option<BtfType> {
+    let string = |off: u32| cstr_at(strings, off as usize).to_string();
+    let name = string(r.u32()?);
+    let info = r.u32()?;
+    let size_or_type = r.u32()?;
+    let vlen = info & 0xffff;
+    let kind_flag = info >> 31 == 1;
+
+    let members = |r: &mut Reader| -> Option<Vec<BtfMember>> {
+        (0..vlen)
+            .map(|_| {
+                let name = string(r.u32()?);
+                let type_id = r.u32()?;
+                let offset = r.u32()?;
+                // With kind_flag set the offset holds the bitfield size in its top byte
+                let (bit_offset, bitfield_size) = if kind_flag {
+                    (offset & 0xff_ffff, (offset >> 24) as u8)
+                } else {
+                    (offset, 0)
+                };
+                Some(BtfMember {
+                    name,
+                    type_id,
+                    bit_offset,
+                    bitfield_size,
+                })
+            })
+            .collect()
+    };
+
+    let kind = match ((info >> 24) & 0x1f) as u8 {
+        BTF_KIND_INT => {
+            let enc = r.u32()?;
+            BtfKind::Int {
+                size: size_or_type,
+                encoding: (enc >> 24 & 0xf) as u8,
+                offset: (enc >> 16 & 0xff) as u8,
+                bits: (enc & 0xff) as u8,
+            }
+        }
+        BTF_KIND_PTR => BtfKind::Ptr(size_or_type),
+        BTF_KIND_ARRAY => BtfKind::Array {
+            elem_type: r.u32()?,
+            index_type: r.u32()?,
+            nelems: r.u32()?,
+        },
+        BTF_KIND_STRUCT => BtfKind::Struct {
+            size: size_or_type,
+            members: members(r)?,
+        },
+        BTF_KIND_UNION => BtfKind::Union {
+            size: size_or_type,
+            members: members(r)?,
+        },
+        BTF_KIND_ENUM => BtfKind::Enum {
+            size: size_or_type,
+            signed: kind_flag,
+            values: (0..vlen)
+                .map(|_| {
+                    let name = string(r.u32()?);
+                    let val = r.u32()?;
+                    let val = if kind_flag {
+                        val as i32 as i64
+                    } else {
+                        val as i64
+                    };
+                    Some((name, val))
+                })
+                .collect::<Option<_>>()?,
+        },
+        BTF_KIND_FWD => BtfKind::Fwd { union: kind_flag },
+        BTF_KIND_TYPEDEF => BtfKind::Typedef(size_or_type),
+        BTF_KIND_VOLATILE => BtfKind::Volatile(size_or_type),
+        BTF_KIND_CONST => BtfKind::Const(size_or_type),
+        BTF_KIND_RESTRICT => BtfKind::Restrict(size_or_type),
+        BTF_KIND_FUNC => BtfKind::Func {
+            type_id: size_or_type,
+            linkage: vlen as u16,
+        },
+        BTF_KIND_FUNC_PROTO => BtfKind::FuncProto {
+            return_type: size_or_type,
+            params: (0..vlen)
+                .map(|_| Some((string(r.u32()?), r.u32()?)))
+                .collect::<Option<_>>()?,
+        },
+        BTF_KIND_VAR => BtfKind::Var {
+            type_id: size_or_type,
+            linkage: r.u32()?,
+        },
+        BTF_KIND_DATASEC => BtfKind::Datasec {
+            size: size_or_type,
+            vars: (0..vlen)
+                .map(|_| {
+                    Some(BtfVarSecinfo {
+                        type_id: r.u32()?,
+                        offset: r.u32()?,
+                        size: r.u32()?,
+                    })
+                })
+                .collect::<Option<_>>()?,
+        },
+        BTF_KIND_FLOAT => BtfKind::Float { size: size_or_type },
+        BTF_KIND_DECL_TAG => BtfKind::DeclTag {
+            type_id: size_or_type,
+            component_idx: r.u32()? as i32,
+        },
+        BTF_KIND_TYPE_TAG => BtfKind::TypeTag(size_or_type),
+        BTF_KIND_ENUM64 => BtfKind::Enum64 {
+            size: size_or_type,
+            signed: kind_flag,
+            values: (0..vlen)
+                .map(|_| {
+                    let name = string(r.u32()?);
+                    let lo = r.u32()? as u64;
+                    let hi = r.u32()? as u64;
+                    Some((name, (hi << 32 | lo) as i64))
+                })
+                .collect::<Option<_>>()?,
+        },
+        _ => return None,
+    };
+    Some(BtfType { name, kind })
+}
+
+impl CoreRelo {
+    /// Parses the access specifier into its indices. The first one indexes into the root
+    /// type as an array, the following ones select members or array elements.
+    pub fn access_spec(&self) -> Vec<u32> {
+        self.access
+            .split(':')
+            .filter_map(|i| i.parse().ok())
+            .collect()
+    }
+
+    /// Returns the libbpf name of the relocation kind
+    pub fn kind_name(&self) -> &'static str {
+        match self.kind {
+            BPF_CORE_FIELD_BYTE_OFFSET => "byte_off",
+            BPF_CORE_FIELD_BYTE_SIZE => "byte_sz",
+            BPF_CORE_FIELD_EXISTS => "field_exists",
+            BPF_CORE_FIELD_SIGNED => "signed",
+            BPF_CORE_FIELD_LSHIFT_U64 => "lshift_u64",
+            BPF_CORE_FIELD_RSHIFT_U64 => "rshift_u64",
+            BPF_CORE_TYPE_ID_LOCAL => "local_type_id",
+            BPF_CORE_TYPE_ID_TARGET => "target_type_id",
+            BPF_CORE_TYPE_EXISTS => "type_exists",
+            BPF_CORE_TYPE_SIZE => "type_size",
+            BPF_CORE_ENUMVAL_EXISTS => "enumval_exists",
+            BPF_CORE_ENUMVAL_VALUE => "enumval_value",
+            BPF_CORE_TYPE_MATCHES => "type_matches",
+            _ => "unknown",
+        }
+    }
+
+    /// Spells out the accessed field, e.g. `struct task.state` for `0:1`
+    pub fn describe(&self, btf: &Btf) -> String {
+        let mut id = self.type_id;
+        let mut out = btf.type_name(id);
+        let spec = self.access_spec();
+        if let Some(&first) = spec.first().filter(|&&i| i != 0) {
+            out += &format!("[{}]", first);
+        }
+        for &i in spec.iter().skip(1) {
+            id = btf.resolve(id);
+            match btf.get(id).map(|t| &t.kind) {
+                Some(BtfKind::Struct { members, .. } | BtfKind::Union { members, .. }) => {
+                    let Some(m) = members.get(i as usize) else {
+                        break;
+                    };
+                    out += &format!(".{}", m.name);
+                    id = m.type_id;
+                }
+                Some(BtfKind::Array { elem_type, .. }) => {
+                    out += &format!("[{}]", i);
+                    id = *elem_type;
+                }
+                Some(BtfKind::Enum { values, .. } | BtfKind::Enum64 { values, .. }) => {
+                    if let Some((name, _)) = values.get(i as usize) {
+                        out += &format!("::{}", name);
+                    }
+                    break;
+                }
+                _ => break,
+            }
+        }
+        out
+    }
+}
+
+/// Splits one `.BTF.ext` info subsection into `(section name, record)` pairs. The
+/// subsection starts with the record size followed by per section blocks of records.
+fn ext_records<'a>(
+    data: &'a [u8],
+    big_endian: bool,
+    btf: &Btf,
+    min_size: usize,
+) -> Result<Vec<(String, Reader<'a>)>, Box<dyn Error>> {
+    if data.is_empty() {
+        return Ok(Vec::new());
+    }
+    let truncated = || "truncated .BTF.ext info";
+    let mut r = Reader::new(data, 0, big_endian);
+    let rec_size = r.u32().ok_or_else(truncated)? as usize;
+    if rec_size < min_size {
+        return Err(format!("bad .BTF.ext record size {}", rec_size).into());
+    }
+    let mut out = Vec::new();
+    while !r.is_empty() {
+        let section = btf.string(r.u32().ok_or_else(truncated)?).to_string();
+        let count = r.u32().ok_or_else(truncated)?;
+        for _ in 0..count {
+            let rec = r.bytes(rec_size).ok_or_else(truncated)?;
+            out.push((section.clone(), Reader::new(rec, 0, big_endian)));
+        }
+    }
+    Ok(out)
+}
+
+impl BtfExt {
+    /// Parses a `.BTF.ext` section, resolving its strings through the matching `.BTF`
+    pub fn parse(data: &[u8], btf: &Btf) -> Result<BtfExt, Box<dyn Error>> {
+        let big_endian = btf_byte_order(data, ".BTF.ext")?;
+        let mut r = Reader::new(data, 4, big_endian);
+        let truncated = || "truncated .BTF.ext header";
+        let hdr_len = r.u32().ok_or_else(truncated)?;
+        let mut subsection = || -> Result<&[u8], Box<dyn Error>> {
+            let off = r.u32().ok_or_else(truncated)?;
+            let len = r.u32().ok_or_else(truncated)?;
+            Ok(btf_subsection(data, hdr_len as usize, off, len)
+                .ok_or(".BTF.ext subsection exceeds the data")?)
+        };
+        let func_data = subsection()?;
+        let line_data = subsection()?;
+        // CO-RE relocations were added later and extend the header
+        let core_data = if hdr_len as usize >= SIZEOF_BTF_EXT_HEADER {
+            subsection()?
+        } else {
+            &[]
+        };
+
+        let mut ext = BtfExt::default();
+        for (section, mut rec) in ext_records(func_data, big_endian, btf, 8)? {
+            ext.func_info.push(BtfFuncInfo {
+                section,
+                insn_off: rec.u32().unwrap_or_default(),
+                type_id: rec.u32().unwrap_or_default(),
+            });
+        }
+        for (section, mut rec) in ext_records(line_data, big_endian, btf, 16)? {
+            let insn_off = rec.u32().unwrap_or_default();
+            let file = btf.string(rec.u32().unwrap_or_default()).to_string();
+            let source = btf.string(rec.u32().unwrap_or_default()).to_string();
+            let line_col = rec.u32().unwrap_or_default();
+            ext.line_info.push(BtfLineInfo {
+                section,
+                insn_off,
+                file,
+                source,
+                line: line_col >> 10,
+                column: line_col & 0x3ff,
+            });
+        }
+        for (section, mut rec) in ext_records(core_data, big_endian, btf, 16)? {
+            ext.core_relos.push(CoreRelo {
+                section,
+                insn_off: rec.u32().unwrap_or_default(),
+                type_id: rec.u32().unwrap_or_default(),
+                access: btf.string(rec.u32().unwrap_or_default()).to_string(),
+                kind: rec.u32().unwrap_or_default(),
+            });
+        }
+        Ok(ext)
+    }
+}
+
+impl ElfFile {
+    /// Parses the `.BTF` section
+    pub fn btf(&self) -> Result<Btf, Box<dyn Error>> {
+        let sh = self.section_by_name(".BTF").ok_or("no .BTF section")?;
+        Btf::parse(self.section_data(sh).ok_or(".BTF lies outside the file")?)
+    }
+
+    /// Parses the `.BTF.ext` section against the `.BTF` section
+    pub fn btf_ext(&self) -> Result<BtfExt, Box<dyn Error>> {
+        let sh = self
+            .section_by_name(".BTF.ext")
+            .ok_or("no .BTF.ext section")?;
+        let data = self
+            .section_data(sh)
+            .ok_or(".BTF.ext lies outside the file")?;
+        BtfExt::parse(data, &self.btf()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_btf_types() {
+        for path in ["tests/bin/prog.bpf.o", "tests/bin/prog.bpfeb.o"] {
+            let btf = ElfFile::open(path).unwrap().btf().unwrap();
+            assert_eq!(btf.version, 1);
+            let task = btf.find("task").unwrap();
+            let BtfKind::Struct { size, members } = &btf.get(task).unwrap().kind else {
+                panic!("task is not a struct");
+            };
+            assert_eq!(*size, 16);
+            assert_eq!(members[1].name, "state");
+            assert_eq!(members[1].bit_offset, 64);
+            assert_eq!(btf.type_name(members[1].type_id), "long");
+            assert_eq!(btf.type_size(btf.find("LICENSE").unwrap()), Some(4));
+            let func = btf.find("trace_open").unwrap();
+            let BtfKind::Func { type_id, .. } = btf.get(func).unwrap().kind else {
+                panic!("trace_open is not a function");
+            };
+            assert_eq!(btf.type_name(type_id), "int (*)(struct task *)");
+        }
+    }
+
+    #[test]
+    fn test_btf_ext() {
+        for path in ["tests/bin/prog.bpf.o", "tests/bin/prog.bpfeb.o"] {
+            let elf = ElfFile::open(path).unwrap();
+            let (btf, ext) = (elf.btf().unwrap(), elf.btf_ext().unwrap());
+            let funcs: Vec<(&str, &str)> = ext
+                .func_info
+                .iter()
+                .map(|f| {
+                    (
+                        f.section.as_str(),
+                        btf.get(f.type_id).unwrap().name.as_str(),
+                    )
+                })
+                .collect();
+            assert_eq!(
+                funcs,
+                [("kprobe/do_sys_open", "trace_open"), ("xdp", "xdp_pass")]
+            );
+            let xdp = ext.line_info.iter().find(|l| l.section == "xdp").unwrap();
+            assert_eq!((xdp.insn_off, xdp.line, xdp.column), (0, 37, 2));
+            assert!(xdp.file.ends_with("prog.bpf.c"));
+            let relos: Vec<(u32, String)> = ext
+                .core_relos
+                .iter()
+                .map(|r| (r.insn_off, r.describe(&btf)))
+                .collect();
+            assert_eq!(
+                relos,
+                [
+                    (0, "struct task.state".to_string()),
+                    (8, "struct task.pid".to_string())
+                ]
+            );
+            assert_eq!(ext.core_relos[0].kind_name(), "byte_off");
+        }
+    }
+}
@@ -0,0 +1,226 @@
+use std::borrow::Cow;
+use std::error::Error;
+use std::io::Read;
+
+use crate::elf_file::ElfFile;
+use crate::{SectionFlags, SectionHeader64};
+
+pub const ELFCOMPRESS_ZLIB: u32 = 1;
+pub const ELFCOMPRESS_ZSTD: u32 = 2;
+pub const SIZEOF_CHDR32: usize = 12;
+pub const SIZEOF_CHDR64: usize = 24;
+
+/// Magic prefix of legacy GNU style `.zdebug_*` sections, followed by a big endian u64 size
+const ZDEBUG_MAGIC: &[u8; 4] = b"ZLIB";
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Chdr32 {
+    pub ch_type: u32,
+    pub ch_size: u32,
+    pub ch_addralign: u32,
+}
+
+unsafe impl plain::Plain for Chdr32 {}
+
+impl Chdr32 {
+    fn fix_chdr(ch: &mut Chdr32, bit: u8) -> &mut Chdr32 {
+        if bit == 2 {
+            ch.ch_type = ch.ch_type.to_be();
+            ch.ch_size = ch.ch_size.to_be();
+            ch.ch_addralign = ch.ch_addralign.to_be();
+        }
+        ch
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Chdr64 {
+    pub ch_type: u32,
+    pub ch_reserved: u32,
+    pub ch_size: u64,
+    pub ch_addralign: u64,
+}
+
+unsafe impl plain::Plain for Chdr64 {}
+
+impl Chdr64 {
+    fn fix_chdr(ch: &mut Chdr64, bit: u8) -> &mut Chdr64 {
+        if bit == 2 {
+            ch.ch_type = ch.ch_type.to_be();
+            ch.ch_size = ch.ch_size.to_be();
+            ch.ch_addralign = ch.ch_addralign.to_be();
+        }
+        ch
+    }
+}
+
+impl From<Chdr32> for Chdr64 {
+    fn from(c: Chdr32) -> Chdr64 {
+        Chdr64 {
+            ch_type: c.ch_type,
+            ch_reserved: 0,
+            ch_size: c.ch_size as u64,
+            ch_addralign: c.ch_addralign as u64,
+        }
+    }
+}
+
+impl ElfFile {
+    /// Returns the compression header of an `SHF_COMPRESSED` section
+    pub fn compression_header(&self, sh: &SectionHeader64) -> Option<Chdr64> {
+        if !SectionFlags::from(sh.sh_flags).contains(SectionFlags::COMPRESSED) {
+            return None;
+        }
+        let data = self.section_data(sh)?;
+        let bit = self.header.e_ident[0x5];
+        if self.is_64() {
+            let mut ch = Chdr64::default();
+            plain::copy_from_bytes(&mut ch, data).ok()?;
+            Some(*Chdr64::fix_chdr(&mut ch, bit))
+        } else {
+            let mut ch = Chdr32::default();
+            plain::copy_from_bytes(&mut ch, data).ok()?;
+            Some(Chdr64::from(*Chdr32::fix_chdr(&mut ch, bit)))
+        }
+    }
+
+    /// Returns the contents of a section, decompressing it if needed.
+    ///
+    /// Handles `SHF_COMPRESSED` sections using zlib or zstd as well as legacy `.zdebug_*`
+    /// sections. Uncompressed sections are returned borrowed from the image.
+    pub fn section_data_decompressed(
+        &self,
+        sh: &SectionHeader64,
+    ) -> Result<Cow<'_, [u8]>, Box<dyn Error>> {
+        let data = self
+            .section_data(sh)
+            .ok_or("section data lies outside the file")?;
+
+        if let Some(ch) = self.compression_header(sh) {
+            let hdr_size = if self.is_64() {
+                SIZEOF_CHDR64
+            } else {
+                SIZEOF_CHDR32
+            };
+            let payload = &data[hdr_size..];
+            let out = match ch.ch_type {
+                ELFCOMPRESS_ZLIB => inflate_zlib(payload, ch.ch_size)?,
+                ELFCOMPRESS_ZSTD => inflate_zstd(payload, ch.ch_size)?,
+                t => return Err(format!("unknown compression type {}", t).into()),
+            };
+            return Ok(Cow::Owned(out));
+        }
+
+        if self.section_name(sh).starts_with(".zdebug") && data.starts_with(ZDEBUG_MAGIC) {
+            let size: [u8; 8] = data
+                .get(4..12)
+                .ok_or("truncated .zdebug header")?
+                .try_into()?;
+            let out = inflate_zlib(&data[12..], u64::from_be_bytes(size))?;
+            return Ok(Cow::Owned(out));
+        }
+
+        Ok(Cow::Borrowed(data))
+    }
+
+    /// Returns the decompressed contents of a debug section, looking up both the
+    /// `.debug_*` name and its legacy `.zdebug_*` counterpart
+    pub fn debug_section_data(&self, name: &str) -> Option<Cow<'_, [u8]>> {
+        let sh = self.section_by_name(name).or_else(|| {
+            let legacy = format!(".z{}", name.strip_prefix('.')?);
+            self.section_by_name(&legacy)
+        })?;
+        self.section_data_decompressed(sh).ok()
+    }
+}
+
+fn inflate_zlib(payload: &[u8], size: u64) -> Result<Vec<u8>, Box<dyn Error>> {
+    let size = usize::try_from(size)?;
+    let out = miniz_oxide::inflate::decompress_to_vec_zlib_with_limit(payload, size)
+        .map_err(|e| format!("zlib decompression failed: {}", e))?;
+    check_size(out, size)
+}
+
+fn inflate_zstd(payload: &[u8], size: u64) -> Result<Vec<u8>, Box<dyn Error>> {
+    let size = usize::try_from(size)?;
+    let decoder = ruzstd::decoding::StreamingDecoder::new(payload)
+        .map_err(|e| format!("zstd decompression failed: {}", e))?;
+    let mut out = Vec::new();
+    decoder
+        .take(size as u64 + 1)
+        .read_to_end(&mut out)
+        .map_err(|e| format!("zstd decompression failed: {}", e))?;
+    check_size(out, size)
+}
+
+fn check_size(out: Vec<u8>, size: usize) -> Result<Vec<u8>, Box<dyn Error>> {
+    if out.len() != size {
+        return Err(format!(
+            "decompressed size {:#x} does not match the expected {:#x}",
+            out.len(),
+            size
+        )
+        .into());
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn debug_sections(elf: &ElfFile) -> Vec<(String, Vec<u8>)> {
+        [".debug_info", ".debug_abbrev", ".debug_line", ".debug_str"]
+            .iter()
+            .map(|n| {
+                (
+                    n.to_string(),
+                    elf.debug_section_data(n).unwrap().into_owned(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_decompress_debug_sections() {
+        let plain = ElfFile::open("tests/bin/debug.o").unwrap();
+        let expected = debug_sections(&plain);
+        for path in [
+            "tests/bin/debug.zlib.o",
+            "tests/bin/debug.zstd.o",
+            "tests/bin/debug.zdebug.o",
+        ] {
+            let elf = ElfFile::open(path).unwrap();
+            assert_eq!(debug_sections(&elf), expected, "{}", path);
+        }
+    }
+
+    #[test]
+    fn test_compression_header() {
+        let elf = ElfFile::open("tests/bin/debug.zstd.o").unwrap();
+        let sh = elf.section_by_name(".debug_info").unwrap();
+        let ch = elf.compression_header(sh).unwrap();
+        assert_eq!(ch.ch_type, ELFCOMPRESS_ZSTD);
+        let plain = ElfFile::open("tests/bin/debug.o").unwrap();
+        assert_eq!(
+            ch.ch_size,
+            plain.section_by_name(".debug_info").unwrap().sh_size
+        );
+        assert!(plain
+            .compression_header(plain.section_by_name(".debug_info").unwrap())
+            .is_none());
+    }
+
+    #[test]
+    fn test_corrupted_compressed_section() {
+        let mut data = std::fs::read("tests/bin/debug.zlib.o").unwrap();
+        let elf = ElfFile::parse(data.clone()).unwrap();
+        let sh = *elf.section_by_name(".debug_info").unwrap();
+        let start = sh.sh_offset as usize + SIZEOF_CHDR64;
+        data[start..start + 8].fill(0xff);
+        let elf = ElfFile::parse(data).unwrap();
+        assert!(elf.section_data_decompressed(&sh).is_err());
+    }
+}
@@ -0,0 +1,378 @@
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::dwarf::Addr2Line;
+use crate::elf_file::ElfFile;
+use crate::symbols::Symbol;
+
+/// Default global directory for separate debug files
+pub const DEBUG_DIR: &str = "/usr/lib/debug";
+
+/// The contents of `.gnu_debuglink`: file name and CRC32 of the separate debug file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DebugLink {
+    pub name: String,
+    pub crc: u32,
+}
+
+/// The contents of `.gnu_debugaltlink`: the supplementary file shared by several debug
+/// files (as created by `dwz`) and its build-id
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DebugAltLink {
+    pub name: String,
+    pub build_id: Vec<u8>,
+}
+
+/// How a separate debug file was found
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DebugLookup {
+    /// `<debug dir>/.build-id/xx/yyyy.debug`
+    BuildId,
+    /// `.gnu_debuglink` next to the binary or below a debug directory
+    DebugLink,
+    /// `<debuginfod dir>/<build-id>/debuginfo`
+    Debuginfod,
+    /// `.gnu_debugaltlink` path of a supplementary file
+    AltLink,
+}
+
+/// A separate debug file that matched the binary it was searched for
+#[derive(Debug, Clone)]
+pub struct DebugFile {
+    pub path: PathBuf,
+    pub elf: ElfFile,
+    pub found_by: DebugLookup,
+}
+
+/// Where to look for separate debug files.
+///
+/// Build-id paths are searched first, then the `.gnu_debuglink` locations used by GDB and
+/// finally the debuginfod style trees. Candidates are only accepted if their build-id or
+/// CRC matches.
+#[derive(Debug, Clone)]
+pub struct DebugSearch {
+    /// Global debug directories, [`DEBUG_DIR`] by default
+    pub debug_dirs: Vec<PathBuf>,
+    /// Local trees laid out like a debuginfod client cache
+    pub debuginfod_dirs: Vec<PathBuf>,
+}
+
+impl Default for DebugSearch {
+    fn default() -> DebugSearch {
+        DebugSearch {
+            debug_dirs: vec![PathBuf::from(DEBUG_DIR)],
+            debuginfod_dirs: Vec::new(),
+        }
+    }
+}
+
+const fn crc32_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                crc >> 1 ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+static CRC32_TABLE: [u32; 256] = crc32_table();
+
+/// The CRC-32 (IEEE 802.3) `.gnu_debuglink` stores for the debug file
+pub fn gnu_debuglink_crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0u32, |crc, &b| {
+        CRC32_TABLE[((crc ^ b as u32) & 0xff) as usize] ^ crc >> 8
+    })
+}
+
+/// Returns the lowercase hex spelling of a build-id
+pub fn build_id_hex(build_id: &[u8]) -> String {
+    build_id.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn section_bytes<'a>(elf: &'a ElfFile, name: &str) -> Option<&'a [u8]> {
+    elf.section_by_name(name)
+        .and_then(|sh| elf.section_data(sh))
+}
+
+impl ElfFile {
+    /// Parses `.gnu_debuglink`: a NUL terminated file name padded to 4 bytes and the CRC
+    pub fn debuglink(&self) -> Option<DebugLink> {
+        let data = section_bytes(self, ".gnu_debuglink")?;
+        let len = data.iter().position(|&b| b == 0)?;
+        let crc_at = (len + 1 + 3) & !3;
+        Some(DebugLink {
+            name: String::from_utf8_lossy(&data[..len]).into_owned(),
+            crc: self.read_u32(data, crc_at)?,
+        })
+    }
+
+    /// Parses `.gnu_debugaltlink`: a NUL terminated file name followed by the build-id
+    pub fn debugaltlink(&self) -> Option<DebugAltLink> {
+        let data = section_bytes(self, ".gnu_debugaltlink")?;
+        let len = data.iter().position(|&b| b == 0)?;
+        Some(DebugAltLink {
+            name: String::from_utf8_lossy(&data[..len]).into_owned(),
+            build_id: data[len + 1..].to_vec(),
+        })
+    }
+}
+
+impl DebugSearch {
+    /// Finds the separate debug file of `elf`, which was read from `path`
+    pub fn find_debug_file(&self, elf: &ElfFile, path: &Path) -> Option<DebugFile> {
+        let build_id = elf.build_id().filter(|id| !id.is_empty());
+        let matches_id = |candidate: &ElfFile| candidate.build_id() == build_id;
+        let own_path = fs::canonicalize(path).ok();
+
+        if let Some(id) = &build_id {
+            let found = self
+                .build_id_paths(id)
+                .into_iter()
+                .find_map(|p| load_debug_file(&p, DebugLookup::BuildId, &matches_id));
+            if found.is_some() {
+                return found;
+            }
+        }
+
+        if let Some(link) = elf.debuglink() {
+            let dir = path.parent().unwrap_or(Path::new(""));
+            let abs_dir = fs::canonicalize(dir).unwrap_or_else(|_| dir.to_path_buf());
+            let mut candidates = vec![dir.join(&link.name), dir.join(".debug").join(&link.name)];
+            for debug_dir in &self.debug_dirs {
+                let relative = abs_dir.strip_prefix("/").unwrap_or(&abs_dir);
+                candidates.push(debug_dir.join(relative).join(&link.name));
+            }
+            let found = candidates
+                .into_iter()
+                // The binary itself may be named like its debug link
+                .filter(|p| fs::canonicalize(p).ok() != own_path)
+                .find_map(|p| {
+                    let data = fs::read(&p).ok()?;
+                    if gnu_debuglink_crc32(&data) != link.crc {
+                        return None;
+                    }
+                    Some(DebugFile {
+                        elf: ElfFile::parse(data).ok()?,
+                        path: p,
+                        found_by: DebugLookup::DebugLink,
+                    })
+                });
+            if found.is_some() {
+                return found;
+            }
+        }
+
+        self.debuginfod_paths(build_id.as_deref()?)
+            .into_iter()
+            .find_map(|p| load_debug_file(&p, DebugLookup::Debuginfod, &matches_id))
+    }
+
+    /// Finds the supplementary file referenced by the `.gnu_debugaltlink` of the debug
+    /// file `debug`, which was read from `path`
+    pub fn find_alt_file(&self, debug: &ElfFile, path: &Path) -> Option<DebugFile> {
+        let link = debug.debugaltlink()?;
+        let matches_id =
+            |candidate: &ElfFile| candidate.build_id().as_deref() == Some(&link.build_id[..]);
+        let dir = path.parent().unwrap_or(Path::new(""));
+        // Relative links are relative to the directory of the debug file
+        let linked = dir.join(&link.name);
+        load_debug_file(&linked, DebugLookup::AltLink, &matches_id)
+            .or_else(|| {
+                self.build_id_paths(&link.build_id)
+                    .into_iter()
+                    .find_map(|p| load_debug_file(&p, DebugLookup::BuildId, &matches_id))
+            })
+            .or_else(|| {
+                self.debuginfod_paths(&link.build_id)
+                    .into_iter()
+                    .find_map(|p| load_debug_file(&p, DebugLookup::Debuginfod, &matches_id))
+            })
+    }
+
+    fn build_id_paths(&self, build_id: &[u8]) -> Vec<PathBuf> {
+        let hex = build_id_hex(build_id);
+        if hex.len() < 3 {
+            return Vec::new();
+        }
+        self.debug_dirs
+            .iter()
+            .map(|d| {
+                d.join(".build-id")
+                    .join(&hex[..2])
+                    .join(format!("{}.debug", &hex[2..]))
+            })
+            .collect()
+    }
+
+    fn debuginfod_paths(&self, build_id: &[u8]) -> Vec<PathBuf> {
+        let hex = build_id_hex(build_id);
+        self.debuginfod_dirs
+            .iter()
+            .map(|d| d.join(&hex).join("debuginfo"))
+            .collect()
+    }
+}
+
+fn load_debug_file(
+    path: &Path,
+    found_by: DebugLookup,
+    accept: &dyn Fn(&ElfFile) -> bool,
+) -> Option<DebugFile> {
+    let elf = ElfFile::open(path).ok()?;
+    accept(&elf).then(|| DebugFile {
+        path: path.to_path_buf(),
+        elf,
+        found_by,
+    })
+}
+
+/// A binary together with its separate debug file and the supplementary file of that,
+/// answering symbol and source queries from whichever file has the information
+#[derive(Debug, Clone)]
+pub struct DebugElf {
+    pub elf: ElfFile,
+    pub debug: Option<DebugFile>,
+    pub alt: Option<DebugFile>,
+}
+
+impl DebugElf {
+    /// Opens the binary at `path` and looks up its debug files
+    pub fn open<P: AsRef<Path>>(path: P, search: &DebugSearch) -> Result<DebugElf, Box<dyn Error>> {
+        let path = path.as_ref();
+        let elf = ElfFile::open(path)?;
+        let debug = search.find_debug_file(&elf, path);
+        // Binaries that were never split may still refer to a dwz file themselves
+        let alt = match &debug {
+            Some(debug) => search.find_alt_file(&debug.elf, &debug.path),
+            None => search.find_alt_file(&elf, path),
+        };
+        Ok(DebugElf { elf, debug, alt })
+    }
+
+    /// Returns the `.symtab` of the binary, or of the debug file if the binary is stripped
+    pub fn symtab(&self) -> Vec<Symbol> {
+        let symbols = self.elf.symtab();
+        match &self.debug {
+            Some(debug) if symbols.is_empty() => debug.elf.symtab(),
+            _ => symbols,
+        }
+    }
+
+    /// Builds an address to source lookup from the merged debug information
+    pub fn addr2line(&self) -> Addr2Line {
+        Addr2Line::with_debug_files(
+            &self.elf,
+            self.debug.as_ref().map(|d| &d.elf),
+            self.alt.as_ref().map(|d| &d.elf),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn no_global_dirs() -> DebugSearch {
+        DebugSearch {
+            debug_dirs: Vec::new(),
+            debuginfod_dirs: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_debuglink() {
+        let elf = ElfFile::open("tests/bin/split").unwrap();
+        let link = elf.debuglink().unwrap();
+        assert_eq!(link.name, "split.debug");
+        let debug = std::fs::read("tests/bin/split.debug").unwrap();
+        assert_eq!(gnu_debuglink_crc32(&debug), link.crc);
+        assert_eq!(gnu_debuglink_crc32(b"123456789"), 0xcbf4_3926);
+
+        let split = DebugElf::open("tests/bin/split", &no_global_dirs()).unwrap();
+        let found = split.debug.as_ref().unwrap();
+        assert_eq!(found.found_by, DebugLookup::DebugLink);
+        assert_eq!(found.elf.build_id(), elf.build_id());
+        assert!(elf.symtab().is_empty());
+        let compute = split
+            .symtab()
+            .into_iter()
+            .find(|s| s.name == "compute")
+            .unwrap();
+        let frames = split.addr2line().find_frames(compute.value);
+        assert_eq!(frames[0].function.as_deref(), Some("compute"));
+        assert!(frames[0].file.as_deref().unwrap().ends_with("split.c"));
+        assert_eq!(frames[0].line, 7);
+    }
+
+    #[test]
+    fn test_build_id_lookup() {
+        let elf = ElfFile::open("tests/bin/split").unwrap();
+        let hex = build_id_hex(&elf.build_id().unwrap());
+        let root = std::env::temp_dir().join(format!("elf_loader_debug_{}", std::process::id()));
+        let bin_dir = root.join("bin");
+        let id_dir = root.join("debug/.build-id").join(&hex[..2]);
+        let debuginfod_dir = root.join("debuginfod").join(&hex);
+        for dir in [&bin_dir, &id_dir, &debuginfod_dir] {
+            std::fs::create_dir_all(dir).unwrap();
+        }
+        // A debug link candidate with the wrong CRC must be ignored
+        std::fs::copy("tests/bin/split", bin_dir.join("split")).unwrap();
+        std::fs::copy("tests/bin/split", bin_dir.join("split.debug")).unwrap();
+        let binary = bin_dir.join("split");
+
+        let mut search = no_global_dirs();
+        assert!(search.find_debug_file(&elf, &binary).is_none());
+        search.debuginfod_dirs.push(root.join("debuginfod"));
+        std::fs::copy("tests/bin/split.debug", debuginfod_dir.join("debuginfo")).unwrap();
+        let found = search.find_debug_file(&elf, &binary).unwrap();
+        assert_eq!(found.found_by, DebugLookup::Debuginfod);
+
+        search.debug_dirs.push(root.join("debug"));
+        let debug_path = id_dir.join(format!("{}.debug", &hex[2..]));
+        std::fs::copy("tests/bin/split.debug", &debug_path).unwrap();
+        let found = search.find_debug_file(&elf, &binary).unwrap();
+        assert_eq!(
+            (found.found_by, found.path),
+            (DebugLookup::BuildId, debug_path)
+        );
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_debugaltlink() {
+        let split = DebugElf::open("tests/bin/split", &no_global_dirs()).unwrap();
+        let link = split.debug.as_ref().unwrap().elf.debugaltlink().unwrap();
+        assert_eq!(link.name, "split.alt.debug");
+        let alt = split.alt.as_ref().unwrap();
+        assert_eq!(alt.found_by, DebugLookup::AltLink);
+        assert_eq!(alt.elf.build_id(), Some(link.build_id));
+
+        // The supplementary string table spells the function name in upper case, without it
+        // the name falls back to the symbol table
+        let square = split
+            .symtab()
+            .into_iter()
+            .find(|s| s.name == "square")
+            .unwrap();
+        let a2l =
+            Addr2Line::with_debug_files(&split.elf, split.debug.as_ref().map(|d| &d.elf), None);
+        let frames = a2l.find_frames(square.value);
+        assert_eq!(frames[0].function.as_deref(), Some("square"));
+        assert_eq!(frames[0].line, 3);
+        let frames = split.addr2line().find_frames(square.value);
+        assert_eq!(frames[0].function.as_deref(), Some("SQUARE"));
+        assert_eq!(frames[0].line, 3);
+    }
+}
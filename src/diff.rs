@@ -0,0 +1,600 @@
+use core::fmt;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+use std::{error::Error, path::Path};
+
+use crate::debug_file::{build_id_hex, gnu_debuglink_crc32};
+use crate::elf_file::ElfFile;
+use crate::elf_types::{SegmentType, SHT_NOBITS};
+use crate::elf_utils::sh_type_to_str;
+use crate::symbols::{Symbol, STT_FILE, STT_SECTION};
+use crate::{ElfHeader64, ProgramHeader64, SectionHeader64};
+
+/// A header field whose value differs between the two files
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldChange {
+    pub field: &'static str,
+    pub old: u64,
+    pub new: u64,
+}
+
+/// The compared properties of a section
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SectionInfo {
+    pub sh_type: u32,
+    pub flags: u64,
+    pub addr: u64,
+    pub size: u64,
+    /// CRC32 of the contents, `None` for `SHT_NOBITS` and truncated sections
+    pub crc32: Option<u32>,
+}
+
+/// A section matched by name. `old` or `new` is `None` if it was added or removed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SectionDiff {
+    pub name: String,
+    pub old: Option<SectionInfo>,
+    pub new: Option<SectionInfo>,
+}
+
+impl SectionDiff {
+    pub fn is_changed(&self) -> bool {
+        self.old != self.new
+    }
+
+    /// Returns the growth in bytes, counting a missing section as empty
+    pub fn size_delta(&self) -> i64 {
+        let size = |info: &Option<SectionInfo>| info.map_or(0, |i| i.size) as i64;
+        size(&self.new) - size(&self.old)
+    }
+}
+
+/// The compared properties of a segment
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SegmentInfo {
+    pub flags: u32,
+    pub vaddr: u64,
+    pub paddr: u64,
+    pub filesz: u64,
+    pub memsz: u64,
+    pub align: u64,
+}
+
+/// A segment matched by its type and position among the segments of that type
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SegmentDiff {
+    /// E.g. `PT_LOAD[1]` for the second `PT_LOAD` segment
+    pub label: String,
+    pub old: Option<SegmentInfo>,
+    pub new: Option<SegmentInfo>,
+}
+
+/// A defined symbol that was added, removed or resized
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolDiff {
+    pub name: String,
+    pub old_size: Option<u64>,
+    pub new_size: Option<u64>,
+}
+
+/// Structural differences between two ELF files, see [`ElfFile::diff`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ElfDiff {
+    pub header: Vec<FieldChange>,
+    /// Segments that differ
+    pub segments: Vec<SegmentDiff>,
+    /// All sections, changed or not, so the content hashes of both files are available
+    pub sections: Vec<SectionDiff>,
+    pub symbols: Vec<SymbolDiff>,
+    pub needed_added: Vec<String>,
+    pub needed_removed: Vec<String>,
+    pub old_build_id: Option<Vec<u8>>,
+    pub new_build_id: Option<Vec<u8>>,
+}
+
+/// Attempts to parse the two ELF binaries at the given paths and compares them.
+/// The **caller** is responsible for handling the return value properly.
+pub fn diff<P: AsRef<Path>>(old_path: P, new_path: P) -> Result<ElfDiff, Box<dyn Error>> {
+    Ok(ElfFile::open(old_path)?.diff(&ElfFile::open(new_path)?))
+}
+
+impl ElfDiff {
+    pub fn is_empty(&self) -> bool {
+        self.header.is_empty()
+            && self.segments.is_empty()
+            && self.changed_sections().next().is_none()
+            && self.symbols.is_empty()
+            && self.needed_added.is_empty()
+            && self.needed_removed.is_empty()
+            && !self.build_id_changed()
+    }
+
+    pub fn build_id_changed(&self) -> bool {
+        self.old_build_id != self.new_build_id
+    }
+
+    pub fn changed_sections(&self) -> impl Iterator<Item = &SectionDiff> {
+        self.sections.iter().filter(|s| s.is_changed())
+    }
+
+    /// Serializes the diff as a JSON object
+    pub fn to_json(&self) -> String {
+        let mut s = String::from("{\"header\":[");
+        for (i, c) in self.header.iter().enumerate() {
+            let _ = write!(
+                s,
+                "{}{{\"field\":{},\"old\":{},\"new\":{}}}",
+                comma(i),
+                json_str(c.field),
+                c.old,
+                c.new
+            );
+        }
+        s.push_str("],\"segments\":[");
+        for (i, d) in self.segments.iter().enumerate() {
+            let info = |info: &Option<SegmentInfo>| {
+                info.map_or("null".to_string(), |p| {
+                    format!(
+                        "{{\"flags\":{},\"vaddr\":{},\"paddr\":{},\"filesz\":{},\"memsz\":{},\"align\":{}}}",
+                        p.flags, p.vaddr, p.paddr, p.filesz, p.memsz, p.align
+                    )
+                })
+            };
+            let _ = write!(
+                s,
+                "{}{{\"segment\":{},\"old\":{},\"new\":{}}}",
+                comma(i),
+                json_str(&d.label),
+                info(&d.old),
+                info(&d.new)
+            );
+        }
+        s.push_str("],\"sections\":[");
+        for (i, d) in self.sections.iter().enumerate() {
+            let info = |info: &Option<SectionInfo>| {
+                info.map_or("null".to_string(), |sh| {
+                    format!(
+                        "{{\"type\":{},\"flags\":{},\"addr\":{},\"size\":{},\"crc32\":{}}}",
+                        sh.sh_type,
+                        sh.flags,
+                        sh.addr,
+                        sh.size,
+                        json_opt(sh.crc32)
+                    )
+                })
+            };
+            let _ = write!(
+                s,
+                "{}{{\"name\":{},\"changed\":{},\"old\":{},\"new\":{}}}",
+                comma(i),
+                json_str(&d.name),
+                d.is_changed(),
+                info(&d.old),
+                info(&d.new)
+            );
+        }
+        s.push_str("],\"symbols\":[");
+        for (i, d) in self.symbols.iter().enumerate() {
+            let _ = write!(
+                s,
+                "{}{{\"name\":{},\"old_size\":{},\"new_size\":{}}}",
+                comma(i),
+                json_str(&d.name),
+                json_opt(d.old_size),
+                json_opt(d.new_size)
+            );
+        }
+        let list = |names: &[String]| {
+            let items: Vec<String> = names.iter().map(|n| json_str(n)).collect();
+            format!("[{}]", items.join(","))
+        };
+        let build_id = |id: &Option<Vec<u8>>| {
+            id.as_ref()
+                .map_or("null".to_string(), |id| json_str(&build_id_hex(id)))
+        };
+        let _ = write!(
+            s,
+            "],\"needed\":{{\"added\":{},\"removed\":{}}},\"build_id\":{{\"old\":{},\"new\":{}}}}}",
+            list(&self.needed_added),
+            list(&self.needed_removed),
+            build_id(&self.old_build_id),
+            build_id(&self.new_build_id)
+        );
+        s
+    }
+}
+
+fn comma(i: usize) -> &'static str {
+    if i == 0 {
+        ""
+    } else {
+        ","
+    }
+}
+
+fn json_opt<T: fmt::Display>(value: Option<T>) -> String {
+    value.map_or("null".to_string(), |v| v.to_string())
+}
+
+fn json_str(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+impl ElfFile {
+    /// Compares this file (the old one) against `new`
+    pub fn diff(&self, new: &ElfFile) -> ElfDiff {
+        let (old_needed, new_needed) = (self.needed(), new.needed());
+        ElfDiff {
+            header: diff_header(&self.header, &new.header),
+            segments: matched(self.segment_infos(), new.segment_infos(), true)
+                .into_iter()
+                .filter(|(_, old, new)| old != new)
+                .map(|(label, old, new)| SegmentDiff { label, old, new })
+                .collect(),
+            sections: matched(self.section_infos(), new.section_infos(), false)
+                .into_iter()
+                .map(|(name, old, new)| SectionDiff { name, old, new })
+                .collect(),
+            symbols: diff_symbols(self.sized_symbols(), new.sized_symbols()),
+            needed_added: new_needed
+                .iter()
+                .filter(|n| !old_needed.contains(n))
+                .cloned()
+                .collect(),
+            needed_removed: old_needed
+                .iter()
+                .filter(|n| !new_needed.contains(n))
+                .cloned()
+                .collect(),
+            old_build_id: self.build_id(),
+            new_build_id: new.build_id(),
+        }
+    }
+
+    fn segment_infos(&self) -> Vec<(String, SegmentInfo)> {
+        self.program_headers
+            .iter()
+            .map(|ph: &ProgramHeader64| {
+                let name = SegmentType::from_raw(ph.p_type, self.header.e_machine).to_string();
+                let info = SegmentInfo {
+                    flags: ph.p_flags,
+                    vaddr: ph.p_vaddr,
+                    paddr: ph.p_paddr,
+                    filesz: ph.p_filesz,
+                    memsz: ph.p_memsz,
+                    align: ph.p_align,
+                };
+                (name, info)
+            })
+            .collect()
+    }
+
+    fn section_infos(&self) -> Vec<(String, SectionInfo)> {
+        self.section_headers
+            .iter()
+            .skip(1)
+            .map(|sh: &SectionHeader64| {
+                let crc32 = if sh.sh_type == SHT_NOBITS {
+                    None
+                } else {
+                    self.section_data(sh).map(gnu_debuglink_crc32)
+                };
+                let info = SectionInfo {
+                    sh_type: sh.sh_type,
+                    flags: sh.sh_flags,
+                    addr: sh.sh_addr,
+                    size: sh.sh_size,
+                    crc32,
+                };
+                (self.section_name(sh).to_string(), info)
+            })
+            .collect()
+    }
+
+    /// Returns the defined, named symbols of `.symtab`, or `.dynsym` if there is none
+    fn sized_symbols(&self) -> Vec<(String, u64)> {
+        let mut symbols: Vec<Symbol> = self.symtab();
+        if symbols.is_empty() {
+            symbols = self.dynsym();
+        }
+        symbols
+            .into_iter()
+            .filter(|s| {
+                s.is_defined()
+                    && !s.name.is_empty()
+                    && !matches!(s.sym_type(), STT_SECTION | STT_FILE)
+            })
+            .map(|s| (s.name, s.size))
+            .collect()
+    }
+}
+
+fn diff_header(old: &ElfHeader64, new: &ElfHeader64) -> Vec<FieldChange> {
+    let fields = |h: &ElfHeader64| {
+        [
+            ("EI_CLASS", h.e_ident[0x4] as u64),
+            ("EI_DATA", h.e_ident[0x5] as u64),
+            ("EI_OSABI", h.e_ident[0x7] as u64),
+            ("EI_ABIVERSION", h.e_ident[0x8] as u64),
+            ("e_type", h.e_type as u64),
+            ("e_machine", h.e_machine as u64),
+            ("e_version", h.e_version as u64),
+            ("e_entry", h.e_entry),
+            ("e_flags", h.e_flags as u64),
+            ("e_phnum", h.e_phnum as u64),
+            ("e_shnum", h.e_shnum as u64),
+        ]
+    };
+    fields(old)
+        .into_iter()
+        .zip(fields(new))
+        .filter(|((_, old), (_, new))| old != new)
+        .map(|((field, old), (_, new))| FieldChange { field, old, new })
+        .collect()
+}
+
+/// Pairs up entries by key, numbering repeated keys so that e.g. the second `.group`
+/// section of both files is compared. Keys get an `[n]` suffix if they repeat or
+/// `always_number` is set. Entries of `new` come first in their order, followed by the
+/// ones only `old` has.
+fn matched<T: Copy>(
+    old: Vec<(String, T)>,
+    new: Vec<(String, T)>,
+    always_number: bool,
+) -> Vec<(String, Option<T>, Option<T>)> {
+    let number = |entries: Vec<(String, T)>| {
+        let mut seen: HashMap<String, usize> = HashMap::new();
+        entries
+            .into_iter()
+            .map(|(key, value)| {
+                let n = seen.entry(key.clone()).or_default();
+                *n += 1;
+                ((key, *n - 1), value)
+            })
+            .collect::<Vec<_>>()
+    };
+    let old = number(old);
+    let new = number(new);
+    let repeats = |key: &str| {
+        old.iter()
+            .chain(new.iter())
+            .any(|((k, n), _)| k == key && *n > 0)
+    };
+    let label = |(key, n): &(String, usize)| {
+        if always_number || repeats(key) {
+            format!("{}[{}]", key, n)
+        } else {
+            key.clone()
+        }
+    };
+    let mut old_by_key: HashMap<(String, usize), T> = old.iter().cloned().collect();
+    let mut out: Vec<(String, Option<T>, Option<T>)> = new
+        .iter()
+        .map(|(key, value)| (label(key), old_by_key.remove(key), Some(*value)))
+        .collect();
+    out.extend(
+        old.iter()
+            .filter(|(key, _)| old_by_key.contains_key(key))
+            .map(|(key, value)| (label(key), Some(*value), None)),
+    );
+    out
+}
+
+fn diff_symbols(old: Vec<(String, u64)>, new: Vec<(String, u64)>) -> Vec<SymbolDiff> {
+    let mut by_name: BTreeMap<String, (Option<u64>, Option<u64>)> = BTreeMap::new();
+    // Local symbols may repeat, keep the first definition of each name
+    for (name, size) in old {
+        by_name.entry(name).or_default().0.get_or_insert(size);
+    }
+    for (name, size) in new {
+        by_name.entry(name).or_default().1.get_or_insert(size);
+    }
+    by_name
+        .into_iter()
+        .filter(|(_, (old, new))| old != new)
+        .map(|(name, (old_size, new_size))| SymbolDiff {
+            name,
+            old_size,
+            new_size,
+        })
+        .collect()
+}
+
+impl fmt::Display for ElfDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "files are structurally identical");
+        }
+        if !self.header.is_empty() {
+            writeln!(f, "Header:")?;
+            for c in &self.header {
+                writeln!(f, "  ~ {}: {:#x} -> {:#x}", c.field, c.old, c.new)?;
+            }
+        }
+        if !self.segments.is_empty() {
+            writeln!(f, "Segments:")?;
+            for d in &self.segments {
+                match (&d.old, &d.new) {
+                    (None, Some(p)) => writeln!(
+                        f,
+                        "  + {} vaddr {:#x} filesz {:#x} memsz {:#x}",
+                        d.label, p.vaddr, p.filesz, p.memsz
+                    )?,
+                    (Some(p), None) => writeln!(
+                        f,
+                        "  - {} vaddr {:#x} filesz {:#x} memsz {:#x}",
+                        d.label, p.vaddr, p.filesz, p.memsz
+                    )?,
+                    (Some(o), Some(n)) => {
+                        write!(f, "  ~ {}", d.label)?;
+                        for (field, old, new) in [
+                            ("vaddr", o.vaddr, n.vaddr),
+                            ("paddr", o.paddr, n.paddr),
+                            ("filesz", o.filesz, n.filesz),
+                            ("memsz", o.memsz, n.memsz),
+                            ("flags", o.flags as u64, n.flags as u64),
+                            ("align", o.align, n.align),
+                        ] {
+                            if old != new {
+                                write!(f, " {} {:#x} -> {:#x}", field, old, new)?;
+                            }
+                        }
+                        writeln!(f)?;
+                    }
+                    (None, None) => {}
+                }
+            }
+        }
+        if self.changed_sections().next().is_some() {
+            writeln!(f, "Sections:")?;
+            for d in self.changed_sections() {
+                match (&d.old, &d.new) {
+                    (None, Some(sh)) => writeln!(f, "  + {} ({} bytes)", d.name, sh.size)?,
+                    (Some(sh), None) => writeln!(f, "  - {} ({} bytes)", d.name, sh.size)?,
+                    (Some(o), Some(n)) => {
+                        write!(f, "  ~ {}", d.name)?;
+                        if o.size != n.size {
+                            write!(f, " size {} -> {} ({:+})", o.size, n.size, d.size_delta())?;
+                        }
+                        if o.sh_type != n.sh_type {
+                            write!(
+                                f,
+                                " type {} -> {}",
+                                sh_type_to_str(o.sh_type, 0),
+                                sh_type_to_str(n.sh_type, 0)
+                            )?;
+                        }
+                        if o.addr != n.addr {
+                            write!(f, " addr {:#x} -> {:#x}", o.addr, n.addr)?;
+                        }
+                        if o.flags != n.flags {
+                            write!(f, " flags {:#x} -> {:#x}", o.flags, n.flags)?;
+                        }
+                        if o.crc32 != n.crc32 {
+                            write!(f, " contents changed")?;
+                        }
+                        writeln!(f)?;
+                    }
+                    (None, None) => {}
+                }
+            }
+        }
+        if !self.symbols.is_empty() {
+            writeln!(f, "Symbols:")?;
+            for d in &self.symbols {
+                match (d.old_size, d.new_size) {
+                    (None, Some(size)) => writeln!(f, "  + {} ({} bytes)", d.name, size)?,
+                    (Some(size), None) => writeln!(f, "  - {} ({} bytes)", d.name, size)?,
+                    (Some(old), Some(new)) => writeln!(
+                        f,
+                        "  ~ {} {} -> {} ({:+})",
+                        d.name,
+                        old,
+                        new,
+                        new as i64 - old as i64
+                    )?,
+                    (None, None) => {}
+                }
+            }
+        }
+        if !self.needed_added.is_empty() || !self.needed_removed.is_empty() {
+            writeln!(f, "Needed libraries:")?;
+            for name in &self.needed_added {
+                writeln!(f, "  + {}", name)?;
+            }
+            for name in &self.needed_removed {
+                writeln!(f, "  - {}", name)?;
+            }
+        }
+        if self.build_id_changed() {
+            let hex = |id: &Option<Vec<u8>>| id.as_deref().map_or("none".to_string(), build_id_hex);
+            writeln!(
+                f,
+                "Build ID: {} -> {}",
+                hex(&self.old_build_id),
+                hex(&self.new_build_id)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identical() {
+        let d = diff("tests/bin/firmware.arm", "tests/bin/firmware.arm").unwrap();
+        assert!(d.is_empty());
+        assert!(d
+            .sections
+            .iter()
+            .all(|s| s.old.is_some() && s.new.is_some()));
+        assert_eq!(d.to_string(), "files are structurally identical\n");
+    }
+
+    #[test]
+    fn test_changes() {
+        let old = ElfFile::open("tests/bin/firmware.arm").unwrap();
+        // Grow .rodata, patch a byte of .text and move the entry point
+        let mut data = old.data().to_vec();
+        let rodata = 3;
+        let size_at = old.header.e_shoff as usize + rodata * 40 + 20;
+        data[size_at] += 4;
+        data[old.section_headers[2].sh_offset as usize] ^= 0xff;
+        data[0x18] += 2;
+        let new = ElfFile::parse(data).unwrap();
+
+        let d = old.diff(&new);
+        assert_eq!(
+            d.header,
+            [FieldChange {
+                field: "e_entry",
+                old: 0x0800_0011,
+                new: 0x0800_0013
+            }]
+        );
+        let changed: Vec<&str> = d.changed_sections().map(|s| s.name.as_str()).collect();
+        assert_eq!(changed, [".text", ".rodata"]);
+        assert_eq!(d.sections[2].size_delta(), 4);
+        assert!(d.segments.is_empty() && d.symbols.is_empty());
+        let text = d.to_string();
+        assert!(text.contains("  ~ .text contents changed\n"), "{}", text);
+        assert!(text.contains("  ~ .rodata size 16 -> 20 (+4) contents changed\n"));
+        let json = d.to_json();
+        assert!(json.starts_with(
+            "{\"header\":[{\"field\":\"e_entry\",\"old\":134217745,\"new\":134217747}]"
+        ));
+        assert!(json.ends_with(
+            "\"needed\":{\"added\":[],\"removed\":[]},\"build_id\":{\"old\":null,\"new\":null}}"
+        ));
+
+        let d = diff("tests/bin/split", "/bin/ls").unwrap();
+        assert_eq!(d.needed_added, ["libselinux.so.1"]);
+        assert!(d.needed_removed.is_empty());
+        assert!(d.build_id_changed());
+        assert!(d
+            .symbols
+            .iter()
+            .any(|s| s.name == "_obstack_begin" && s.old_size.is_none()));
+        let labels: Vec<&str> = d.segments.iter().map(|s| s.label.as_str()).collect();
+        assert!(labels.contains(&"PT_LOAD[0]"));
+        assert_eq!(json_str("a\"b\\\n\u{1}"), "\"a\\\"b\\\\\\n\\u0001\"");
+    }
+}
@@ -0,0 +1,1421 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::ops::Range;
+use std::path::Path;
+use std::rc::Rc;
+
+use crate::debug_file::{DebugElf, DebugSearch};
+use crate::elf_file::ElfFile;
+use crate::elf_types::Machine;
+use crate::symbols::STT_FUNC;
+
+const DW_TAG_ENTRY_POINT: u64 = 0x03;
+const DW_TAG_INLINED_SUBROUTINE: u64 = 0x1d;
+const DW_TAG_SUBPROGRAM: u64 = 0x2e;
+
+const DW_AT_NAME: u64 = 0x03;
+const DW_AT_STMT_LIST: u64 = 0x10;
+const DW_AT_LOW_PC: u64 = 0x11;
+const DW_AT_HIGH_PC: u64 = 0x12;
+const DW_AT_COMP_DIR: u64 = 0x1b;
+const DW_AT_ABSTRACT_ORIGIN: u64 = 0x31;
+const DW_AT_SPECIFICATION: u64 = 0x47;
+const DW_AT_RANGES: u64 = 0x55;
+const DW_AT_CALL_COLUMN: u64 = 0x57;
+const DW_AT_CALL_FILE: u64 = 0x58;
+const DW_AT_CALL_LINE: u64 = 0x59;
+const DW_AT_LINKAGE_NAME: u64 = 0x6e;
+const DW_AT_STR_OFFSETS_BASE: u64 = 0x72;
+const DW_AT_ADDR_BASE: u64 = 0x73;
+const DW_AT_RNGLISTS_BASE: u64 = 0x74;
+const DW_AT_MIPS_LINKAGE_NAME: u64 = 0x2007;
+const DW_AT_GNU_ADDR_BASE: u64 = 0x2133;
+
+const DW_FORM_ADDR: u64 = 0x01;
+const DW_FORM_BLOCK2: u64 = 0x03;
+const DW_FORM_BLOCK4: u64 = 0x04;
+const DW_FORM_DATA2: u64 = 0x05;
+const DW_FORM_DATA4: u64 = 0x06;
+const DW_FORM_DATA8: u64 = 0x07;
+const DW_FORM_STRING: u64 = 0x08;
+const DW_FORM_BLOCK: u64 = 0x09;
+const DW_FORM_BLOCK1: u64 = 0x0a;
+const DW_FORM_DATA1: u64 = 0x0b;
+const DW_FORM_FLAG: u64 = 0x0c;
+const DW_FORM_SDATA: u64 = 0x0d;
+const DW_FORM_STRP: u64 = 0x0e;
+const DW_FORM_UDATA: u64 = 0x0f;
+const DW_FORM_REF_ADDR: u64 = 0x10;
+const DW_FORM_REF1: u64 = 0x11;
+const DW_FORM_REF2: u64 = 0x12;
+const DW_FORM_REF4: u64 = 0x13;
+const DW_FORM_REF8: u64 = 0x14;
+const DW_FORM_REF_UDATA: u64 = 0x15;
+const DW_FORM_INDIRECT: u64 = 0x16;
+const DW_FORM_SEC_OFFSET: u64 = 0x17;
+const DW_FORM_EXPRLOC: u64 = 0x18;
+const DW_FORM_FLAG_PRESENT: u64 = 0x19;
+const DW_FORM_STRX: u64 = 0x1a;
+const DW_FORM_ADDRX: u64 = 0x1b;
+const DW_FORM_REF_SUP4: u64 = 0x1c;
+const DW_FORM_STRP_SUP: u64 = 0x1d;
+const DW_FORM_DATA16: u64 = 0x1e;
+const DW_FORM_LINE_STRP: u64 = 0x1f;
+const DW_FORM_REF_SIG8: u64 = 0x20;
+const DW_FORM_IMPLICIT_CONST: u64 = 0x21;
+const DW_FORM_LOCLISTX: u64 = 0x22;
+const DW_FORM_RNGLISTX: u64 = 0x23;
+const DW_FORM_REF_SUP8: u64 = 0x24;
+const DW_FORM_STRX1: u64 = 0x25;
+const DW_FORM_STRX2: u64 = 0x26;
+const DW_FORM_STRX3: u64 = 0x27;
+const DW_FORM_STRX4: u64 = 0x28;
+const DW_FORM_ADDRX1: u64 = 0x29;
+const DW_FORM_ADDRX2: u64 = 0x2a;
+const DW_FORM_ADDRX3: u64 = 0x2b;
+const DW_FORM_ADDRX4: u64 = 0x2c;
+const DW_FORM_GNU_ADDR_INDEX: u64 = 0x1f01;
+const DW_FORM_GNU_STR_INDEX: u64 = 0x1f02;
+const DW_FORM_GNU_REF_ALT: u64 = 0x1f20;
+const DW_FORM_GNU_STRP_ALT: u64 = 0x1f21;
+
+const DW_UT_COMPILE: u8 = 0x01;
+const DW_UT_PARTIAL: u8 = 0x03;
+const DW_UT_SKELETON: u8 = 0x04;
+const DW_UT_SPLIT_COMPILE: u8 = 0x05;
+
+const DW_LNCT_PATH: u64 = 0x1;
+const DW_LNCT_DIRECTORY_INDEX: u64 = 0x2;
+
+const DW_LNS_COPY: u8 = 0x01;
+const DW_LNS_ADVANCE_PC: u8 = 0x02;
+const DW_LNS_ADVANCE_LINE: u8 = 0x03;
+const DW_LNS_SET_FILE: u8 = 0x04;
+const DW_LNS_SET_COLUMN: u8 = 0x05;
+const DW_LNS_CONST_ADD_PC: u8 = 0x08;
+const DW_LNS_FIXED_ADVANCE_PC: u8 = 0x09;
+
+const DW_LNE_END_SEQUENCE: u8 = 0x01;
+const DW_LNE_SET_ADDRESS: u8 = 0x02;
+const DW_LNE_DEFINE_FILE: u8 = 0x03;
+
+const DW_RLE_END_OF_LIST: u8 = 0x00;
+const DW_RLE_BASE_ADDRESSX: u8 = 0x01;
+const DW_RLE_STARTX_ENDX: u8 = 0x02;
+const DW_RLE_STARTX_LENGTH: u8 = 0x03;
+const DW_RLE_OFFSET_PAIR: u8 = 0x04;
+const DW_RLE_BASE_ADDRESS: u8 = 0x05;
+const DW_RLE_START_END: u8 = 0x06;
+const DW_RLE_START_LENGTH: u8 = 0x07;
+
+/// Bounds checked cursor over DWARF encoded data in the byte order of the ELF file
+#[derive(Debug, Clone)]
+pub(crate) struct Reader<'a> {
+    data: &'a [u8],
+    pub(crate) pos: usize,
+    big_endian: bool,
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(data: &'a [u8], pos: usize, big_endian: bool) -> Reader<'a> {
+        Reader {
+            data,
+            pos,
+            big_endian,
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.pos >= self.data.len()
+    }
+
+    pub(crate) fn bytes(&mut self, n: usize) -> Option<&'a [u8]> {
+        let b = self.data.get(self.pos..self.pos.checked_add(n)?)?;
+        self.pos += n;
+        Some(b)
+    }
+
+    /// Consumes and returns all remaining bytes
+    pub(crate) fn rest(&mut self) -> &'a [u8] {
+        let rest = self.data.get(self.pos..).unwrap_or_default();
+        self.pos = self.data.len();
+        rest
+    }
+
+    /// Reads an unsigned integer of `n` (at most 8) bytes
+    pub(crate) fn uint(&mut self, n: usize) -> Option<u64> {
+        let b = self.bytes(n)?;
+        let fold = |acc: u64, &x: &u8| acc << 8 | x as u64;
+        Some(if self.big_endian {
+            b.iter().fold(0, fold)
+        } else {
+            b.iter().rev().fold(0, fold)
+        })
+    }
+
+    pub(crate) fn u8(&mut self) -> Option<u8> {
+        self.uint(1).map(|v| v as u8)
+    }
+
+    pub(crate) fn u16(&mut self) -> Option<u16> {
+        self.uint(2).map(|v| v as u16)
+    }
+
+    pub(crate) fn u32(&mut self) -> Option<u32> {
+        self.uint(4).map(|v| v as u32)
+    }
+
+    pub(crate) fn u64(&mut self) -> Option<u64> {
+        self.uint(8)
+    }
+
+    pub(crate) fn uleb(&mut self) -> Option<u64> {
+        let mut result = 0u64;
+        let mut shift = 0;
+        loop {
+            let b = self.u8()?;
+            if shift < 64 {
+                result |= ((b & 0x7f) as u64) << shift;
+            }
+            shift += 7;
+            if b & 0x80 == 0 {
+                return Some(result);
+            }
+        }
+    }
+
+    pub(crate) fn sleb(&mut self) -> Option<i64> {
+        let mut result = 0i64;
+        let mut shift = 0;
+        loop {
+            let b = self.u8()?;
+            if shift < 64 {
+                result |= ((b & 0x7f) as i64) << shift;
+            }
+            shift += 7;
+            if b & 0x80 == 0 {
+                if shift < 64 && b & 0x40 != 0 {
+                    result |= -1i64 << shift;
+                }
+                return Some(result);
+            }
+        }
+    }
+
+    /// Reads a NUL terminated string, returning it without the terminator
+    pub(crate) fn cstr(&mut self) -> Option<&'a [u8]> {
+        let tail = self.data.get(self.pos..)?;
+        let len = tail.iter().position(|&c| c == 0)?;
+        self.pos += len + 1;
+        Some(&tail[..len])
+    }
+
+    /// Reads a DWARF initial length, returning the length and whether the 64-bit format is used
+    pub(crate) fn initial_length(&mut self) -> Option<(u64, bool)> {
+        match self.u32()? {
+            0xffff_ffff => Some((self.u64()?, true)),
+            len => Some((len as u64, false)),
+        }
+    }
+
+    pub(crate) fn offset(&mut self, dwarf64: bool) -> Option<u64> {
+        if dwarf64 {
+            self.u64()
+        } else {
+            self.u32().map(u64::from)
+        }
+    }
+
+    /// Splits off the next `len` bytes into a reader of their own
+    pub(crate) fn split(&mut self, len: u64) -> Option<Reader<'a>> {
+        let len = usize::try_from(len).ok()?;
+        let start = self.pos;
+        self.bytes(len)?;
+        Some(Reader::new(
+            &self.data[..start + len],
+            start,
+            self.big_endian,
+        ))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Encoding {
+    version: u16,
+    address_size: u8,
+    dwarf64: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum AttrValue<'a> {
+    Addr(u64),
+    Addrx(u64),
+    Udata(u64),
+    Sdata(i64),
+    Str(&'a [u8]),
+    Strp(u64),
+    /// Offset into the `.debug_str` of the supplementary (dwz) file
+    StrpAlt(u64),
+    LineStrp(u64),
+    Strx(u64),
+    UnitRef(u64),
+    InfoRef(u64),
+    SecOffset(u64),
+    Rnglistx(u64),
+    Other,
+}
+
+impl AttrValue<'_> {
+    fn udata(self) -> Option<u64> {
+        match self {
+            AttrValue::Udata(v) | AttrValue::SecOffset(v) => Some(v),
+            AttrValue::Sdata(v) => Some(v as u64),
+            _ => None,
+        }
+    }
+}
+
+fn read_form<'a>(
+    r: &mut Reader<'a>,
+    form: u64,
+    enc: Encoding,
+    implicit_const: i64,
+) -> Option<AttrValue<'a>> {
+    use AttrValue::*;
+    let addr_size = enc.address_size as usize;
+    // The real form follows inline and may not be DW_FORM_indirect again
+    let form = match form {
+        DW_FORM_INDIRECT => Some(r.uleb()?).filter(|&f| f != DW_FORM_INDIRECT)?,
+        form => form,
+    };
+    Some(match form {
+        DW_FORM_ADDR => Addr(r.uint(addr_size)?),
+        DW_FORM_DATA1 => Udata(r.u8()? as u64),
+        DW_FORM_DATA2 => Udata(r.u16()? as u64),
+        DW_FORM_DATA4 => Udata(r.u32()? as u64),
+        DW_FORM_DATA8 => Udata(r.u64()?),
+        DW_FORM_UDATA => Udata(r.uleb()?),
+        DW_FORM_SDATA => Sdata(r.sleb()?),
+        DW_FORM_IMPLICIT_CONST => Sdata(implicit_const),
+        DW_FORM_STRING => Str(r.cstr()?),
+        DW_FORM_STRP => Strp(r.offset(enc.dwarf64)?),
+        DW_FORM_LINE_STRP => LineStrp(r.offset(enc.dwarf64)?),
+        DW_FORM_STRX | DW_FORM_GNU_STR_INDEX => Strx(r.uleb()?),
+        DW_FORM_STRX1 => Strx(r.uint(1)?),
+        DW_FORM_STRX2 => Strx(r.uint(2)?),
+        DW_FORM_STRX3 => Strx(r.uint(3)?),
+        DW_FORM_STRX4 => Strx(r.uint(4)?),
+        DW_FORM_ADDRX | DW_FORM_GNU_ADDR_INDEX => Addrx(r.uleb()?),
+        DW_FORM_ADDRX1 => Addrx(r.uint(1)?),
+        DW_FORM_ADDRX2 => Addrx(r.uint(2)?),
+        DW_FORM_ADDRX3 => Addrx(r.uint(3)?),
+        DW_FORM_ADDRX4 => Addrx(r.uint(4)?),
+        DW_FORM_REF1 => UnitRef(r.uint(1)?),
+        DW_FORM_REF2 => UnitRef(r.uint(2)?),
+        DW_FORM_REF4 => UnitRef(r.uint(4)?),
+        DW_FORM_REF8 => UnitRef(r.uint(8)?),
+        DW_FORM_REF_UDATA => UnitRef(r.uleb()?),
+        // DWARF 2 encodes DW_FORM_REF_ADDR with the size of an address
+        DW_FORM_REF_ADDR if enc.version <= 2 => InfoRef(r.uint(addr_size)?),
+        DW_FORM_REF_ADDR => InfoRef(r.offset(enc.dwarf64)?),
+        DW_FORM_SEC_OFFSET => SecOffset(r.offset(enc.dwarf64)?),
+        DW_FORM_RNGLISTX => Rnglistx(r.uleb()?),
+        DW_FORM_LOCLISTX => {
+            r.uleb()?;
+            Other
+        }
+        DW_FORM_FLAG => {
+            r.u8()?;
+            Other
+        }
+        DW_FORM_FLAG_PRESENT => Other,
+        DW_FORM_BLOCK1 => {
+            let n = r.u8()?;
+            r.bytes(n as usize)?;
+            Other
+        }
+        DW_FORM_BLOCK2 => {
+            let n = r.u16()?;
+            r.bytes(n as usize)?;
+            Other
+        }
+        DW_FORM_BLOCK4 => {
+            let n = r.u32()?;
+            r.bytes(n as usize)?;
+            Other
+        }
+        DW_FORM_BLOCK | DW_FORM_EXPRLOC => {
+            let n = r.uleb()?;
+            r.bytes(usize::try_from(n).ok()?)?;
+            Other
+        }
+        DW_FORM_DATA16 => {
+            r.bytes(16)?;
+            Other
+        }
+        DW_FORM_REF_SUP4 => {
+            r.u32()?;
+            Other
+        }
+        DW_FORM_REF_SUP8 | DW_FORM_REF_SIG8 => {
+            r.u64()?;
+            Other
+        }
+        DW_FORM_STRP_SUP | DW_FORM_GNU_STRP_ALT => StrpAlt(r.offset(enc.dwarf64)?),
+        DW_FORM_GNU_REF_ALT => {
+            r.offset(enc.dwarf64)?;
+            Other
+        }
+        _ => return None,
+    })
+}
+
+#[derive(Debug)]
+struct AttrSpec {
+    name: u64,
+    form: u64,
+    implicit_const: i64,
+}
+
+#[derive(Debug)]
+struct Abbrev {
+    tag: u64,
+    has_children: bool,
+    attrs: Vec<AttrSpec>,
+}
+
+fn parse_abbrevs(data: &[u8], offset: u64) -> Option<HashMap<u64, Abbrev>> {
+    // Abbreviations are LEB128 only, the byte order does not matter
+    let mut r = Reader::new(data, usize::try_from(offset).ok()?, false);
+    let mut abbrevs = HashMap::new();
+    loop {
+        let code = r.uleb()?;
+        if code == 0 {
+            return Some(abbrevs);
+        }
+        let tag = r.uleb()?;
+        let has_children = r.u8()? != 0;
+        let mut attrs = Vec::new();
+        loop {
+            let name = r.uleb()?;
+            let form = r.uleb()?;
+            let implicit_const = if form == DW_FORM_IMPLICIT_CONST {
+                r.sleb()?
+            } else {
+                0
+            };
+            if name == 0 && form == 0 {
+                break;
+            }
+            attrs.push(AttrSpec {
+                name,
+                form,
+                implicit_const,
+            });
+        }
+        abbrevs.insert(
+            code,
+            Abbrev {
+                tag,
+                has_children,
+                attrs,
+            },
+        );
+    }
+}
+
+/// The DWARF sections used for address lookup, decompressed where needed
+struct Sections<'a> {
+    info: Cow<'a, [u8]>,
+    abbrev: Cow<'a, [u8]>,
+    line: Cow<'a, [u8]>,
+    str: Cow<'a, [u8]>,
+    line_str: Cow<'a, [u8]>,
+    str_offsets: Cow<'a, [u8]>,
+    addr: Cow<'a, [u8]>,
+    ranges: Cow<'a, [u8]>,
+    rnglists: Cow<'a, [u8]>,
+    alt_str: Cow<'a, [u8]>,
+    big_endian: bool,
+}
+
+impl<'a> Sections<'a> {
+    fn load(elf: &'a ElfFile, alt: Option<&'a ElfFile>) -> Sections<'a> {
+        let load_from = |elf: &'a ElfFile, name: &str| -> Cow<'a, [u8]> {
+            #[cfg(feature = "compression")]
+            let data = elf.debug_section_data(name);
+            #[cfg(not(feature = "compression"))]
+            let data = elf
+                .section_by_name(name)
+                .and_then(|sh| elf.section_data(sh))
+                .map(Cow::Borrowed);
+            data.unwrap_or_default()
+        };
+        let load = |name: &str| load_from(elf, name);
+        Sections {
+            info: load(".debug_info"),
+            abbrev: load(".debug_abbrev"),
+            line: load(".debug_line"),
+            str: load(".debug_str"),
+            line_str: load(".debug_line_str"),
+            str_offsets: load(".debug_str_offsets"),
+            addr: load(".debug_addr"),
+            ranges: load(".debug_ranges"),
+            rnglists: load(".debug_rnglists"),
+            alt_str: alt
+                .map(|alt| load_from(alt, ".debug_str"))
+                .unwrap_or_default(),
+            big_endian: elf.is_big_endian(),
+        }
+    }
+
+    fn reader<'s>(&'s self, data: &'s [u8], offset: u64) -> Option<Reader<'s>> {
+        Some(Reader::new(
+            data,
+            usize::try_from(offset).ok()?,
+            self.big_endian,
+        ))
+    }
+}
+
+/// Per unit state needed to resolve indirect attribute values
+struct UnitCtx<'s, 'a> {
+    sections: &'s Sections<'a>,
+    enc: Encoding,
+    offset: u64,
+    str_offsets_base: u64,
+    addr_base: u64,
+    rnglists_base: u64,
+    base_address: u64,
+}
+
+impl UnitCtx<'_, '_> {
+    fn string(&self, v: AttrValue) -> Option<String> {
+        let s = self.sections;
+        let bytes = match v {
+            AttrValue::Str(b) => b,
+            AttrValue::Strp(off) => s.reader(&s.str, off)?.cstr()?,
+            AttrValue::StrpAlt(off) => s.reader(&s.alt_str, off)?.cstr()?,
+            AttrValue::LineStrp(off) => s.reader(&s.line_str, off)?.cstr()?,
+            AttrValue::Strx(i) => {
+                let size = if self.enc.dwarf64 { 8 } else { 4 };
+                let pos = self.str_offsets_base.checked_add(i.checked_mul(size)?)?;
+                let off = s.reader(&s.str_offsets, pos)?.offset(self.enc.dwarf64)?;
+                s.reader(&s.str, off)?.cstr()?
+            }
+            _ => return None,
+        };
+        Some(String::from_utf8_lossy(bytes).into_owned())
+    }
+
+    fn address(&self, v: AttrValue) -> Option<u64> {
+        match v {
+            AttrValue::Addr(a) => Some(a),
+            AttrValue::Addrx(i) => self.addrx(i),
+            _ => None,
+        }
+    }
+
+    fn addrx(&self, index: u64) -> Option<u64> {
+        let size = self.enc.address_size as u64;
+        let pos = self.addr_base.checked_add(index.checked_mul(size)?)?;
+        self.sections
+            .reader(&self.sections.addr, pos)?
+            .uint(size as usize)
+    }
+
+    /// Returns the reference target of an attribute as an offset into `.debug_info`
+    fn reference(&self, v: AttrValue) -> Option<u64> {
+        match v {
+            AttrValue::UnitRef(off) => self.offset.checked_add(off),
+            AttrValue::InfoRef(off) => Some(off),
+            _ => None,
+        }
+    }
+
+    /// Resolves the address ranges described by `DW_AT_LOW_PC`, `DW_AT_HIGH_PC` and `DW_AT_RANGES`
+    fn pc_ranges(
+        &self,
+        low: Option<AttrValue>,
+        high: Option<AttrValue>,
+        ranges: Option<AttrValue>,
+    ) -> Vec<Range<u64>> {
+        if let Some(ranges) = ranges {
+            return self.ranges(ranges).unwrap_or_default();
+        }
+        let Some(low) = low.and_then(|v| self.address(v)) else {
+            return Vec::new();
+        };
+        let high = match high {
+            Some(v @ (AttrValue::Addr(_) | AttrValue::Addrx(_))) => self.address(v),
+            Some(v) => v.udata().map(|len| low.wrapping_add(len)),
+            None => None,
+        };
+        match high {
+            Some(high) if high > low => std::iter::once(low..high).collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    fn ranges(&self, v: AttrValue) -> Option<Vec<Range<u64>>> {
+        if self.enc.version >= 5 {
+            let offset = match v {
+                AttrValue::Rnglistx(i) => {
+                    let size = if self.enc.dwarf64 { 8 } else { 4 };
+                    let pos = self.rnglists_base.checked_add(i.checked_mul(size)?)?;
+                    let rel = self
+                        .sections
+                        .reader(&self.sections.rnglists, pos)?
+                        .offset(self.enc.dwarf64)?;
+                    self.rnglists_base.checked_add(rel)?
+                }
+                v => v.udata()?,
+            };
+            self.rnglist(offset)
+        } else {
+            self.debug_ranges(v.udata()?)
+        }
+    }
+
+    /// Parses a DWARF 2-4 `.debug_ranges` list
+    fn debug_ranges(&self, offset: u64) -> Option<Vec<Range<u64>>> {
+        let size = self.enc.address_size as usize;
+        let max = if size == 8 { u64::MAX } else { u32::MAX as u64 };
+        let mut r = self.sections.reader(&self.sections.ranges, offset)?;
+        let mut base = self.base_address;
+        let mut out = Vec::new();
+        loop {
+            let begin = r.uint(size)?;
+            let end = r.uint(size)?;
+            match (begin, end) {
+                (0, 0) => return Some(out),
+                (b, e) if b == max => base = e,
+                (b, e) => push_range(&mut out, base.wrapping_add(b), base.wrapping_add(e)),
+            }
+        }
+    }
+
+    /// Parses a DWARF 5 `.debug_rnglists` list
+    fn rnglist(&self, offset: u64) -> Option<Vec<Range<u64>>> {
+        let size = self.enc.address_size as usize;
+        let mut r = self.sections.reader(&self.sections.rnglists, offset)?;
+        let mut base = self.base_address;
+        let mut out = Vec::new();
+        loop {
+            match r.u8()? {
+                DW_RLE_END_OF_LIST => return Some(out),
+                DW_RLE_BASE_ADDRESSX => base = self.addrx(r.uleb()?)?,
+                DW_RLE_STARTX_ENDX => {
+                    let begin = self.addrx(r.uleb()?)?;
+                    let end = self.addrx(r.uleb()?)?;
+                    push_range(&mut out, begin, end);
+                }
+                DW_RLE_STARTX_LENGTH => {
+                    let begin = self.addrx(r.uleb()?)?;
+                    let len = r.uleb()?;
+                    push_range(&mut out, begin, begin.wrapping_add(len));
+                }
+                DW_RLE_OFFSET_PAIR => {
+                    let begin = base.wrapping_add(r.uleb()?);
+                    let end = base.wrapping_add(r.uleb()?);
+                    push_range(&mut out, begin, end);
+                }
+                DW_RLE_BASE_ADDRESS => base = r.uint(size)?,
+                DW_RLE_START_END => {
+                    let begin = r.uint(size)?;
+                    let end = r.uint(size)?;
+                    push_range(&mut out, begin, end);
+                }
+                DW_RLE_START_LENGTH => {
+                    let begin = r.uint(size)?;
+                    let len = r.uleb()?;
+                    push_range(&mut out, begin, begin.wrapping_add(len));
+                }
+                _ => return None,
+            }
+        }
+    }
+}
+
+fn push_range(out: &mut Vec<Range<u64>>, begin: u64, end: u64) {
+    if begin < end {
+        out.push(begin..end);
+    }
+}
+
+fn join_path(dir: &str, name: &str) -> String {
+    if name.starts_with('/') || dir.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}/{}", dir.trim_end_matches('/'), name)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Row {
+    address: u64,
+    file: u64,
+    line: u32,
+    column: u32,
+}
+
+#[derive(Debug)]
+struct Sequence {
+    range: Range<u64>,
+    rows: Vec<Row>,
+}
+
+#[derive(Debug, Default)]
+struct LineTable {
+    /// Full paths indexed by the file numbers used in the line program
+    files: Vec<Option<String>>,
+    sequences: Vec<Sequence>,
+}
+
+impl LineTable {
+    fn file(&self, index: u64) -> Option<String> {
+        self.files.get(usize::try_from(index).ok()?)?.clone()
+    }
+
+    fn find(&self, addr: u64) -> Option<&Row> {
+        let seq = self.sequences.iter().find(|s| s.range.contains(&addr))?;
+        let i = seq.rows.partition_point(|row| row.address <= addr);
+        seq.rows.get(i.checked_sub(1)?)
+    }
+}
+
+struct FileEntry {
+    path: String,
+    dir: u64,
+}
+
+/// Parses the entry format description and entries of a DWARF 5 directory or file table
+fn read_v5_entries(r: &mut Reader, enc: Encoding, ctx: &UnitCtx) -> Option<Vec<FileEntry>> {
+    let format_count = r.u8()?;
+    let mut format = Vec::new();
+    for _ in 0..format_count {
+        format.push((r.uleb()?, r.uleb()?));
+    }
+    let count = r.uleb()?;
+    let mut entries = Vec::new();
+    for _ in 0..count {
+        let mut entry = FileEntry {
+            path: String::new(),
+            dir: 0,
+        };
+        for &(content, form) in &format {
+            let v = read_form(r, form, enc, 0)?;
+            match content {
+                DW_LNCT_PATH => entry.path = ctx.string(v).unwrap_or_default(),
+                DW_LNCT_DIRECTORY_INDEX => entry.dir = v.udata().unwrap_or_default(),
+                _ => {}
+            }
+        }
+        entries.push(entry);
+    }
+    Some(entries)
+}
+
+fn parse_line_program(ctx: &UnitCtx, offset: u64, comp_dir: &str) -> Option<LineTable> {
+    let sections = ctx.sections;
+    let mut r = sections.reader(&sections.line, offset)?;
+    let (len, dwarf64) = r.initial_length()?;
+    let mut r = r.split(len)?;
+    let version = r.u16()?;
+    if !(2..=5).contains(&version) {
+        return None;
+    }
+    let mut address_size = ctx.enc.address_size;
+    if version >= 5 {
+        address_size = r.u8()?;
+        r.u8()?;
+    }
+    let header_length = r.offset(dwarf64)?;
+    let program = r.pos.checked_add(usize::try_from(header_length).ok()?)?;
+    let min_inst_length = r.u8()? as u64;
+    if version >= 4 {
+        r.u8()?;
+    }
+    r.u8()?;
+    let line_base = r.u8()? as i8 as i64;
+    let line_range = r.u8()?;
+    let opcode_base = r.u8()?;
+    let std_lengths = r.bytes((opcode_base as usize).saturating_sub(1))?;
+    if line_range == 0 {
+        return None;
+    }
+    let enc = Encoding {
+        version,
+        address_size,
+        dwarf64,
+    };
+
+    let (dirs, files) = if version >= 5 {
+        let dirs = read_v5_entries(&mut r, enc, ctx)?
+            .into_iter()
+            .map(|e| e.path)
+            .collect::<Vec<_>>();
+        (dirs, read_v5_entries(&mut r, enc, ctx)?)
+    } else {
+        // Directory 0 is the compilation directory, entry 0 is unused in the file table
+        let mut dirs = vec![comp_dir.to_string()];
+        loop {
+            let d = r.cstr()?;
+            if d.is_empty() {
+                break;
+            }
+            dirs.push(String::from_utf8_lossy(d).into_owned());
+        }
+        let mut files = vec![FileEntry {
+            path: String::new(),
+            dir: 0,
+        }];
+        loop {
+            let name = r.cstr()?;
+            if name.is_empty() {
+                break;
+            }
+            let dir = r.uleb()?;
+            r.uleb()?;
+            r.uleb()?;
+            files.push(FileEntry {
+                path: String::from_utf8_lossy(name).into_owned(),
+                dir,
+            });
+        }
+        (dirs, files)
+    };
+    let resolve = |f: &FileEntry| -> Option<String> {
+        if f.path.is_empty() {
+            return None;
+        }
+        let dir = dirs.get(f.dir as usize).map_or("", String::as_str);
+        Some(join_path(&join_path(comp_dir, dir), &f.path))
+    };
+    let mut table = LineTable {
+        files: files.iter().map(resolve).collect(),
+        sequences: Vec::new(),
+    };
+
+    r.pos = program;
+    let initial = Row {
+        address: 0,
+        file: 1,
+        line: 1,
+        column: 0,
+    };
+    let mut row = initial;
+    let mut rows: Vec<Row> = Vec::new();
+    let advance_line = |row: &mut Row, delta: i64| {
+        row.line = (row.line as i64)
+            .wrapping_add(delta)
+            .clamp(0, u32::MAX as i64) as u32;
+    };
+    while !r.is_empty() {
+        let op = r.u8()?;
+        if op >= opcode_base {
+            let adj = (op - opcode_base) as u64;
+            row.address = row
+                .address
+                .wrapping_add(adj / line_range as u64 * min_inst_length);
+            advance_line(&mut row, line_base + (adj % line_range as u64) as i64);
+            rows.push(row);
+            continue;
+        }
+        match op {
+            0 => {
+                let len = r.uleb()?;
+                let mut ext = r.split(len)?;
+                match ext.u8()? {
+                    DW_LNE_END_SEQUENCE => {
+                        rows.sort_by_key(|row| row.address);
+                        if let Some(first) = rows.first() {
+                            if first.address < row.address {
+                                table.sequences.push(Sequence {
+                                    range: first.address..row.address,
+                                    rows: std::mem::take(&mut rows),
+                                });
+                            }
+                        }
+                        rows.clear();
+                        row = initial;
+                    }
+                    DW_LNE_SET_ADDRESS => {
+                        row.address = ext.uint(len.saturating_sub(1) as usize)?;
+                    }
+                    DW_LNE_DEFINE_FILE => {
+                        let path = String::from_utf8_lossy(ext.cstr()?).into_owned();
+                        let dir = ext.uleb()?;
+                        table.files.push(resolve(&FileEntry { path, dir }));
+                    }
+                    _ => {}
+                }
+            }
+            DW_LNS_COPY => rows.push(row),
+            DW_LNS_ADVANCE_PC => {
+                row.address = row
+                    .address
+                    .wrapping_add(r.uleb()?.wrapping_mul(min_inst_length));
+            }
+            DW_LNS_ADVANCE_LINE => advance_line(&mut row, r.sleb()?),
+            DW_LNS_SET_FILE => row.file = r.uleb()?,
+            DW_LNS_SET_COLUMN => row.column = r.uleb()? as u32,
+            DW_LNS_CONST_ADD_PC => {
+                let adj = (255 - opcode_base) as u64;
+                row.address = row
+                    .address
+                    .wrapping_add(adj / line_range as u64 * min_inst_length);
+            }
+            DW_LNS_FIXED_ADVANCE_PC => row.address = row.address.wrapping_add(r.u16()? as u64),
+            _ => {
+                // Skip the operands of opcodes we do not track, as described by the header
+                for _ in 0..std_lengths[op as usize - 1] {
+                    r.uleb()?;
+                }
+            }
+        }
+    }
+    table.sequences.sort_by_key(|s| s.range.start);
+    Some(table)
+}
+
+/// A subprogram or inlined subroutine covering some code
+#[derive(Debug)]
+struct Function {
+    die: u64,
+    ranges: Vec<Range<u64>>,
+    parent: Option<usize>,
+    depth: usize,
+    inlined: bool,
+    call_file: u64,
+    call_line: u32,
+    call_column: u32,
+}
+
+#[derive(Debug)]
+struct Unit {
+    ranges: Vec<Range<u64>>,
+    lines: LineTable,
+    functions: Vec<Function>,
+}
+
+impl Unit {
+    fn contains(&self, addr: u64) -> bool {
+        if self.ranges.is_empty() {
+            self.lines.sequences.iter().any(|s| s.range.contains(&addr))
+        } else {
+            self.ranges.iter().any(|r| r.contains(&addr))
+        }
+    }
+}
+
+#[derive(Debug)]
+struct DieName {
+    name: Option<String>,
+    origin: Option<u64>,
+}
+
+/// The attributes of a function DIE that matter for symbolization
+#[derive(Default)]
+struct FunctionAttrs<'a> {
+    name: Option<AttrValue<'a>>,
+    linkage_name: Option<AttrValue<'a>>,
+    origin: Option<AttrValue<'a>>,
+    low_pc: Option<AttrValue<'a>>,
+    high_pc: Option<AttrValue<'a>>,
+    ranges: Option<AttrValue<'a>>,
+    call_file: u64,
+    call_line: u64,
+    call_column: u64,
+}
+
+impl<'a> FunctionAttrs<'a> {
+    fn set(&mut self, name: u64, v: AttrValue<'a>) {
+        match name {
+            DW_AT_NAME => self.name = Some(v),
+            DW_AT_LINKAGE_NAME | DW_AT_MIPS_LINKAGE_NAME => self.linkage_name = Some(v),
+            DW_AT_ABSTRACT_ORIGIN | DW_AT_SPECIFICATION => self.origin = Some(v),
+            DW_AT_LOW_PC => self.low_pc = Some(v),
+            DW_AT_HIGH_PC => self.high_pc = Some(v),
+            DW_AT_RANGES => self.ranges = Some(v),
+            DW_AT_CALL_FILE => self.call_file = v.udata().unwrap_or_default(),
+            DW_AT_CALL_LINE => self.call_line = v.udata().unwrap_or_default(),
+            DW_AT_CALL_COLUMN => self.call_column = v.udata().unwrap_or_default(),
+            _ => {}
+        }
+    }
+}
+
+fn parse_unit(
+    sections: &Sections,
+    abbrev_cache: &mut HashMap<u64, Rc<HashMap<u64, Abbrev>>>,
+    names: &mut HashMap<u64, DieName>,
+    offset: u64,
+) -> Option<Unit> {
+    let mut r = sections.reader(&sections.info, offset)?;
+    let (len, dwarf64) = r.initial_length()?;
+    let mut r = r.split(len)?;
+    let version = r.u16()?;
+    if !(2..=5).contains(&version) {
+        return None;
+    }
+    let (unit_type, abbrev_offset, address_size) = if version >= 5 {
+        let unit_type = r.u8()?;
+        let address_size = r.u8()?;
+        (unit_type, r.offset(dwarf64)?, address_size)
+    } else {
+        let abbrev_offset = r.offset(dwarf64)?;
+        (DW_UT_COMPILE, abbrev_offset, r.u8()?)
+    };
+    match unit_type {
+        DW_UT_COMPILE | DW_UT_PARTIAL => {}
+        DW_UT_SKELETON | DW_UT_SPLIT_COMPILE => {
+            r.u64()?;
+        }
+        // Type units carry no code
+        _ => return None,
+    }
+    if !matches!(address_size, 1 | 2 | 4 | 8) {
+        return None;
+    }
+    let abbrevs = match abbrev_cache.get(&abbrev_offset) {
+        Some(a) => a.clone(),
+        None => {
+            let a = Rc::new(parse_abbrevs(&sections.abbrev, abbrev_offset)?);
+            abbrev_cache.insert(abbrev_offset, a.clone());
+            a
+        }
+    };
+    let mut ctx = UnitCtx {
+        sections,
+        enc: Encoding {
+            version,
+            address_size,
+            dwarf64,
+        },
+        offset,
+        str_offsets_base: 0,
+        addr_base: 0,
+        rnglists_base: 0,
+        base_address: 0,
+    };
+
+    // The unit DIE is read in full first since the base attributes may follow the
+    // attributes that depend on them
+    let cu = abbrevs.get(&r.uleb()?)?;
+    let mut attrs = Vec::with_capacity(cu.attrs.len());
+    for spec in &cu.attrs {
+        attrs.push((
+            spec.name,
+            read_form(&mut r, spec.form, ctx.enc, spec.implicit_const)?,
+        ));
+    }
+    let mut cu_attrs = FunctionAttrs::default();
+    let (mut comp_dir, mut stmt_list) = (None, None);
+    for &(name, v) in &attrs {
+        match name {
+            DW_AT_STR_OFFSETS_BASE => ctx.str_offsets_base = v.udata()?,
+            DW_AT_ADDR_BASE | DW_AT_GNU_ADDR_BASE => ctx.addr_base = v.udata()?,
+            DW_AT_RNGLISTS_BASE => ctx.rnglists_base = v.udata()?,
+            _ => {}
+        }
+    }
+    for &(name, v) in &attrs {
+        match name {
+            DW_AT_COMP_DIR => comp_dir = ctx.string(v),
+            DW_AT_STMT_LIST => stmt_list = v.udata(),
+            _ => cu_attrs.set(name, v),
+        }
+    }
+    if let Some(low) = cu_attrs.low_pc.and_then(|v| ctx.address(v)) {
+        ctx.base_address = low;
+    }
+    let comp_dir = comp_dir.unwrap_or_default();
+    let lines = stmt_list
+        .and_then(|off| parse_line_program(&ctx, off, &comp_dir))
+        .unwrap_or_default();
+    let mut unit = Unit {
+        ranges: ctx.pc_ranges(cu_attrs.low_pc, cu_attrs.high_pc, cu_attrs.ranges),
+        lines,
+        functions: Vec::new(),
+    };
+
+    // Enclosing function node for each open DIE nesting level
+    let mut stack: Vec<Option<usize>> = Vec::new();
+    if cu.has_children {
+        stack.push(None);
+    }
+    while let Some(&parent) = stack.last() {
+        let die = r.pos as u64;
+        let code = r.uleb()?;
+        if code == 0 {
+            stack.pop();
+            continue;
+        }
+        let abbrev = abbrevs.get(&code)?;
+        let is_function = matches!(
+            abbrev.tag,
+            DW_TAG_SUBPROGRAM | DW_TAG_INLINED_SUBROUTINE | DW_TAG_ENTRY_POINT
+        );
+        let mut f = FunctionAttrs::default();
+        for spec in &abbrev.attrs {
+            let v = read_form(&mut r, spec.form, ctx.enc, spec.implicit_const)?;
+            if is_function {
+                f.set(spec.name, v);
+            }
+        }
+        let mut node = parent;
+        if is_function {
+            let name = f
+                .linkage_name
+                .and_then(|v| ctx.string(v))
+                .or_else(|| f.name.and_then(|v| ctx.string(v)));
+            let origin = f.origin.and_then(|v| ctx.reference(v));
+            if name.is_some() || origin.is_some() {
+                names.insert(die, DieName { name, origin });
+            }
+            let ranges = ctx.pc_ranges(f.low_pc, f.high_pc, f.ranges);
+            let inlined = abbrev.tag == DW_TAG_INLINED_SUBROUTINE;
+            // Inlined subroutines are kept even without code of their own, since the
+            // frames nested inside them still belong to their call chain
+            if !ranges.is_empty() || inlined {
+                unit.functions.push(Function {
+                    die,
+                    ranges,
+                    parent,
+                    depth: parent.map_or(0, |p| unit.functions[p].depth + 1),
+                    inlined,
+                    call_file: f.call_file,
+                    call_line: f.call_line as u32,
+                    call_column: f.call_column as u32,
+                });
+                node = Some(unit.functions.len() - 1);
+            }
+        }
+        if abbrev.has_children {
+            stack.push(node);
+        }
+    }
+    Some(unit)
+}
+
+/// A single source level frame an address maps to
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Frame {
+    pub function: Option<String>,
+    pub file: Option<String>,
+    /// Source line, 0 if unknown
+    pub line: u32,
+    /// Source column, 0 if unknown
+    pub column: u32,
+}
+
+impl fmt::Display for Frame {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} at {}:",
+            self.function.as_deref().unwrap_or("??"),
+            self.file.as_deref().unwrap_or("??")
+        )?;
+        match self.line {
+            0 => write!(f, "?"),
+            l => write!(f, "{}", l),
+        }
+    }
+}
+
+/// Address to source location lookup built from the DWARF debug information of an ELF file.
+///
+/// Supports DWARF versions 2 to 5 and reports inlined call chains. Functions without debug
+/// information fall back to the symbol table. Relocations of `ET_REL` objects are not applied.
+#[derive(Debug)]
+pub struct Addr2Line {
+    units: Vec<Unit>,
+    names: HashMap<u64, DieName>,
+    symbols: Vec<(Range<u64>, String)>,
+}
+
+impl Addr2Line {
+    /// Parses the debug information of `elf`. Malformed units are skipped.
+    pub fn new(elf: &ElfFile) -> Addr2Line {
+        Addr2Line::with_debug_files(elf, None, None)
+    }
+
+    /// Like [`Addr2Line::new`] for a binary whose debug information was split off.
+    ///
+    /// DWARF is read from `debug` unless `elf` has its own `.debug_info`, strings referenced
+    /// through `DW_FORM_GNU_strp_alt` are resolved in the supplementary file `alt`. The
+    /// symbol table of `debug` stands in for a stripped `.symtab`.
+    pub fn with_debug_files(
+        elf: &ElfFile,
+        debug: Option<&ElfFile>,
+        alt: Option<&ElfFile>,
+    ) -> Addr2Line {
+        let dwarf_elf = match debug {
+            Some(debug) if elf.section_by_name(".debug_info").is_none() => debug,
+            _ => elf,
+        };
+        let sections = Sections::load(dwarf_elf, alt);
+        let mut units = Vec::new();
+        let mut names = HashMap::new();
+        let mut abbrev_cache = HashMap::new();
+        let mut offset = 0u64;
+        while let Some(mut r) = sections.reader(&sections.info, offset) {
+            let Some((len, dwarf64)) = r.initial_length() else {
+                break;
+            };
+            if let Some(unit) = parse_unit(&sections, &mut abbrev_cache, &mut names, offset) {
+                units.push(unit);
+            }
+            let header: u64 = if dwarf64 { 12 } else { 4 };
+            match header.checked_add(len).and_then(|n| offset.checked_add(n)) {
+                Some(next) if next > offset => offset = next,
+                _ => break,
+            }
+        }
+
+        let mut syms = elf.symtab();
+        if syms.is_empty() {
+            syms = debug.map(ElfFile::symtab).unwrap_or_default();
+        }
+        if syms.is_empty() {
+            syms = elf.dynsym();
+        }
+        let thumb = elf.header.machine() == Ok(Machine::Arm);
+        let mut symbols: Vec<(Range<u64>, String)> = syms
+            .into_iter()
+            .filter(|s| s.sym_type() == STT_FUNC && s.size != 0 && s.is_defined())
+            .map(|s| {
+                let start = if thumb { s.value & !1 } else { s.value };
+                (start..start.wrapping_add(s.size), s.name)
+            })
+            .collect();
+        symbols.sort_by_key(|(r, _)| r.start);
+
+        Addr2Line {
+            units,
+            names,
+            symbols,
+        }
+    }
+
+    /// Returns the source frames for `addr`, innermost inlined frame first.
+    ///
+    /// The list is empty if neither the debug information nor the symbol table cover `addr`.
+    pub fn find_frames(&self, addr: u64) -> Vec<Frame> {
+        let Some(unit) = self.units.iter().find(|u| u.contains(addr)) else {
+            return self
+                .symbol_name(addr)
+                .map(|name| Frame {
+                    function: Some(name),
+                    ..Default::default()
+                })
+                .into_iter()
+                .collect();
+        };
+        let mut location = unit
+            .lines
+            .find(addr)
+            .map(|row| (unit.lines.file(row.file), row.line, row.column))
+            .unwrap_or_default();
+
+        let innermost = unit
+            .functions
+            .iter()
+            .enumerate()
+            .filter(|(_, f)| f.ranges.iter().any(|r| r.contains(&addr)))
+            .max_by_key(|(_, f)| f.depth)
+            .map(|(i, _)| i);
+        let mut frames = Vec::new();
+        let mut next = innermost;
+        while let Some(i) = next {
+            let f = &unit.functions[i];
+            let (file, line, column) = location;
+            frames.push(Frame {
+                function: self.die_name(f.die),
+                file,
+                line,
+                column,
+            });
+            location = if f.inlined {
+                (unit.lines.file(f.call_file), f.call_line, f.call_column)
+            } else {
+                Default::default()
+            };
+            next = f.parent;
+        }
+        if frames.is_empty() {
+            let (file, line, column) = location;
+            frames.push(Frame {
+                function: None,
+                file,
+                line,
+                column,
+            });
+        }
+        if let Some(outer) = frames.last_mut() {
+            if outer.function.is_none() {
+                outer.function = self.symbol_name(addr);
+            }
+        }
+        frames
+    }
+
+    fn die_name(&self, mut die: u64) -> Option<String> {
+        // Bounded to not loop forever on cyclic references
+        for _ in 0..16 {
+            let entry = self.names.get(&die)?;
+            if let Some(name) = &entry.name {
+                return Some(name.clone());
+            }
+            die = entry.origin?;
+        }
+        None
+    }
+
+    fn symbol_name(&self, addr: u64) -> Option<String> {
+        let i = self.symbols.partition_point(|(r, _)| r.start <= addr);
+        let (range, name) = self.symbols.get(i.checked_sub(1)?)?;
+        range.contains(&addr).then(|| name.clone())
+    }
+}
+
+/// Maps an address of the ELF binary at the given path to its source frames,
+/// innermost inlined frame first. Separate debug files are looked up in the default
+/// locations, see [`DebugSearch`].
+pub fn addr2line<P: AsRef<Path>>(elf_path: P, addr: u64) -> Result<Vec<Frame>, Box<dyn Error>> {
+    let elf = DebugElf::open(elf_path, &DebugSearch::default())?;
+    Ok(elf.addr2line().find_frames(addr))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(function: &str, file: Option<&str>, line: u32) -> Frame {
+        Frame {
+            function: Some(function.to_string()),
+            file: file.map(str::to_string),
+            line,
+            column: 0,
+        }
+    }
+
+    fn lookup(a2l: &Addr2Line, addr: u64) -> Vec<Frame> {
+        a2l.find_frames(addr)
+            .into_iter()
+            .map(|f| Frame { column: 0, ..f })
+            .collect()
+    }
+
+    #[test]
+    fn test_indirect_form() {
+        let enc = Encoding {
+            version: 4,
+            address_size: 8,
+            dwarf64: false,
+        };
+        let read = |data: &[u8]| {
+            read_form(&mut Reader::new(data, 0, false), DW_FORM_INDIRECT, enc, 0)
+                .and_then(AttrValue::udata)
+        };
+        assert_eq!(read(&[0x0b, 5]), Some(5));
+        assert_eq!(read(&[0x16, 0x0b, 5]), None);
+        assert_eq!(read(&[0x16; 0x10000]), None);
+    }
+
+    #[test]
+    fn test_inlined_frames() {
+        const MAIN: Option<&str> = Some("/tmp/dw/main.c");
+        for path in ["tests/bin/dwarf4", "tests/bin/dwarf5"] {
+            let a2l = Addr2Line::new(&ElfFile::open(path).unwrap());
+            assert_eq!(
+                lookup(&a2l, 0x11a5),
+                [
+                    frame("square", MAIN, 7),
+                    frame("sum_squares", MAIN, 14),
+                    frame("compute", MAIN, 20),
+                ],
+                "{}",
+                path
+            );
+            assert_eq!(
+                lookup(&a2l, 0x11af),
+                [frame("sum_squares", MAIN, 14), frame("compute", MAIN, 20)],
+                "{}",
+                path
+            );
+            assert_eq!(lookup(&a2l, 0x11ba), [frame("compute", MAIN, 20)]);
+            assert_eq!(
+                lookup(&a2l, 0x11d3),
+                [frame("scale", Some("/tmp/dw/util.c"), 6)]
+            );
+            assert_eq!(lookup(&a2l, 0x1075), [frame("main", MAIN, 26)]);
+        }
+    }
+
+    #[test]
+    fn test_dwarf2_without_call_sites() {
+        let frames = addr2line("tests/bin/dwarf2", 0x11a0).unwrap();
+        let frames: Vec<_> = frames
+            .iter()
+            .map(|f| Frame {
+                column: 0,
+                ..f.clone()
+            })
+            .collect();
+        assert_eq!(
+            frames,
+            [
+                frame("square", Some("/tmp/dw/main.c"), 7),
+                frame("sum_squares", None, 0),
+                frame("compute", None, 0),
+            ]
+        );
+        assert_eq!(frames[1].to_string(), "sum_squares at ??:?");
+    }
+
+    #[test]
+    fn test_mips_big_endian() {
+        const LIBGCC2: Option<&str> = Some(
+            "/home/anon/buildroot-2016.02/output/build/host-gcc-final-4.9.3/build/\
+             mips-buildroot-linux-uclibc/libgcc/../../../libgcc/libgcc2.c",
+        );
+        let a2l = Addr2Line::new(&ElfFile::open("tests/bin/objdump.mips").unwrap());
+        assert_eq!(lookup(&a2l, 0x4f4170), [frame("__lshrdi3", LIBGCC2, 412)]);
+        assert_eq!(
+            lookup(&a2l, 0x4f4300),
+            [
+                frame("__udivmoddi4", LIBGCC2, 1078),
+                frame("__divdi3", LIBGCC2, 1241),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_symbol_fallback() {
+        let a2l = Addr2Line::new(&ElfFile::open("tests/bin/dwarf5").unwrap());
+        assert!(a2l.find_frames(0x1000).is_empty());
+        let elf = ElfFile::open("tests/bin/dd.armel").unwrap();
+        let main = elf.symtab().into_iter().find(|s| s.name == "main").unwrap();
+        let frames = Addr2Line::new(&elf).find_frames(main.value + 4);
+        assert_eq!(frames, [frame("main", None, 0)]);
+    }
+
+    #[test]
+    fn test_huge_unit_length() {
+        let orig = ElfFile::open("tests/bin/dwarf2").unwrap();
+        let info = orig.section_by_name(".debug_info").unwrap().sh_offset as usize;
+        let mut data = orig.data().to_vec();
+        data[info..info + 4].fill(0xff);
+        data[info + 4..info + 12].copy_from_slice(&(u64::MAX - 4).to_le_bytes());
+        let elf = ElfFile::parse(data).unwrap();
+        // The unit is skipped and the symbol table still resolves addresses
+        let main = elf.symtab().into_iter().find(|s| s.name == "main").unwrap();
+        let frames = Addr2Line::new(&elf).find_frames(main.value);
+        assert_eq!(frames, [frame("main", None, 0)]);
+    }
+}
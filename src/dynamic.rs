@@ -0,0 +1,209 @@
+use crate::cstr_at;
+use crate::elf_file::ElfFile;
+use crate::elf_types::{PT_DYNAMIC, SHT_DYNAMIC};
+
+pub const SIZEOF_DYN32: usize = 8;
+pub const SIZEOF_DYN64: usize = 16;
+
+pub const DT_NULL: i64 = 0;
+pub const DT_NEEDED: i64 = 1;
+pub const DT_PLTRELSZ: i64 = 2;
+pub const DT_PLTGOT: i64 = 3;
+pub const DT_HASH: i64 = 4;
+pub const DT_STRTAB: i64 = 5;
+pub const DT_SYMTAB: i64 = 6;
+pub const DT_RELA: i64 = 7;
+pub const DT_RELASZ: i64 = 8;
+pub const DT_RELAENT: i64 = 9;
+pub const DT_STRSZ: i64 = 10;
+pub const DT_SYMENT: i64 = 11;
+pub const DT_INIT: i64 = 12;
+pub const DT_FINI: i64 = 13;
+pub const DT_SONAME: i64 = 14;
+pub const DT_RPATH: i64 = 15;
+pub const DT_SYMBOLIC: i64 = 16;
+pub const DT_REL: i64 = 17;
+pub const DT_RELSZ: i64 = 18;
+pub const DT_RELENT: i64 = 19;
+pub const DT_PLTREL: i64 = 20;
+pub const DT_DEBUG: i64 = 21;
+pub const DT_TEXTREL: i64 = 22;
+pub const DT_JMPREL: i64 = 23;
+pub const DT_BIND_NOW: i64 = 24;
+pub const DT_INIT_ARRAY: i64 = 25;
+pub const DT_FINI_ARRAY: i64 = 26;
+pub const DT_INIT_ARRAYSZ: i64 = 27;
+pub const DT_FINI_ARRAYSZ: i64 = 28;
+pub const DT_RUNPATH: i64 = 29;
+pub const DT_FLAGS: i64 = 30;
+pub const DT_PREINIT_ARRAY: i64 = 32;
+pub const DT_PREINIT_ARRAYSZ: i64 = 33;
+pub const DT_SYMTAB_SHNDX: i64 = 34;
+pub const DT_RELRSZ: i64 = 35;
+pub const DT_RELR: i64 = 36;
+pub const DT_RELRENT: i64 = 37;
+pub const DT_GNU_HASH: i64 = 0x6fff_fef5;
+pub const DT_VERSYM: i64 = 0x6fff_fff0;
+pub const DT_RELACOUNT: i64 = 0x6fff_fff9;
+pub const DT_RELCOUNT: i64 = 0x6fff_fffa;
+pub const DT_FLAGS_1: i64 = 0x6fff_fffb;
+pub const DT_VERDEF: i64 = 0x6fff_fffc;
+pub const DT_VERDEFNUM: i64 = 0x6fff_fffd;
+pub const DT_VERNEED: i64 = 0x6fff_fffe;
+pub const DT_VERNEEDNUM: i64 = 0x6fff_ffff;
+
+pub const DT_MIPS_LOCAL_GOTNO: i64 = 0x7000_000a;
+pub const DT_MIPS_SYMTABNO: i64 = 0x7000_0011;
+pub const DT_MIPS_GOTSYM: i64 = 0x7000_0013;
+pub const DT_MIPS_PLTGOT: i64 = 0x7000_0032;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Dyn32 {
+    pub d_tag: i32,
+    pub d_val: u32,
+}
+
+unsafe impl plain::Plain for Dyn32 {}
+
+impl Dyn32 {
+    fn fix_dyn(d: &mut Dyn32, bit: u8) -> &mut Dyn32 {
+        if bit == 2 {
+            d.d_tag = d.d_tag.to_be();
+            d.d_val = d.d_val.to_be();
+        }
+        d
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Dyn64 {
+    pub d_tag: i64,
+    pub d_val: u64,
+}
+
+unsafe impl plain::Plain for Dyn64 {}
+
+impl Dyn64 {
+    fn fix_dyn(d: &mut Dyn64, bit: u8) -> &mut Dyn64 {
+        if bit == 2 {
+            d.d_tag = d.d_tag.to_be();
+            d.d_val = d.d_val.to_be();
+        }
+        d
+    }
+}
+
+impl From<Dyn32> for Dyn64 {
+    fn from(d: Dyn32) -> Dyn64 {
+        Dyn64 {
+            d_tag: d.d_tag as i64,
+            d_val: d.d_val as u64,
+        }
+    }
+}
+
+impl ElfFile {
+    /// Parses the dynamic section up to its `DT_NULL` terminator.
+    ///
+    /// Uses the `SHT_DYNAMIC` section and falls back to the `PT_DYNAMIC` segment for files
+    /// without section headers. Returns an empty list for statically linked files.
+    pub fn dynamic(&self) -> Vec<Dyn64> {
+        let data = self
+            .section_headers
+            .iter()
+            .find(|sh| sh.sh_type == SHT_DYNAMIC)
+            .and_then(|sh| self.section_data(sh))
+            .or_else(|| {
+                self.program_headers
+                    .iter()
+                    .find(|ph| ph.p_type == PT_DYNAMIC)
+                    .and_then(|ph| self.segment_data(ph))
+            });
+        let Some(data) = data else {
+            return Vec::new();
+        };
+
+        let bit = self.header.e_ident[0x5];
+        let entsize = if self.is_64() {
+            SIZEOF_DYN64
+        } else {
+            SIZEOF_DYN32
+        };
+        data.chunks_exact(entsize)
+            .map(|raw| {
+                if self.is_64() {
+                    let mut d = Dyn64::default();
+                    plain::copy_from_bytes(&mut d, raw).unwrap_or_default();
+                    *Dyn64::fix_dyn(&mut d, bit)
+                } else {
+                    let mut d = Dyn32::default();
+                    plain::copy_from_bytes(&mut d, raw).unwrap_or_default();
+                    Dyn64::from(*Dyn32::fix_dyn(&mut d, bit))
+                }
+            })
+            .take_while(|d| d.d_tag != DT_NULL)
+            .collect()
+    }
+
+    /// Returns the value of the first dynamic entry with the given tag
+    pub fn dynamic_value(&self, tag: i64) -> Option<u64> {
+        self.dynamic()
+            .into_iter()
+            .find(|d| d.d_tag == tag)
+            .map(|d| d.d_val)
+    }
+
+    /// Returns the dynamic string table: the section linked from `SHT_DYNAMIC`, or the
+    /// `DT_STRTAB` and `DT_STRSZ` range for files without section headers
+    pub fn dynstr(&self) -> Option<&[u8]> {
+        if let Some(sh) = self
+            .section_headers
+            .iter()
+            .find(|sh| sh.sh_type == SHT_DYNAMIC)
+        {
+            return self
+                .section_headers
+                .get(sh.sh_link as usize)
+                .and_then(|s| self.section_data(s));
+        }
+        let offset = self.vaddr_to_offset(self.dynamic_value(DT_STRTAB)?)?;
+        self.bytes_at(offset, self.dynamic_value(DT_STRSZ)?)
+    }
+
+    /// Returns the `DT_NEEDED` library names in the order the loader processes them
+    pub fn needed(&self) -> Vec<String> {
+        let strtab = self.dynstr().unwrap_or_default();
+        self.dynamic()
+            .iter()
+            .filter(|d| d.d_tag == DT_NEEDED)
+            .map(|d| cstr_at(strtab, d.d_val as usize).to_string())
+            .collect()
+    }
+
+    /// Returns the `DT_SONAME` of a shared library
+    pub fn soname(&self) -> Option<String> {
+        let offset = self.dynamic_value(DT_SONAME)?;
+        Some(cstr_at(self.dynstr()?, offset as usize).to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_x64_dynamic() {
+        let elf = ElfFile::open("/bin/ls").unwrap();
+        let got = elf.section_by_name(".got.plt").unwrap().sh_addr;
+        assert_eq!(elf.dynamic_value(DT_PLTGOT), Some(got));
+        assert!(elf.dynamic().iter().any(|d| d.d_tag == DT_NEEDED));
+        assert_eq!(elf.needed(), ["libselinux.so.1", "libc.so.6"]);
+        assert_eq!(elf.soname(), None);
+        assert!(ElfFile::open("tests/bin/dd.armel")
+            .unwrap()
+            .dynamic()
+            .is_empty());
+    }
+}
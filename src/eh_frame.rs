@@ -0,0 +1,996 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::ops::Range;
+
+use crate::dwarf::Reader;
+use crate::elf_file::ElfFile;
+use crate::elf_types::{Machine, PT_GNU_EH_FRAME, PT_LOAD};
+
+pub const DW_EH_PE_ABSPTR: u8 = 0x00;
+pub const DW_EH_PE_ULEB128: u8 = 0x01;
+pub const DW_EH_PE_UDATA2: u8 = 0x02;
+pub const DW_EH_PE_UDATA4: u8 = 0x03;
+pub const DW_EH_PE_UDATA8: u8 = 0x04;
+pub const DW_EH_PE_SLEB128: u8 = 0x09;
+pub const DW_EH_PE_SDATA2: u8 = 0x0a;
+pub const DW_EH_PE_SDATA4: u8 = 0x0b;
+pub const DW_EH_PE_SDATA8: u8 = 0x0c;
+pub const DW_EH_PE_PCREL: u8 = 0x10;
+pub const DW_EH_PE_TEXTREL: u8 = 0x20;
+pub const DW_EH_PE_DATAREL: u8 = 0x30;
+pub const DW_EH_PE_FUNCREL: u8 = 0x40;
+pub const DW_EH_PE_ALIGNED: u8 = 0x50;
+pub const DW_EH_PE_INDIRECT: u8 = 0x80;
+pub const DW_EH_PE_OMIT: u8 = 0xff;
+
+const DW_CFA_ADVANCE_LOC: u8 = 0x40;
+const DW_CFA_OFFSET: u8 = 0x80;
+const DW_CFA_RESTORE: u8 = 0xc0;
+const DW_CFA_NOP: u8 = 0x00;
+const DW_CFA_SET_LOC: u8 = 0x01;
+const DW_CFA_ADVANCE_LOC1: u8 = 0x02;
+const DW_CFA_ADVANCE_LOC2: u8 = 0x03;
+const DW_CFA_ADVANCE_LOC4: u8 = 0x04;
+const DW_CFA_OFFSET_EXTENDED: u8 = 0x05;
+const DW_CFA_RESTORE_EXTENDED: u8 = 0x06;
+const DW_CFA_UNDEFINED: u8 = 0x07;
+const DW_CFA_SAME_VALUE: u8 = 0x08;
+const DW_CFA_REGISTER: u8 = 0x09;
+const DW_CFA_REMEMBER_STATE: u8 = 0x0a;
+const DW_CFA_RESTORE_STATE: u8 = 0x0b;
+const DW_CFA_DEF_CFA: u8 = 0x0c;
+const DW_CFA_DEF_CFA_REGISTER: u8 = 0x0d;
+const DW_CFA_DEF_CFA_OFFSET: u8 = 0x0e;
+const DW_CFA_DEF_CFA_EXPRESSION: u8 = 0x0f;
+const DW_CFA_EXPRESSION: u8 = 0x10;
+const DW_CFA_OFFSET_EXTENDED_SF: u8 = 0x11;
+const DW_CFA_DEF_CFA_SF: u8 = 0x12;
+const DW_CFA_DEF_CFA_OFFSET_SF: u8 = 0x13;
+const DW_CFA_VAL_OFFSET: u8 = 0x14;
+const DW_CFA_VAL_OFFSET_SF: u8 = 0x15;
+const DW_CFA_VAL_EXPRESSION: u8 = 0x16;
+const DW_CFA_GNU_WINDOW_SAVE: u8 = 0x2d;
+const DW_CFA_GNU_ARGS_SIZE: u8 = 0x2e;
+const DW_CFA_GNU_NEGATIVE_OFFSET_EXTENDED: u8 = 0x2f;
+
+/// Everything needed to resolve a `DW_EH_PE_*` encoded pointer
+#[derive(Clone, Copy)]
+struct PointerCtx<'e> {
+    elf: Option<&'e ElfFile>,
+    /// Virtual address of byte 0 of the data being read
+    base: u64,
+    data_base: u64,
+    text_base: u64,
+    address_size: u8,
+    big_endian: bool,
+}
+
+impl PointerCtx<'_> {
+    fn read(&self, r: &mut Reader, enc: u8, func_base: u64) -> Option<u64> {
+        if enc == DW_EH_PE_OMIT {
+            return None;
+        }
+        let size = self.address_size as usize;
+        if enc & 0x70 == DW_EH_PE_ALIGNED {
+            r.pos = r.pos.checked_add(size - 1)? / size * size;
+        }
+        let field = self.base.wrapping_add(r.pos as u64);
+        let value = match enc & 0x0f {
+            DW_EH_PE_ABSPTR => r.uint(size)?,
+            DW_EH_PE_ULEB128 => r.uleb()?,
+            DW_EH_PE_UDATA2 => r.uint(2)?,
+            DW_EH_PE_UDATA4 => r.uint(4)?,
+            DW_EH_PE_UDATA8 => r.uint(8)?,
+            DW_EH_PE_SLEB128 => r.sleb()? as u64,
+            DW_EH_PE_SDATA2 => r.uint(2)? as i16 as u64,
+            DW_EH_PE_SDATA4 => r.uint(4)? as i32 as u64,
+            DW_EH_PE_SDATA8 => r.uint(8)?,
+            _ => return None,
+        };
+        let base = match enc & 0x70 {
+            DW_EH_PE_ABSPTR | DW_EH_PE_ALIGNED => 0,
+            DW_EH_PE_PCREL => field,
+            DW_EH_PE_TEXTREL => self.text_base,
+            DW_EH_PE_DATAREL => self.data_base,
+            DW_EH_PE_FUNCREL => func_base,
+            _ => return None,
+        };
+        let mut addr = self.truncate(base.wrapping_add(value));
+        if enc & DW_EH_PE_INDIRECT != 0 {
+            let elf = self.elf?;
+            let off = usize::try_from(elf.vaddr_to_offset(addr)?).ok()?;
+            addr = Reader::new(elf.data(), off, self.big_endian).uint(size)?;
+        }
+        Some(addr)
+    }
+
+    fn truncate(&self, addr: u64) -> u64 {
+        if self.address_size == 4 {
+            addr & 0xffff_ffff
+        } else {
+            addr
+        }
+    }
+}
+
+/// A Common Information Entry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cie {
+    /// Offset of the entry inside its section
+    pub offset: u64,
+    pub version: u8,
+    pub augmentation: String,
+    pub address_size: u8,
+    pub code_alignment_factor: u64,
+    pub data_alignment_factor: i64,
+    pub return_address_register: u16,
+    pub fde_encoding: u8,
+    pub lsda_encoding: u8,
+    pub personality: Option<u64>,
+    pub signal_frame: bool,
+    pub initial_instructions: Vec<u8>,
+    instructions_addr: u64,
+}
+
+/// A Frame Description Entry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fde {
+    /// Offset of the entry inside its section
+    pub offset: u64,
+    /// Index of the owning CIE in [`EhFrame::cies`]
+    pub cie: usize,
+    pub pc_begin: u64,
+    pub pc_range: u64,
+    /// Address of the language specific data area, i.e. the C++ exception tables
+    pub lsda: Option<u64>,
+    pub instructions: Vec<u8>,
+    instructions_addr: u64,
+}
+
+impl Fde {
+    /// Returns the code addresses covered by this FDE
+    pub fn range(&self) -> Range<u64> {
+        self.pc_begin..self.pc_begin.saturating_add(self.pc_range)
+    }
+}
+
+/// How the canonical frame address is computed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CfaRule {
+    RegisterOffset { register: u16, offset: i64 },
+    Expression(Vec<u8>),
+}
+
+/// How the value of a register in the previous frame is recovered
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegisterRule {
+    Undefined,
+    SameValue,
+    /// Saved at `CFA + n`
+    Offset(i64),
+    /// The value is `CFA + n`
+    ValOffset(i64),
+    /// Saved in another register
+    Register(u16),
+    /// Saved at the address computed by a DWARF expression
+    Expression(Vec<u8>),
+    /// The value is computed by a DWARF expression
+    ValExpression(Vec<u8>),
+}
+
+/// One row of the unwind table: the rules that apply to `address`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnwindRow {
+    pub address: Range<u64>,
+    pub cfa: CfaRule,
+    /// Rules of all registers that do not use the default rule
+    pub registers: BTreeMap<u16, RegisterRule>,
+}
+
+#[derive(Debug, Clone)]
+struct State {
+    cfa: CfaRule,
+    registers: BTreeMap<u16, RegisterRule>,
+}
+
+/// The parsed contents of an `.eh_frame` or `.debug_frame` section.
+///
+/// Pointers are resolved against the load addresses in the file, relocations of
+/// `ET_REL` objects are not applied.
+#[derive(Debug, Clone)]
+pub struct EhFrame {
+    /// Virtual address of the section, 0 for `.debug_frame`
+    pub address: u64,
+    pub cies: Vec<Cie>,
+    /// All FDEs sorted by `pc_begin`
+    pub fdes: Vec<Fde>,
+    machine: u16,
+    ctx: PointerBases,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct PointerBases {
+    data_base: u64,
+    text_base: u64,
+    address_size: u8,
+    big_endian: bool,
+}
+
+impl PointerBases {
+    fn ctx<'e>(&self, elf: Option<&'e ElfFile>, base: u64) -> PointerCtx<'e> {
+        PointerCtx {
+            elf,
+            base,
+            data_base: self.data_base,
+            text_base: self.text_base,
+            address_size: self.address_size,
+            big_endian: self.big_endian,
+        }
+    }
+}
+
+impl EhFrame {
+    fn parse(
+        elf: &ElfFile,
+        data: &[u8],
+        address: u64,
+        debug_frame: bool,
+    ) -> Result<EhFrame, Box<dyn Error>> {
+        let section_addr = |name: &str| elf.section_by_name(name).map_or(0, |sh| sh.sh_addr);
+        let bases = PointerBases {
+            data_base: section_addr(".got"),
+            text_base: section_addr(".text"),
+            address_size: elf.addr_size() as u8,
+            big_endian: elf.is_big_endian(),
+        };
+        let ctx = bases.ctx(Some(elf), address);
+
+        // Collect all entries first, FDEs in .debug_frame may precede their CIE
+        let mut entries = Vec::new();
+        let mut r = Reader::new(data, 0, bases.big_endian);
+        while !r.is_empty() {
+            let offset = r.pos as u64;
+            let (len, dwarf64) = r
+                .initial_length()
+                .ok_or_else(|| format!("truncated entry at {:#x}", offset))?;
+            if len == 0 {
+                // Terminator of .eh_frame, padding in .debug_frame
+                if debug_frame {
+                    continue;
+                }
+                break;
+            }
+            let mut entry = r
+                .split(len)
+                .ok_or_else(|| format!("entry at {:#x} exceeds the section", offset))?;
+            let id_pos = entry.pos as u64;
+            let id = entry
+                .offset(dwarf64)
+                .ok_or_else(|| format!("truncated entry at {:#x}", offset))?;
+            let is_cie = if debug_frame {
+                id == if dwarf64 { u64::MAX } else { 0xffff_ffff }
+            } else {
+                id == 0
+            };
+            let cie_offset = if debug_frame {
+                id
+            } else {
+                id_pos.wrapping_sub(id)
+            };
+            entries.push((offset, is_cie, cie_offset, entry));
+        }
+
+        let mut cies = Vec::new();
+        let mut cie_index = BTreeMap::new();
+        for (offset, _, _, entry) in entries.iter_mut().filter(|e| e.1) {
+            let cie = parse_cie(entry, *offset, &ctx)
+                .ok_or_else(|| format!("malformed CIE at {:#x}", offset))?;
+            cie_index.insert(*offset, cies.len());
+            cies.push(cie);
+        }
+        let mut fdes = Vec::new();
+        for (offset, _, cie_offset, entry) in entries.iter_mut().filter(|e| !e.1) {
+            let cie = *cie_index
+                .get(cie_offset)
+                .ok_or_else(|| format!("FDE at {:#x} references no CIE", offset))?;
+            let fde = parse_fde(entry, *offset, cie, &cies[cie], &ctx)
+                .ok_or_else(|| format!("malformed FDE at {:#x}", offset))?;
+            fdes.push(fde);
+        }
+        fdes.sort_by_key(|f| f.pc_begin);
+
+        Ok(EhFrame {
+            address,
+            cies,
+            fdes,
+            machine: elf.machine(),
+            ctx: bases,
+        })
+    }
+
+    /// Returns the FDE covering `pc`
+    pub fn fde_for_pc(&self, pc: u64) -> Option<&Fde> {
+        let i = self.fdes.partition_point(|f| f.pc_begin <= pc);
+        self.fdes[..i]
+            .iter()
+            .rev()
+            .take_while(|f| f.pc_begin == self.fdes[i - 1].pc_begin)
+            .find(|f| f.range().contains(&pc))
+    }
+
+    /// Returns the address ranges of all functions described by an FDE, sorted by start.
+    /// This is a reliable source of function boundaries in stripped binaries.
+    pub fn function_ranges(&self) -> Vec<Range<u64>> {
+        self.fdes
+            .iter()
+            .filter(|f| f.pc_range != 0)
+            .map(Fde::range)
+            .collect()
+    }
+
+    /// Evaluates the call frame instructions of `fde` into its complete unwind table
+    pub fn unwind_table(&self, fde: &Fde) -> Result<Vec<UnwindRow>, Box<dyn Error>> {
+        let cie = self.cies.get(fde.cie).ok_or("FDE references no CIE")?;
+        let mut state = State {
+            cfa: CfaRule::RegisterOffset {
+                register: 0,
+                offset: 0,
+            },
+            registers: BTreeMap::new(),
+        };
+        let mut loc = fde.pc_begin;
+        let mut rows = Vec::new();
+        let mut exec = Executor {
+            frame: self,
+            cie,
+            initial: None,
+            stack: Vec::new(),
+        };
+        exec.run(
+            &cie.initial_instructions,
+            cie.instructions_addr,
+            &mut state,
+            &mut loc,
+            None,
+        )
+        .map_err(|e| format!("CIE at {:#x}: {}", cie.offset, e))?;
+        exec.initial = Some(state.clone());
+        exec.run(
+            &fde.instructions,
+            fde.instructions_addr,
+            &mut state,
+            &mut loc,
+            Some(&mut rows),
+        )
+        .map_err(|e| format!("FDE at {:#x}: {}", fde.offset, e))?;
+        let end = fde.range().end;
+        if loc < end {
+            rows.push(UnwindRow {
+                address: loc..end,
+                cfa: state.cfa,
+                registers: state.registers,
+            });
+        }
+        Ok(rows)
+    }
+
+    /// Computes the CFA and register rules that apply at `pc`
+    pub fn unwind_row(&self, pc: u64) -> Result<UnwindRow, Box<dyn Error>> {
+        let fde = self
+            .fde_for_pc(pc)
+            .ok_or_else(|| format!("no FDE covers {:#x}", pc))?;
+        self.unwind_table(fde)?
+            .into_iter()
+            .find(|row| row.address.contains(&pc))
+            .ok_or_else(|| format!("no unwind row covers {:#x}", pc).into())
+    }
+}
+
+fn parse_cie(r: &mut Reader, offset: u64, ctx: &PointerCtx) -> Option<Cie> {
+    let version = r.u8()?;
+    if !matches!(version, 1 | 3 | 4) {
+        return None;
+    }
+    let augmentation = String::from_utf8_lossy(r.cstr()?).into_owned();
+    if augmentation.contains("eh") {
+        // Pre-EH augmentation used by ancient GCC: a pointer sized eh_data field
+        r.uint(ctx.address_size as usize)?;
+    }
+    let address_size = if version >= 4 {
+        let size = r.u8()?;
+        r.u8()?;
+        if size == 0 {
+            return None;
+        }
+        size
+    } else {
+        ctx.address_size
+    };
+    let code_alignment_factor = r.uleb()?;
+    let data_alignment_factor = r.sleb()?;
+    let return_address_register = if version == 1 {
+        r.u8()? as u64
+    } else {
+        r.uleb()?
+    };
+    let mut cie = Cie {
+        offset,
+        version,
+        augmentation,
+        address_size,
+        code_alignment_factor,
+        data_alignment_factor,
+        return_address_register: u16::try_from(return_address_register).ok()?,
+        fde_encoding: DW_EH_PE_ABSPTR,
+        lsda_encoding: DW_EH_PE_OMIT,
+        personality: None,
+        signal_frame: false,
+        initial_instructions: Vec::new(),
+        instructions_addr: 0,
+    };
+    if let Some(aug) = cie.augmentation.strip_prefix('z') {
+        let len = r.uleb()?;
+        let mut data = r.split(len)?;
+        for c in aug.chars() {
+            match c {
+                'L' => cie.lsda_encoding = data.u8()?,
+                'P' => {
+                    let enc = data.u8()?;
+                    cie.personality = ctx.read(&mut data, enc, 0);
+                }
+                'R' => cie.fde_encoding = data.u8()?,
+                'S' => cie.signal_frame = true,
+                // AArch64 BTI and MTE markers carry no data
+                'B' | 'G' => {}
+                _ => break,
+            }
+        }
+    }
+    cie.instructions_addr = ctx.base.wrapping_add(r.pos as u64);
+    cie.initial_instructions = r.rest().to_vec();
+    Some(cie)
+}
+
+fn parse_fde(
+    r: &mut Reader,
+    offset: u64,
+    cie_index: usize,
+    cie: &Cie,
+    ctx: &PointerCtx,
+) -> Option<Fde> {
+    let ctx = PointerCtx {
+        address_size: cie.address_size,
+        ..*ctx
+    };
+    let pc_begin = ctx.read(r, cie.fde_encoding, 0)?;
+    let pc_range = ctx.read(r, cie.fde_encoding & 0x0f, 0)?;
+    let mut lsda = None;
+    if cie.augmentation.starts_with('z') {
+        let len = r.uleb()?;
+        let mut data = r.split(len)?;
+        if cie.lsda_encoding != DW_EH_PE_OMIT {
+            lsda = ctx
+                .read(&mut data, cie.lsda_encoding, pc_begin)
+                .filter(|&a| a != 0);
+        }
+    }
+    Some(Fde {
+        offset,
+        cie: cie_index,
+        pc_begin,
+        pc_range,
+        lsda,
+        instructions_addr: ctx.base.wrapping_add(r.pos as u64),
+        instructions: r.rest().to_vec(),
+    })
+}
+
+/// Call frame instruction interpreter for one FDE
+struct Executor<'a> {
+    frame: &'a EhFrame,
+    cie: &'a Cie,
+    /// The state after the CIE's initial instructions, used by `DW_CFA_restore`
+    initial: Option<State>,
+    stack: Vec<State>,
+}
+
+impl Executor<'_> {
+    fn run(
+        &mut self,
+        instructions: &[u8],
+        addr: u64,
+        state: &mut State,
+        loc: &mut u64,
+        mut rows: Option<&mut Vec<UnwindRow>>,
+    ) -> Result<(), Box<dyn Error>> {
+        let truncated = || -> Box<dyn Error> { "truncated call frame instruction".into() };
+        let ctx = self.frame.ctx.ctx(None, addr);
+        let mut r = Reader::new(instructions, 0, self.frame.ctx.big_endian);
+        let code_align = self.cie.code_alignment_factor;
+        let data_align = self.cie.data_alignment_factor;
+        while !r.is_empty() {
+            let op = r.u8().ok_or_else(truncated)?;
+            let operand = op & 0x3f;
+            let new_loc = match op & 0xc0 {
+                DW_CFA_ADVANCE_LOC => Some(loc.wrapping_add(advance(operand as u64, code_align)?)),
+                DW_CFA_OFFSET => {
+                    let off = factor(signed(r.uleb().ok_or_else(truncated)?)?, data_align)?;
+                    state
+                        .registers
+                        .insert(operand as u16, RegisterRule::Offset(off));
+                    None
+                }
+                DW_CFA_RESTORE => {
+                    self.restore(state, operand as u16);
+                    None
+                }
+                _ => match self.extended(op, &mut r, &ctx, state)? {
+                    Some(Advance::By(delta)) => Some(loc.wrapping_add(advance(delta, code_align)?)),
+                    Some(Advance::To(addr)) => Some(addr),
+                    None => None,
+                },
+            };
+            if let Some(new_loc) = new_loc {
+                let new_loc = ctx.truncate(new_loc);
+                if new_loc < *loc {
+                    return Err(format!("location moves backwards to {:#x}", new_loc).into());
+                }
+                if let Some(rows) = rows.as_deref_mut() {
+                    if new_loc > *loc {
+                        rows.push(UnwindRow {
+                            address: *loc..new_loc,
+                            cfa: state.cfa.clone(),
+                            registers: state.registers.clone(),
+                        });
+                    }
+                }
+                *loc = new_loc;
+            }
+        }
+        Ok(())
+    }
+
+    fn restore(&self, state: &mut State, reg: u16) {
+        match self.initial.as_ref().and_then(|s| s.registers.get(&reg)) {
+            Some(rule) => state.registers.insert(reg, rule.clone()),
+            None => state.registers.remove(&reg),
+        };
+    }
+
+    /// Executes an instruction without an operand in its opcode byte
+    fn extended(
+        &mut self,
+        op: u8,
+        r: &mut Reader,
+        ctx: &PointerCtx,
+        state: &mut State,
+    ) -> Result<Option<Advance>, Box<dyn Error>> {
+        let data_align = self.cie.data_alignment_factor;
+        let reg = |r: &mut Reader| -> Result<u16, Box<dyn Error>> {
+            let reg = r.uleb().ok_or("truncated call frame instruction")?;
+            Ok(u16::try_from(reg).map_err(|_| format!("invalid register {}", reg))?)
+        };
+        let uleb = |r: &mut Reader| r.uleb().ok_or("truncated call frame instruction");
+        let sleb = |r: &mut Reader| r.sleb().ok_or("truncated call frame instruction");
+        let block = |r: &mut Reader| -> Result<Vec<u8>, Box<dyn Error>> {
+            let len = usize::try_from(r.uleb().ok_or("truncated call frame instruction")?)?;
+            Ok(r.bytes(len).ok_or("truncated DWARF expression")?.to_vec())
+        };
+        let set_cfa_offset = |state: &mut State, new: i64| {
+            if let CfaRule::RegisterOffset { offset, .. } = &mut state.cfa {
+                *offset = new;
+            }
+        };
+        match op {
+            DW_CFA_NOP => {}
+            DW_CFA_SET_LOC => {
+                let addr = ctx
+                    .read(r, self.cie.fde_encoding, 0)
+                    .ok_or("truncated DW_CFA_set_loc")?;
+                return Ok(Some(Advance::To(addr)));
+            }
+            DW_CFA_ADVANCE_LOC1 => return Ok(r.uint(1).map(Advance::By)),
+            DW_CFA_ADVANCE_LOC2 => return Ok(r.uint(2).map(Advance::By)),
+            DW_CFA_ADVANCE_LOC4 => return Ok(r.uint(4).map(Advance::By)),
+            DW_CFA_OFFSET_EXTENDED => {
+                let reg = reg(r)?;
+                let off = factor(signed(uleb(r)?)?, data_align)?;
+                state.registers.insert(reg, RegisterRule::Offset(off));
+            }
+            DW_CFA_RESTORE_EXTENDED => {
+                let reg = reg(r)?;
+                self.restore(state, reg);
+            }
+            DW_CFA_UNDEFINED => {
+                state.registers.insert(reg(r)?, RegisterRule::Undefined);
+            }
+            DW_CFA_SAME_VALUE => {
+                state.registers.insert(reg(r)?, RegisterRule::SameValue);
+            }
+            DW_CFA_REGISTER => {
+                let reg1 = reg(r)?;
+                let reg2 = reg(r)?;
+                state.registers.insert(reg1, RegisterRule::Register(reg2));
+            }
+            DW_CFA_REMEMBER_STATE => self.stack.push(state.clone()),
+            DW_CFA_RESTORE_STATE => {
+                *state = self
+                    .stack
+                    .pop()
+                    .ok_or("DW_CFA_restore_state without state")?;
+            }
+            DW_CFA_DEF_CFA => {
+                let register = reg(r)?;
+                let offset = signed(uleb(r)?)?;
+                state.cfa = CfaRule::RegisterOffset { register, offset };
+            }
+            DW_CFA_DEF_CFA_SF => {
+                let register = reg(r)?;
+                let offset = factor(sleb(r)?, data_align)?;
+                state.cfa = CfaRule::RegisterOffset { register, offset };
+            }
+            DW_CFA_DEF_CFA_REGISTER => {
+                let new = reg(r)?;
+                match &mut state.cfa {
+                    CfaRule::RegisterOffset { register, .. } => *register = new,
+                    cfa => {
+                        *cfa = CfaRule::RegisterOffset {
+                            register: new,
+                            offset: 0,
+                        }
+                    }
+                }
+            }
+            DW_CFA_DEF_CFA_OFFSET => set_cfa_offset(state, signed(uleb(r)?)?),
+            DW_CFA_DEF_CFA_OFFSET_SF => set_cfa_offset(state, factor(sleb(r)?, data_align)?),
+            DW_CFA_DEF_CFA_EXPRESSION => state.cfa = CfaRule::Expression(block(r)?),
+            DW_CFA_EXPRESSION => {
+                let reg = reg(r)?;
+                state
+                    .registers
+                    .insert(reg, RegisterRule::Expression(block(r)?));
+            }
+            DW_CFA_VAL_EXPRESSION => {
+                let reg = reg(r)?;
+                state
+                    .registers
+                    .insert(reg, RegisterRule::ValExpression(block(r)?));
+            }
+            DW_CFA_OFFSET_EXTENDED_SF => {
+                let reg = reg(r)?;
+                let off = factor(sleb(r)?, data_align)?;
+                state.registers.insert(reg, RegisterRule::Offset(off));
+            }
+            DW_CFA_VAL_OFFSET => {
+                let reg = reg(r)?;
+                let off = factor(signed(uleb(r)?)?, data_align)?;
+                state.registers.insert(reg, RegisterRule::ValOffset(off));
+            }
+            DW_CFA_VAL_OFFSET_SF => {
+                let reg = reg(r)?;
+                let off = factor(sleb(r)?, data_align)?;
+                state.registers.insert(reg, RegisterRule::ValOffset(off));
+            }
+            DW_CFA_GNU_ARGS_SIZE => {
+                uleb(r)?;
+            }
+            DW_CFA_GNU_NEGATIVE_OFFSET_EXTENDED => {
+                let reg = reg(r)?;
+                let off = factor(-signed(uleb(r)?)?, data_align)?;
+                state.registers.insert(reg, RegisterRule::Offset(off));
+            }
+            DW_CFA_GNU_WINDOW_SAVE => {
+                // On SPARC the in and local registers of the caller are saved in the
+                // register window at the CFA. AArch64 reuses the opcode for return
+                // address signing, which does not change any rule.
+                let sparc = [Machine::Sparc, Machine::Sparc32Plus, Machine::SparcV9]
+                    .iter()
+                    .any(|&m| u16::from(m) == self.frame.machine);
+                if sparc {
+                    let size = self.frame.ctx.address_size as i64;
+                    for reg in 16..32u16 {
+                        let off = (reg as i64 - 16) * size;
+                        state.registers.insert(reg, RegisterRule::Offset(off));
+                    }
+                }
+            }
+            op => return Err(format!("unknown call frame instruction {:#x}", op).into()),
+        }
+        Ok(None)
+    }
+}
+
+/// Scales a location delta by the code alignment factor
+fn advance(delta: u64, code_align: u64) -> Result<u64, Box<dyn Error>> {
+    delta
+        .checked_mul(code_align)
+        .ok_or_else(|| "location advance overflows".into())
+}
+
+/// Scales an offset by the data alignment factor
+fn factor(value: i64, data_align: i64) -> Result<i64, Box<dyn Error>> {
+    value
+        .checked_mul(data_align)
+        .ok_or_else(|| "call frame offset overflows".into())
+}
+
+/// Converts an unsigned operand to an offset
+fn signed(value: u64) -> Result<i64, Box<dyn Error>> {
+    i64::try_from(value).map_err(|_| "call frame offset overflows".into())
+}
+
+enum Advance {
+    /// Advance by a number of code alignment units
+    By(u64),
+    /// Move to an absolute address
+    To(u64),
+}
+
+/// The `.eh_frame_hdr` binary search table
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EhFrameHdr {
+    /// Virtual address of the header
+    pub address: u64,
+    pub version: u8,
+    /// Virtual address of the `.eh_frame` section
+    pub eh_frame_ptr: u64,
+    /// Pairs of function start address and FDE address, sorted by the former
+    pub table: Vec<(u64, u64)>,
+}
+
+impl EhFrameHdr {
+    /// Returns the address of the FDE that may cover `pc`, as found by the binary search
+    /// table. The FDE's range still needs to be checked.
+    pub fn lookup(&self, pc: u64) -> Option<u64> {
+        let i = self.table.partition_point(|&(start, _)| start <= pc);
+        self.table.get(i.checked_sub(1)?).map(|&(_, fde)| fde)
+    }
+}
+
+impl ElfFile {
+    /// Returns the contents and address of `.eh_frame_hdr`, falling back to the
+    /// `PT_GNU_EH_FRAME` segment for files without section headers
+    fn eh_frame_hdr_data(&self) -> Option<(&[u8], u64)> {
+        if let Some(sh) = self.section_by_name(".eh_frame_hdr") {
+            return Some((self.section_data(sh)?, sh.sh_addr));
+        }
+        let ph = self
+            .program_headers
+            .iter()
+            .find(|ph| ph.p_type == PT_GNU_EH_FRAME)?;
+        Some((self.segment_data(ph)?, ph.p_vaddr))
+    }
+
+    /// Parses the `.eh_frame_hdr` search table
+    pub fn eh_frame_hdr(&self) -> Result<EhFrameHdr, Box<dyn Error>> {
+        let (data, address) = self.eh_frame_hdr_data().ok_or("no .eh_frame_hdr found")?;
+        let ctx = PointerCtx {
+            elf: Some(self),
+            base: address,
+            data_base: address,
+            text_base: 0,
+            address_size: self.addr_size() as u8,
+            big_endian: self.is_big_endian(),
+        };
+        let truncated = || "truncated .eh_frame_hdr";
+        let mut r = Reader::new(data, 0, self.is_big_endian());
+        let version = r.u8().ok_or_else(truncated)?;
+        if version != 1 {
+            return Err(format!("unsupported .eh_frame_hdr version {}", version).into());
+        }
+        let ptr_enc = r.u8().ok_or_else(truncated)?;
+        let count_enc = r.u8().ok_or_else(truncated)?;
+        let table_enc = r.u8().ok_or_else(truncated)?;
+        let eh_frame_ptr = ctx.read(&mut r, ptr_enc, 0).ok_or_else(truncated)?;
+        let mut table = Vec::new();
+        if count_enc != DW_EH_PE_OMIT && table_enc != DW_EH_PE_OMIT {
+            let count = ctx.read(&mut r, count_enc, 0).ok_or_else(truncated)?;
+            for _ in 0..count {
+                let start = ctx.read(&mut r, table_enc, 0).ok_or_else(truncated)?;
+                let fde = ctx.read(&mut r, table_enc, 0).ok_or_else(truncated)?;
+                table.push((start, fde));
+            }
+        }
+        Ok(EhFrameHdr {
+            address,
+            version,
+            eh_frame_ptr,
+            table,
+        })
+    }
+
+    /// Parses the `.eh_frame` section. Files without section headers are handled by
+    /// following `PT_GNU_EH_FRAME` to the start of the section.
+    pub fn eh_frame(&self) -> Result<EhFrame, Box<dyn Error>> {
+        if let Some(sh) = self.section_by_name(".eh_frame") {
+            let data = self
+                .section_data(sh)
+                .ok_or(".eh_frame lies outside the file")?;
+            return EhFrame::parse(self, data, sh.sh_addr, false);
+        }
+        let address = self.eh_frame_hdr()?.eh_frame_ptr;
+        let offset = self
+            .vaddr_to_offset(address)
+            .ok_or(".eh_frame is not file backed")?;
+        let end = self
+            .program_headers
+            .iter()
+            .filter(|ph| ph.p_type == PT_LOAD)
+            .find(|ph| ph.p_offset <= offset && offset < ph.p_offset.saturating_add(ph.p_filesz))
+            .map_or(offset, |ph| ph.p_offset.saturating_add(ph.p_filesz));
+        let data = self
+            .bytes_at(offset, end - offset)
+            .ok_or(".eh_frame lies outside the file")?;
+        EhFrame::parse(self, data, address, false)
+    }
+
+    /// Parses the `.debug_frame` section
+    pub fn debug_frame(&self) -> Result<EhFrame, Box<dyn Error>> {
+        let sh = self
+            .section_by_name(".debug_frame")
+            .ok_or("no .debug_frame section")?;
+        let data = self
+            .section_data(sh)
+            .ok_or(".debug_frame lies outside the file")?;
+        EhFrame::parse(self, data, 0, true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RSP: u16 = 7;
+    const RA: u16 = 16;
+
+    #[test]
+    fn test_eh_frame_hdr_matches_fdes() {
+        let elf = ElfFile::open("tests/bin/dwarf5").unwrap();
+        let hdr = elf.eh_frame_hdr().unwrap();
+        let eh = elf.eh_frame().unwrap();
+        assert_eq!(hdr.eh_frame_ptr, eh.address);
+        assert_eq!(hdr.table.len(), eh.fdes.len());
+        for &(start, fde_addr) in &hdr.table {
+            let fde = eh
+                .fdes
+                .iter()
+                .find(|f| f.offset == fde_addr - eh.address)
+                .unwrap();
+            assert_eq!(fde.pc_begin, start);
+        }
+        let fde_addr = hdr.lookup(0x11a5).unwrap();
+        assert_eq!(eh.fde_for_pc(0x11a5).unwrap().offset, fde_addr - eh.address);
+        assert_eq!(eh.cies[0].augmentation, "zR");
+        assert_eq!(eh.cies[0].return_address_register, RA);
+    }
+
+    #[test]
+    fn test_unwind_rows() {
+        let eh = ElfFile::open("tests/bin/dwarf5")
+            .unwrap()
+            .eh_frame()
+            .unwrap();
+        let fde = eh.fde_for_pc(0x1180).unwrap();
+        assert_eq!(fde.range(), 0x1180..0x11cd);
+        let rows = eh.unwind_table(fde).unwrap();
+        assert_eq!(rows.len(), 11);
+
+        let entry = eh.unwind_row(0x1180).unwrap();
+        assert_eq!(
+            entry.cfa,
+            CfaRule::RegisterOffset {
+                register: RSP,
+                offset: 8
+            }
+        );
+        assert_eq!(entry.registers[&RA], RegisterRule::Offset(-8));
+
+        // After four pushes and the stack adjustment, restored again in the epilogue
+        let body = eh.unwind_row(0x11a5).unwrap();
+        assert_eq!(body.address, 0x1190..0x11c3);
+        assert_eq!(
+            body.cfa,
+            CfaRule::RegisterOffset {
+                register: RSP,
+                offset: 48
+            }
+        );
+        assert_eq!(body.registers[&3], RegisterRule::Offset(-40));
+        assert_eq!(body.registers[&13], RegisterRule::Offset(-16));
+        let ret = eh.unwind_row(0x11cc).unwrap();
+        assert_eq!(ret.cfa, entry.cfa);
+        assert!(eh.unwind_row(0x11cd).is_err());
+    }
+
+    #[test]
+    fn test_rule_overflow() {
+        let mut eh = ElfFile::open("tests/bin/dwarf5")
+            .unwrap()
+            .eh_frame()
+            .unwrap();
+        let mut fde = eh.fde_for_pc(0x1180).unwrap().clone();
+        let uleb = |mut v: u64| {
+            let mut out = Vec::new();
+            loop {
+                let byte = (v & 0x7f) as u8;
+                v >>= 7;
+                if v == 0 {
+                    out.push(byte);
+                    return out;
+                }
+                out.push(byte | 0x80);
+            }
+        };
+        for instructions in [
+            // DW_CFA_offset r3 with an offset that overflows once scaled
+            [vec![DW_CFA_OFFSET | 3], uleb(1 << 62)].concat(),
+            // DW_CFA_def_cfa with an offset beyond i64::MAX
+            [vec![DW_CFA_DEF_CFA, 7], uleb(u64::MAX)].concat(),
+            [vec![DW_CFA_GNU_NEGATIVE_OFFSET_EXTENDED, 3], uleb(1 << 62)].concat(),
+            [vec![DW_CFA_VAL_OFFSET_SF, 3], vec![0xff; 8], vec![0x3f]].concat(),
+        ] {
+            fde.instructions = instructions;
+            assert!(eh.unwind_table(&fde).is_err());
+        }
+        fde.instructions = vec![DW_CFA_ADVANCE_LOC | 2];
+        eh.cies[fde.cie].code_alignment_factor = u64::MAX;
+        assert!(eh.unwind_table(&fde).is_err());
+    }
+
+    #[test]
+    fn test_cie_address_size() {
+        let ctx = PointerCtx {
+            elf: None,
+            base: 0,
+            data_base: 0,
+            text_base: 0,
+            address_size: 8,
+            big_endian: false,
+        };
+        // Version 4, no augmentation, address and segment selector size, alignment
+        // factors and the return address register
+        let cie = |size: u8| [4, 0, size, 0, 1, 0x78, 16];
+        let parse = |data: &[u8]| parse_cie(&mut Reader::new(data, 0, false), 0, &ctx);
+        assert_eq!(parse(&cie(4)).unwrap().address_size, 4);
+        assert!(parse(&cie(0)).is_none());
+    }
+
+    #[test]
+    fn test_mips_big_endian_frames() {
+        let elf = ElfFile::open("tests/bin/objdump.mips").unwrap();
+        let eh = elf.eh_frame().unwrap();
+        assert!(!eh.fdes.is_empty());
+        for fde in &eh.fdes {
+            eh.unwind_table(fde).unwrap();
+        }
+        let debug = elf.debug_frame().unwrap();
+        assert!(!debug.fdes.is_empty());
+        let fde = &debug.fdes[0];
+        let row = debug.unwind_row(fde.pc_begin).unwrap();
+        // The MIPS stack pointer is $29
+        assert_eq!(
+            row.cfa,
+            CfaRule::RegisterOffset {
+                register: 29,
+                offset: 0
+            }
+        );
+    }
+
+    #[test]
+    fn test_segment_size_overflow() {
+        let mut elf = ElfFile::open("tests/bin/dwarf5").unwrap();
+        let address = elf.eh_frame().unwrap().address;
+        elf.section_headers.clear();
+        for ph in elf
+            .program_headers
+            .iter_mut()
+            .filter(|ph| ph.p_type == PT_LOAD)
+        {
+            if ph.p_vaddr <= address && address - ph.p_vaddr < ph.p_filesz {
+                ph.p_filesz = u64::MAX;
+            }
+        }
+        assert!(elf.eh_frame().is_err());
+    }
+}
@@ -0,0 +1,297 @@
+use std::{error::Error, fs, path::Path};
+
+use crate::archive::Archive;
+use crate::elf_types::SHT_NOBITS;
+use crate::{
+    cstr_at, extended_numbering, ElfHeader32, ElfHeader64, ProgramHeader32, ProgramHeader64,
+    SectionHeader32, SectionHeader64, EI_NIDENT, SIZEOF_EHDR32, SIZEOF_EHDR64, SIZEOF_PHDR32,
+    SIZEOF_PHDR64, SIZEOF_SHDR32, SIZEOF_SHDR64,
+};
+
+pub const ELFMAG: [u8; 4] = [0x7f, b'E', b'L', b'F'];
+pub const ELFCLASS32: u8 = 1;
+pub const ELFCLASS64: u8 = 2;
+pub const ELFDATA2LSB: u8 = 1;
+pub const ELFDATA2MSB: u8 = 2;
+
+/// An ELF image held in memory together with its parsed header tables.
+///
+/// All headers are widened to their 64-bit representation so the analysis passes only
+/// have to be written once. Unlike the path based helpers this never panics on malformed
+/// input: table entries that do not fit into the file are simply not part of the parsed
+/// view, which is what [`crate::validate`] reports on.
+#[derive(Debug, Clone)]
+pub struct ElfFile {
+    data: Vec<u8>,
+    pub header: ElfHeader64,
+    pub program_headers: Vec<ProgramHeader64>,
+    pub section_headers: Vec<SectionHeader64>,
+    phnum: usize,
+    shnum: usize,
+    shstrndx: usize,
+}
+
+impl ElfFile {
+    /// Reads and parses the ELF binary at the given path
+    pub fn open<P: AsRef<Path>>(elf_path: P) -> Result<ElfFile, Box<dyn Error>> {
+        ElfFile::parse(fs::read(elf_path)?)
+    }
+
+    /// Parses an ELF image from an owned byte buffer
+    pub fn parse(data: Vec<u8>) -> Result<ElfFile, Box<dyn Error>> {
+        if Archive::is_archive(&data) {
+            return Err("not an ELF file but an ar archive, use Archive::parse".into());
+        }
+        if data.len() < EI_NIDENT || data[..4] != ELFMAG {
+            return Err("not an ELF file (bad magic)".into());
+        }
+        let bit = data[0x5];
+        let header = match data[0x4] {
+            ELFCLASS32 => {
+                let mut eh = ElfHeader32::default();
+                plain::copy_from_bytes(
+                    &mut eh,
+                    data.get(..SIZEOF_EHDR32).ok_or("truncated ELF32 header")?,
+                )
+                .map_err(|_| "truncated ELF32 header")?;
+                ElfHeader64::from(*ElfHeader32::fix_header(&mut eh))
+            }
+            ELFCLASS64 => {
+                let mut eh = ElfHeader64::default();
+                plain::copy_from_bytes(
+                    &mut eh,
+                    data.get(..SIZEOF_EHDR64).ok_or("truncated ELF64 header")?,
+                )
+                .map_err(|_| "truncated ELF64 header")?;
+                *ElfHeader64::fix_header(&mut eh)
+            }
+            c => return Err(format!("invalid ELF class {}", c).into()),
+        };
+        let is_64 = data[0x4] == ELFCLASS64;
+
+        let entsize = if is_64 { SIZEOF_SHDR64 } else { SIZEOF_SHDR32 };
+        let sh0 = table_offsets(&data, header.e_shoff, header.e_shentsize, 1, entsize)
+            .first()
+            .filter(|_| header.e_shoff != 0)
+            .map(|&off| read_section_header(&data[off..off + entsize], is_64, bit))
+            .transpose()?;
+        let (phnum, shnum, shstrndx) = extended_numbering(&header, sh0.as_ref());
+
+        let mut program_headers = Vec::new();
+        let entsize = if is_64 { SIZEOF_PHDR64 } else { SIZEOF_PHDR32 };
+        for off in table_offsets(
+            &data,
+            header.e_phoff,
+            header.e_phentsize,
+            phnum as u64,
+            entsize,
+        ) {
+            let raw = &data[off..off + entsize];
+            program_headers.push(if is_64 {
+                let mut ph = ProgramHeader64::default();
+                plain::copy_from_bytes(&mut ph, raw).map_err(|_| "bad program header")?;
+                *ProgramHeader64::fix_program_header(&mut ph, bit)
+            } else {
+                let mut ph = ProgramHeader32::default();
+                plain::copy_from_bytes(&mut ph, raw).map_err(|_| "bad program header")?;
+                ProgramHeader64::from(*ProgramHeader32::fix_program_header(&mut ph, bit))
+            });
+        }
+
+        let mut section_headers = Vec::new();
+        let entsize = if is_64 { SIZEOF_SHDR64 } else { SIZEOF_SHDR32 };
+        for off in table_offsets(
+            &data,
+            header.e_shoff,
+            header.e_shentsize,
+            shnum as u64,
+            entsize,
+        ) {
+            section_headers.push(read_section_header(&data[off..off + entsize], is_64, bit)?);
+        }
+
+        Ok(ElfFile {
+            data,
+            header,
+            program_headers,
+            section_headers,
+            phnum,
+            shnum,
+            shstrndx,
+        })
+    }
+
+    /// Returns the number of program headers, honouring the `PN_XNUM` escape
+    pub fn phnum(&self) -> usize {
+        self.phnum
+    }
+
+    /// Returns the number of section headers, honouring the `e_shnum == 0` escape
+    pub fn shnum(&self) -> usize {
+        self.shnum
+    }
+
+    /// Returns the section header string table index, honouring the `SHN_XINDEX` escape
+    pub fn shstrndx(&self) -> usize {
+        self.shstrndx
+    }
+
+    /// Returns the raw bytes of the whole image
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Returns `true` for ELFCLASS64 images
+    pub fn is_64(&self) -> bool {
+        self.header.e_ident[0x4] == ELFCLASS64
+    }
+
+    /// Returns `true` for ELFDATA2MSB images
+    pub fn is_big_endian(&self) -> bool {
+        self.header.e_ident[0x5] == ELFDATA2MSB
+    }
+
+    /// Returns the raw `e_machine` value
+    pub fn machine(&self) -> u16 {
+        self.header.e_machine
+    }
+
+    /// Returns the bytes in `[offset, offset + size)` if they are fully inside the file
+    pub fn bytes_at(&self, offset: u64, size: u64) -> Option<&[u8]> {
+        let end = offset.checked_add(size)?;
+        if end > self.data.len() as u64 {
+            return None;
+        }
+        Some(&self.data[offset as usize..end as usize])
+    }
+
+    /// Returns the file contents of a section. `SHT_NOBITS` sections yield an empty slice.
+    pub fn section_data(&self, sh: &SectionHeader64) -> Option<&[u8]> {
+        if sh.sh_type == SHT_NOBITS {
+            return Some(&[]);
+        }
+        self.bytes_at(sh.sh_offset, sh.sh_size)
+    }
+
+    /// Returns the file backed contents of a segment
+    pub fn segment_data(&self, ph: &ProgramHeader64) -> Option<&[u8]> {
+        self.bytes_at(ph.p_offset, ph.p_filesz)
+    }
+
+    /// Returns the section header string table, if `e_shstrndx` points to a valid section
+    pub fn shstrtab(&self) -> Option<&[u8]> {
+        let sh = self.section_headers.get(self.shstrndx)?;
+        self.section_data(sh)
+    }
+
+    /// Returns the name of a section as found in the section header string table
+    pub fn section_name(&self, sh: &SectionHeader64) -> &str {
+        self.shstrtab()
+            .map_or("", |t| cstr_at(t, sh.sh_name as usize))
+    }
+
+    /// Returns the first section with the given name
+    pub fn section_by_name(&self, name: &str) -> Option<&SectionHeader64> {
+        self.section_headers
+            .iter()
+            .find(|sh| self.section_name(sh) == name)
+    }
+
+    /// Translates a virtual address into a file offset using the PT_LOAD segments
+    pub fn vaddr_to_offset(&self, vaddr: u64) -> Option<u64> {
+        self.program_headers
+            .iter()
+            .filter(|ph| ph.p_type == crate::elf_types::PT_LOAD)
+            .find(|ph| vaddr >= ph.p_vaddr && vaddr - ph.p_vaddr < ph.p_filesz)
+            .and_then(|ph| (vaddr - ph.p_vaddr).checked_add(ph.p_offset))
+    }
+
+    /// Translates a file offset into a virtual address using the PT_LOAD segments
+    pub fn offset_to_vaddr(&self, offset: u64) -> Option<u64> {
+        self.program_headers
+            .iter()
+            .filter(|ph| ph.p_type == crate::elf_types::PT_LOAD)
+            .find(|ph| offset >= ph.p_offset && offset - ph.p_offset < ph.p_filesz)
+            .and_then(|ph| (offset - ph.p_offset).checked_add(ph.p_vaddr))
+    }
+
+    /// Reads a `u16` in file byte order at `off` within `bytes`
+    pub fn read_u16(&self, bytes: &[u8], off: usize) -> Option<u16> {
+        let b: [u8; 2] = bytes.get(off..off.checked_add(2)?)?.try_into().ok()?;
+        Some(if self.is_big_endian() {
+            u16::from_be_bytes(b)
+        } else {
+            u16::from_le_bytes(b)
+        })
+    }
+
+    /// Reads a `u32` in file byte order at `off` within `bytes`
+    pub fn read_u32(&self, bytes: &[u8], off: usize) -> Option<u32> {
+        let b: [u8; 4] = bytes.get(off..off.checked_add(4)?)?.try_into().ok()?;
+        Some(if self.is_big_endian() {
+            u32::from_be_bytes(b)
+        } else {
+            u32::from_le_bytes(b)
+        })
+    }
+
+    /// Reads a `u64` in file byte order at `off` within `bytes`
+    pub fn read_u64(&self, bytes: &[u8], off: usize) -> Option<u64> {
+        let b: [u8; 8] = bytes.get(off..off.checked_add(8)?)?.try_into().ok()?;
+        Some(if self.is_big_endian() {
+            u64::from_be_bytes(b)
+        } else {
+            u64::from_le_bytes(b)
+        })
+    }
+
+    /// Reads a class sized address (4 or 8 bytes) at `off` within `bytes`
+    pub fn read_addr(&self, bytes: &[u8], off: usize) -> Option<u64> {
+        if self.is_64() {
+            self.read_u64(bytes, off)
+        } else {
+            self.read_u32(bytes, off).map(u64::from)
+        }
+    }
+
+    /// Returns the size in bytes of a class sized address
+    pub fn addr_size(&self) -> usize {
+        if self.is_64() {
+            8
+        } else {
+            4
+        }
+    }
+}
+
+fn read_section_header(
+    raw: &[u8],
+    is_64: bool,
+    bit: u8,
+) -> Result<SectionHeader64, Box<dyn Error>> {
+    Ok(if is_64 {
+        let mut sh = SectionHeader64::default();
+        plain::copy_from_bytes(&mut sh, raw).map_err(|_| "bad section header")?;
+        *SectionHeader64::fix_section_header(&mut sh, bit)
+    } else {
+        let mut sh = SectionHeader32::default();
+        plain::copy_from_bytes(&mut sh, raw).map_err(|_| "bad section header")?;
+        SectionHeader64::from(*SectionHeader32::fix_section_header(&mut sh, bit))
+    })
+}
+
+/// Returns the file offsets of all table entries that are fully inside `data`
+fn table_offsets(data: &[u8], base: u64, entsize: u16, count: u64, min_size: usize) -> Vec<usize> {
+    let stride = if entsize == 0 {
+        min_size as u64
+    } else {
+        entsize as u64
+    };
+    (0..count)
+        .map_while(|i| {
+            let off = base.checked_add(i.checked_mul(stride)?)?;
+            let end = off.checked_add(min_size as u64)?;
+            (end <= data.len() as u64).then_some(off as usize)
+        })
+        .collect()
+}
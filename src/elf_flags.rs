@@ -0,0 +1,675 @@
+use crate::elf_types::Machine;
+use core::fmt;
+
+// ARM specific e_flags
+pub const EF_ARM_EABIMASK: u32 = 0xff00_0000;
+pub const EF_ARM_RELEXEC: u32 = 0x01;
+pub const EF_ARM_HASENTRY: u32 = 0x02;
+pub const EF_ARM_INTERWORK: u32 = 0x04;
+pub const EF_ARM_APCS_26: u32 = 0x08;
+pub const EF_ARM_APCS_FLOAT: u32 = 0x10;
+pub const EF_ARM_PIC: u32 = 0x20;
+pub const EF_ARM_ALIGN8: u32 = 0x40;
+pub const EF_ARM_NEW_ABI: u32 = 0x80;
+pub const EF_ARM_OLD_ABI: u32 = 0x100;
+pub const EF_ARM_SOFT_FLOAT: u32 = 0x200;
+pub const EF_ARM_VFP_FLOAT: u32 = 0x400;
+pub const EF_ARM_MAVERICK_FLOAT: u32 = 0x800;
+pub const EF_ARM_SYMSARESORTED: u32 = 0x04;
+pub const EF_ARM_DYNSYMSUSESEGIDX: u32 = 0x08;
+pub const EF_ARM_MAPSYMSFIRST: u32 = 0x10;
+pub const EF_ARM_ABI_FLOAT_SOFT: u32 = 0x200;
+pub const EF_ARM_ABI_FLOAT_HARD: u32 = 0x400;
+pub const EF_ARM_LE8: u32 = 0x0040_0000;
+pub const EF_ARM_BE8: u32 = 0x0080_0000;
+
+// MIPS specific e_flags
+pub const EF_MIPS_NOREORDER: u32 = 0x1;
+pub const EF_MIPS_PIC: u32 = 0x2;
+pub const EF_MIPS_CPIC: u32 = 0x4;
+pub const EF_MIPS_XGOT: u32 = 0x8;
+pub const EF_MIPS_UCODE: u32 = 0x10;
+pub const EF_MIPS_ABI2: u32 = 0x20;
+pub const EF_MIPS_OPTIONS_FIRST: u32 = 0x80;
+pub const EF_MIPS_32BITMODE: u32 = 0x100;
+pub const EF_MIPS_FP64: u32 = 0x200;
+pub const EF_MIPS_NAN2008: u32 = 0x400;
+pub const EF_MIPS_ABI: u32 = 0x0000_f000;
+pub const EF_MIPS_MACH: u32 = 0x00ff_0000;
+pub const EF_MIPS_ARCH_ASE: u32 = 0x0f00_0000;
+pub const EF_MIPS_ARCH: u32 = 0xf000_0000;
+
+// RISC-V specific e_flags
+pub const EF_RISCV_RVC: u32 = 0x1;
+pub const EF_RISCV_FLOAT_ABI: u32 = 0x6;
+pub const EF_RISCV_RVE: u32 = 0x8;
+pub const EF_RISCV_TSO: u32 = 0x10;
+
+// PowerPC specific e_flags
+pub const EF_PPC_EMB: u32 = 0x8000_0000;
+pub const EF_PPC_RELOCATABLE: u32 = 0x0001_0000;
+pub const EF_PPC_RELOCATABLE_LIB: u32 = 0x0000_8000;
+pub const EF_PPC64_ABI: u32 = 0x3;
+
+// SPARC specific e_flags
+pub const EF_SPARCV9_MM: u32 = 0x3;
+pub const EF_SPARC_32PLUS: u32 = 0x100;
+pub const EF_SPARC_SUN_US1: u32 = 0x200;
+pub const EF_SPARC_HAL_R1: u32 = 0x400;
+pub const EF_SPARC_SUN_US3: u32 = 0x800;
+pub const EF_SPARC_LEDATA: u32 = 0x0080_0000;
+
+// LoongArch specific e_flags
+pub const EF_LOONGARCH_ABI_MODIFIER_MASK: u32 = 0x7;
+pub const EF_LOONGARCH_OBJABI_MASK: u32 = 0xc0;
+
+/// Appends the names of all set bits in `table` to `out` and clears them in `rest`
+fn push_bits(out: &mut Vec<String>, rest: &mut u32, table: &[(u32, &str)]) {
+    for (bit, name) in table {
+        if *rest & bit != 0 {
+            out.push(name.to_string());
+            *rest &= !bit;
+        }
+    }
+}
+
+fn decode_arm(flags: u32) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut rest = flags & !EF_ARM_EABIMASK;
+    match flags >> 24 {
+        0 => {
+            out.push("GNU EABI".to_string());
+            push_bits(
+                &mut out,
+                &mut rest,
+                &[
+                    (EF_ARM_RELEXEC, "relocatable executable"),
+                    (EF_ARM_HASENTRY, "has entry point"),
+                    (EF_ARM_INTERWORK, "interworking enabled"),
+                    (EF_ARM_APCS_26, "uses APCS/26"),
+                    (EF_ARM_APCS_FLOAT, "uses APCS/float"),
+                    (EF_ARM_PIC, "position independent"),
+                    (EF_ARM_ALIGN8, "8 bit structure alignment"),
+                    (EF_ARM_NEW_ABI, "uses new ABI"),
+                    (EF_ARM_OLD_ABI, "uses old ABI"),
+                    (EF_ARM_SOFT_FLOAT, "software FP"),
+                    (EF_ARM_VFP_FLOAT, "VFP"),
+                    (EF_ARM_MAVERICK_FLOAT, "Maverick FP"),
+                ],
+            );
+        }
+        1 => {
+            out.push("Version1 EABI".to_string());
+            push_bits(
+                &mut out,
+                &mut rest,
+                &[(EF_ARM_SYMSARESORTED, "sorted symbol tables")],
+            );
+        }
+        2 => {
+            out.push("Version2 EABI".to_string());
+            push_bits(
+                &mut out,
+                &mut rest,
+                &[
+                    (EF_ARM_SYMSARESORTED, "sorted symbol tables"),
+                    (EF_ARM_DYNSYMSUSESEGIDX, "dynamic symbols use segment index"),
+                    (EF_ARM_MAPSYMSFIRST, "mapping symbols precede others"),
+                ],
+            );
+        }
+        3 => out.push("Version3 EABI".to_string()),
+        v @ (4 | 5) => {
+            out.push(format!("Version{} EABI", v));
+            let mut table = vec![(EF_ARM_BE8, "BE8"), (EF_ARM_LE8, "LE8")];
+            if v == 5 {
+                table.push((EF_ARM_ABI_FLOAT_SOFT, "soft-float ABI"));
+                table.push((EF_ARM_ABI_FLOAT_HARD, "hard-float ABI"));
+            }
+            push_bits(&mut out, &mut rest, &table);
+        }
+        v => out.push(format!("<unsupported EABI version {}>", v)),
+    }
+    if rest != 0 {
+        out.push(format!("<unknown: 0x{:x}>", rest));
+    }
+    out
+}
+
+fn decode_mips(flags: u32) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut rest = flags & !(EF_MIPS_ABI | EF_MIPS_MACH | EF_MIPS_ARCH_ASE | EF_MIPS_ARCH);
+    push_bits(
+        &mut out,
+        &mut rest,
+        &[
+            (EF_MIPS_NOREORDER, "noreorder"),
+            (EF_MIPS_PIC, "pic"),
+            (EF_MIPS_CPIC, "cpic"),
+            (EF_MIPS_XGOT, "xgot"),
+            (EF_MIPS_UCODE, "ugen_reserved"),
+            (EF_MIPS_OPTIONS_FIRST, "odk first"),
+        ],
+    );
+    let mach = match flags & EF_MIPS_MACH {
+        0 => None,
+        0x0081_0000 => Some("3900"),
+        0x0082_0000 => Some("4010"),
+        0x0083_0000 => Some("4100"),
+        0x0084_0000 => Some("allegrex"),
+        0x0085_0000 => Some("4650"),
+        0x0087_0000 => Some("4120"),
+        0x0088_0000 => Some("4111"),
+        0x008a_0000 => Some("sb1"),
+        0x008b_0000 => Some("octeon"),
+        0x008c_0000 => Some("xlr"),
+        0x008d_0000 => Some("octeon2"),
+        0x008e_0000 => Some("octeon3"),
+        0x0091_0000 => Some("5400"),
+        0x0092_0000 => Some("5900"),
+        0x0093_0000 => Some("interaptiv-mr2"),
+        0x0098_0000 => Some("5500"),
+        0x0099_0000 => Some("9000"),
+        0x00a0_0000 => Some("loongson-2e"),
+        0x00a1_0000 => Some("loongson-2f"),
+        0x00a2_0000 => Some("gs464"),
+        0x00a3_0000 => Some("gs464e"),
+        0x00a4_0000 => Some("gs264e"),
+        _ => Some("unknown CPU"),
+    };
+    out.extend(mach.map(str::to_string));
+    push_bits(
+        &mut out,
+        &mut rest,
+        &[
+            (EF_MIPS_32BITMODE, "32bitmode"),
+            (EF_MIPS_FP64, "fp64"),
+            (EF_MIPS_NAN2008, "nan2008"),
+        ],
+    );
+    let abi = match flags & EF_MIPS_ABI {
+        0 if rest & EF_MIPS_ABI2 != 0 => Some("n32"),
+        0 => None,
+        0x1000 => Some("o32"),
+        0x2000 => Some("o64"),
+        0x3000 => Some("eabi32"),
+        0x4000 => Some("eabi64"),
+        _ => Some("unknown ABI"),
+    };
+    rest &= !EF_MIPS_ABI2;
+    out.extend(abi.map(str::to_string));
+    let ase = flags & EF_MIPS_ARCH_ASE;
+    for (bit, name) in [
+        (0x0800_0000, "mdmx"),
+        (0x0400_0000, "mips16"),
+        (0x0200_0000, "micromips"),
+    ] {
+        if ase & bit != 0 {
+            out.push(name.to_string());
+        }
+    }
+    let isa = match flags & EF_MIPS_ARCH {
+        0x0000_0000 => "mips1",
+        0x1000_0000 => "mips2",
+        0x2000_0000 => "mips3",
+        0x3000_0000 => "mips4",
+        0x4000_0000 => "mips5",
+        0x5000_0000 => "mips32",
+        0x6000_0000 => "mips64",
+        0x7000_0000 => "mips32r2",
+        0x8000_0000 => "mips64r2",
+        0x9000_0000 => "mips32r6",
+        0xa000_0000 => "mips64r6",
+        _ => "unknown ISA",
+    };
+    out.push(isa.to_string());
+    if rest != 0 {
+        out.push(format!("<unknown: 0x{:x}>", rest));
+    }
+    out
+}
+
+fn decode_riscv(flags: u32) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut rest = flags & !EF_RISCV_FLOAT_ABI;
+    push_bits(&mut out, &mut rest, &[(EF_RISCV_RVC, "RVC")]);
+    out.push(
+        match flags & EF_RISCV_FLOAT_ABI {
+            0x0 => "soft-float ABI",
+            0x2 => "single-float ABI",
+            0x4 => "double-float ABI",
+            _ => "quad-float ABI",
+        }
+        .to_string(),
+    );
+    push_bits(
+        &mut out,
+        &mut rest,
+        &[(EF_RISCV_RVE, "RVE"), (EF_RISCV_TSO, "TSO")],
+    );
+    if rest != 0 {
+        out.push(format!("<unknown: 0x{:x}>", rest));
+    }
+    out
+}
+
+fn decode_ppc(flags: u32) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut rest = flags;
+    push_bits(
+        &mut out,
+        &mut rest,
+        &[
+            (EF_PPC_EMB, "emb"),
+            (EF_PPC_RELOCATABLE, "relocatable"),
+            (EF_PPC_RELOCATABLE_LIB, "relocatable-lib"),
+        ],
+    );
+    if rest != 0 {
+        out.push(format!("<unknown: 0x{:x}>", rest));
+    }
+    out
+}
+
+fn decode_ppc64(flags: u32) -> Vec<String> {
+    let mut out = Vec::new();
+    match flags & EF_PPC64_ABI {
+        0 => {}
+        1 => out.push("abiv1".to_string()),
+        2 => out.push("abiv2".to_string()),
+        v => out.push(format!("<unknown ABI {}>", v)),
+    }
+    let rest = flags & !EF_PPC64_ABI;
+    if rest != 0 {
+        out.push(format!("<unknown: 0x{:x}>", rest));
+    }
+    out
+}
+
+fn decode_sparc(flags: u32, machine: Machine) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut rest = flags;
+    if machine == Machine::SparcV9 {
+        out.push(
+            match flags & EF_SPARCV9_MM {
+                0 => "tso",
+                1 => "pso",
+                2 => "rmo",
+                _ => "unknown memory model",
+            }
+            .to_string(),
+        );
+        rest &= !EF_SPARCV9_MM;
+    }
+    push_bits(
+        &mut out,
+        &mut rest,
+        &[
+            (EF_SPARC_32PLUS, "v8+"),
+            (EF_SPARC_SUN_US1, "ultrasparcI"),
+            (EF_SPARC_HAL_R1, "halr1"),
+            (EF_SPARC_SUN_US3, "ultrasparcIII"),
+            (EF_SPARC_LEDATA, "little endian data"),
+        ],
+    );
+    if rest != 0 {
+        out.push(format!("<unknown: 0x{:x}>", rest));
+    }
+    out
+}
+
+fn decode_loongarch(flags: u32) -> Vec<String> {
+    let mut out = Vec::new();
+    out.push(
+        match flags & EF_LOONGARCH_ABI_MODIFIER_MASK {
+            1 => "SOFT-FLOAT",
+            2 => "SINGLE-FLOAT",
+            3 => "DOUBLE-FLOAT",
+            _ => "unknown float ABI",
+        }
+        .to_string(),
+    );
+    out.push(format!("OBJ-v{}", (flags & EF_LOONGARCH_OBJABI_MASK) >> 6));
+    let rest = flags & !(EF_LOONGARCH_ABI_MODIFIER_MASK | EF_LOONGARCH_OBJABI_MASK);
+    if rest != 0 {
+        out.push(format!("<unknown: 0x{:x}>", rest));
+    }
+    out
+}
+
+/// Decodes the architecture specific `e_flags` into a list of readable attributes.
+/// We assume both `flags` and `machine` have the correct endianness
+pub fn decode_e_flags(flags: u32, machine: u16) -> Vec<String> {
+    let machine = match Machine::try_from(machine) {
+        Ok(m) => m,
+        Err(_) => return Vec::new(),
+    };
+    match machine {
+        Machine::Arm => decode_arm(flags),
+        Machine::Mips | Machine::MipsRs3Le => decode_mips(flags),
+        Machine::Riscv => decode_riscv(flags),
+        Machine::Ppc => decode_ppc(flags),
+        Machine::Ppc64 => decode_ppc64(flags),
+        Machine::Sparc | Machine::Sparc32Plus | Machine::SparcV9 => decode_sparc(flags, machine),
+        Machine::Loongarch => decode_loongarch(flags),
+        _ => Vec::new(),
+    }
+}
+
+/// Returns a readelf-like string representation for the E_FLAGS field,
+/// e.g. `0x70001007, noreorder, pic, cpic, o32, mips32r2`
+pub fn e_flags_to_str(flags: u32, machine: u16) -> String {
+    let mut s = format!("0x{:x}", flags);
+    if flags != 0 {
+        for attr in decode_e_flags(flags, machine) {
+            s.push_str(", ");
+            s.push_str(&attr);
+        }
+    }
+    s
+}
+
+/// Generates a transparent bit set over a raw flag word with the usual set operations
+macro_rules! elf_bitflags {
+    (
+        $(#[$meta:meta])*
+        pub struct $name:ident: $repr:ty {
+            $(const $flag:ident = $value:expr;)*
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Clone, Copy, PartialEq, Eq, Hash, Default)]
+        pub struct $name($repr);
+
+        impl $name {
+            $(pub const $flag: $name = $name($value);)*
+
+            /// Wraps a raw flag word, keeping bits without a known meaning
+            pub const fn from_bits_retain(bits: $repr) -> $name {
+                $name(bits)
+            }
+
+            /// Returns the raw flag word
+            pub const fn bits(self) -> $repr {
+                self.0
+            }
+
+            /// Returns `true` if all bits of `other` are set
+            pub const fn contains(self, other: $name) -> bool {
+                self.0 & other.0 == other.0
+            }
+
+            /// Returns `true` if any bit of `other` is set
+            pub const fn intersects(self, other: $name) -> bool {
+                self.0 & other.0 != 0
+            }
+
+            /// Returns `true` if no bit is set
+            pub const fn is_empty(self) -> bool {
+                self.0 == 0
+            }
+
+            /// Returns the names of all set constants that are a single bit
+            pub fn names(self) -> Vec<&'static str> {
+                let mut v = Vec::new();
+                $(
+                    if ($value as $repr).count_ones() == 1 && self.contains($name::$flag) {
+                        v.push(stringify!($flag));
+                    }
+                )*
+                v
+            }
+        }
+
+        impl From<$repr> for $name {
+            fn from(bits: $repr) -> $name {
+                $name(bits)
+            }
+        }
+
+        impl From<$name> for $repr {
+            fn from(f: $name) -> $repr {
+                f.0
+            }
+        }
+
+        impl core::ops::BitOr for $name {
+            type Output = $name;
+            fn bitor(self, rhs: $name) -> $name {
+                $name(self.0 | rhs.0)
+            }
+        }
+
+        impl core::ops::BitOrAssign for $name {
+            fn bitor_assign(&mut self, rhs: $name) {
+                self.0 |= rhs.0;
+            }
+        }
+
+        impl core::ops::BitAnd for $name {
+            type Output = $name;
+            fn bitand(self, rhs: $name) -> $name {
+                $name(self.0 & rhs.0)
+            }
+        }
+
+        impl core::ops::Not for $name {
+            type Output = $name;
+            fn not(self) -> $name {
+                $name(!self.0)
+            }
+        }
+
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "{}(0x{:x}: {:?})", stringify!($name), self.0, self.names())
+            }
+        }
+    };
+}
+
+elf_bitflags! {
+    /// Segment permission and attribute bits as stored in `p_flags`
+    pub struct SegmentFlags: u32 {
+        const X = 0x1;
+        const W = 0x2;
+        const R = 0x4;
+        const PAGEEXEC = 0x10;
+        const NOPAGEEXEC = 0x20;
+        const SEGMEXEC = 0x40;
+        const NOSEGMEXEC = 0x80;
+        const MPROTECT = 0x100;
+        const NOMPROTECT = 0x200;
+        const RANDEXEC = 0x400;
+        const NORANDEXEC = 0x800;
+        const EMUTRAMP = 0x1000;
+        const NOEMUTRAMP = 0x2000;
+        const RANDMMAP = 0x4000;
+        const NORANDMMAP = 0x8000;
+        const MASKOS = 0x0ff0_0000;
+        const MASKPROC = 0xf000_0000;
+    }
+}
+
+/// PaX markings live in the otherwise unused bits above `PF_R`
+const PF_PAX_MASK: u32 = 0xfff0;
+const PF_RWX_MASK: u32 = 0x7;
+
+pub const PF_ARM_SB: u32 = 0x1000_0000;
+pub const PF_ARM_PI: u32 = 0x2000_0000;
+pub const PF_ARM_ABS: u32 = 0x4000_0000;
+pub const PF_MIPS_LOCAL: u32 = 0x1000_0000;
+pub const PF_HP_PAGE_SIZE: u32 = 0x0010_0000;
+pub const PF_HP_FAR_SHARED: u32 = 0x0020_0000;
+pub const PF_HP_NEAR_SHARED: u32 = 0x0040_0000;
+pub const PF_HP_CODE: u32 = 0x0100_0000;
+pub const PF_HP_MODIFY: u32 = 0x0200_0000;
+pub const PF_HP_LAZYSWAP: u32 = 0x0400_0000;
+pub const PF_HP_SBP: u32 = 0x0800_0000;
+
+impl SegmentFlags {
+    /// Returns the names of the processor specific bits for the given `e_machine`,
+    /// followed by the raw value of anything left unnamed
+    fn proc_names(self, machine: u16) -> Vec<String> {
+        let table: &[(u32, &str)] = match Machine::try_from(machine) {
+            Ok(Machine::Arm) => &[
+                (PF_ARM_SB, "ARM_SB"),
+                (PF_ARM_PI, "ARM_PI"),
+                (PF_ARM_ABS, "ARM_ABS"),
+            ],
+            Ok(Machine::Mips | Machine::MipsRs3Le) => &[(PF_MIPS_LOCAL, "MIPS_LOCAL")],
+            Ok(Machine::Parisc) => &[
+                (PF_HP_PAGE_SIZE, "HP_PAGE_SIZE"),
+                (PF_HP_FAR_SHARED, "HP_FAR_SHARED"),
+                (PF_HP_NEAR_SHARED, "HP_NEAR_SHARED"),
+                (PF_HP_CODE, "HP_CODE"),
+                (PF_HP_MODIFY, "HP_MODIFY"),
+                (PF_HP_LAZYSWAP, "HP_LAZYSWAP"),
+                (PF_HP_SBP, "HP_SBP"),
+            ],
+            _ => &[],
+        };
+        let mut out = Vec::new();
+        let mut rest = self.0 & (SegmentFlags::MASKOS.0 | SegmentFlags::MASKPROC.0);
+        push_bits(&mut out, &mut rest, table);
+        let os = rest & SegmentFlags::MASKOS.0;
+        if os != 0 {
+            out.push(format!("OS:0x{:x}", os));
+        }
+        let proc = rest & SegmentFlags::MASKPROC.0;
+        if proc != 0 {
+            out.push(format!("PROC:0x{:x}", proc));
+        }
+        out
+    }
+
+    /// Returns the readelf style `RWE` columns, followed by the names of any
+    /// PaX, OS or processor specific bits that are set
+    pub fn to_str(self, machine: u16) -> String {
+        let mut s = String::with_capacity(3);
+        s.push(if self.contains(SegmentFlags::R) {
+            'R'
+        } else {
+            ' '
+        });
+        s.push(if self.contains(SegmentFlags::W) {
+            'W'
+        } else {
+            ' '
+        });
+        s.push(if self.contains(SegmentFlags::X) {
+            'E'
+        } else {
+            ' '
+        });
+        let mut extra: Vec<String> = Vec::new();
+        let pax = self.0 & PF_PAX_MASK;
+        for name in SegmentFlags(pax).names() {
+            extra.push(name.to_string());
+        }
+        extra.extend(self.proc_names(machine));
+        let unknown = self.0
+            & !(PF_RWX_MASK | PF_PAX_MASK | SegmentFlags::MASKOS.0 | SegmentFlags::MASKPROC.0);
+        if unknown != 0 {
+            extra.push(format!("0x{:x}", unknown));
+        }
+        if !extra.is_empty() {
+            s.push_str(" [");
+            s.push_str(&extra.join(", "));
+            s.push(']');
+        }
+        s
+    }
+}
+
+impl fmt::Display for SegmentFlags {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.to_str(0))
+    }
+}
+
+elf_bitflags! {
+    /// Section attribute bits as stored in `sh_flags`
+    pub struct SectionFlags: u64 {
+        const WRITE = 0x1;
+        const ALLOC = 0x2;
+        const EXECINSTR = 0x4;
+        const MERGE = 0x10;
+        const STRINGS = 0x20;
+        const INFO_LINK = 0x40;
+        const LINK_ORDER = 0x80;
+        const OS_NONCONFORMING = 0x100;
+        const GROUP = 0x200;
+        const TLS = 0x400;
+        const COMPRESSED = 0x800;
+        const GNU_RETAIN = 0x0020_0000;
+        const GNU_MBIND = 0x0100_0000;
+        const ORDERED = 0x4000_0000;
+        const EXCLUDE = 0x8000_0000;
+        const MASKOS = 0x0ff0_0000;
+        const MASKPROC = 0xf000_0000;
+    }
+}
+
+pub const SHF_X86_64_LARGE: u64 = 0x1000_0000;
+pub const SHF_ARM_PURECODE: u64 = 0x2000_0000;
+pub const SHF_MIPS_GPREL: u64 = 0x1000_0000;
+
+impl SectionFlags {
+    /// Returns the readelf style flag key (e.g. `WAX`, `AMS`, `WAT`).
+    /// Bits without a dedicated letter are summarised as `o` (OS specific),
+    /// `p` (processor specific) or `x` (unknown).
+    pub fn to_str(self, machine: u16) -> String {
+        let letters = [
+            (SectionFlags::WRITE, 'W'),
+            (SectionFlags::ALLOC, 'A'),
+            (SectionFlags::EXECINSTR, 'X'),
+            (SectionFlags::MERGE, 'M'),
+            (SectionFlags::STRINGS, 'S'),
+            (SectionFlags::INFO_LINK, 'I'),
+            (SectionFlags::LINK_ORDER, 'L'),
+            (SectionFlags::OS_NONCONFORMING, 'O'),
+            (SectionFlags::GROUP, 'G'),
+            (SectionFlags::TLS, 'T'),
+            (SectionFlags::COMPRESSED, 'C'),
+            (SectionFlags::GNU_RETAIN, 'R'),
+            (SectionFlags::GNU_MBIND, 'D'),
+            (SectionFlags::EXCLUDE, 'E'),
+        ];
+        let mut s = String::new();
+        let mut rest = self.0;
+        for (flag, c) in letters {
+            if self.contains(flag) {
+                s.push(c);
+                rest &= !flag.0;
+            }
+        }
+        let proc = match Machine::try_from(machine) {
+            Ok(Machine::X86_64) => Some((SHF_X86_64_LARGE, 'l')),
+            Ok(Machine::Arm) => Some((SHF_ARM_PURECODE, 'y')),
+            _ => None,
+        };
+        if let Some((bit, c)) = proc {
+            if rest & bit != 0 {
+                s.push(c);
+                rest &= !bit;
+            }
+        }
+        if rest & !(SectionFlags::MASKOS.0 | SectionFlags::MASKPROC.0) != 0 {
+            s.push('x');
+        }
+        if rest & SectionFlags::MASKOS.0 != 0 {
+            s.push('o');
+        }
+        if rest & SectionFlags::MASKPROC.0 != 0 {
+            s.push('p');
+        }
+        s
+    }
+}
+
+impl fmt::Display for SectionFlags {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.to_str(0))
+    }
+}
@@ -0,0 +1,781 @@
+use core::fmt;
+use std::error::Error;
+
+/// Error returned when a raw header value has no registered meaning
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnknownValue {
+    pub field: &'static str,
+    pub value: u64,
+}
+
+impl fmt::Display for UnknownValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "unknown {} value 0x{:x}", self.field, self.value)
+    }
+}
+
+impl Error for UnknownValue {}
+
+/// Generates a C-like enum for a registered ELF value set together with its
+/// `TryFrom<raw>`, `From<enum> for raw` and `Display` implementations.
+macro_rules! elf_enum {
+    (
+        $(#[$meta:meta])*
+        pub enum $name:ident: $repr:ty, $field:literal {
+            $($variant:ident = $value:literal => $desc:literal,)*
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum $name {
+            $($variant,)*
+        }
+
+        impl $name {
+            /// Returns a human readable description of the value
+            pub fn description(self) -> &'static str {
+                match self {
+                    $($name::$variant => $desc,)*
+                }
+            }
+        }
+
+        impl From<$name> for $repr {
+            fn from(v: $name) -> $repr {
+                match v {
+                    $($name::$variant => $value,)*
+                }
+            }
+        }
+
+        impl TryFrom<$repr> for $name {
+            type Error = UnknownValue;
+
+            fn try_from(v: $repr) -> Result<Self, Self::Error> {
+                match v {
+                    $($value => Ok($name::$variant),)*
+                    _ => Err(UnknownValue {
+                        field: $field,
+                        value: v as u64,
+                    }),
+                }
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str(self.description())
+            }
+        }
+    };
+}
+
+elf_enum! {
+    /// Target instruction set architecture as stored in `e_machine`.
+    /// Covers the gABI registry plus the unofficial values still found in the wild.
+    pub enum Machine: u16, "e_machine" {
+        None = 0 => "No machine",
+        M32 = 1 => "AT&T WE 32100",
+        Sparc = 2 => "SUN SPARC",
+        I386 = 3 => "Intel 80386",
+        M68k = 4 => "Motorola 68000",
+        M88k = 5 => "Motorola 88000",
+        Iamcu = 6 => "Intel MCU",
+        I860 = 7 => "Intel 80860",
+        Mips = 8 => "MIPS R3000 big-endian",
+        S370 = 9 => "IBM System/370",
+        MipsRs3Le = 10 => "MIPS R3000 little-endian",
+        Parisc = 15 => "Hewlett-Packard PA-RISC",
+        Vpp500 = 17 => "Fujitsu VPP500",
+        Sparc32Plus = 18 => "Enhanced instruction set SPARC",
+        I960 = 19 => "Intel 80960",
+        Ppc = 20 => "PowerPC",
+        Ppc64 = 21 => "PowerPC64",
+        S390 = 22 => "IBM S/390",
+        Spu = 23 => "Cell BE SPU",
+        V800 = 36 => "NEC V800",
+        Fr20 = 37 => "Fujitsu FR20",
+        Rh32 = 38 => "TRW RH-32",
+        Rce = 39 => "Motorola RCE",
+        Arm = 40 => "Advanced RISC Machines ARM",
+        FakeAlpha = 41 => "Digital Alpha",
+        Sh = 42 => "Hitachi SH",
+        SparcV9 = 43 => "SPARC Version 9 64-bit",
+        Tricore = 44 => "Siemens TriCore embedded processor",
+        Arc = 45 => "Argonaut RISC Core",
+        H8300 = 46 => "Renesas H8/300",
+        H8300h = 47 => "Renesas H8/300H",
+        H8s = 48 => "Renesas H8S",
+        H8500 = 49 => "Renesas H8/500",
+        Ia64 = 50 => "Intel IA-64 processor architecture",
+        MipsX = 51 => "Stanford MIPS-X",
+        Coldfire = 52 => "Motorola ColdFire",
+        M68hc12 = 53 => "Motorola M68HC12",
+        Mma = 54 => "Fujitsu MMA Multimedia Accelerator",
+        Pcp = 55 => "Siemens PCP",
+        Ncpu = 56 => "Sony nCPU embedded RISC processor",
+        Ndr1 = 57 => "Denso NDR1 microprocessor",
+        Starcore = 58 => "Motorola Star*Core processor",
+        Me16 = 59 => "Toyota ME16 processor",
+        St100 = 60 => "STMicroelectronics ST100 processor",
+        Tinyj = 61 => "Advanced Logic Corp. TinyJ embedded processor",
+        X86_64 = 62 => "AMD x86-64 architecture",
+        Pdsp = 63 => "Sony DSP Processor",
+        Pdp10 = 64 => "Digital Equipment Corp. PDP-10",
+        Pdp11 = 65 => "Digital Equipment Corp. PDP-11",
+        Fx66 = 66 => "Siemens FX66 microcontroller",
+        St9Plus = 67 => "STMicroelectronics ST9+ 8/16 bit microcontroller",
+        St7 = 68 => "STMicroelectronics ST7 8-bit microcontroller",
+        M68hc16 = 69 => "Motorola MC68HC16 Microcontroller",
+        M68hc11 = 70 => "Motorola MC68HC11 Microcontroller",
+        M68hc08 = 71 => "Motorola MC68HC08 Microcontroller",
+        M68hc05 = 72 => "Motorola MC68HC05 Microcontroller",
+        Svx = 73 => "Silicon Graphics SVx",
+        St19 = 74 => "STMicroelectronics ST19 8-bit microcontroller",
+        Vax = 75 => "Digital VAX",
+        Cris = 76 => "Axis Communications 32-bit embedded processor",
+        Javelin = 77 => "Infineon Technologies 32-bit embedded processor",
+        Firepath = 78 => "Element 14 64-bit DSP Processor",
+        Zsp = 79 => "LSI Logic 16-bit DSP Processor",
+        Mmix = 80 => "Donald Knuth's educational 64-bit processor",
+        Huany = 81 => "Harvard University machine-independent object files",
+        Prism = 82 => "SiTera Prism",
+        Avr = 83 => "Atmel AVR 8-bit microcontroller",
+        Fr30 = 84 => "Fujitsu FR30",
+        D10v = 85 => "Mitsubishi D10V",
+        D30v = 86 => "Mitsubishi D30V",
+        V850 = 87 => "NEC v850",
+        M32r = 88 => "Renesas M32R",
+        Mn10300 = 89 => "Panasonic/MEI MN10300, AM33",
+        Mn10200 = 90 => "Matsushita MN10200",
+        Pj = 91 => "picoJava",
+        Openrisc = 92 => "OpenRISC 32-bit embedded processor",
+        ArcCompact = 93 => "ARC Cores Tangent-A5",
+        Xtensa = 94 => "Tensilica Xtensa Architecture",
+        Videocore = 95 => "Alphamosaic VideoCore processor",
+        TmmGpp = 96 => "Thompson Multimedia General Purpose Processor",
+        Ns32k = 97 => "National Semiconductor 32000 series",
+        Tpc = 98 => "Tenor Network TPC processor",
+        Snp1k = 99 => "Trebia SNP 1000 processor",
+        St200 = 100 => "STMicroelectronics ST200 microcontroller",
+        Ip2k = 101 => "Ubicom IP2xxx microcontroller family",
+        Max = 102 => "MAX Processor",
+        Cr = 103 => "National Semiconductor CompactRISC microprocessor",
+        F2mc16 = 104 => "Fujitsu F2MC16",
+        Msp430 = 105 => "Texas Instruments embedded microcontroller msp430",
+        Blackfin = 106 => "ADI Blackfin processor",
+        SeC33 = 107 => "S1C33 Family of Seiko Epson processors",
+        Sep = 108 => "Sharp embedded microprocessor",
+        Arca = 109 => "Arca RISC Microprocessor",
+        Unicore = 110 => "UniCore-32",
+        Excess = 111 => "eXcess: 16/32/64-bit configurable embedded CPU",
+        Dxp = 112 => "Icera Semiconductor Inc. Deep Execution Processor",
+        AlteraNios2 = 113 => "Altera Nios II soft-core processor",
+        Crx = 114 => "National Semiconductor CompactRISC CRX microprocessor",
+        Xgate = 115 => "Motorola XGATE embedded processor",
+        C166 = 116 => "Infineon C16x/XC16x processor",
+        M16c = 117 => "Renesas M16C series microprocessors",
+        Dspic30f = 118 => "Microchip Technology dsPIC30F Digital Signal Controller",
+        Ce = 119 => "Freescale Communication Engine RISC core",
+        M32c = 120 => "Renesas M32C series microprocessors",
+        Tsk3000 = 131 => "Altium TSK3000 core",
+        Rs08 = 132 => "Freescale RS08 embedded processor",
+        Sharc = 133 => "Analog Devices SHARC family of 32-bit DSP processors",
+        Ecog2 = 134 => "Cyan Technology eCOG2 microprocessor",
+        Score7 = 135 => "Sunplus S+core7 RISC processor",
+        Dsp24 = 136 => "New Japan Radio (NJR) 24-bit DSP Processor",
+        Videocore3 = 137 => "Broadcom VideoCore III processor",
+        Latticemico32 = 138 => "RISC processor for Lattice FPGA architecture",
+        SeC17 = 139 => "Seiko Epson C17 family",
+        TiC6000 = 140 => "TMS320C6000 Family",
+        TiC2000 = 141 => "TMS320C2000 Family",
+        TiC5500 = 142 => "TMS320C55x Family",
+        TiArp32 = 143 => "Texas Instruments Application Specific RISC Processor, 32bit fetch",
+        TiPru = 144 => "Texas Instruments Programmable Realtime Unit",
+        MmdspPlus = 160 => "STMicroelectronics 64bit VLIW Data Signal Processor",
+        CypressM8c = 161 => "Cypress M8C microprocessor",
+        R32c = 162 => "Renesas R32C series microprocessors",
+        Trimedia = 163 => "NXP Semiconductors TriMedia architecture family",
+        Hexagon = 164 => "QUALCOMM Hexagon",
+        I8051 = 165 => "Intel 8051 and variants",
+        Stxp7x = 166 => "STMicroelectronics STxP7x family of configurable and extensible RISC processors",
+        Nds32 = 167 => "Andes Technology embedded RISC processor",
+        Ecog1x = 168 => "Cyan Technology eCOG1X family",
+        Maxq30 = 169 => "Dallas Semiconductor MAXQ30 Core Micro-controllers",
+        Ximo16 = 170 => "New Japan Radio (NJR) 16-bit DSP Processor",
+        Manik = 171 => "M2000 Reconfigurable RISC Microprocessor",
+        Craynv2 = 172 => "Cray Inc. NV2 vector architecture",
+        Rx = 173 => "Renesas RX family",
+        Metag = 174 => "Imagination Technologies META processor architecture",
+        McstElbrus = 175 => "MCST Elbrus general purpose hardware architecture",
+        Ecog16 = 176 => "Cyan Technology eCOG16 family",
+        Cr16 = 177 => "National Semiconductor CompactRISC CR16 16-bit microprocessor",
+        Etpu = 178 => "Freescale Extended Time Processing Unit",
+        Sle9x = 179 => "Infineon Technologies SLE9X core",
+        L10m = 180 => "Intel L10M",
+        K10m = 181 => "Intel K10M",
+        Aarch64 = 183 => "ARM 64-bits (ARMv8/Aarch64)",
+        Avr32 = 185 => "Atmel Corporation 32-bit microprocessor family",
+        Stm8 = 186 => "STMicroelectronics STM8 8-bit microcontroller",
+        Tile64 = 187 => "Tilera TILE64 multicore architecture family",
+        Tilepro = 188 => "Tilera TILEPro multicore architecture family",
+        Microblaze = 189 => "Xilinx MicroBlaze 32-bit RISC soft processor core",
+        Cuda = 190 => "NVIDIA CUDA architecture",
+        Tilegx = 191 => "Tilera TILE-Gx",
+        Cloudshield = 192 => "CloudShield architecture family",
+        Corea1st = 193 => "KIPO-KAIST Core-A 1st generation processor family",
+        Corea2nd = 194 => "KIPO-KAIST Core-A 2nd generation processor family",
+        Arcv2 = 195 => "ARCv2 Cores",
+        Open8 = 196 => "Open8 8-bit RISC soft processor core",
+        Rl78 = 197 => "Renesas RL78 family",
+        Videocore5 = 198 => "Broadcom VideoCore V processor",
+        R78kor = 199 => "Renesas 78KOR family",
+        F56800ex = 200 => "Freescale 56800EX Digital Signal Controller (DSC)",
+        Ba1 = 201 => "Beyond BA1 CPU architecture",
+        Ba2 = 202 => "Beyond BA2 CPU architecture",
+        Xcore = 203 => "XMOS xCORE processor family",
+        MchpPic = 204 => "Microchip 8-bit PIC(r) family",
+        IntelGt = 205 => "Intel Graphics Technology",
+        Km32 = 210 => "KM211 KM32 32-bit processor",
+        Kmx32 = 211 => "KM211 KMX32 32-bit processor",
+        Kmx16 = 212 => "KM211 KMX16 16-bit processor",
+        Kmx8 = 213 => "KM211 KMX8 8-bit processor",
+        Kvarc = 214 => "KM211 KVARC processor",
+        Cdp = 215 => "Paneve CDP architecture family",
+        Coge = 216 => "Cognitive Smart Memory Processor",
+        Cool = 217 => "Bluechip Systems CoolEngine",
+        Norc = 218 => "Nanoradio Optimized RISC",
+        CsrKalimba = 219 => "CSR Kalimba architecture family",
+        Z80 = 220 => "Zilog Z80",
+        Visium = 221 => "Controls and Data Services VISIUMcore processor",
+        Ft32 = 222 => "FTDI Chip FT32 high performance 32-bit RISC architecture",
+        Moxie = 223 => "Moxie processor family",
+        Amdgpu = 224 => "AMD GPU architecture",
+        Riscv = 243 => "RISC-V",
+        Lanai = 244 => "Lanai 32-bit processor",
+        Ceva = 245 => "CEVA Processor Architecture Family",
+        CevaX2 = 246 => "CEVA X2 Processor Family",
+        Bpf = 247 => "Linux BPF",
+        GraphcoreIpu = 248 => "Graphcore Intelligent Processing Unit",
+        Img1 = 249 => "Imagination Technologies",
+        Nfp = 250 => "Netronome Flow Processor",
+        Ve = 251 => "NEC Vector Engine",
+        Csky = 252 => "C-SKY",
+        ArcCompact3_64 = 253 => "Synopsys ARCv2.3 64-bit",
+        Mcs6502 = 254 => "MOS Technology MCS 6502 processor",
+        ArcCompact3 = 255 => "Synopsys ARCv2.3 32-bit",
+        Kvx = 256 => "Kalray VLIW core of the MPPA processor family",
+        Wdc65816 = 257 => "WDC 65816/65C816",
+        Loongarch = 258 => "LoongArch",
+        Kf32 = 259 => "ChipON KungFu32",
+        U16U8core = 260 => "LAPIS nX-U16/U8",
+        Tachyum = 261 => "Tachyum",
+        Nxp56800ef = 262 => "NXP 56800EF Digital Signal Controller (DSC)",
+        Sbf = 263 => "Solana Bytecode Format",
+        Aiengine = 264 => "AMD/Xilinx AIEngine architecture",
+        SimaMla = 265 => "SiMa MLA",
+        Bang = 266 => "Cambricon BANG",
+        Loonggpu = 267 => "Loongson LoongGPU",
+        Sw64 = 268 => "Wuxi Institute of Advanced Technology SW64",
+        AvrOld = 0x1057 => "Atmel AVR 8-bit microcontroller (old)",
+        Msp430Old = 0x1059 => "Texas Instruments msp430 (old)",
+        AdaptevaEpiphany = 0x1223 => "Adapteva EPIPHANY",
+        Mt = 0x2530 => "Morpho Technologies MT processor",
+        CygnusFr30 = 0x3330 => "Fujitsu FR30 (old)",
+        Webassembly = 0x4157 => "WebAssembly",
+        Xc16xOld = 0x4688 => "Infineon Technologies xc16x",
+        S12z = 0x4def => "Freescale S12Z",
+        CygnusFrv = 0x5441 => "Fujitsu FR-V",
+        DlxOld = 0x5aa5 => "OpenDLX",
+        CygnusD10v = 0x7650 => "Mitsubishi D10V (old)",
+        CygnusD30v = 0x7676 => "Mitsubishi D30V (old)",
+        Ip2kOld = 0x8217 => "Ubicom IP2xxx (old)",
+        CygnusPowerpc = 0x9025 => "PowerPC (old)",
+        Alpha = 0x9026 => "Digital Alpha (unofficial)",
+        CygnusM32r = 0x9041 => "Renesas M32R (old)",
+        CygnusV850 = 0x9080 => "NEC v850 (old)",
+        S390Old = 0xa390 => "IBM S/390 (old)",
+        XtensaOld = 0xabc7 => "Tensilica Xtensa Architecture (old)",
+        Xstormy16 = 0xad45 => "Sanyo XStormy16 CPU core",
+        MicroblazeOld = 0xbaab => "Xilinx MicroBlaze (old)",
+        CygnusMn10300 = 0xbeef => "Panasonic/MEI MN10300 (old)",
+        CygnusMn10200 = 0xdead => "Matsushita MN10200 (old)",
+        CygnusMep = 0xf00d => "Toshiba MeP Media Engine",
+        M32cOld = 0xfeb0 => "Renesas M32C (old)",
+        Iq2000 = 0xfeba => "Vitesse IQ2000",
+        Nios32 = 0xfebb => "Altera Nios 32",
+        MoxieOld = 0xfeed => "Moxie (old)",
+    }
+}
+
+elf_enum! {
+    /// Target operating system ABI as stored in `e_ident[EI_OSABI]`
+    pub enum OsAbi: u8, "EI_OSABI" {
+        SystemV = 0 => "System V",
+        HpUx = 1 => "HP-UX",
+        NetBsd = 2 => "NetBSD",
+        Linux = 3 => "Linux",
+        Hurd = 4 => "GNU Hurd",
+        Solaris = 6 => "Solaris",
+        Aix = 7 => "AIX",
+        Irix = 8 => "IRIX",
+        FreeBsd = 9 => "FreeBSD",
+        Tru64 = 10 => "Tru64",
+        Modesto = 11 => "Novell Modesto",
+        OpenBsd = 12 => "OpenBSD",
+        OpenVms = 13 => "OpenVMS",
+        Nsk = 14 => "NonStop Kernel",
+        Aros = 15 => "AROS",
+        FenixOs = 16 => "Fenix OS",
+        CloudAbi = 17 => "CloudABI",
+        OpenVos = 18 => "Stratus Technologies OpenVOS",
+        ArmAeabi = 64 => "ARM EABI",
+        Arm = 97 => "ARM",
+        Standalone = 255 => "Standalone (embedded) application",
+    }
+}
+
+pub const ET_NONE: u16 = 0;
+pub const ET_REL: u16 = 1;
+pub const ET_EXEC: u16 = 2;
+pub const ET_DYN: u16 = 3;
+pub const ET_CORE: u16 = 4;
+pub const ET_LOOS: u16 = 0xfe00;
+pub const ET_HIOS: u16 = 0xfeff;
+pub const ET_LOPROC: u16 = 0xff00;
+pub const ET_HIPROC: u16 = 0xffff;
+
+/// Object file type as stored in `e_type`.
+/// The OS and processor specific ranges keep their raw value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ElfType {
+    None,
+    Rel,
+    Exec,
+    Dyn,
+    Core,
+    Os(u16),
+    Proc(u16),
+}
+
+impl ElfType {
+    /// Returns a human readable description of the value
+    pub fn description(self) -> &'static str {
+        match self {
+            ElfType::None => "No file type",
+            ElfType::Rel => "Relocatable file",
+            ElfType::Exec => "Executable file",
+            ElfType::Dyn => "Shared object file",
+            ElfType::Core => "Core file",
+            ElfType::Os(_) => "Operating system-specific",
+            ElfType::Proc(_) => "Processor-specific",
+        }
+    }
+}
+
+impl From<ElfType> for u16 {
+    fn from(v: ElfType) -> u16 {
+        match v {
+            ElfType::None => ET_NONE,
+            ElfType::Rel => ET_REL,
+            ElfType::Exec => ET_EXEC,
+            ElfType::Dyn => ET_DYN,
+            ElfType::Core => ET_CORE,
+            ElfType::Os(c) | ElfType::Proc(c) => c,
+        }
+    }
+}
+
+impl TryFrom<u16> for ElfType {
+    type Error = UnknownValue;
+
+    fn try_from(v: u16) -> Result<Self, Self::Error> {
+        match v {
+            ET_NONE => Ok(ElfType::None),
+            ET_REL => Ok(ElfType::Rel),
+            ET_EXEC => Ok(ElfType::Exec),
+            ET_DYN => Ok(ElfType::Dyn),
+            ET_CORE => Ok(ElfType::Core),
+            ET_LOOS..=ET_HIOS => Ok(ElfType::Os(v)),
+            ET_LOPROC..=ET_HIPROC => Ok(ElfType::Proc(v)),
+            _ => Err(UnknownValue {
+                field: "e_type",
+                value: v as u64,
+            }),
+        }
+    }
+}
+
+impl fmt::Display for ElfType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ElfType::Os(c) | ElfType::Proc(c) => write!(f, "{} (0x{:x})", self.description(), c),
+            _ => f.write_str(self.description()),
+        }
+    }
+}
+
+pub const PT_NULL: u32 = 0;
+pub const PT_LOAD: u32 = 1;
+pub const PT_DYNAMIC: u32 = 2;
+pub const PT_INTERP: u32 = 3;
+pub const PT_NOTE: u32 = 4;
+pub const PT_SHLIB: u32 = 5;
+pub const PT_PHDR: u32 = 6;
+pub const PT_TLS: u32 = 7;
+pub const PT_LOOS: u32 = 0x6000_0000;
+pub const PT_HIOS: u32 = 0x6fff_ffff;
+pub const PT_LOPROC: u32 = 0x7000_0000;
+pub const PT_HIPROC: u32 = 0x7fff_ffff;
+pub const PT_GNU_EH_FRAME: u32 = 0x6474_e550;
+pub const PT_GNU_STACK: u32 = 0x6474_e551;
+pub const PT_GNU_RELRO: u32 = 0x6474_e552;
+pub const PT_GNU_PROPERTY: u32 = 0x6474_e553;
+pub const PT_GNU_SFRAME: u32 = 0x6474_e554;
+pub const PT_GNU_MBIND_LO: u32 = 0x6474_e555;
+pub const PT_GNU_MBIND_HI: u32 = 0x6474_f554;
+pub const PT_SUNW_UNWIND: u32 = 0x6464_e550;
+pub const PT_SUNWBSS: u32 = 0x6fff_fffa;
+pub const PT_SUNWSTACK: u32 = 0x6fff_fffb;
+pub const PT_SUNWDTRACE: u32 = 0x6fff_fffc;
+pub const PT_SUNWCAP: u32 = 0x6fff_fffd;
+pub const PT_OPENBSD_MUTABLE: u32 = 0x65a3_dbe5;
+pub const PT_OPENBSD_RANDOMIZE: u32 = 0x65a3_dbe6;
+pub const PT_OPENBSD_WXNEEDED: u32 = 0x65a3_dbe7;
+pub const PT_OPENBSD_NOBTCFI: u32 = 0x65a3_dbe8;
+pub const PT_OPENBSD_SYSCALLS: u32 = 0x65a3_dbe9;
+pub const PT_OPENBSD_BOOTDATA: u32 = 0x65a4_1be6;
+pub const PT_PAX_FLAGS: u32 = 0x6504_1580;
+
+/// Segment type as stored in `p_type`.
+/// Values in the OS and processor specific ranges are resolved against the
+/// `e_machine` of the file, as the same number means different things per architecture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SegmentType {
+    Null,
+    Load,
+    Dynamic,
+    Interp,
+    Note,
+    Shlib,
+    Phdr,
+    Tls,
+    GnuEhFrame,
+    GnuStack,
+    GnuRelro,
+    GnuProperty,
+    GnuSframe,
+    GnuMbind(u32),
+    SunwUnwind,
+    SunwBss,
+    SunwStack,
+    SunwDtrace,
+    SunwCap,
+    OpenbsdMutable,
+    OpenbsdRandomize,
+    OpenbsdWxneeded,
+    OpenbsdNobtcfi,
+    OpenbsdSyscalls,
+    OpenbsdBootdata,
+    PaxFlags,
+    HpTls,
+    HpCoreNone,
+    HpCoreVersion,
+    HpCoreKernel,
+    HpCoreComm,
+    HpCoreProc,
+    HpCoreLoadable,
+    HpCoreStack,
+    HpCoreShm,
+    HpCoreMmf,
+    HpParallel,
+    HpFastbind,
+    HpOptAnnot,
+    HpHslAnnot,
+    HpStack,
+    HpCoreUtsname,
+    ArmArchext,
+    ArmExidx,
+    Aarch64Archext,
+    Aarch64Unwind,
+    Aarch64MemtagMte,
+    MipsReginfo,
+    MipsRtproc,
+    MipsOptions,
+    MipsAbiflags,
+    RiscvAttributes,
+    PariscArchext,
+    PariscUnwind,
+    PariscWeakorder,
+    Ia64Archext,
+    Ia64Unwind,
+    S390Pgste,
+    /// Unassigned value in `PT_LOOS..=PT_HIOS`
+    Os(u32),
+    /// Unassigned value in `PT_LOPROC..=PT_HIPROC`
+    Proc(u32),
+    Unknown(u32),
+}
+
+impl SegmentType {
+    /// Resolves a raw `p_type` for the given `e_machine`.
+    /// We assume both values have the correct endianness
+    pub fn from_raw(p_type: u32, machine: u16) -> SegmentType {
+        use SegmentType::*;
+        let machine = Machine::try_from(machine).ok();
+        let generic = match p_type {
+            PT_NULL => Some(Null),
+            PT_LOAD => Some(Load),
+            PT_DYNAMIC => Some(Dynamic),
+            PT_INTERP => Some(Interp),
+            PT_NOTE => Some(Note),
+            PT_SHLIB => Some(Shlib),
+            PT_PHDR => Some(Phdr),
+            PT_TLS => Some(Tls),
+            PT_GNU_EH_FRAME => Some(GnuEhFrame),
+            PT_GNU_STACK => Some(GnuStack),
+            PT_GNU_RELRO => Some(GnuRelro),
+            PT_GNU_PROPERTY => Some(GnuProperty),
+            PT_GNU_SFRAME => Some(GnuSframe),
+            PT_GNU_MBIND_LO..=PT_GNU_MBIND_HI => Some(GnuMbind(p_type - PT_GNU_MBIND_LO)),
+            PT_SUNW_UNWIND => Some(SunwUnwind),
+            PT_SUNWBSS => Some(SunwBss),
+            PT_SUNWSTACK => Some(SunwStack),
+            PT_SUNWDTRACE => Some(SunwDtrace),
+            PT_SUNWCAP => Some(SunwCap),
+            PT_OPENBSD_MUTABLE => Some(OpenbsdMutable),
+            PT_OPENBSD_RANDOMIZE => Some(OpenbsdRandomize),
+            PT_OPENBSD_WXNEEDED => Some(OpenbsdWxneeded),
+            PT_OPENBSD_NOBTCFI => Some(OpenbsdNobtcfi),
+            PT_OPENBSD_SYSCALLS => Some(OpenbsdSyscalls),
+            PT_OPENBSD_BOOTDATA => Some(OpenbsdBootdata),
+            PT_PAX_FLAGS => Some(PaxFlags),
+            _ => None,
+        };
+        if let Some(t) = generic {
+            return t;
+        }
+        let specific = match (machine, p_type) {
+            // HP-UX assigns its own meaning to the start of the OS range
+            (Some(Machine::Parisc | Machine::Ia64), 0x6000_0000) => Some(HpTls),
+            (Some(Machine::Parisc | Machine::Ia64), 0x6000_0001) => Some(HpCoreNone),
+            (Some(Machine::Parisc | Machine::Ia64), 0x6000_0002) => Some(HpCoreVersion),
+            (Some(Machine::Parisc | Machine::Ia64), 0x6000_0003) => Some(HpCoreKernel),
+            (Some(Machine::Parisc | Machine::Ia64), 0x6000_0004) => Some(HpCoreComm),
+            (Some(Machine::Parisc | Machine::Ia64), 0x6000_0005) => Some(HpCoreProc),
+            (Some(Machine::Parisc | Machine::Ia64), 0x6000_0006) => Some(HpCoreLoadable),
+            (Some(Machine::Parisc | Machine::Ia64), 0x6000_0007) => Some(HpCoreStack),
+            (Some(Machine::Parisc | Machine::Ia64), 0x6000_0008) => Some(HpCoreShm),
+            (Some(Machine::Parisc | Machine::Ia64), 0x6000_0009) => Some(HpCoreMmf),
+            (Some(Machine::Parisc | Machine::Ia64), 0x6000_0010) => Some(HpParallel),
+            (Some(Machine::Parisc | Machine::Ia64), 0x6000_0011) => Some(HpFastbind),
+            (Some(Machine::Parisc | Machine::Ia64), 0x6000_0012) => Some(HpOptAnnot),
+            (Some(Machine::Parisc | Machine::Ia64), 0x6000_0013) => Some(HpHslAnnot),
+            (Some(Machine::Parisc | Machine::Ia64), 0x6000_0014) => Some(HpStack),
+            (Some(Machine::Parisc | Machine::Ia64), 0x6000_0015) => Some(HpCoreUtsname),
+            (Some(Machine::Arm), 0x7000_0000) => Some(ArmArchext),
+            (Some(Machine::Arm), 0x7000_0001) => Some(ArmExidx),
+            (Some(Machine::Aarch64), 0x7000_0000) => Some(Aarch64Archext),
+            (Some(Machine::Aarch64), 0x7000_0001) => Some(Aarch64Unwind),
+            (Some(Machine::Aarch64), 0x7000_0002) => Some(Aarch64MemtagMte),
+            (Some(Machine::Mips | Machine::MipsRs3Le), 0x7000_0000) => Some(MipsReginfo),
+            (Some(Machine::Mips | Machine::MipsRs3Le), 0x7000_0001) => Some(MipsRtproc),
+            (Some(Machine::Mips | Machine::MipsRs3Le), 0x7000_0002) => Some(MipsOptions),
+            (Some(Machine::Mips | Machine::MipsRs3Le), 0x7000_0003) => Some(MipsAbiflags),
+            (Some(Machine::Riscv), 0x7000_0003) => Some(RiscvAttributes),
+            (Some(Machine::Parisc), 0x7000_0000) => Some(PariscArchext),
+            (Some(Machine::Parisc), 0x7000_0001) => Some(PariscUnwind),
+            (Some(Machine::Parisc), 0x7000_0002) => Some(PariscWeakorder),
+            (Some(Machine::Ia64), 0x7000_0000) => Some(Ia64Archext),
+            (Some(Machine::Ia64), 0x7000_0001) => Some(Ia64Unwind),
+            (Some(Machine::S390), 0x7000_0000) => Some(S390Pgste),
+            _ => None,
+        };
+        specific.unwrap_or(match p_type {
+            PT_LOOS..=PT_HIOS => Os(p_type),
+            PT_LOPROC..=PT_HIPROC => Proc(p_type),
+            _ => Unknown(p_type),
+        })
+    }
+
+    /// Returns the name of the segment type, or `None` for unassigned values
+    pub fn name(self) -> Option<&'static str> {
+        use SegmentType::*;
+        Some(match self {
+            Null => "PT_NULL",
+            Load => "PT_LOAD",
+            Dynamic => "PT_DYNAMIC",
+            Interp => "PT_INTERP",
+            Note => "PT_NOTE",
+            Shlib => "PT_SHLIB",
+            Phdr => "PT_PHDR",
+            Tls => "PT_TLS",
+            GnuEhFrame => "GNU_EH_FRAME",
+            GnuStack => "GNU_STACK",
+            GnuRelro => "GNU_RELRO",
+            GnuProperty => "GNU_PROPERTY",
+            GnuSframe => "GNU_SFRAME",
+            GnuMbind(_) => "GNU_MBIND",
+            SunwUnwind => "PT_SUNW_UNWIND",
+            SunwBss => "PT_SUNWBSS",
+            SunwStack => "PT_SUNWSTACK",
+            SunwDtrace => "PT_SUNWDTRACE",
+            SunwCap => "PT_SUNWCAP",
+            OpenbsdMutable => "PT_OPENBSD_MUTABLE",
+            OpenbsdRandomize => "PT_OPENBSD_RANDOMIZE",
+            OpenbsdWxneeded => "PT_OPENBSD_WXNEEDED",
+            OpenbsdNobtcfi => "PT_OPENBSD_NOBTCFI",
+            OpenbsdSyscalls => "PT_OPENBSD_SYSCALLS",
+            OpenbsdBootdata => "PT_OPENBSD_BOOTDATA",
+            PaxFlags => "PT_PAX_FLAGS",
+            HpTls => "PT_HP_TLS",
+            HpCoreNone => "PT_HP_CORE_NONE",
+            HpCoreVersion => "PT_HP_CORE_VERSION",
+            HpCoreKernel => "PT_HP_CORE_KERNEL",
+            HpCoreComm => "PT_HP_CORE_COMM",
+            HpCoreProc => "PT_HP_CORE_PROC",
+            HpCoreLoadable => "PT_HP_CORE_LOADABLE",
+            HpCoreStack => "PT_HP_CORE_STACK",
+            HpCoreShm => "PT_HP_CORE_SHM",
+            HpCoreMmf => "PT_HP_CORE_MMF",
+            HpParallel => "PT_HP_PARALLEL",
+            HpFastbind => "PT_HP_FASTBIND",
+            HpOptAnnot => "PT_HP_OPT_ANNOT",
+            HpHslAnnot => "PT_HP_HSL_ANNOT",
+            HpStack => "PT_HP_STACK",
+            HpCoreUtsname => "PT_HP_CORE_UTSNAME",
+            ArmArchext => "PT_ARM_ARCHEXT",
+            ArmExidx => "PT_ARM_EXIDX",
+            Aarch64Archext => "PT_AARCH64_ARCHEXT",
+            Aarch64Unwind => "PT_AARCH64_UNWIND",
+            Aarch64MemtagMte => "PT_AARCH64_MEMTAG_MTE",
+            MipsReginfo => "PT_MIPS_REGINFO",
+            MipsRtproc => "PT_MIPS_RTPROC",
+            MipsOptions => "PT_MIPS_OPTIONS",
+            MipsAbiflags => "PT_MIPS_ABIFLAGS",
+            RiscvAttributes => "PT_RISCV_ATTRIBUTES",
+            PariscArchext => "PT_PARISC_ARCHEXT",
+            PariscUnwind => "PT_PARISC_UNWIND",
+            PariscWeakorder => "PT_PARISC_WEAKORDER",
+            Ia64Archext => "PT_IA_64_ARCHEXT",
+            Ia64Unwind => "PT_IA_64_UNWIND",
+            S390Pgste => "PT_S390_PGSTE",
+            Os(_) | Proc(_) | Unknown(_) => return None,
+        })
+    }
+
+    /// Returns the raw `p_type` value
+    pub fn raw(self) -> u32 {
+        use SegmentType::*;
+        match self {
+            Null => PT_NULL,
+            Load => PT_LOAD,
+            Dynamic => PT_DYNAMIC,
+            Interp => PT_INTERP,
+            Note => PT_NOTE,
+            Shlib => PT_SHLIB,
+            Phdr => PT_PHDR,
+            Tls => PT_TLS,
+            GnuEhFrame => PT_GNU_EH_FRAME,
+            GnuStack => PT_GNU_STACK,
+            GnuRelro => PT_GNU_RELRO,
+            GnuProperty => PT_GNU_PROPERTY,
+            GnuSframe => PT_GNU_SFRAME,
+            GnuMbind(i) => PT_GNU_MBIND_LO + i,
+            SunwUnwind => PT_SUNW_UNWIND,
+            SunwBss => PT_SUNWBSS,
+            SunwStack => PT_SUNWSTACK,
+            SunwDtrace => PT_SUNWDTRACE,
+            SunwCap => PT_SUNWCAP,
+            OpenbsdMutable => PT_OPENBSD_MUTABLE,
+            OpenbsdRandomize => PT_OPENBSD_RANDOMIZE,
+            OpenbsdWxneeded => PT_OPENBSD_WXNEEDED,
+            OpenbsdNobtcfi => PT_OPENBSD_NOBTCFI,
+            OpenbsdSyscalls => PT_OPENBSD_SYSCALLS,
+            OpenbsdBootdata => PT_OPENBSD_BOOTDATA,
+            PaxFlags => PT_PAX_FLAGS,
+            HpTls => 0x6000_0000,
+            HpCoreNone => 0x6000_0001,
+            HpCoreVersion => 0x6000_0002,
+            HpCoreKernel => 0x6000_0003,
+            HpCoreComm => 0x6000_0004,
+            HpCoreProc => 0x6000_0005,
+            HpCoreLoadable => 0x6000_0006,
+            HpCoreStack => 0x6000_0007,
+            HpCoreShm => 0x6000_0008,
+            HpCoreMmf => 0x6000_0009,
+            HpParallel => 0x6000_0010,
+            HpFastbind => 0x6000_0011,
+            HpOptAnnot => 0x6000_0012,
+            HpHslAnnot => 0x6000_0013,
+            HpStack => 0x6000_0014,
+            HpCoreUtsname => 0x6000_0015,
+            ArmArchext | Aarch64Archext | MipsReginfo | PariscArchext | Ia64Archext | S390Pgste => {
+                0x7000_0000
+            }
+            ArmExidx | Aarch64Unwind | MipsRtproc | PariscUnwind | Ia64Unwind => 0x7000_0001,
+            Aarch64MemtagMte | MipsOptions | PariscWeakorder => 0x7000_0002,
+            MipsAbiflags | RiscvAttributes => 0x7000_0003,
+            Os(v) | Proc(v) | Unknown(v) => v,
+        }
+    }
+}
+
+impl fmt::Display for SegmentType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SegmentType::GnuMbind(i) => write!(f, "GNU_MBIND+0x{:x}", i),
+            SegmentType::Os(v) => write!(f, "LOOS+0x{:x}", v - PT_LOOS),
+            SegmentType::Proc(v) => write!(f, "LOPROC+0x{:x}", v - PT_LOPROC),
+            SegmentType::Unknown(v) => write!(f, "<unknown>: 0x{:x}", v),
+            t => f.write_str(t.name().unwrap_or_default()),
+        }
+    }
+}
+
+pub const SHN_UNDEF: u16 = 0;
+pub const SHN_LORESERVE: u16 = 0xff00;
+pub const SHN_ABS: u16 = 0xfff1;
+pub const SHN_COMMON: u16 = 0xfff2;
+pub const SHN_XINDEX: u16 = 0xffff;
+
+pub const SHT_NULL: u32 = 0;
+pub const SHT_PROGBITS: u32 = 1;
+pub const SHT_SYMTAB: u32 = 2;
+pub const SHT_STRTAB: u32 = 3;
+pub const SHT_RELA: u32 = 4;
+pub const SHT_HASH: u32 = 5;
+pub const SHT_DYNAMIC: u32 = 6;
+pub const SHT_NOTE: u32 = 7;
+pub const SHT_NOBITS: u32 = 8;
+pub const SHT_REL: u32 = 9;
+pub const SHT_SHLIB: u32 = 10;
+pub const SHT_DYNSYM: u32 = 11;
+pub const SHT_INIT_ARRAY: u32 = 14;
+pub const SHT_FINI_ARRAY: u32 = 15;
+pub const SHT_PREINIT_ARRAY: u32 = 16;
+pub const SHT_GROUP: u32 = 17;
+pub const SHT_SYMTAB_SHNDX: u32 = 18;
+pub const SHT_RELR: u32 = 19;
+pub const SHT_LOOS: u32 = 0x6000_0000;
+pub const SHT_ANDROID_REL: u32 = 0x6000_0001;
+pub const SHT_ANDROID_RELA: u32 = 0x6000_0002;
+pub const SHT_LLVM_ODRTAB: u32 = 0x6fff_4c00;
+pub const SHT_LLVM_LINKER_OPTIONS: u32 = 0x6fff_4c01;
+pub const SHT_LLVM_ADDRSIG: u32 = 0x6fff_4c03;
+pub const SHT_LLVM_DEPENDENT_LIBRARIES: u32 = 0x6fff_4c04;
+pub const SHT_LLVM_SYMPART: u32 = 0x6fff_4c05;
+pub const SHT_LLVM_BB_ADDR_MAP: u32 = 0x6fff_4c0a;
+pub const SHT_GNU_SFRAME: u32 = 0x6fff_fff4;
+pub const SHT_GNU_ATTRIBUTES: u32 = 0x6fff_fff5;
+pub const SHT_GNU_HASH: u32 = 0x6fff_fff6;
+pub const SHT_GNU_LIBLIST: u32 = 0x6fff_fff7;
+pub const SHT_CHECKSUM: u32 = 0x6fff_fff8;
+pub const SHT_GNU_VERDEF: u32 = 0x6fff_fffd;
+pub const SHT_GNU_VERNEED: u32 = 0x6fff_fffe;
+pub const SHT_GNU_VERSYM: u32 = 0x6fff_ffff;
+pub const SHT_HIOS: u32 = 0x6fff_ffff;
+pub const SHT_LOPROC: u32 = 0x7000_0000;
+pub const SHT_HIPROC: u32 = 0x7fff_ffff;
+pub const SHT_LOUSER: u32 = 0x8000_0000;
+pub const SHT_HIUSER: u32 = 0xffff_ffff;
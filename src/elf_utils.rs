@@ -1,3 +1,7 @@
+use crate::elf_flags::SegmentFlags;
+use crate::elf_types::*;
+use crate::symbols::*;
+
 /// Returns a human readable string representation for the E_CLASS field
 pub fn e_class_to_str(c: u8) -> &'static str {
     match c {
@@ -19,143 +23,117 @@ pub fn e_bit_to_str(c: u8) -> &'static str {
 /// Returns a human readable string representation for the E_TYPE field
 /// We assume `c` has the correct endianness
 pub fn e_type_to_str(c: u16) -> &'static str {
-    match c {
-        0 => "No file type",
-        1 => "Relocatable file",
-        2 => "Executable file",
-        3 => "Shared object file",
-        4 => "Core file",
-        0xfe00 | 0xfeff => "Operating system-specfic",
-        0xff00 | 0xffff => "Processor-specific",
-        _ => "Unknown",
-    }
+    ElfType::try_from(c).map_or("Unknown", ElfType::description)
 }
 
 /// Returns a human readable string representation for the E_ABI field
 pub fn e_abi_to_str(c: u8) -> &'static str {
-    match c {
-        0 => "System V",
-        1 => "HP-UX",
-        2 => "NetBSD",
-        3 => "Linux",
-        4 => "GNU Hurd",
-        6 => "Solaris",
-        7 => "AIX",
-        8 => "IRIX",
-        9 => "FreeBSD",
-        10 => "Tru64",
-        11 => "Novell Modesto",
-        12 => "OpenBSD",
-        13 => "OpenVMS",
-        14 => "NonStop Kernel",
-        15 => "AROS",
-        16 => "Fenix OS",
-        17 => "CloudABI",
-        18 => "Stratus Technologies OpenVOS",
-        _ => "Unknown",
-    }
+    OsAbi::try_from(c).map_or("Unknown", OsAbi::description)
 }
 
 /// Returns a human readable string representation for the E_MACHINE field
 /// We assume `c` has the correct endianness
 pub fn e_machine_to_str(c: u16) -> &'static str {
-    match c {
-        0 => "No machine",
-        1 => "AT&T WE 32100",
-        2 => "SUN SPARC",
-        3 => "Intel 80386",
-        4 => "Motorola 68000",
-        5 => "Motorola 88000",
-        7 => "Intel 80860",
-        8 => "MIPS R3000 big-endian",
-        15 => "Hewlett-Packard PA-RISC",
-        18 => "Enhanced instruction set SPARC",
-        20 => "PowerPC",
-        21 => "PowerPC64",
-        22 => "IBM S/390",
-        23 => "Cell BE SPU",
-        40 => "Advanced RISC Machines ARM",
-        41 => "Digital Alpha",
-        42 => "Hitachi SH",
-        43 => "SPARC Version 9 64-bit",
-        46 => "Renesas H8/300",
-        50 => "Intel IA-64 processor architecture",
-        62 => "AMD x86-64 architecture",
-        76 => "Axis Communications 32-bit embedded processor",
-        88 => "Renesas M32R",
-        89 => "Panasonic/MEI MN10300, AM33",
-        92 => "OpenRISC 32-bit embedded processor",
-        93 => "ARC Cores Tangent-A5",
-        94 => "Tensilica Xtensa Architecture",
-        106 => "ADI Blackfin processor",
-        110 => "UniCore-32",
-        113 => "Altera Nios II soft-core processor",
-        140 => "TMS320C6000 Family",
-        164 => "QUALCOMM Hexagon",
-        167 => "Andes Technology embedded RISC processor",
-        183 => "ARM 64-bits (ARMv8/Aarch64)",
-        188 => "Tilera TILE-Gx",
-        195 => "ARCv2 Cores",
-        243 => "RISC-V",
-        247 => "Linux BPF",
-        252 => "C-SKY",
+    Machine::try_from(c).map_or("Unknown", Machine::description)
+}
+
+/// Returns a readelf style string representation for the P_FLAGS field
+/// We assume `c` and `d` have the correct endianness
+pub fn p_flags_to_str(c: u32, d: u16) -> String {
+    SegmentFlags::from_bits_retain(c).to_str(d)
+}
+
+/// Returns a human readable string representation for the P_TYPE field
+/// We assume `c` and `d` have the correct endianness
+pub fn p_type_to_str(c: u32, d: u16) -> String {
+    SegmentType::from_raw(c, d).to_string()
+}
+
+/// Returns a human readable string representation for the SH_TYPE field
+/// We assume `c` and `d` have the correct endianness
+pub fn sh_type_to_str(c: u32, d: u16) -> String {
+    let machine = Machine::try_from(d).ok();
+    let name = match c {
+        SHT_NULL => "NULL",
+        SHT_PROGBITS => "PROGBITS",
+        SHT_SYMTAB => "SYMTAB",
+        SHT_STRTAB => "STRTAB",
+        SHT_RELA => "RELA",
+        SHT_HASH => "HASH",
+        SHT_DYNAMIC => "DYNAMIC",
+        SHT_NOTE => "NOTE",
+        SHT_NOBITS => "NOBITS",
+        SHT_REL => "REL",
+        SHT_SHLIB => "SHLIB",
+        SHT_DYNSYM => "DYNSYM",
+        SHT_INIT_ARRAY => "INIT_ARRAY",
+        SHT_FINI_ARRAY => "FINI_ARRAY",
+        SHT_PREINIT_ARRAY => "PREINIT_ARRAY",
+        SHT_GROUP => "GROUP",
+        SHT_SYMTAB_SHNDX => "SYMTAB_SHNDX",
+        SHT_RELR => "RELR",
+        SHT_ANDROID_REL => "ANDROID_REL",
+        SHT_ANDROID_RELA => "ANDROID_RELA",
+        SHT_LLVM_ODRTAB => "LLVM_ODRTAB",
+        SHT_LLVM_LINKER_OPTIONS => "LLVM_LINKER_OPTIONS",
+        SHT_LLVM_ADDRSIG => "LLVM_ADDRSIG",
+        SHT_LLVM_DEPENDENT_LIBRARIES => "LLVM_DEPENDENT_LIBRARIES",
+        SHT_LLVM_SYMPART => "LLVM_SYMPART",
+        SHT_LLVM_BB_ADDR_MAP => "LLVM_BB_ADDR_MAP",
+        SHT_GNU_SFRAME => "GNU_SFRAME",
+        SHT_GNU_ATTRIBUTES => "GNU_ATTRIBUTES",
+        SHT_GNU_HASH => "GNU_HASH",
+        SHT_GNU_LIBLIST => "GNU_LIBLIST",
+        SHT_CHECKSUM => "CHECKSUM",
+        SHT_GNU_VERDEF => "VERDEF",
+        SHT_GNU_VERNEED => "VERNEED",
+        SHT_GNU_VERSYM => "VERSYM",
+        SHT_LOPROC..=SHT_HIPROC => match (machine, c - SHT_LOPROC) {
+            (Some(Machine::Arm), 1) => "ARM_EXIDX",
+            (Some(Machine::Arm), 2) => "ARM_PREEMPTMAP",
+            (Some(Machine::Arm), 3) => "ARM_ATTRIBUTES",
+            (Some(Machine::Aarch64), 3) => "AARCH64_ATTRIBUTES",
+            (Some(Machine::Riscv), 3) => "RISCV_ATTRIBUTES",
+            (Some(Machine::X86_64), 1) => "X86_64_UNWIND",
+            (Some(Machine::Mips | Machine::MipsRs3Le), 0x0) => "MIPS_LIBLIST",
+            (Some(Machine::Mips | Machine::MipsRs3Le), 0x2) => "MIPS_CONFLICT",
+            (Some(Machine::Mips | Machine::MipsRs3Le), 0x3) => "MIPS_GPTAB",
+            (Some(Machine::Mips | Machine::MipsRs3Le), 0x6) => "MIPS_REGINFO",
+            (Some(Machine::Mips | Machine::MipsRs3Le), 0xd) => "MIPS_OPTIONS",
+            (Some(Machine::Mips | Machine::MipsRs3Le), 0x1e) => "MIPS_DWARF",
+            (Some(Machine::Mips | Machine::MipsRs3Le), 0x2a) => "MIPS_ABIFLAGS",
+            (Some(Machine::Mips | Machine::MipsRs3Le), 0x2b) => "MIPS_XHASH",
+            _ => return format!("LOPROC+0x{:x}", c - SHT_LOPROC),
+        },
+        SHT_LOOS..=SHT_HIOS => return format!("LOOS+0x{:x}", c - SHT_LOOS),
+        SHT_LOUSER..=SHT_HIUSER => return format!("LOUSER+0x{:x}", c - SHT_LOUSER),
         _ => "Unknown",
-    }
+    };
+    name.to_string()
 }
 
-/// Returns a human readable string representation for the P_FLAGS field
-/// We assume `c` has the correct endianness
-pub fn p_flags_to_str(c: u32) -> &'static str {
+/// Returns a readelf style string representation for the type nibble of ST_INFO
+pub fn st_type_to_str(c: u8) -> &'static str {
     match c {
-        1 => "X",
-        2 => "W",
-        3 => " WX",
-        4 => "R",
-        5 => "R X",
-        6 => "RW",
-        7 => "RWX",
-        _ => "???",
+        STT_NOTYPE => "NOTYPE",
+        STT_OBJECT => "OBJECT",
+        STT_FUNC => "FUNC",
+        STT_SECTION => "SECTION",
+        STT_FILE => "FILE",
+        STT_COMMON => "COMMON",
+        STT_TLS => "TLS",
+        STT_GNU_IFUNC => "IFUNC",
+        _ => "Unknown",
     }
 }
 
-/// Returns a human readable string representation for the P_TYPE field
-/// We assume `c` has the correct endianness
-pub fn p_type_to_str(c: u32, d: u16) -> &'static str {
-    let arch = e_machine_to_str(d).to_lowercase();
+/// Returns a readelf style string representation for the binding nibble of ST_INFO
+pub fn st_bind_to_str(c: u8) -> &'static str {
     match c {
-        0 => "PT_NULL",
-        1 => "PT_LOAD",
-        2 => "PT_DYNAMIC",
-        3 => "PT_INTERP",
-        4 => "PT_NOTE",
-        5 => "PT_SHLIB",
-        6 => "PT_PHDR",
-        7 => "PT_TLS",
-        0x60000000 => "PT_LOOS",
-        0x6FFFFFFF => "PT_HIOS",
-        0x7FFFFFFF => "PT_HIPROC",
-        0x6474e553 => "GNU_PROPERTY",
-        0x6474e550 => "GNU_EH_FRAME",
-        0x6474e551 => "GNU_STACK",
-        0x6474e552 => "GNU_RELRO",
-        0x70000000 => {
-            if arch.contains("arm") {
-                "APT_ARM_ARCHEXT"
-            } else if arch.contains("mips") {
-                "PT_MIPS_REGINFO"
-            } else {
-                "PT_LOPROC"
-            }
-        }
-        0x70000001 => {
-            if arch.contains("arm") {
-                "PT_ARM_EXIDX"
-            } else {
-                "PT_MIPS_RTPROC"
-            }
-        }
-        0x70000002 => "PT_MIPS_OPTIONS",
-        0x70000003 => "PT_MIPS_ABI_FLAGS",
+        STB_LOCAL => "LOCAL",
+        STB_GLOBAL => "GLOBAL",
+        STB_WEAK => "WEAK",
+        STB_GNU_UNIQUE => "UNIQUE",
         _ => "Unknown",
     }
 }
@@ -0,0 +1,109 @@
+use crate::{ElfHeader64, ProgramHeader64, SectionHeader64};
+
+/// Serializes the widened 64-bit header structures in the class and byte order of a file
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ElfWriter {
+    pub(crate) is_64: bool,
+    pub(crate) big_endian: bool,
+}
+
+impl ElfWriter {
+    pub(crate) fn new(is_64: bool, big_endian: bool) -> ElfWriter {
+        ElfWriter { is_64, big_endian }
+    }
+
+    /// Appends the low `size` bytes of `value`
+    pub(crate) fn put(&self, out: &mut Vec<u8>, value: u64, size: usize) {
+        let bytes = value.to_le_bytes();
+        if self.big_endian {
+            out.extend(bytes[..size].iter().rev());
+        } else {
+            out.extend_from_slice(&bytes[..size]);
+        }
+    }
+
+    /// Appends an address or offset sized word of the file's class
+    pub(crate) fn put_addr(&self, out: &mut Vec<u8>, value: u64) {
+        self.put(out, value, if self.is_64 { 8 } else { 4 });
+    }
+
+    pub(crate) fn header(&self, eh: &ElfHeader64) -> Vec<u8> {
+        let mut out = eh.e_ident.to_vec();
+        self.put(&mut out, eh.e_type as u64, 2);
+        self.put(&mut out, eh.e_machine as u64, 2);
+        self.put(&mut out, eh.e_version as u64, 4);
+        self.put_addr(&mut out, eh.e_entry);
+        self.put_addr(&mut out, eh.e_phoff);
+        self.put_addr(&mut out, eh.e_shoff);
+        self.put(&mut out, eh.e_flags as u64, 4);
+        for v in [
+            eh.e_ehsize,
+            eh.e_phentsize,
+            eh.e_phnum,
+            eh.e_shentsize,
+            eh.e_shnum,
+            eh.e_shstrndx,
+        ] {
+            self.put(&mut out, v as u64, 2);
+        }
+        out
+    }
+
+    pub(crate) fn program_header(&self, ph: &ProgramHeader64) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.put(&mut out, ph.p_type as u64, 4);
+        // p_flags moved behind p_type in the 64-bit layout
+        if self.is_64 {
+            self.put(&mut out, ph.p_flags as u64, 4);
+        }
+        for v in [ph.p_offset, ph.p_vaddr, ph.p_paddr, ph.p_filesz, ph.p_memsz] {
+            self.put_addr(&mut out, v);
+        }
+        if !self.is_64 {
+            self.put(&mut out, ph.p_flags as u64, 4);
+        }
+        self.put_addr(&mut out, ph.p_align);
+        out
+    }
+
+    pub(crate) fn section_header(&self, sh: &SectionHeader64) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.put(&mut out, sh.sh_name as u64, 4);
+        self.put(&mut out, sh.sh_type as u64, 4);
+        for v in [sh.sh_flags, sh.sh_addr, sh.sh_offset, sh.sh_size] {
+            self.put_addr(&mut out, v);
+        }
+        self.put(&mut out, sh.sh_link as u64, 4);
+        self.put(&mut out, sh.sh_info as u64, 4);
+        self.put_addr(&mut out, sh.sh_addralign);
+        self.put_addr(&mut out, sh.sh_entsize);
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::elf_file::ElfFile;
+
+    #[test]
+    fn test_round_trip() {
+        for path in ["/bin/ls", "tests/bin/dd.armel", "tests/bin/objdump.mips"] {
+            let elf = ElfFile::open(path).unwrap();
+            let w = ElfWriter::new(elf.is_64(), elf.is_big_endian());
+            let data = elf.data();
+            let header = w.header(&elf.header);
+            assert_eq!(&data[..header.len()], &header[..], "{}", path);
+            let entsize = elf.header.e_phentsize as usize;
+            for (i, ph) in elf.program_headers.iter().enumerate() {
+                let off = elf.header.e_phoff as usize + i * entsize;
+                assert_eq!(&data[off..off + entsize], &w.program_header(ph)[..]);
+            }
+            let entsize = elf.header.e_shentsize as usize;
+            for (i, sh) in elf.section_headers.iter().enumerate() {
+                let off = elf.header.e_shoff as usize + i * entsize;
+                assert_eq!(&data[off..off + entsize], &w.section_header(sh)[..]);
+            }
+        }
+    }
+}
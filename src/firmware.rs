@@ -0,0 +1,389 @@
+use std::error::Error;
+use std::fmt::Write;
+
+use crate::elf_file::{ElfFile, ELFCLASS32, ELFCLASS64, ELFDATA2LSB, ELFDATA2MSB, ELFMAG};
+use crate::elf_types::{Machine, ET_EXEC, PT_LOAD, SHT_PROGBITS, SHT_STRTAB};
+use crate::elf_writer::ElfWriter;
+use crate::{
+    ElfHeader64, ProgramHeader64, SectionFlags, SectionHeader64, SegmentFlags, SIZEOF_EHDR32,
+    SIZEOF_EHDR64, SIZEOF_PHDR32, SIZEOF_PHDR64, SIZEOF_SHDR32, SIZEOF_SHDR64,
+};
+
+/// Data bytes per Intel HEX and S-record line, as written by objcopy
+pub const RECORD_LEN: usize = 16;
+
+/// A sensible `max_size` for [`raw_binary`]. Larger images usually come from a segment
+/// at a far away address, e.g. a bootloader vector table in a separate flash bank.
+pub const RAW_BINARY_LIMIT: usize = 256 << 20;
+
+/// Which program header address places a segment in the flat image
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoadAddress {
+    /// `p_paddr`, the load memory address (LMA) the data is flashed to
+    Physical,
+    /// `p_vaddr`, the virtual memory address (VMA) the data is used at
+    Virtual,
+}
+
+/// Parameters for [`wrap_raw_binary`]
+#[derive(Debug, Clone)]
+pub struct RawImageOptions {
+    pub load_address: u64,
+    /// Entry point, the load address if `None`
+    pub entry: Option<u64>,
+    pub machine: u16,
+    pub e_flags: u32,
+    pub is_64: bool,
+    pub big_endian: bool,
+    /// Name of the section holding the data
+    pub section_name: String,
+}
+
+impl Default for RawImageOptions {
+    fn default() -> RawImageOptions {
+        RawImageOptions {
+            load_address: 0,
+            entry: None,
+            machine: u16::from(Machine::Arm),
+            e_flags: 0,
+            is_64: false,
+            big_endian: false,
+            section_name: ".text".to_string(),
+        }
+    }
+}
+
+impl ElfFile {
+    /// Returns the file contents of all `PT_LOAD` segments with their load address, sorted
+    /// by address. The zero filled tail of `p_memsz` is not part of the image.
+    pub fn load_chunks(&self, by: LoadAddress) -> Vec<(u64, &[u8])> {
+        let mut chunks: Vec<(u64, &[u8])> = self
+            .program_headers
+            .iter()
+            .filter(|ph| ph.p_type == PT_LOAD && ph.p_filesz != 0)
+            .filter_map(|ph| {
+                let address = match by {
+                    LoadAddress::Physical => ph.p_paddr,
+                    LoadAddress::Virtual => ph.p_vaddr,
+                };
+                Some((address, self.segment_data(ph)?))
+            })
+            .collect();
+        chunks.sort_by_key(|&(address, _)| address);
+        chunks
+    }
+
+    /// Flattens the loadable segments into one image like `objcopy -O binary`, returning
+    /// the address of its first byte. Gaps are filled with `fill`, images larger than
+    /// `max_size` bytes are rejected.
+    pub fn to_raw_binary(
+        &self,
+        by: LoadAddress,
+        fill: u8,
+        max_size: usize,
+    ) -> Result<(u64, Vec<u8>), Box<dyn Error>> {
+        raw_binary(&self.load_chunks(by), fill, max_size)
+    }
+
+    /// Converts the loadable segments into Intel HEX like `objcopy -O ihex`
+    pub fn to_ihex(&self, by: LoadAddress) -> Result<String, Box<dyn Error>> {
+        ihex(&self.load_chunks(by), Some(self.header.e_entry))
+    }
+
+    /// Converts the loadable segments into Motorola S-records like `objcopy -O srec`, with
+    /// `header` as the contents of the S0 record
+    pub fn to_srec(&self, by: LoadAddress, header: &str) -> Result<String, Box<dyn Error>> {
+        srec(&self.load_chunks(by), self.header.e_entry, header)
+    }
+}
+
+/// Lays out address tagged chunks in one buffer, later chunks overwriting earlier ones.
+/// Fails if the buffer would be larger than `max_size` bytes.
+pub fn raw_binary(
+    chunks: &[(u64, &[u8])],
+    fill: u8,
+    max_size: usize,
+) -> Result<(u64, Vec<u8>), Box<dyn Error>> {
+    let Some(base) = chunks.iter().map(|&(address, _)| address).min() else {
+        return Ok((0, Vec::new()));
+    };
+    let end = chunks
+        .iter()
+        .map(|&(address, data)| address.checked_add(data.len() as u64))
+        .try_fold(base, |max, end| Some(max.max(end?)))
+        .ok_or("segment end overflows the address space")?;
+    let size = usize::try_from(end - base).map_err(|_| "image too large")?;
+    if size > max_size {
+        return Err(format!(
+            "image of {:#x} bytes from {:#x} exceeds the limit of {:#x} bytes",
+            size, base, max_size
+        )
+        .into());
+    }
+    let mut image = vec![fill; size];
+    for &(address, data) in chunks {
+        let off = (address - base) as usize;
+        image[off..off + data.len()].copy_from_slice(data);
+    }
+    Ok((base, image))
+}
+
+/// Formats one record as hex digits followed by the negated (Intel) or inverted (Motorola)
+/// byte sum as checksum. Lines end in CRLF like the output of objcopy.
+fn hex_record(out: &mut String, prefix: &str, bytes: &[u8], ones_complement: bool) {
+    let sum = bytes.iter().fold(0u8, |acc, &b| acc.wrapping_add(b));
+    let checksum = if ones_complement {
+        !sum
+    } else {
+        sum.wrapping_neg()
+    };
+    out.push_str(prefix);
+    for b in bytes.iter().chain([checksum].iter()) {
+        let _ = write!(out, "{:02X}", b);
+    }
+    out.push_str("\r\n");
+}
+
+fn ihex_record(out: &mut String, address: u16, record_type: u8, data: &[u8]) {
+    let mut bytes = vec![data.len() as u8];
+    bytes.extend_from_slice(&address.to_be_bytes());
+    bytes.push(record_type);
+    bytes.extend_from_slice(data);
+    hex_record(out, ":", &bytes, false);
+}
+
+/// Encodes address tagged chunks as Intel HEX using extended linear address records.
+/// A start linear address record is written for a non zero `entry`.
+pub fn ihex(chunks: &[(u64, &[u8])], entry: Option<u64>) -> Result<String, Box<dyn Error>> {
+    let mut out = String::new();
+    let mut upper = 0u64;
+    for &(address, data) in chunks {
+        if address.saturating_add(data.len() as u64) > 1 << 32 {
+            return Err(format!("address {:#x} does not fit into Intel HEX", address).into());
+        }
+        let mut pos = 0;
+        while pos < data.len() {
+            let addr = address + pos as u64;
+            if addr >> 16 != upper {
+                upper = addr >> 16;
+                ihex_record(&mut out, 0, 4, &(upper as u16).to_be_bytes());
+            }
+            // Records must not cross a 64K boundary
+            let len = (data.len() - pos)
+                .min(RECORD_LEN)
+                .min(0x1_0000 - (addr & 0xffff) as usize);
+            ihex_record(&mut out, addr as u16, 0, &data[pos..pos + len]);
+            pos += len;
+        }
+    }
+    match entry {
+        Some(entry) if entry > u32::MAX as u64 => {
+            return Err(format!("entry {:#x} does not fit into Intel HEX", entry).into())
+        }
+        Some(entry) if entry != 0 => ihex_record(&mut out, 0, 5, &(entry as u32).to_be_bytes()),
+        _ => {}
+    }
+    ihex_record(&mut out, 0, 1, &[]);
+    Ok(out)
+}
+
+fn srec_record(out: &mut String, record_type: u8, address: u64, addr_len: usize, data: &[u8]) {
+    let mut bytes = vec![(addr_len + data.len() + 1) as u8];
+    bytes.extend_from_slice(&address.to_be_bytes()[8 - addr_len..]);
+    bytes.extend_from_slice(data);
+    hex_record(out, &format!("S{}", record_type), &bytes, true);
+}
+
+/// Encodes address tagged chunks as Motorola S-records. The narrowest of the S1/S2/S3
+/// data records that fits every address is used, with the matching S9/S8/S7 terminator.
+pub fn srec(chunks: &[(u64, &[u8])], entry: u64, header: &str) -> Result<String, Box<dyn Error>> {
+    let highest = chunks
+        .iter()
+        .map(|&(address, data)| address.saturating_add(data.len().max(1) as u64 - 1))
+        .fold(entry, u64::max);
+    let (data_type, addr_len) = match highest {
+        0..=0xffff => (1, 2),
+        0x1_0000..=0xff_ffff => (2, 3),
+        0x100_0000..=0xffff_ffff => (3, 4),
+        _ => return Err(format!("address {:#x} does not fit into S-records", highest).into()),
+    };
+
+    let mut out = String::new();
+    srec_record(&mut out, 0, 0, 2, header.as_bytes());
+    for &(address, data) in chunks {
+        for (i, line) in data.chunks(RECORD_LEN).enumerate() {
+            let addr = address + (i * RECORD_LEN) as u64;
+            srec_record(&mut out, data_type, addr, addr_len, line);
+        }
+    }
+    srec_record(&mut out, 10 - data_type, entry, addr_len, &[]);
+    Ok(out)
+}
+
+/// Wraps a raw image into a minimal executable ELF with a single RWX `PT_LOAD` segment at
+/// the load address, the reverse of [`ElfFile::to_raw_binary`]
+pub fn wrap_raw_binary(data: &[u8], opts: &RawImageOptions) -> Vec<u8> {
+    let w = ElfWriter::new(opts.is_64, opts.big_endian);
+    let (ehsize, phentsize, shentsize) = if opts.is_64 {
+        (SIZEOF_EHDR64, SIZEOF_PHDR64, SIZEOF_SHDR64)
+    } else {
+        (SIZEOF_EHDR32, SIZEOF_PHDR32, SIZEOF_SHDR32)
+    };
+    // Keep p_offset congruent to p_vaddr modulo p_align
+    let align = 16u64;
+    let headers = (ehsize + phentsize) as u64;
+    let data_off = headers.div_ceil(align) * align + opts.load_address % align;
+    let mut shstrtab = b"\0.shstrtab\0".to_vec();
+    let name_off = shstrtab.len() as u32;
+    shstrtab.extend_from_slice(opts.section_name.as_bytes());
+    shstrtab.push(0);
+    let shstrtab_off = data_off + data.len() as u64;
+    let shoff = (shstrtab_off + shstrtab.len() as u64 + 7) & !7;
+
+    let mut e_ident = [0u8; 16];
+    e_ident[..4].copy_from_slice(&ELFMAG);
+    e_ident[4] = if opts.is_64 { ELFCLASS64 } else { ELFCLASS32 };
+    e_ident[5] = if opts.big_endian {
+        ELFDATA2MSB
+    } else {
+        ELFDATA2LSB
+    };
+    e_ident[6] = 1;
+    let header = ElfHeader64 {
+        e_ident,
+        e_type: ET_EXEC,
+        e_machine: opts.machine,
+        e_version: 1,
+        e_entry: opts.entry.unwrap_or(opts.load_address),
+        e_phoff: ehsize as u64,
+        e_shoff: shoff,
+        e_flags: opts.e_flags,
+        e_ehsize: ehsize as u16,
+        e_phentsize: phentsize as u16,
+        e_phnum: 1,
+        e_shentsize: shentsize as u16,
+        e_shnum: 3,
+        e_shstrndx: 1,
+    };
+    let segment = ProgramHeader64 {
+        p_type: PT_LOAD,
+        p_flags: (SegmentFlags::R | SegmentFlags::W | SegmentFlags::X).bits(),
+        p_offset: data_off,
+        p_vaddr: opts.load_address,
+        p_paddr: opts.load_address,
+        p_filesz: data.len() as u64,
+        p_memsz: data.len() as u64,
+        p_align: align,
+    };
+    let sections = [
+        SectionHeader64::default(),
+        SectionHeader64 {
+            sh_name: 1,
+            sh_type: SHT_STRTAB,
+            sh_offset: shstrtab_off,
+            sh_size: shstrtab.len() as u64,
+            sh_addralign: 1,
+            ..Default::default()
+        },
+        SectionHeader64 {
+            sh_name: name_off,
+            sh_type: SHT_PROGBITS,
+            sh_flags: (SectionFlags::ALLOC | SectionFlags::WRITE | SectionFlags::EXECINSTR).bits(),
+            sh_addr: opts.load_address,
+            sh_offset: data_off,
+            sh_size: data.len() as u64,
+            sh_addralign: 1,
+            ..Default::default()
+        },
+    ];
+
+    let mut out = w.header(&header);
+    out.extend(w.program_header(&segment));
+    out.resize(data_off as usize, 0);
+    out.extend_from_slice(data);
+    out.extend(shstrtab);
+    out.resize(shoff as usize, 0);
+    for sh in &sections {
+        out.extend(w.section_header(sh));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_objcopy_formats() {
+        let elf = ElfFile::open("tests/bin/firmware.arm").unwrap();
+        let (base, image) = elf
+            .to_raw_binary(LoadAddress::Physical, 0, RAW_BINARY_LIMIT)
+            .unwrap();
+        assert_eq!(base, 0x0800_0000);
+        assert_eq!(image, std::fs::read("tests/bin/firmware.bin").unwrap());
+        assert_eq!(
+            elf.to_ihex(LoadAddress::Physical).unwrap(),
+            std::fs::read_to_string("tests/bin/firmware.hex").unwrap()
+        );
+        assert_eq!(
+            elf.to_srec(LoadAddress::Physical, "firmware.srec").unwrap(),
+            std::fs::read_to_string("tests/bin/firmware.srec").unwrap()
+        );
+
+        // By VMA the .data initializers sit in RAM instead of after .rodata in flash
+        let chunks = elf.load_chunks(LoadAddress::Virtual);
+        assert_eq!(chunks.last().unwrap().0, 0x2000_0000);
+        assert_eq!(&chunks.last().unwrap().1[..4], &[0x44, 0x33, 0x22, 0x11]);
+        let (_, image) = raw_binary(&[(0x10, &[1, 2]), (0x14, &[3])], 0xff, 8).unwrap();
+        assert_eq!(image, [1, 2, 0xff, 0xff, 3]);
+        assert!(raw_binary(&[(0x10, &[1, 2]), (0x14, &[3])], 0xff, 4).is_err());
+        // Flash and RAM are 0x1800_0000 bytes apart
+        let err = elf
+            .to_raw_binary(LoadAddress::Virtual, 0, RAW_BINARY_LIMIT)
+            .unwrap_err();
+        assert!(err.to_string().contains("exceeds the limit"));
+    }
+
+    #[test]
+    fn test_record_boundaries() {
+        let data = [0xaa; 24];
+        let hex = ihex(&[(0x1fff8, &data[..])], None).unwrap();
+        let lines: Vec<&str> = hex.lines().collect();
+        assert_eq!(
+            lines[..3],
+            [
+                ":020000040001F9",
+                ":08FFF800AAAAAAAAAAAAAAAAB1",
+                ":020000040002F8"
+            ]
+        );
+        assert_eq!(lines.last(), Some(&":00000001FF"));
+        let s = srec(&[(0x100, &data[..4])], 0, "").unwrap();
+        assert_eq!(s, "S0030000FC\r\nS1070100AAAAAAAA4F\r\nS9030000FC\r\n");
+        assert!(ihex(&[(0xffff_fff0, &data[..])], None).is_err());
+    }
+
+    #[test]
+    fn test_wrap_raw_binary() {
+        let image = std::fs::read("tests/bin/firmware.bin").unwrap();
+        for big_endian in [false, true] {
+            let opts = RawImageOptions {
+                load_address: 0x0800_0000,
+                entry: Some(0x0800_0011),
+                e_flags: 0x0500_0000,
+                big_endian,
+                ..Default::default()
+            };
+            let elf = ElfFile::parse(wrap_raw_binary(&image, &opts)).unwrap();
+            assert_eq!(elf.header.machine(), Ok(Machine::Arm));
+            assert_eq!(elf.header.e_entry, 0x0800_0011);
+            assert_eq!(elf.section_name(&elf.section_headers[2]), ".text");
+            assert_eq!(
+                elf.to_raw_binary(LoadAddress::Physical, 0, RAW_BINARY_LIMIT)
+                    .unwrap(),
+                (0x0800_0000, image.clone())
+            );
+            assert!(elf.validate().is_empty());
+        }
+    }
+}
@@ -0,0 +1,396 @@
+use std::collections::HashMap;
+use std::ops::Range;
+
+use crate::elf_file::ElfFile;
+use crate::elf_types::{
+    Machine, SegmentType, PT_LOAD, SHT_FINI_ARRAY, SHT_INIT_ARRAY, SHT_PREINIT_ARRAY,
+};
+use crate::plt::ImportKind;
+use crate::symbols::{Symbol, STT_FUNC, STT_GNU_IFUNC};
+use crate::{SectionFlags, SegmentFlags};
+
+/// Evidence a recovered function boundary is based on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum FunctionSource {
+    /// A sized or unsized `STT_FUNC` entry of `.symtab`
+    Symbol,
+    /// A defined `STT_FUNC` entry of `.dynsym`
+    DynamicSymbol,
+    /// An FDE of `.eh_frame`
+    EhFrame,
+    /// An entry of the ARM exception index table
+    ArmExidx,
+    /// The `e_entry` field of the ELF header
+    Entry,
+    /// A pointer stored in `.preinit_array`
+    PreinitArray,
+    /// A pointer stored in `.init_array`
+    InitArray,
+    /// A pointer stored in `.fini_array`
+    FiniArray,
+    /// A stub of a procedure linkage table
+    Plt,
+}
+
+/// A function boundary recovered from the metadata of a (possibly stripped) binary.
+///
+/// Functions without a symbol are named `sub_<start>` after the IDA convention.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecoveredFunction {
+    pub name: String,
+    pub address: Range<u64>,
+    pub sources: Vec<FunctionSource>,
+}
+
+impl RecoveredFunction {
+    /// Returns `true` if the name was taken from a symbol table
+    pub fn has_symbol(&self) -> bool {
+        self.sources
+            .iter()
+            .any(|s| matches!(s, FunctionSource::Symbol | FunctionSource::DynamicSymbol))
+    }
+}
+
+/// A function start collected from one piece of evidence
+struct Candidate {
+    start: u64,
+    end: Option<u64>,
+    name: Option<String>,
+    source: FunctionSource,
+}
+
+impl ElfFile {
+    /// Synthesises function boundaries from every source of evidence in the file: symbol
+    /// tables, `.eh_frame` FDEs, ARM `.ARM.exidx` entries, `e_entry`, the init and fini
+    /// arrays and the PLT stub layout.
+    ///
+    /// PLT stubs are named `<import>@plt` after the symbol they jump to. Starts outside
+    /// executable memory are dropped. A function without a known size ends
+    /// at the next recovered start or the end of its executable region. The result is
+    /// sorted by start address.
+    pub fn recover_functions(&self) -> Vec<RecoveredFunction> {
+        let thumb = self.header.machine() == Ok(Machine::Arm);
+        let code = self.code_ranges();
+        let mut candidates = Vec::new();
+
+        for (syms, source) in [
+            (self.symtab(), FunctionSource::Symbol),
+            (self.dynsym(), FunctionSource::DynamicSymbol),
+        ] {
+            candidates.extend(syms.into_iter().filter(is_function).map(|s| Candidate {
+                start: s.value,
+                end: (s.size != 0).then(|| s.value.wrapping_add(s.size)),
+                name: Some(s.name),
+                source,
+            }));
+        }
+        if let Ok(eh) = self.eh_frame() {
+            candidates.extend(eh.function_ranges().into_iter().map(|r| Candidate {
+                start: r.start,
+                end: Some(r.end),
+                name: None,
+                source: FunctionSource::EhFrame,
+            }));
+        }
+        candidates.extend(self.arm_exidx_starts().into_iter().map(|start| Candidate {
+            start,
+            end: None,
+            name: None,
+            source: FunctionSource::ArmExidx,
+        }));
+        candidates.push(Candidate {
+            start: self.header.e_entry,
+            end: None,
+            name: None,
+            source: FunctionSource::Entry,
+        });
+        for (sh_type, source) in [
+            (SHT_PREINIT_ARRAY, FunctionSource::PreinitArray),
+            (SHT_INIT_ARRAY, FunctionSource::InitArray),
+            (SHT_FINI_ARRAY, FunctionSource::FiniArray),
+        ] {
+            candidates.extend(
+                self.pointer_array(sh_type)
+                    .into_iter()
+                    .map(|start| Candidate {
+                        start,
+                        end: None,
+                        name: None,
+                        source,
+                    }),
+            );
+        }
+        candidates.extend(self.plt_stubs().into_iter().map(|r| Candidate {
+            start: r.start,
+            end: Some(r.end),
+            name: None,
+            source: FunctionSource::Plt,
+        }));
+
+        for c in &mut candidates {
+            if thumb {
+                c.start &= !1;
+                c.end = c.end.map(|e| e & !1);
+            }
+        }
+        candidates.retain(|c| code.iter().any(|r| r.contains(&c.start)));
+        // Symbols first so their names and sizes win over weaker evidence
+        candidates.sort_by_key(|c| (c.start, c.source));
+
+        let mut functions: Vec<RecoveredFunction> = Vec::new();
+        let mut ends: Vec<Option<u64>> = Vec::new();
+        for c in candidates {
+            match functions.last_mut() {
+                Some(f) if f.address.start == c.start => {
+                    if !f.sources.contains(&c.source) {
+                        f.sources.push(c.source);
+                    }
+                    let end = ends.last_mut().unwrap();
+                    if end.is_none() {
+                        *end = c.end;
+                    }
+                }
+                _ => {
+                    functions.push(RecoveredFunction {
+                        name: c.name.unwrap_or_else(|| format!("sub_{:x}", c.start)),
+                        address: c.start..c.start,
+                        sources: vec![c.source],
+                    });
+                    ends.push(c.end);
+                }
+            }
+        }
+
+        let plt_names: HashMap<u64, String> = self
+            .import_slots()
+            .into_iter()
+            .filter(|s| s.kind == ImportKind::Plt)
+            .map(|s| (s.address, format!("{}@plt", s.name)))
+            .collect();
+        for i in 0..functions.len() {
+            let start = functions[i].address.start;
+            if let Some(name) = plt_names.get(&start).filter(|_| !functions[i].has_symbol()) {
+                functions[i].name = name.clone();
+            }
+            let region_end = code
+                .iter()
+                .find(|r| r.contains(&start))
+                .map_or(start, |r| r.end);
+            let next = functions
+                .get(i + 1)
+                .map_or(region_end, |f| f.address.start.min(region_end));
+            // Explicit ends are clamped as a single FDE may cover several PLT stubs
+            functions[i].address.end = match ends[i] {
+                Some(end) if end > start => end.min(next),
+                _ => next,
+            };
+        }
+        functions
+    }
+
+    /// Returns the executable address ranges, taken from the sections if present and
+    /// from the PT_LOAD segments otherwise
+    fn code_ranges(&self) -> Vec<Range<u64>> {
+        let sections: Vec<Range<u64>> = self
+            .section_headers
+            .iter()
+            .filter(|sh| {
+                let flags = SectionFlags::from(sh.sh_flags);
+                flags.contains(SectionFlags::ALLOC) && flags.contains(SectionFlags::EXECINSTR)
+            })
+            .map(|sh| sh.sh_addr..sh.sh_addr.wrapping_add(sh.sh_size))
+            .collect();
+        if !sections.is_empty() {
+            return sections;
+        }
+        self.program_headers
+            .iter()
+            .filter(|ph| ph.p_type == PT_LOAD && ph.flags().contains(SegmentFlags::X))
+            .map(|ph| ph.p_vaddr..ph.p_vaddr.wrapping_add(ph.p_memsz))
+            .collect()
+    }
+
+    /// Returns the function pointers stored in all sections of type `sh_type`,
+    /// skipping the 0 and -1 sentinels
+    fn pointer_array(&self, sh_type: u32) -> Vec<u64> {
+        let size = self.addr_size();
+        let sentinel = if self.is_64() {
+            u64::MAX
+        } else {
+            u32::MAX as u64
+        };
+        self.section_headers
+            .iter()
+            .filter(|sh| sh.sh_type == sh_type)
+            .filter_map(|sh| self.section_data(sh))
+            .flat_map(|data| {
+                (0..data.len() / size).filter_map(move |i| self.read_addr(data, i * size))
+            })
+            .filter(|&p| p != 0 && p != sentinel)
+            .collect()
+    }
+
+    /// Decodes the function start addresses of the ARM exception index table. Each 8 byte
+    /// entry begins with a prel31 offset to the function it covers.
+    fn arm_exidx_starts(&self) -> Vec<u64> {
+        let machine = self.machine();
+        let table = self
+            .program_headers
+            .iter()
+            .find(|ph| ph.segment_type(machine) == SegmentType::ArmExidx)
+            .and_then(|ph| Some((self.segment_data(ph)?, ph.p_vaddr)))
+            .or_else(|| {
+                let sh = self.section_by_name(".ARM.exidx")?;
+                Some((self.section_data(sh)?, sh.sh_addr))
+            });
+        let Some((data, address)) = table else {
+            return Vec::new();
+        };
+        (0..data.len() / 8)
+            .filter_map(|i| {
+                let word = self.read_u32(data, i * 8)?;
+                // Sign extend the 31-bit place relative offset
+                let offset = ((word << 1) as i32 >> 1) as i64;
+                Some(
+                    address
+                        .wrapping_add((i * 8) as u64)
+                        .wrapping_add_signed(offset)
+                        & 0xffff_ffff,
+                )
+            })
+            .collect()
+    }
+
+    /// Returns the address ranges of the individual PLT stubs, skipping the
+    /// lazy binding header of `.plt`
+    pub(crate) fn plt_stubs(&self) -> Vec<Range<u64>> {
+        let mut stubs = Vec::new();
+        let machine = self.header.machine();
+        let x86 = matches!(machine, Ok(Machine::X86_64 | Machine::I386));
+        for sh in &self.section_headers {
+            // Only the bytes present in the file can hold stubs
+            let size = sh
+                .sh_size
+                .min((self.data().len() as u64).saturating_sub(sh.sh_offset));
+            let end = sh.sh_addr.saturating_add(size);
+            // (header size, entry size) of the known PLT layouts
+            let (header, entsize) = match (machine, self.section_name(sh)) {
+                (_, ".plt") if x86 => (16, 16),
+                (_, ".plt.sec") if x86 => (0, 16),
+                (_, ".plt.got") if x86 => (0, 8),
+                (Ok(Machine::Aarch64), ".plt") => (32, 16),
+                // GNU ld and lld lay out the ARM PLT differently, so each stub runs
+                // from its decoded start to the next one
+                (Ok(Machine::Arm), ".plt") => {
+                    let mut starts: Vec<u64> = self
+                        .plt_targets()
+                        .into_iter()
+                        .map(|(start, _)| start)
+                        .filter(|&start| start >= sh.sh_addr && start < end)
+                        .collect();
+                    starts.sort();
+                    starts.dedup();
+                    let ends = starts.iter().skip(1).copied().chain([end]);
+                    stubs.extend(starts.iter().zip(ends).map(|(&start, end)| start..end));
+                    continue;
+                }
+                _ => continue,
+            };
+            // x86-64 records the stub size in sh_entsize while i386 and AArch64 use
+            // the instruction size
+            let entsize = if x86 && sh.sh_entsize >= 8 {
+                sh.sh_entsize
+            } else {
+                entsize
+            };
+            let Some(mut addr) = sh.sh_addr.checked_add(header) else {
+                continue;
+            };
+            while let Some(next) = addr.checked_add(entsize).filter(|&next| next <= end) {
+                stubs.push(addr..next);
+                addr = next;
+            }
+        }
+        stubs
+    }
+}
+
+fn is_function(s: &Symbol) -> bool {
+    matches!(s.sym_type(), STT_FUNC | STT_GNU_IFUNC) && s.is_defined() && s.value != 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recover_stripped_x64() {
+        let elf = ElfFile::open("/bin/ls").unwrap();
+        assert!(elf.symtab().is_empty());
+        let funcs = elf.recover_functions();
+        assert!(funcs
+            .windows(2)
+            .all(|w| w[0].address.end <= w[1].address.start));
+
+        let entry = funcs
+            .iter()
+            .find(|f| f.address.start == elf.header.e_entry)
+            .unwrap();
+        assert_eq!(entry.name, format!("sub_{:x}", elf.header.e_entry));
+        assert!(entry.sources.contains(&FunctionSource::Entry));
+        assert!(entry.sources.contains(&FunctionSource::EhFrame));
+
+        for f in elf.eh_frame().unwrap().function_ranges() {
+            assert!(funcs.iter().any(|r| r.address.start == f.start), "{:x?}", f);
+        }
+        let plt = elf.section_by_name(".plt").unwrap();
+        let stubs = funcs
+            .iter()
+            .filter(|f| f.sources.contains(&FunctionSource::Plt))
+            .filter(|f| {
+                f.address.start >= plt.sh_addr && f.address.start < plt.sh_addr + plt.sh_size
+            })
+            .count() as u64;
+        assert_eq!(stubs, plt.sh_size / 16 - 1);
+        assert!(funcs
+            .iter()
+            .any(|f| f.sources.contains(&FunctionSource::InitArray)));
+    }
+
+    #[test]
+    fn test_recover_arm_exidx() {
+        let elf = ElfFile::open("tests/bin/dd.armel").unwrap();
+        let funcs = elf.recover_functions();
+        let start = funcs.iter().find(|f| f.name == "_start").unwrap();
+        assert_eq!(start.address.start, elf.header.e_entry);
+        assert_eq!(
+            start.sources,
+            [
+                FunctionSource::Symbol,
+                FunctionSource::ArmExidx,
+                FunctionSource::Entry
+            ]
+        );
+        let main = funcs.iter().find(|f| f.name == "main").unwrap();
+        assert_eq!(main.address, 0xd970..0xd970 + 2128);
+
+        // Hand written assembly gets exidx entries in the middle of a symbol
+        let starts = elf.arm_exidx_starts();
+        assert_eq!(starts.len(), 227);
+        let split = funcs.iter().find(|f| f.address.start == 0x21430).unwrap();
+        assert_eq!(split.name, "sub_21430");
+        assert_eq!(split.sources, [FunctionSource::ArmExidx]);
+    }
+
+    #[test]
+    fn test_recover_arm_plt() {
+        // lld's ARM PLT has a 32 byte header and 16 byte stubs
+        let elf = ElfFile::open("tests/bin/plt.arm").unwrap();
+        assert_eq!(elf.plt_stubs(), [0x10210..0x10220, 0x10220..0x10230]);
+        let funcs = elf.recover_functions();
+        let exit = funcs.iter().find(|f| f.name == "exit@plt").unwrap();
+        assert_eq!(exit.address, 0x10220..0x10230);
+        assert!(funcs.iter().any(|f| f.name == "puts@plt"));
+        assert!(funcs.iter().all(|f| f.name != "sub_10204"));
+    }
+}
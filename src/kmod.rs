@@ -0,0 +1,349 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::ops::Range;
+
+use crate::cstr_at;
+use crate::elf_file::ElfFile;
+use crate::elf_types::{ET_REL, SHT_REL, SHT_RELA};
+
+/// Trailer marking a module signature appended by `scripts/sign-file`
+pub const MODULE_SIG_STRING: &[u8; 28] = b"~Module signature appended~\n";
+pub const SIZEOF_MODULE_SIGNATURE: usize = 12;
+/// `id_type` of signatures stored as a PKCS#7 message
+pub const PKEY_ID_PKCS7: u8 = 2;
+/// Size of a `struct modversion_info` entry in `__versions`
+pub const SIZEOF_MODVERSION_INFO: usize = 64;
+
+/// The `struct module_signature` trailer of a signed module
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModuleSignature {
+    pub algo: u8,
+    pub hash: u8,
+    pub id_type: u8,
+    pub signer_len: u8,
+    pub key_id_len: u8,
+    /// File range of the signature blob, a DER encoded PKCS#7 message for `PKEY_ID_PKCS7`
+    pub signature: Range<usize>,
+    /// Size of the module without the appended signature
+    pub module_len: usize,
+}
+
+/// A `__versions` entry: the CRC of an imported symbol the module was built against
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModVersion {
+    pub name: String,
+    pub crc: u32,
+}
+
+/// A module parameter described by the `parmtype=` and `parm=` modinfo tags
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ModParam {
+    pub name: String,
+    pub param_type: Option<String>,
+    pub description: Option<String>,
+}
+
+/// The metadata of a Linux kernel module (`.ko`)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ModuleInfo {
+    /// Module name from `.gnu.linkonce.this_module`, falling back to the `name=` tag
+    pub name: Option<String>,
+    pub license: Option<String>,
+    pub vermagic: Option<String>,
+    pub depends: Vec<String>,
+    pub aliases: Vec<String>,
+    pub params: Vec<ModParam>,
+    /// All `.modinfo` tags in file order
+    pub modinfo: Vec<(String, String)>,
+    pub versions: Vec<ModVersion>,
+    /// Symbols `struct module` points to as init and exit functions
+    pub init: Option<String>,
+    pub exit: Option<String>,
+    pub signature: Option<ModuleSignature>,
+}
+
+impl ModuleInfo {
+    /// Returns the first value of a `.modinfo` tag
+    pub fn tag(&self, key: &str) -> Option<&str> {
+        self.modinfo
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Compares the `__versions` CRCs against a kernel's exported symbol CRCs, as parsed by
+    /// [`parse_symvers`]. Returns `(symbol, module CRC, kernel CRC)` for every import the
+    /// kernel does not export with the same CRC.
+    pub fn crc_mismatches(
+        &self,
+        symvers: &HashMap<String, u32>,
+    ) -> Vec<(String, u32, Option<u32>)> {
+        self.versions
+            .iter()
+            .filter_map(|v| {
+                let expected = symvers.get(&v.name).copied();
+                (expected != Some(v.crc)).then(|| (v.name.clone(), v.crc, expected))
+            })
+            .collect()
+    }
+}
+
+/// Parses a `Module.symvers` file into a map from symbol to CRC.
+/// Lines are `<crc>\t<symbol>\t<module>\t<export type>[\t<namespace>]`.
+pub fn parse_symvers(text: &str) -> HashMap<String, u32> {
+    text.lines()
+        .filter_map(|line| {
+            let mut fields = line.split('\t');
+            let crc = fields.next()?.trim_start_matches("0x");
+            let crc = u32::from_str_radix(crc, 16).ok()?;
+            Some((fields.next()?.to_string(), crc))
+        })
+        .collect()
+}
+
+impl ElfFile {
+    /// Returns `true` for relocatable files carrying module metadata
+    pub fn is_kernel_module(&self) -> bool {
+        self.header.e_type == ET_REL
+            && (self.section_by_name(".modinfo").is_some()
+                || self.section_by_name(".gnu.linkonce.this_module").is_some())
+    }
+
+    /// Decodes the appended module signature, if any
+    pub fn module_signature(&self) -> Option<ModuleSignature> {
+        let data = self.data();
+        let end = data.len().checked_sub(MODULE_SIG_STRING.len())?;
+        if &data[end..] != MODULE_SIG_STRING {
+            return None;
+        }
+        let start = end.checked_sub(SIZEOF_MODULE_SIGNATURE)?;
+        let ms = &data[start..end];
+        let sig_len = u32::from_be_bytes(ms[8..12].try_into().ok()?) as usize;
+        let sig_start = start.checked_sub(sig_len)?;
+        Some(ModuleSignature {
+            algo: ms[0],
+            hash: ms[1],
+            id_type: ms[2],
+            signer_len: ms[3],
+            key_id_len: ms[4],
+            signature: sig_start..start,
+            module_len: sig_start,
+        })
+    }
+
+    /// Decodes `.modinfo`, `__versions`, `.gnu.linkonce.this_module` and the appended
+    /// signature of a kernel module
+    pub fn module_info(&self) -> Result<ModuleInfo, Box<dyn Error>> {
+        if !self.is_kernel_module() {
+            return Err("not a kernel module".into());
+        }
+        let mut info = ModuleInfo {
+            signature: self.module_signature(),
+            ..Default::default()
+        };
+
+        if let Some(sh) = self.section_by_name(".modinfo") {
+            let data = self
+                .section_data(sh)
+                .ok_or(".modinfo lies outside the file")?;
+            for entry in data.split(|&b| b == 0).filter(|e| !e.is_empty()) {
+                let entry = String::from_utf8_lossy(entry);
+                if let Some((key, value)) = entry.split_once('=') {
+                    info.modinfo.push((key.to_string(), value.to_string()));
+                }
+            }
+        }
+        for (key, value) in &info.modinfo {
+            match key.as_str() {
+                "license" => info.license = Some(value.clone()),
+                "vermagic" => info.vermagic = Some(value.clone()),
+                "alias" => info.aliases.push(value.clone()),
+                "depends" => info.depends.extend(
+                    value
+                        .split(',')
+                        .filter(|d| !d.is_empty())
+                        .map(str::to_string),
+                ),
+                "parm" | "parmtype" => {
+                    let (name, text) = value.split_once(':').unwrap_or((value, ""));
+                    let i = match info.params.iter().position(|p| p.name == name) {
+                        Some(i) => i,
+                        None => {
+                            info.params.push(ModParam {
+                                name: name.to_string(),
+                                ..Default::default()
+                            });
+                            info.params.len() - 1
+                        }
+                    };
+                    let field = if key == "parm" {
+                        &mut info.params[i].description
+                    } else {
+                        &mut info.params[i].param_type
+                    };
+                    *field = Some(text.to_string());
+                }
+                _ => {}
+            }
+        }
+
+        info.versions = self.module_versions();
+        if let Some(index) = self
+            .section_headers
+            .iter()
+            .position(|sh| self.section_name(sh) == ".gnu.linkonce.this_module")
+        {
+            // struct module starts with an enum and a list_head before the name
+            let name_off = if self.is_64() { 24 } else { 12 };
+            let data = self
+                .section_data(&self.section_headers[index])
+                .unwrap_or_default();
+            let name = cstr_at(data, name_off);
+            if !name.is_empty() {
+                info.name = Some(name.to_string());
+            }
+            let (init, exit) = self.this_module_functions(index);
+            info.init = init;
+            info.exit = exit;
+        }
+        if info.name.is_none() {
+            info.name = info.tag("name").map(str::to_string);
+        }
+        Ok(info)
+    }
+
+    /// Parses `__versions`, or the split `__version_ext_crcs` / `__version_ext_names`
+    /// tables of kernels built with `CONFIG_EXTENDED_MODVERSIONS`
+    fn module_versions(&self) -> Vec<ModVersion> {
+        let crcs = self
+            .section_by_name("__version_ext_crcs")
+            .and_then(|sh| self.section_data(sh));
+        let names = self
+            .section_by_name("__version_ext_names")
+            .and_then(|sh| self.section_data(sh));
+        if let (Some(crcs), Some(names)) = (crcs, names) {
+            return names
+                .split(|&b| b == 0)
+                .filter(|n| !n.is_empty())
+                .enumerate()
+                .filter_map(|(i, name)| {
+                    Some(ModVersion {
+                        name: String::from_utf8_lossy(name).into_owned(),
+                        crc: self.read_u32(crcs, i * 4)?,
+                    })
+                })
+                .collect();
+        }
+
+        let Some(data) = self
+            .section_by_name("__versions")
+            .and_then(|sh| self.section_data(sh))
+        else {
+            return Vec::new();
+        };
+        // The CRC is stored in an unsigned long, only its low 32 bits are significant
+        let crc_size = self.addr_size();
+        data.chunks_exact(SIZEOF_MODVERSION_INFO)
+            .filter_map(|entry| {
+                Some(ModVersion {
+                    crc: self.read_addr(entry, 0)? as u32,
+                    name: cstr_at(entry, crc_size).to_string(),
+                })
+            })
+            .collect()
+    }
+
+    /// Resolves the init and exit pointers of `struct module` through the relocations
+    /// applied to `.gnu.linkonce.this_module`. Init precedes exit in the structure.
+    fn this_module_functions(&self, index: usize) -> (Option<String>, Option<String>) {
+        let mut targets: Vec<(u64, String)> = Vec::new();
+        for (i, sh) in self.section_headers.iter().enumerate() {
+            if sh.sh_info as usize != index || (sh.sh_type != SHT_RELA && sh.sh_type != SHT_REL) {
+                continue;
+            }
+            let symbols = self.symbols(sh.sh_link as usize);
+            for r in self.relocations(i) {
+                if let Some(sym) = symbols.get(r.sym as usize).filter(|s| !s.name.is_empty()) {
+                    targets.push((r.offset, sym.name.clone()));
+                }
+            }
+        }
+        targets.sort();
+        let mut names = targets.into_iter().map(|(_, name)| name);
+        (names.next(), names.next())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_module_info() {
+        let elf = ElfFile::open("tests/bin/hello.ko").unwrap();
+        assert!(elf.is_kernel_module());
+        let info = elf.module_info().unwrap();
+        assert_eq!(info.name.as_deref(), Some("hello"));
+        assert_eq!(info.license.as_deref(), Some("GPL"));
+        assert_eq!(
+            info.vermagic.as_deref(),
+            Some("6.1.0-18-amd64 SMP preempt mod_unload modversions ")
+        );
+        assert_eq!(info.depends, ["usbcore", "i2c-core"]);
+        assert_eq!(info.aliases.len(), 2);
+        let debug = info.params.iter().find(|p| p.name == "debug").unwrap();
+        assert_eq!(debug.param_type.as_deref(), Some("int"));
+        assert_eq!(
+            debug.description.as_deref(),
+            Some("Enable debug output (int)")
+        );
+        let mode = info.params.iter().find(|p| p.name == "mode").unwrap();
+        assert_eq!(mode.param_type.as_deref(), Some("charp"));
+        assert_eq!(mode.description, None);
+        assert_eq!(info.init.as_deref(), Some("hello_init"));
+        assert_eq!(info.exit.as_deref(), Some("hello_exit"));
+        assert!(info.signature.is_none());
+        assert!(!ElfFile::open("tests/bin/debug.o")
+            .unwrap()
+            .is_kernel_module());
+    }
+
+    #[test]
+    fn test_module_versions() {
+        let info = ElfFile::open("tests/bin/hello.ko")
+            .unwrap()
+            .module_info()
+            .unwrap();
+        assert_eq!(
+            info.versions[1],
+            ModVersion {
+                name: "_printk".into(),
+                crc: 0x92997ed8,
+            }
+        );
+        let symvers = parse_symvers(
+            "0xbf1981cb\tmodule_layout\tvmlinux\tEXPORT_SYMBOL\t\n\
+             0x12345678\t_printk\tvmlinux\tEXPORT_SYMBOL\t\n",
+        );
+        assert_eq!(
+            info.crc_mismatches(&symvers),
+            [
+                ("_printk".to_string(), 0x92997ed8, Some(0x12345678)),
+                ("__x86_return_thunk".to_string(), 0x5b8239ca, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_module_signature() {
+        let elf = ElfFile::open("tests/bin/hello.signed.ko").unwrap();
+        let sig = elf.module_signature().unwrap();
+        assert_eq!(sig.id_type, PKEY_ID_PKCS7);
+        assert_eq!(sig.signature.len(), 260);
+        // The blob is a DER SEQUENCE and the module itself is unchanged
+        assert_eq!(elf.data()[sig.signature.start], 0x30);
+        let unsigned = std::fs::read("tests/bin/hello.ko").unwrap();
+        assert_eq!(&elf.data()[..sig.module_len], &unsigned[..]);
+        assert_eq!(elf.module_info().unwrap().signature, Some(sig));
+    }
+}
@@ -5,17 +5,118 @@ use core::fmt;
 use std::io::{prelude::*, SeekFrom};
 use std::{error::Error, fs, path::Path};
 
+pub mod elf_flags;
+use elf_flags::e_flags_to_str;
+pub use elf_flags::{SectionFlags, SegmentFlags};
+
+pub mod elf_types;
+pub use elf_types::{ElfType, Machine, OsAbi, SegmentType, UnknownValue};
+
+pub mod abi;
+pub use abi::{check_abi, AbiChange, AbiReport, AbiSymbol, Compatibility};
+
+pub mod archive;
+pub use archive::{Archive, ArchiveMember};
+
+pub mod bpf;
+pub use bpf::{BpfMap, BpfObject, BpfProgram};
+
+pub mod btf;
+pub use btf::{Btf, BtfExt, BtfFuncInfo, BtfKind, BtfLineInfo, BtfMember, BtfType, CoreRelo};
+
+#[cfg(feature = "compression")]
+pub mod compression;
+
+pub mod debug_file;
+pub use debug_file::{DebugAltLink, DebugElf, DebugFile, DebugLink, DebugLookup, DebugSearch};
+
+pub mod diff;
+pub use diff::{
+    diff, ElfDiff, FieldChange, SectionDiff, SectionInfo, SegmentDiff, SegmentInfo, SymbolDiff,
+};
+
+pub mod dwarf;
+pub use dwarf::{addr2line, Addr2Line, Frame};
+
+pub mod dynamic;
+pub use dynamic::{Dyn32, Dyn64};
+
+pub mod eh_frame;
+pub use eh_frame::{CfaRule, EhFrame, EhFrameHdr, RegisterRule, UnwindRow};
+
+mod elf_file;
+pub use elf_file::ElfFile;
+
+mod elf_writer;
+
+pub mod firmware;
+pub use firmware::{LoadAddress, RawImageOptions};
+
+pub mod functions;
+pub use functions::{FunctionSource, RecoveredFunction};
+
+pub mod kmod;
+pub use kmod::{ModParam, ModVersion, ModuleInfo, ModuleSignature};
+
+pub mod memory_map;
+pub use memory_map::{
+    MapIssue, MemoryMap, MemoryRegion, Overlay, RegionUsage, SectionPlacement, SegmentPlacement,
+};
+
+pub mod notes;
+pub use notes::Note;
+
+pub mod packer;
+pub use packer::{
+    detect_packer, shannon_entropy, PackVerdict, PackerIndicator, PackerReport, RegionEntropy,
+    UpxInfo, UpxPackHeader,
+};
+
+pub mod plt;
+pub use plt::{ImportKind, ImportSlot};
+
+pub mod reconstruct;
+pub use reconstruct::{rebuild_section_headers, SyntheticSection};
+
+pub mod relocations;
+pub use relocations::{Rel32, Rel64, Rela32, Rela64, Relocation};
+
+pub mod size;
+pub use size::{SizeClass, SizeEntry, SizeReport, SymbolSize};
+
+pub mod strings;
+pub use strings::{FoundString, StringEncoding, StringScope, StringsOptions};
+
+pub mod strip;
+pub use strip::{is_debug_section, strip, StripOptions};
+
+pub mod symbols;
+pub use symbols::{Sym32, Sym64, Symbol};
+
+pub mod upx;
+pub use upx::{upx_decompress, upx_unpack, BlockInfo};
+
+pub mod versions;
+pub use versions::{SymbolVersion, VersionDef, VersionNeed, VersionNeedAux};
+
+mod validate;
+pub use validate::{validate, Finding, FindingKind, Severity};
+
 mod elf_utils;
 use elf_utils::{
     e_abi_to_str, e_bit_to_str, e_class_to_str, e_machine_to_str, e_type_to_str, p_flags_to_str,
-    p_type_to_str,
+    p_type_to_str, sh_type_to_str,
 };
 
 pub const EI_NIDENT: usize = 16;
-pub const SIZEOF_EHDR32: usize = 54;
+pub const SIZEOF_EHDR32: usize = 52;
 pub const SIZEOF_EHDR64: usize = 64;
 pub const SIZEOF_PHDR32: usize = 32;
 pub const SIZEOF_PHDR64: usize = 56;
+pub const SIZEOF_SHDR32: usize = 40;
+pub const SIZEOF_SHDR64: usize = 64;
+/// `e_phnum` escape value: the real count is stored in `sh_info` of section header 0
+pub const PN_XNUM: u16 = 0xffff;
 
 pub enum ELFHDR {
     ELF32(ElfHeader32),
@@ -65,7 +166,7 @@ impl fmt::Debug for ELFHDR {
 }
 
 #[repr(C)]
-#[derive(Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default)]
 pub struct ElfHeader32 {
     pub e_ident: [u8; EI_NIDENT],
     pub e_type: u16,
@@ -84,7 +185,7 @@ pub struct ElfHeader32 {
 }
 
 #[repr(C)]
-#[derive(Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default)]
 pub struct ElfHeader64 {
     pub e_ident: [u8; EI_NIDENT],
     pub e_type: u16,
@@ -104,6 +205,21 @@ pub struct ElfHeader64 {
 
 unsafe impl plain::Plain for ElfHeader32 {}
 impl ElfHeader32 {
+    /// Returns the typed `e_machine` value
+    pub fn machine(&self) -> Result<Machine, UnknownValue> {
+        Machine::try_from(self.e_machine)
+    }
+
+    /// Returns the typed `EI_OSABI` value
+    pub fn os_abi(&self) -> Result<OsAbi, UnknownValue> {
+        OsAbi::try_from(self.e_ident[0x7])
+    }
+
+    /// Returns the typed `e_type` value
+    pub fn elf_type(&self) -> Result<ElfType, UnknownValue> {
+        ElfType::try_from(self.e_type)
+    }
+
     /// Returns the Elf Header from a given byte array.
     fn get_elf_header(bytes: &[u8]) -> ElfHeader32 {
         let mut eh: ElfHeader32 = *plain::from_bytes(bytes).expect("Failed to get ELF header");
@@ -119,7 +235,7 @@ impl ElfHeader32 {
             elf.e_entry = elf.e_entry.to_be();
             elf.e_phoff = elf.e_phoff.to_be();
             elf.e_shoff = elf.e_shoff.to_be();
-            //elf.e_flags = elf.e_flags.to_be();
+            elf.e_flags = elf.e_flags.to_be();
             elf.e_ehsize = elf.e_ehsize.to_be();
             elf.e_phentsize = elf.e_phentsize.to_be();
             elf.e_phnum = elf.e_phnum.to_be();
@@ -149,7 +265,7 @@ impl ElfHeader32 {
   {:34} {} (in bytes)
   {:34} {}
   {:34} {}
-  {:34} {:x}
+  {:34} {}
   {:34} {}
   ",
             "Magic:",
@@ -178,8 +294,8 @@ impl ElfHeader32 {
             elf_header.e_phnum,
             "Number of section headers:",
             elf_header.e_shnum,
-            "Flags",
-            elf_header.e_flags,
+            "Flags:",
+            e_flags_to_str(elf_header.e_flags, elf_header.e_machine),
             "Section header string table index:",
             elf_header.e_shstrndx,
         );
@@ -189,6 +305,21 @@ impl ElfHeader32 {
 
 unsafe impl plain::Plain for ElfHeader64 {}
 impl ElfHeader64 {
+    /// Returns the typed `e_machine` value
+    pub fn machine(&self) -> Result<Machine, UnknownValue> {
+        Machine::try_from(self.e_machine)
+    }
+
+    /// Returns the typed `EI_OSABI` value
+    pub fn os_abi(&self) -> Result<OsAbi, UnknownValue> {
+        OsAbi::try_from(self.e_ident[0x7])
+    }
+
+    /// Returns the typed `e_type` value
+    pub fn elf_type(&self) -> Result<ElfType, UnknownValue> {
+        ElfType::try_from(self.e_type)
+    }
+
     /// Returns the Elf Header from a given byte array.
     fn get_elf_header(bytes: &[u8]) -> ElfHeader64 {
         let mut eh: ElfHeader64 = *plain::from_bytes(bytes).expect("Failed to get ELF header");
@@ -234,7 +365,7 @@ impl ElfHeader64 {
   {:34} {} (in bytes)
   {:34} {}
   {:34} {}
-  {:34} {:x}
+  {:34} {}
   {:34} {}
   ",
             "Magic:",
@@ -263,8 +394,8 @@ impl ElfHeader64 {
             elf_header.e_phnum,
             "Number of section headers:",
             elf_header.e_shnum,
-            "Flags",
-            elf_header.e_flags,
+            "Flags:",
+            e_flags_to_str(elf_header.e_flags, elf_header.e_machine),
             "Section header string table index:",
             elf_header.e_shstrndx,
         );
@@ -281,31 +412,41 @@ pub enum PHS {
 #[repr(C)]
 #[derive(Debug, Clone, Copy, Default)]
 pub struct ProgramHeader32 {
-    p_type: u32,
-    p_offset: u32,
-    p_vaddr: u32,
-    p_paddr: u32,
-    p_filesz: u32,
-    p_memsz: u32,
-    p_flags: u32,
-    p_align: u32,
+    pub p_type: u32,
+    pub p_offset: u32,
+    pub p_vaddr: u32,
+    pub p_paddr: u32,
+    pub p_filesz: u32,
+    pub p_memsz: u32,
+    pub p_flags: u32,
+    pub p_align: u32,
 }
 
 #[repr(C)]
 #[derive(Debug, Clone, Copy, Default)]
 pub struct ProgramHeader64 {
-    p_type: u32,
-    p_flags: u32,
-    p_offset: u64,
-    p_vaddr: u64,
-    p_paddr: u64,
-    p_filesz: u64,
-    p_memsz: u64,
-    p_align: u64,
+    pub p_type: u32,
+    pub p_flags: u32,
+    pub p_offset: u64,
+    pub p_vaddr: u64,
+    pub p_paddr: u64,
+    pub p_filesz: u64,
+    pub p_memsz: u64,
+    pub p_align: u64,
 }
 
 unsafe impl plain::Plain for ProgramHeader32 {}
 impl ProgramHeader32 {
+    /// Returns the typed `p_type` resolved for the given `e_machine`
+    pub fn segment_type(&self, machine: u16) -> SegmentType {
+        SegmentType::from_raw(self.p_type, machine)
+    }
+
+    /// Returns the typed `p_flags`
+    pub fn flags(&self) -> SegmentFlags {
+        SegmentFlags::from_bits_retain(self.p_flags)
+    }
+
     fn fix_program_header(ph: &mut ProgramHeader32, bit: u8) -> &ProgramHeader32 {
         if bit == 2 {
             ph.p_type = ph.p_type.to_be();
@@ -327,25 +468,32 @@ impl ProgramHeader32 {
         plain::from_bytes(bytes).expect("Failed to get ELF32 program header")
     }
 
-    fn get_program_headers<P: AsRef<Path>>(
-        elf_bin: P,
-    ) -> Result<Vec<ProgramHeader32>, Box<dyn Error>>
+    fn get_program_headers<P>(elf_bin: P) -> Result<Vec<ProgramHeader32>, Box<dyn Error>>
     where
-        P: Copy,
+        P: AsRef<Path> + Copy,
     {
-        let hdr = get_elf_header(&elf_bin)?;
+        let hdr = get_elf_header(elf_bin)?;
         if let ELFHDR::ELF32(elf) = hdr {
-            let mut pharr: Vec<ProgramHeader32> = vec![Default::default(); elf.e_phnum as usize];
+            let (phnum, _, _) = SectionHeader32::get_numbering(elf_bin, &elf)?;
             let mut f = fs::File::open(elf_bin)?;
-            for i in 0..elf.e_phnum {
+            check_table(
+                f.metadata()?.len(),
+                elf.e_phoff as u64,
+                phnum,
+                elf.e_phentsize,
+                SIZEOF_PHDR32,
+                "program header",
+            )?;
+            let mut pharr: Vec<ProgramHeader32> = Vec::with_capacity(phnum);
+            for i in 0..phnum {
                 let mut ph_buf: Vec<u8> = vec![0; SIZEOF_PHDR32];
                 let offset = i as i64 * elf.e_phentsize as i64;
                 f.seek(SeekFrom::Start(offset as u64 + elf.e_phoff as u64))?;
                 f.read_exact(&mut ph_buf[..])?;
-                pharr[i as usize] = *ProgramHeader32::fix_program_header(
+                pharr.push(*ProgramHeader32::fix_program_header(
                     &mut ProgramHeader32::get_ph(&ph_buf).to_owned(),
                     elf.e_ident[0x5],
-                );
+                ));
             }
             Ok(pharr)
         } else {
@@ -374,7 +522,7 @@ impl ProgramHeader32 {
         );
         for p in ph.iter() {
             let g = format!(
-                "{:22}0x{:<18.1x}0x{:<18.1x}0x{:x}\n{:22}0x{:<18.1x}0x{:<18.1x}{:<10.3}0x{:x}\n",
+                "{:22}0x{:<18.1x}0x{:<18.1x}0x{:x}\n{:22}0x{:<18.1x}0x{:<18.1x}{:<10}0x{:x}\n",
                 p_type_to_str(p.p_type, hdr.e_machine),
                 p.p_offset,
                 p.p_vaddr,
@@ -382,7 +530,7 @@ impl ProgramHeader32 {
                 "",
                 p.p_filesz,
                 p.p_memsz,
-                p_flags_to_str(p.p_flags),
+                p_flags_to_str(p.p_flags, hdr.e_machine),
                 p.p_align
             );
             s.push_str(&g);
@@ -396,6 +544,16 @@ impl ProgramHeader32 {
 
 unsafe impl plain::Plain for ProgramHeader64 {}
 impl ProgramHeader64 {
+    /// Returns the typed `p_type` resolved for the given `e_machine`
+    pub fn segment_type(&self, machine: u16) -> SegmentType {
+        SegmentType::from_raw(self.p_type, machine)
+    }
+
+    /// Returns the typed `p_flags`
+    pub fn flags(&self) -> SegmentFlags {
+        SegmentFlags::from_bits_retain(self.p_flags)
+    }
+
     fn fix_program_header(ph: &mut ProgramHeader64, bit: u8) -> &ProgramHeader64 {
         if bit == 2 {
             ph.p_type = ph.p_type.to_be();
@@ -417,25 +575,32 @@ impl ProgramHeader64 {
         plain::from_bytes(bytes).expect("Failed to get ELF64 program header")
     }
 
-    fn get_program_headers<P: AsRef<Path>>(
-        elf_bin: P,
-    ) -> Result<Vec<ProgramHeader64>, Box<dyn Error>>
+    fn get_program_headers<P>(elf_bin: P) -> Result<Vec<ProgramHeader64>, Box<dyn Error>>
     where
-        P: Copy,
+        P: AsRef<Path> + Copy,
     {
-        let hdr = get_elf_header(&elf_bin)?;
+        let hdr = get_elf_header(elf_bin)?;
         if let ELFHDR::ELF64(elf) = hdr {
-            let mut pharr: Vec<ProgramHeader64> = vec![Default::default(); elf.e_phnum as usize];
+            let (phnum, _, _) = SectionHeader64::get_numbering(elf_bin, &elf)?;
             let mut f = fs::File::open(elf_bin)?;
-            for i in 0..elf.e_phnum {
+            check_table(
+                f.metadata()?.len(),
+                elf.e_phoff,
+                phnum,
+                elf.e_phentsize,
+                SIZEOF_PHDR64,
+                "program header",
+            )?;
+            let mut pharr: Vec<ProgramHeader64> = Vec::with_capacity(phnum);
+            for i in 0..phnum {
                 let mut ph_buf: Vec<u8> = vec![0; SIZEOF_PHDR64];
                 let offset = i as i64 * elf.e_phentsize as i64;
                 f.seek(SeekFrom::Start(offset as u64 + elf.e_phoff))?;
                 f.read_exact(&mut ph_buf[..])?;
-                pharr[i as usize] = *ProgramHeader64::fix_program_header(
+                pharr.push(*ProgramHeader64::fix_program_header(
                     &mut ProgramHeader64::get_ph(&ph_buf).to_owned(),
                     elf.e_ident[0x5],
-                );
+                ));
             }
             Ok(pharr)
         } else {
@@ -463,7 +628,7 @@ impl ProgramHeader64 {
         );
         for p in ph.iter() {
             let g = format!(
-                "{:22}0x{:<18.1x}0x{:<18.1x}0x{:x}\n{:22}0x{:<18.1x}0x{:<18.1x}{:<10.3}0x{:x}\n",
+                "{:22}0x{:<18.1x}0x{:<18.1x}0x{:x}\n{:22}0x{:<18.1x}0x{:<18.1x}{:<10}0x{:x}\n",
                 p_type_to_str(p.p_type, hdr.e_machine),
                 p.p_offset,
                 p.p_vaddr,
@@ -471,7 +636,7 @@ impl ProgramHeader64 {
                 "",
                 p.p_filesz,
                 p.p_memsz,
-                p_flags_to_str(p.p_flags),
+                p_flags_to_str(p.p_flags, hdr.e_machine),
                 p.p_align
             );
             s.push_str(&g);
@@ -513,11 +678,464 @@ pub fn get_program_headers_as_str<P: AsRef<Path>>(elf_bin: P) -> String {
     }
 }
 
+#[derive(Debug)]
+pub enum SHS {
+    SH32(Vec<SectionHeader32>),
+    SH64(Vec<SectionHeader64>),
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SectionHeader32 {
+    pub sh_name: u32,
+    pub sh_type: u32,
+    pub sh_flags: u32,
+    pub sh_addr: u32,
+    pub sh_offset: u32,
+    pub sh_size: u32,
+    pub sh_link: u32,
+    pub sh_info: u32,
+    pub sh_addralign: u32,
+    pub sh_entsize: u32,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SectionHeader64 {
+    pub sh_name: u32,
+    pub sh_type: u32,
+    pub sh_flags: u64,
+    pub sh_addr: u64,
+    pub sh_offset: u64,
+    pub sh_size: u64,
+    pub sh_link: u32,
+    pub sh_info: u32,
+    pub sh_addralign: u64,
+    pub sh_entsize: u64,
+}
+
+unsafe impl plain::Plain for SectionHeader32 {}
+impl SectionHeader32 {
+    /// Returns the typed `sh_flags`
+    pub fn flags(&self) -> SectionFlags {
+        SectionFlags::from_bits_retain(self.sh_flags as u64)
+    }
+
+    fn fix_section_header(sh: &mut SectionHeader32, bit: u8) -> &SectionHeader32 {
+        if bit == 2 {
+            sh.sh_name = sh.sh_name.to_be();
+            sh.sh_type = sh.sh_type.to_be();
+            sh.sh_flags = sh.sh_flags.to_be();
+            sh.sh_addr = sh.sh_addr.to_be();
+            sh.sh_offset = sh.sh_offset.to_be();
+            sh.sh_size = sh.sh_size.to_be();
+            sh.sh_link = sh.sh_link.to_be();
+            sh.sh_info = sh.sh_info.to_be();
+            sh.sh_addralign = sh.sh_addralign.to_be();
+            sh.sh_entsize = sh.sh_entsize.to_be();
+            sh
+        } else {
+            sh
+        }
+    }
+
+    fn get_sh(bytes: &[u8]) -> &SectionHeader32 {
+        plain::from_bytes(bytes).expect("Failed to get ELF32 section header")
+    }
+
+    /// Returns the real program header count, section header count and section header
+    /// string table index, resolving the extended numbering escapes via section header 0
+    fn get_numbering<P: AsRef<Path>>(
+        elf_bin: P,
+        elf: &ElfHeader32,
+    ) -> Result<(usize, usize, usize), Box<dyn Error>> {
+        let escaped =
+            elf.e_phnum == PN_XNUM || elf.e_shnum == 0 || elf.e_shstrndx == elf_types::SHN_XINDEX;
+        let sh0 = if escaped && elf.e_shoff != 0 {
+            let mut f = fs::File::open(elf_bin)?;
+            let mut sh_buf: Vec<u8> = vec![0; SIZEOF_SHDR32];
+            f.seek(SeekFrom::Start(elf.e_shoff as u64))?;
+            f.read_exact(&mut sh_buf[..])?;
+            Some(SectionHeader64::from(*SectionHeader32::fix_section_header(
+                &mut SectionHeader32::get_sh(&sh_buf).to_owned(),
+                elf.e_ident[0x5],
+            )))
+        } else {
+            None
+        };
+        Ok(extended_numbering(&ElfHeader64::from(*elf), sh0.as_ref()))
+    }
+
+    fn get_section_headers<P>(elf_bin: P) -> Result<Vec<SectionHeader32>, Box<dyn Error>>
+    where
+        P: AsRef<Path> + Copy,
+    {
+        let hdr = get_elf_header(elf_bin)?;
+        if let ELFHDR::ELF32(elf) = hdr {
+            let (_, shnum, _) = SectionHeader32::get_numbering(elf_bin, &elf)?;
+            let mut f = fs::File::open(elf_bin)?;
+            check_table(
+                f.metadata()?.len(),
+                elf.e_shoff as u64,
+                shnum,
+                elf.e_shentsize,
+                SIZEOF_SHDR32,
+                "section header",
+            )?;
+            let mut sharr: Vec<SectionHeader32> = Vec::with_capacity(shnum);
+            for i in 0..shnum {
+                let mut sh_buf: Vec<u8> = vec![0; SIZEOF_SHDR32];
+                let offset = i as u64 * elf.e_shentsize as u64;
+                f.seek(SeekFrom::Start(offset + elf.e_shoff as u64))?;
+                f.read_exact(&mut sh_buf[..])?;
+                sharr.push(*SectionHeader32::fix_section_header(
+                    &mut SectionHeader32::get_sh(&sh_buf).to_owned(),
+                    elf.e_ident[0x5],
+                ));
+            }
+            Ok(sharr)
+        } else {
+            panic!("Failed to get ELF header to process section header!")
+        }
+    }
+
+    fn get_section_headers_as_str<P: AsRef<Path>>(elf_bin: P) -> String {
+        let contents = fs::read(&elf_bin).unwrap();
+        let hdr = ElfHeader32::get_elf_header(&contents);
+        let sh = SectionHeader32::get_section_headers(&elf_bin).unwrap();
+        let (_, _, shstrndx) = SectionHeader32::get_numbering(&elf_bin, &hdr).unwrap();
+        let strtab = sh.get(shstrndx).map(|s| {
+            let start = (s.sh_offset as usize).min(contents.len());
+            let end = start.saturating_add(s.sh_size as usize).min(contents.len());
+            &contents[start..end]
+        });
+        let mut s = format!(
+            "Located {} section headers:
+  {:6}{:20}{:18}{:20}{:10}
+  {:6}{:20}{:18}{:10}{:10}{:10}
+=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-\n",
+            sh.len(),
+            "[Nr]",
+            "Name",
+            "Type",
+            "Address",
+            "Offset",
+            "",
+            "Size",
+            "EntSize",
+            "Flags",
+            "Link",
+            "Info  Align",
+        );
+        for (i, h) in sh.iter().enumerate() {
+            let name = strtab.map_or("", |t| cstr_at(t, h.sh_name as usize));
+            let g = format!(
+                "  [{:>2}]{:20}{:18}0x{:<18x}0x{:x}\n{:8}0x{:<18x}0x{:<16x}{:10}{:<10}{:<6}{}\n",
+                i,
+                format!(" {}", name),
+                sh_type_to_str(h.sh_type, hdr.e_machine),
+                h.sh_addr,
+                h.sh_offset,
+                "",
+                h.sh_size,
+                h.sh_entsize,
+                h.flags().to_str(hdr.e_machine),
+                h.sh_link,
+                h.sh_info,
+                h.sh_addralign
+            );
+            s.push_str(&g);
+            s.push_str(
+                "______________________________________________________________________________\n",
+            );
+        }
+        s
+    }
+}
+
+unsafe impl plain::Plain for SectionHeader64 {}
+impl SectionHeader64 {
+    /// Returns the typed `sh_flags`
+    pub fn flags(&self) -> SectionFlags {
+        SectionFlags::from_bits_retain(self.sh_flags)
+    }
+
+    fn fix_section_header(sh: &mut SectionHeader64, bit: u8) -> &SectionHeader64 {
+        if bit == 2 {
+            sh.sh_name = sh.sh_name.to_be();
+            sh.sh_type = sh.sh_type.to_be();
+            sh.sh_flags = sh.sh_flags.to_be();
+            sh.sh_addr = sh.sh_addr.to_be();
+            sh.sh_offset = sh.sh_offset.to_be();
+            sh.sh_size = sh.sh_size.to_be();
+            sh.sh_link = sh.sh_link.to_be();
+            sh.sh_info = sh.sh_info.to_be();
+            sh.sh_addralign = sh.sh_addralign.to_be();
+            sh.sh_entsize = sh.sh_entsize.to_be();
+            sh
+        } else {
+            sh
+        }
+    }
+
+    fn get_sh(bytes: &[u8]) -> &SectionHeader64 {
+        plain::from_bytes(bytes).expect("Failed to get ELF64 section header")
+    }
+
+    /// Returns the real program header count, section header count and section header
+    /// string table index, resolving the extended numbering escapes via section header 0
+    fn get_numbering<P: AsRef<Path>>(
+        elf_bin: P,
+        elf: &ElfHeader64,
+    ) -> Result<(usize, usize, usize), Box<dyn Error>> {
+        let escaped =
+            elf.e_phnum == PN_XNUM || elf.e_shnum == 0 || elf.e_shstrndx == elf_types::SHN_XINDEX;
+        let sh0 = if escaped && elf.e_shoff != 0 {
+            let mut f = fs::File::open(elf_bin)?;
+            let mut sh_buf: Vec<u8> = vec![0; SIZEOF_SHDR64];
+            f.seek(SeekFrom::Start(elf.e_shoff))?;
+            f.read_exact(&mut sh_buf[..])?;
+            Some(*SectionHeader64::fix_section_header(
+                &mut SectionHeader64::get_sh(&sh_buf).to_owned(),
+                elf.e_ident[0x5],
+            ))
+        } else {
+            None
+        };
+        Ok(extended_numbering(elf, sh0.as_ref()))
+    }
+
+    fn get_section_headers<P>(elf_bin: P) -> Result<Vec<SectionHeader64>, Box<dyn Error>>
+    where
+        P: AsRef<Path> + Copy,
+    {
+        let hdr = get_elf_header(elf_bin)?;
+        if let ELFHDR::ELF64(elf) = hdr {
+            let (_, shnum, _) = SectionHeader64::get_numbering(elf_bin, &elf)?;
+            let mut f = fs::File::open(elf_bin)?;
+            check_table(
+                f.metadata()?.len(),
+                elf.e_shoff,
+                shnum,
+                elf.e_shentsize,
+                SIZEOF_SHDR64,
+                "section header",
+            )?;
+            let mut sharr: Vec<SectionHeader64> = Vec::with_capacity(shnum);
+            for i in 0..shnum {
+                let mut sh_buf: Vec<u8> = vec![0; SIZEOF_SHDR64];
+                let offset = i as u64 * elf.e_shentsize as u64;
+                f.seek(SeekFrom::Start(offset + elf.e_shoff))?;
+                f.read_exact(&mut sh_buf[..])?;
+                sharr.push(*SectionHeader64::fix_section_header(
+                    &mut SectionHeader64::get_sh(&sh_buf).to_owned(),
+                    elf.e_ident[0x5],
+                ));
+            }
+            Ok(sharr)
+        } else {
+            panic!("Failed to get ELF header to process section header!")
+        }
+    }
+
+    fn get_section_headers_as_str<P: AsRef<Path>>(elf_bin: P) -> String {
+        let contents = fs::read(&elf_bin).unwrap();
+        let hdr = ElfHeader64::get_elf_header(&contents);
+        let sh = SectionHeader64::get_section_headers(&elf_bin).unwrap();
+        let (_, _, shstrndx) = SectionHeader64::get_numbering(&elf_bin, &hdr).unwrap();
+        let strtab = sh.get(shstrndx).map(|s| {
+            let start = (s.sh_offset as usize).min(contents.len());
+            let end = start.saturating_add(s.sh_size as usize).min(contents.len());
+            &contents[start..end]
+        });
+        let mut s = format!(
+            "Located {} section headers:
+  {:6}{:20}{:18}{:20}{:10}
+  {:6}{:20}{:18}{:10}{:10}{:10}
+=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-\n",
+            sh.len(),
+            "[Nr]",
+            "Name",
+            "Type",
+            "Address",
+            "Offset",
+            "",
+            "Size",
+            "EntSize",
+            "Flags",
+            "Link",
+            "Info  Align",
+        );
+        for (i, h) in sh.iter().enumerate() {
+            let name = strtab.map_or("", |t| cstr_at(t, h.sh_name as usize));
+            let g = format!(
+                "  [{:>2}]{:20}{:18}0x{:<18x}0x{:x}\n{:8}0x{:<18x}0x{:<16x}{:10}{:<10}{:<6}{}\n",
+                i,
+                format!(" {}", name),
+                sh_type_to_str(h.sh_type, hdr.e_machine),
+                h.sh_addr,
+                h.sh_offset,
+                "",
+                h.sh_size,
+                h.sh_entsize,
+                h.flags().to_str(hdr.e_machine),
+                h.sh_link,
+                h.sh_info,
+                h.sh_addralign
+            );
+            s.push_str(&g);
+            s.push_str(
+                "______________________________________________________________________________\n",
+            );
+        }
+        s
+    }
+}
+
+/// Attempts to read all section headers from a given ELF binary (path)
+/// The **caller** is responsible for handling the return value properly.
+pub fn get_section_headers<P: AsRef<Path>>(elf_path: P) -> Result<SHS, Box<dyn Error>> {
+    let mut bits = vec![0_u8, 1];
+    let mut f = fs::File::open(&elf_path)?;
+    f.seek(SeekFrom::Start(0x4))?;
+    f.read_exact(&mut bits)?;
+    let bits = bits[0];
+    if bits == 1 {
+        Ok(SHS::SH32(SectionHeader32::get_section_headers(&elf_path)?))
+    } else {
+        Ok(SHS::SH64(SectionHeader64::get_section_headers(&elf_path)?))
+    }
+}
+
+/// Returns a formatted and parsed section header table for a given ELF binary (path)
+/// as its string representation
+pub fn get_section_headers_as_str<P: AsRef<Path>>(elf_bin: P) -> String {
+    let mut bits = vec![0_u8, 1];
+    let mut f = fs::File::open(&elf_bin).unwrap();
+    f.seek(SeekFrom::Start(0x4)).unwrap();
+    f.read_exact(&mut bits).unwrap();
+    let bits = bits[0];
+    if bits == 1 {
+        SectionHeader32::get_section_headers_as_str(&elf_bin)
+    } else {
+        SectionHeader64::get_section_headers_as_str(&elf_bin)
+    }
+}
+
+/// Returns the real `(phnum, shnum, shstrndx)` of an image.
+/// Files with more than 0xfeff sections or 0xffff program headers store the real values in
+/// `sh_size`, `sh_info` and `sh_link` of section header 0 and escape the header fields with
+/// `e_shnum == 0`, `e_phnum == PN_XNUM` and `e_shstrndx == SHN_XINDEX` respectively.
+pub(crate) fn extended_numbering(
+    elf: &ElfHeader64,
+    sh0: Option<&SectionHeader64>,
+) -> (usize, usize, usize) {
+    let phnum = match sh0 {
+        Some(sh) if elf.e_phnum == PN_XNUM => sh.sh_info as usize,
+        _ => elf.e_phnum as usize,
+    };
+    let shnum = match sh0 {
+        Some(sh) if elf.e_shnum == 0 => sh.sh_size as usize,
+        _ => elf.e_shnum as usize,
+    };
+    let shstrndx = match sh0 {
+        Some(sh) if elf.e_shstrndx == elf_types::SHN_XINDEX => sh.sh_link as usize,
+        _ => elf.e_shstrndx as usize,
+    };
+    (phnum, shnum, shstrndx)
+}
+
+/// Fails unless `count` entries of `size` bytes, `entsize` bytes apart from `offset` on,
+/// fit in a file of `len` bytes. Keeps corrupt counts from sizing allocations.
+pub(crate) fn check_table(
+    len: u64,
+    offset: u64,
+    count: usize,
+    entsize: u16,
+    size: usize,
+    what: &str,
+) -> Result<(), Box<dyn Error>> {
+    let Some(last) = (count as u64).checked_sub(1) else {
+        return Ok(());
+    };
+    let end = last
+        .checked_mul(entsize as u64)
+        .and_then(|n| n.checked_add(offset))
+        .and_then(|n| n.checked_add(size as u64));
+    match end {
+        Some(end) if end <= len && (count as u64).saturating_mul(size as u64) <= len => Ok(()),
+        _ => Err(format!("{} table lies outside the file", what).into()),
+    }
+}
+
+/// Returns the NUL terminated string starting at `off` inside a string table,
+/// or an empty string if it is out of bounds or not valid UTF-8
+pub(crate) fn cstr_at(table: &[u8], off: usize) -> &str {
+    let tail = table.get(off..).unwrap_or_default();
+    let end = tail.iter().position(|&c| c == 0).unwrap_or(tail.len());
+    std::str::from_utf8(&tail[..end]).unwrap_or_default()
+}
+
+// The 64-bit structures are a superset of their 32-bit counterparts, which allows
+// class agnostic code to work on widened copies.
+impl From<ElfHeader32> for ElfHeader64 {
+    fn from(e: ElfHeader32) -> ElfHeader64 {
+        ElfHeader64 {
+            e_ident: e.e_ident,
+            e_type: e.e_type,
+            e_machine: e.e_machine,
+            e_version: e.e_version,
+            e_entry: e.e_entry as u64,
+            e_phoff: e.e_phoff as u64,
+            e_shoff: e.e_shoff as u64,
+            e_flags: e.e_flags,
+            e_ehsize: e.e_ehsize,
+            e_phentsize: e.e_phentsize,
+            e_phnum: e.e_phnum,
+            e_shentsize: e.e_shentsize,
+            e_shnum: e.e_shnum,
+            e_shstrndx: e.e_shstrndx,
+        }
+    }
+}
+
+impl From<ProgramHeader32> for ProgramHeader64 {
+    fn from(p: ProgramHeader32) -> ProgramHeader64 {
+        ProgramHeader64 {
+            p_type: p.p_type,
+            p_flags: p.p_flags,
+            p_offset: p.p_offset as u64,
+            p_vaddr: p.p_vaddr as u64,
+            p_paddr: p.p_paddr as u64,
+            p_filesz: p.p_filesz as u64,
+            p_memsz: p.p_memsz as u64,
+            p_align: p.p_align as u64,
+        }
+    }
+}
+
+impl From<SectionHeader32> for SectionHeader64 {
+    fn from(s: SectionHeader32) -> SectionHeader64 {
+        SectionHeader64 {
+            sh_name: s.sh_name,
+            sh_type: s.sh_type,
+            sh_flags: s.sh_flags as u64,
+            sh_addr: s.sh_addr as u64,
+            sh_offset: s.sh_offset as u64,
+            sh_size: s.sh_size as u64,
+            sh_link: s.sh_link,
+            sh_info: s.sh_info,
+            sh_addralign: s.sh_addralign as u64,
+            sh_entsize: s.sh_entsize as u64,
+        }
+    }
+}
+
 /// Attempts to read the ELF header information from a given ELF binary (path)
 /// The **caller** is responsible for handling the return value properly.
-pub fn get_elf_header<P: AsRef<Path>>(elf_path: P) -> Result<ELFHDR, Box<dyn Error>>
+pub fn get_elf_header<P>(elf_path: P) -> Result<ELFHDR, Box<dyn Error>>
 where
-    P: Copy,
+    P: AsRef<Path> + Copy,
 {
     let mut bits = vec![0_u8, 1];
     let mut f = fs::File::open(elf_path)?;
@@ -651,4 +1269,193 @@ mod tests {
             _ => assert_eq!(1, 0),
         }
     }
+
+    #[test]
+    fn test_mips_typed_header() {
+        let path = Path::new("tests/bin/objdump.mips");
+        let content = fs::read(path).unwrap();
+        let hdr = ElfHeader32::get_elf_header(&content);
+        assert_eq!(hdr.machine().unwrap(), Machine::Mips);
+        assert_eq!(hdr.os_abi().unwrap(), OsAbi::SystemV);
+        assert_eq!(hdr.elf_type().unwrap(), ElfType::Exec);
+    }
+
+    #[test]
+    fn test_typed_enum_round_trip() {
+        assert_eq!(Machine::try_from(258).unwrap(), Machine::Loongarch);
+        assert_eq!(u16::from(Machine::IntelGt), 205);
+        assert_eq!(OsAbi::try_from(64).unwrap().to_string(), "ARM EABI");
+        assert_eq!(ElfType::try_from(0xfe10).unwrap(), ElfType::Os(0xfe10));
+        assert!(Machine::try_from(11).is_err());
+        assert!(ElfType::try_from(5).is_err());
+    }
+
+    #[test]
+    fn test_mips_e_flags() {
+        let path = Path::new("tests/bin/objdump.mips");
+        let content = fs::read(path).unwrap();
+        let hdr = ElfHeader32::get_elf_header(&content);
+        let expected = "0x70001007, noreorder, pic, cpic, o32, mips32r2";
+        let actual = e_flags_to_str(hdr.e_flags, hdr.e_machine);
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn test_arm_e_flags() {
+        let path = Path::new("tests/bin/dd.armel");
+        let content = fs::read(path).unwrap();
+        let hdr = ElfHeader32::get_elf_header(&content);
+        let expected = "0x5000002, Version5 EABI, <unknown: 0x2>";
+        let actual = e_flags_to_str(hdr.e_flags, hdr.e_machine);
+        assert_eq!(expected, actual);
+        let hard_float = elf_flags::decode_e_flags(0x0500_0400, 40);
+        assert_eq!(hard_float, ["Version5 EABI", "hard-float ABI"]);
+    }
+
+    #[test]
+    fn test_segment_flags_to_str() {
+        assert_eq!(p_flags_to_str(5, 40), "R E");
+        assert_eq!(p_flags_to_str(3, 40), " WE");
+        assert_eq!(p_flags_to_str(0x1000_0004, 40), "R   [ARM_SB]");
+        assert_eq!(p_flags_to_str(0x2806, 62), "RW  [NORANDEXEC, NOEMUTRAMP]");
+    }
+
+    #[test]
+    fn test_section_flags_to_str() {
+        let tls = SectionFlags::WRITE | SectionFlags::ALLOC | SectionFlags::TLS;
+        assert_eq!(tls.to_string(), "WAT");
+        assert!(tls.contains(SectionFlags::TLS));
+        let merge = SectionFlags::from_bits_retain(0x30);
+        assert_eq!(merge.to_string(), "MS");
+        let odd = SectionFlags::from_bits_retain(0x1000_1002);
+        assert_eq!(odd.to_str(62), "Alx");
+        assert_eq!(odd.to_str(8), "Axp");
+    }
+
+    #[test]
+    fn test_arch_specific_segment_types() {
+        assert_eq!(
+            SegmentType::from_raw(0x7000_0002, 183),
+            SegmentType::Aarch64MemtagMte
+        );
+        assert_eq!(
+            SegmentType::from_raw(0x7000_0003, 243),
+            SegmentType::RiscvAttributes
+        );
+        assert_eq!(
+            SegmentType::from_raw(0x7000_0000, 22),
+            SegmentType::S390Pgste
+        );
+        assert_eq!(SegmentType::from_raw(0x6000_0014, 15), SegmentType::HpStack);
+        assert_eq!(
+            SegmentType::from_raw(0x6000_0014, 62),
+            SegmentType::Os(0x6000_0014)
+        );
+        assert_eq!(p_type_to_str(0x7000_0001, 62), "LOPROC+0x1");
+        assert_eq!(sh_type_to_str(0x7000_002a, 62), "LOPROC+0x2a");
+        assert_eq!(sh_type_to_str(0x7000_002a, 8), "MIPS_ABIFLAGS");
+        assert_eq!(sh_type_to_str(0x6000_0100, 62), "LOOS+0x100");
+        assert_eq!(sh_type_to_str(0x8000_0010, 62), "LOUSER+0x10");
+        assert_eq!(p_type_to_str(0x65a3_dbe7, 3), "PT_OPENBSD_WXNEEDED");
+        assert_eq!(p_type_to_str(0x6504_1580, 3), "PT_PAX_FLAGS");
+        assert_eq!(SegmentType::ArmExidx.raw(), 0x7000_0001);
+    }
+
+    #[test]
+    fn test_mips_segment_types() {
+        let path = Path::new("tests/bin/objdump.mips");
+        let hdr = ElfHeader32::get_elf_header(&fs::read(path).unwrap());
+        if let PHS::PH32(ph32) = get_program_headers(path).unwrap() {
+            let types: Vec<SegmentType> =
+                ph32.iter().map(|p| p.segment_type(hdr.e_machine)).collect();
+            assert_eq!(types[0], SegmentType::MipsAbiflags);
+            assert_eq!(
+                p_type_to_str(ph32[0].p_type, hdr.e_machine),
+                "PT_MIPS_ABIFLAGS"
+            );
+            assert_eq!(types[5], SegmentType::GnuStack);
+            assert!(ph32[2].flags().contains(SegmentFlags::X));
+        } else {
+            panic!("expected ELF32 program headers");
+        }
+    }
+
+    #[test]
+    fn test_extended_numbering() {
+        let mut data = fs::read("tests/bin/objdump.mips").unwrap();
+        let orig = ElfFile::parse(data.clone()).unwrap();
+        let sh0 = orig.header.e_shoff as usize;
+        // Move the real counts into section header 0 and set the escape values
+        data[sh0 + 20..sh0 + 24].copy_from_slice(&33u32.to_be_bytes());
+        data[sh0 + 24..sh0 + 28].copy_from_slice(&30u32.to_be_bytes());
+        data[sh0 + 28..sh0 + 32].copy_from_slice(&6u32.to_be_bytes());
+        data[0x2c..0x2e].copy_from_slice(&PN_XNUM.to_be_bytes());
+        data[0x30..0x32].copy_from_slice(&0u16.to_be_bytes());
+        data[0x32..0x34].copy_from_slice(&elf_types::SHN_XINDEX.to_be_bytes());
+
+        let elf = ElfFile::parse(data.clone()).unwrap();
+        assert_eq!((elf.phnum(), elf.shnum(), elf.shstrndx()), (6, 33, 30));
+        assert_eq!(elf.program_headers.len(), 6);
+        assert_eq!(elf.section_headers.len(), 33);
+        assert_eq!(elf.section_name(&elf.section_headers[31]), ".symtab");
+        assert!(!elf.validate().iter().any(|f| f.severity == Severity::Error));
+
+        let path = std::env::temp_dir().join("elf_loader_extended_numbering.mips");
+        fs::write(&path, &data).unwrap();
+        let phs = get_program_headers(&path).unwrap();
+        let shs = get_section_headers(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        match (phs, shs) {
+            (PHS::PH32(ph32), SHS::SH32(sh32)) => {
+                assert_eq!(ph32.len(), 6);
+                assert_eq!(sh32.len(), 33);
+            }
+            _ => panic!("expected ELF32 tables"),
+        }
+    }
+
+    #[test]
+    fn test_corrupt_table_counts() {
+        let mut data = fs::read("tests/bin/objdump.mips").unwrap();
+        let sh0 = ElfFile::parse(data.clone()).unwrap().header.e_shoff as usize;
+        // Escaped counts far beyond what the file can hold
+        data[sh0 + 20..sh0 + 24].copy_from_slice(&u32::MAX.to_be_bytes());
+        data[sh0 + 28..sh0 + 32].copy_from_slice(&u32::MAX.to_be_bytes());
+        data[0x2c..0x2e].copy_from_slice(&PN_XNUM.to_be_bytes());
+        data[0x30..0x32].copy_from_slice(&0u16.to_be_bytes());
+
+        let path = std::env::temp_dir().join("elf_loader_corrupt_counts.mips");
+        fs::write(&path, &data).unwrap();
+        let phs = get_program_headers(&path);
+        let shs = get_section_headers(&path);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(
+            phs.unwrap_err().to_string(),
+            "program header table lies outside the file"
+        );
+        assert_eq!(
+            shs.unwrap_err().to_string(),
+            "section header table lies outside the file"
+        );
+    }
+
+    #[test]
+    fn test_vaddr_to_offset_overflow() {
+        let orig = ElfFile::open("tests/bin/dwarf2").unwrap();
+        let mut data = orig.data().to_vec();
+        data[0x28..0x30].fill(0);
+        data[0x3c..0x3e].fill(0);
+        for (i, ph) in orig.program_headers.iter().enumerate() {
+            if ph.p_type == elf_types::PT_LOAD {
+                let offset = orig.header.e_phoff as usize + i * 56 + 8;
+                data[offset..offset + 8].fill(0xff);
+            }
+        }
+        let elf = ElfFile::parse(data).unwrap();
+        assert_eq!(elf.vaddr_to_offset(0x1000), Some(u64::MAX));
+        assert_eq!(elf.vaddr_to_offset(0x1001), None);
+        assert!(elf.import_slots().is_empty());
+        elf.recover_functions();
+        elf.reconstruct_sections();
+    }
 }
@@ -0,0 +1,538 @@
+use core::fmt;
+use std::ops::Range;
+
+use crate::elf_file::ElfFile;
+use crate::elf_flags::{SectionFlags, SegmentFlags};
+use crate::elf_types::{PT_LOAD, SHT_NOBITS};
+use crate::firmware::LoadAddress;
+use crate::{ProgramHeader64, SectionHeader64};
+
+/// A memory device of the target, like an entry of the `MEMORY` command of a linker script
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryRegion {
+    pub name: String,
+    pub origin: u64,
+    pub length: u64,
+}
+
+impl MemoryRegion {
+    pub fn new(name: &str, origin: u64, length: u64) -> MemoryRegion {
+        MemoryRegion {
+            name: name.to_string(),
+            origin,
+            length,
+        }
+    }
+
+    pub fn range(&self) -> Range<u64> {
+        self.origin..self.origin.saturating_add(self.length)
+    }
+}
+
+/// Where a `PT_LOAD` segment is stored (LMA, `p_paddr`) and where it runs (VMA, `p_vaddr`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SegmentPlacement {
+    /// Index into the program header table
+    pub index: usize,
+    pub lma: u64,
+    pub vma: u64,
+    pub file_size: u64,
+    pub mem_size: u64,
+    pub flags: SegmentFlags,
+}
+
+impl SegmentPlacement {
+    /// Returns the range the file contents occupy in load memory
+    pub fn lma_range(&self) -> Range<u64> {
+        self.lma..self.lma.saturating_add(self.file_size)
+    }
+
+    /// Returns the range the segment occupies at run time, including the zero filled tail
+    pub fn vma_range(&self) -> Range<u64> {
+        self.vma..self.vma.saturating_add(self.mem_size)
+    }
+
+    /// Returns `true` if startup code has to copy the contents from LMA to VMA
+    pub fn is_copy_down(&self) -> bool {
+        self.file_size != 0 && self.lma != self.vma
+    }
+}
+
+/// The load and run address of an allocated section
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SectionPlacement {
+    pub name: String,
+    pub vma: u64,
+    /// Derived from the containing segment like `objdump -h` does, the VMA if there is none
+    pub lma: u64,
+    pub size: u64,
+    /// `SHT_NOBITS` sections take up memory at the VMA but nothing at the LMA
+    pub nobits: bool,
+    /// Index of the containing `PT_LOAD` segment
+    pub segment: Option<usize>,
+}
+
+impl SectionPlacement {
+    pub fn lma_range(&self) -> Range<u64> {
+        let size = if self.nobits { 0 } else { self.size };
+        self.lma..self.lma.saturating_add(size)
+    }
+
+    pub fn vma_range(&self) -> Range<u64> {
+        self.vma..self.vma.saturating_add(self.size)
+    }
+
+    /// Returns `true` for initialized data like `.data` that is copied from flash to RAM
+    pub fn is_copy_down(&self) -> bool {
+        !self.nobits && self.size != 0 && self.lma != self.vma
+    }
+}
+
+/// Sections linked to the same VMA range and swapped in from distinct LMAs at run time
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Overlay {
+    pub vma: Range<u64>,
+    pub sections: Vec<String>,
+}
+
+/// The bytes of a [`MemoryRegion`] taken up by segment contents at their LMA or VMA
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegionUsage {
+    pub region: MemoryRegion,
+    pub used: u64,
+}
+
+impl RegionUsage {
+    pub fn percent(&self) -> f64 {
+        if self.region.length == 0 {
+            0.0
+        } else {
+            self.used as f64 * 100.0 / self.region.length as f64
+        }
+    }
+}
+
+/// A placement that is most likely a linker script mistake
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MapIssue {
+    /// A segment range starts outside every memory region
+    Unmapped {
+        segment: usize,
+        by: LoadAddress,
+        range: Range<u64>,
+    },
+    /// A segment range starts in a memory region but runs past its end
+    Overflow {
+        segment: usize,
+        by: LoadAddress,
+        region: String,
+        excess: u64,
+    },
+    /// The contents of two segments are stored at overlapping load addresses
+    LmaOverlap { first: usize, second: usize },
+    /// Sections share run time addresses without forming an overlay
+    VmaOverlap { sections: Vec<String> },
+}
+
+fn address_kind(by: LoadAddress) -> &'static str {
+    match by {
+        LoadAddress::Physical => "LMA",
+        LoadAddress::Virtual => "VMA",
+    }
+}
+
+impl fmt::Display for MapIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MapIssue::Unmapped { segment, by, range } => write!(
+                f,
+                "segment {} {} range {:#x}..{:#x} lies outside all memory regions",
+                segment,
+                address_kind(*by),
+                range.start,
+                range.end
+            ),
+            MapIssue::Overflow {
+                segment,
+                by,
+                region,
+                excess,
+            } => write!(
+                f,
+                "segment {} {} range overflows region {} by {} bytes",
+                segment,
+                address_kind(*by),
+                region,
+                excess
+            ),
+            MapIssue::LmaOverlap { first, second } => write!(
+                f,
+                "segments {} and {} are loaded to overlapping addresses",
+                first, second
+            ),
+            MapIssue::VmaOverlap { sections } => {
+                write!(f, "sections {} overlap in memory", sections.join(", "))
+            }
+        }
+    }
+}
+
+/// Load versus run time layout of an image, see [`ElfFile::memory_map`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryMap {
+    pub segments: Vec<SegmentPlacement>,
+    pub sections: Vec<SectionPlacement>,
+    pub overlays: Vec<Overlay>,
+    /// Usage of each region passed to [`ElfFile::memory_map`], in the same order
+    pub usage: Vec<RegionUsage>,
+    pub issues: Vec<MapIssue>,
+}
+
+impl MemoryMap {
+    /// Returns the sections whose initializers the startup code copies to RAM
+    pub fn copy_downs(&self) -> impl Iterator<Item = &SectionPlacement> {
+        self.sections.iter().filter(|s| s.is_copy_down())
+    }
+}
+
+impl ElfFile {
+    /// Places every loadable segment and allocated section at its LMA and VMA, detects
+    /// copy-down data and overlays, and checks the placement against `regions`.
+    /// Region checks are skipped if `regions` is empty.
+    pub fn memory_map(&self, regions: &[MemoryRegion]) -> MemoryMap {
+        let segments: Vec<SegmentPlacement> = self
+            .program_headers
+            .iter()
+            .enumerate()
+            .filter(|(_, ph)| ph.p_type == PT_LOAD)
+            .map(|(index, ph)| SegmentPlacement {
+                index,
+                lma: ph.p_paddr,
+                vma: ph.p_vaddr,
+                file_size: ph.p_filesz,
+                mem_size: ph.p_memsz,
+                flags: ph.flags(),
+            })
+            .collect();
+        let sections: Vec<SectionPlacement> = self
+            .section_headers
+            .iter()
+            .filter(|sh| sh.flags().contains(SectionFlags::ALLOC))
+            .map(|sh| self.place_section(sh))
+            .collect();
+
+        let mut issues = Vec::new();
+        let overlays = find_overlays(self, &sections, &mut issues);
+        for (i, a) in segments.iter().enumerate() {
+            for b in &segments[i + 1..] {
+                if overlaps(&a.lma_range(), &b.lma_range()) {
+                    issues.push(MapIssue::LmaOverlap {
+                        first: a.index,
+                        second: b.index,
+                    });
+                }
+            }
+        }
+        if !regions.is_empty() {
+            for seg in &segments {
+                let mut ranges = vec![(LoadAddress::Virtual, seg.vma_range())];
+                if seg.is_copy_down() {
+                    ranges.push((LoadAddress::Physical, seg.lma_range()));
+                }
+                for (by, range) in ranges.into_iter().filter(|(_, r)| !r.is_empty()) {
+                    match regions.iter().find(|r| r.range().contains(&range.start)) {
+                        Some(region) if range.end > region.range().end => {
+                            issues.push(MapIssue::Overflow {
+                                segment: seg.index,
+                                by,
+                                region: region.name.clone(),
+                                excess: range.end - region.range().end,
+                            })
+                        }
+                        Some(_) => {}
+                        None => issues.push(MapIssue::Unmapped {
+                            segment: seg.index,
+                            by,
+                            range,
+                        }),
+                    }
+                }
+            }
+        }
+
+        let usage = regions
+            .iter()
+            .map(|region| {
+                let ranges = segments
+                    .iter()
+                    .flat_map(|s| [s.lma_range(), s.vma_range()])
+                    .map(|r| r.start.max(region.origin)..r.end.min(region.range().end))
+                    .collect();
+                RegionUsage {
+                    region: region.clone(),
+                    used: union_len(ranges),
+                }
+            })
+            .collect();
+
+        MemoryMap {
+            segments,
+            sections,
+            overlays,
+            usage,
+            issues,
+        }
+    }
+
+    fn place_section(&self, sh: &SectionHeader64) -> SectionPlacement {
+        let nobits = sh.sh_type == SHT_NOBITS;
+        let within = |start: u64, base: u64, len: u64| {
+            start >= base && start - base <= len && sh.sh_size <= len - (start - base)
+        };
+        // Overlays share p_vaddr, so the file offset picks the segment for PROGBITS data
+        let segment = self.program_headers.iter().enumerate().find(|(_, ph)| {
+            ph.p_type == PT_LOAD
+                && within(sh.sh_addr, ph.p_vaddr, ph.p_memsz)
+                && (nobits || within(sh.sh_offset, ph.p_offset, ph.p_filesz))
+        });
+        let lma = segment.map_or(sh.sh_addr, |(_, ph): (usize, &ProgramHeader64)| {
+            ph.p_paddr.wrapping_add(sh.sh_addr - ph.p_vaddr)
+        });
+        SectionPlacement {
+            name: self.section_name(sh).to_string(),
+            vma: sh.sh_addr,
+            lma,
+            size: sh.sh_size,
+            nobits,
+            segment: segment.map(|(i, _)| i),
+        }
+    }
+}
+
+fn overlaps(a: &Range<u64>, b: &Range<u64>) -> bool {
+    a.start < b.end && b.start < a.end
+}
+
+/// Returns the number of addresses covered by at least one of `ranges`
+fn union_len(mut ranges: Vec<Range<u64>>) -> u64 {
+    ranges.retain(|r| !r.is_empty());
+    ranges.sort_by_key(|r| r.start);
+    let mut total = 0;
+    let mut covered_to = 0;
+    for r in ranges {
+        let start = r.start.max(covered_to);
+        if r.end > start {
+            total += r.end - start;
+            covered_to = r.end;
+        }
+    }
+    total
+}
+
+/// Groups sections with overlapping VMAs. A group whose members are all loaded from
+/// disjoint LMAs is an overlay, anything else is reported as an issue.
+fn find_overlays(
+    elf: &ElfFile,
+    sections: &[SectionPlacement],
+    issues: &mut Vec<MapIssue>,
+) -> Vec<Overlay> {
+    // .tbss only describes the TLS template and legitimately overlaps what follows it
+    let tls_nobits: Vec<&str> = elf
+        .section_headers
+        .iter()
+        .filter(|sh| sh.sh_type == SHT_NOBITS && sh.flags().contains(SectionFlags::TLS))
+        .map(|sh| elf.section_name(sh))
+        .collect();
+    let mut candidates: Vec<&SectionPlacement> = sections
+        .iter()
+        .filter(|s| s.size != 0 && !tls_nobits.contains(&s.name.as_str()))
+        .collect();
+    candidates.sort_by_key(|s| s.vma);
+
+    let mut groups: Vec<(Range<u64>, Vec<&SectionPlacement>)> = Vec::new();
+    for s in candidates {
+        match groups.last_mut() {
+            Some((range, members)) if s.vma < range.end => {
+                range.end = range.end.max(s.vma_range().end);
+                members.push(s);
+            }
+            _ => groups.push((s.vma_range(), vec![s])),
+        }
+    }
+
+    let mut overlays = Vec::new();
+    for (vma, members) in groups.into_iter().filter(|(_, m)| m.len() > 1) {
+        let names = members.iter().map(|s| s.name.clone()).collect();
+        let disjoint = members.iter().enumerate().all(|(i, a)| {
+            !a.nobits
+                && members[i + 1..]
+                    .iter()
+                    .all(|b| !overlaps(&a.lma_range(), &b.lma_range()))
+        });
+        if disjoint {
+            overlays.push(Overlay {
+                vma,
+                sections: names,
+            });
+        } else {
+            issues.push(MapIssue::VmaOverlap { sections: names });
+        }
+    }
+    overlays
+}
+
+/// Formats a size the way `ld --print-memory-usage` does
+fn human_size(size: u64) -> String {
+    match size {
+        0 => "0 B".to_string(),
+        s if s % (1 << 30) == 0 => format!("{} GB", s >> 30),
+        s if s % (1 << 20) == 0 => format!("{} MB", s >> 20),
+        s if s % (1 << 10) == 0 => format!("{} KB", s >> 10),
+        s => format!("{} B", s),
+    }
+}
+
+impl fmt::Display for MemoryMap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<8}{:<20}{:<20}{:<12}{:<12}Flags",
+            "Segment", "VMA", "LMA", "FileSize", "MemSize"
+        )?;
+        for s in &self.segments {
+            writeln!(
+                f,
+                "{:<8}{:<20}{:<20}{:<12}{:<12}{}{}",
+                s.index,
+                format!("{:#x}", s.vma),
+                format!("{:#x}", s.lma),
+                format!("{:#x}", s.file_size),
+                format!("{:#x}", s.mem_size),
+                s.flags,
+                if s.is_copy_down() { "  copy-down" } else { "" }
+            )?;
+        }
+        writeln!(f)?;
+        writeln!(f, "{:<24}{:<20}{:<20}Size", "Section", "VMA", "LMA")?;
+        for s in &self.sections {
+            let note = if self.overlays.iter().any(|o| o.sections.contains(&s.name)) {
+                "  overlay"
+            } else if s.is_copy_down() {
+                "  copy-down"
+            } else {
+                ""
+            };
+            writeln!(
+                f,
+                "{:<24}{:<20}{:<20}{:#x}{}",
+                s.name,
+                format!("{:#x}", s.vma),
+                format!("{:#x}", s.lma),
+                s.size,
+                note
+            )?;
+        }
+        if !self.usage.is_empty() {
+            writeln!(f)?;
+            writeln!(
+                f,
+                "{:>16}{:>14}{:>14}{:>11}",
+                "Memory region", "Used Size", "Region Size", "%age Used"
+            )?;
+            for u in &self.usage {
+                writeln!(
+                    f,
+                    "{:>16}{:>14}{:>14}{:>10.2}%",
+                    format!("{}:", u.region.name),
+                    human_size(u.used),
+                    human_size(u.region.length),
+                    u.percent()
+                )?;
+            }
+        }
+        for issue in &self.issues {
+            writeln!(f, "warning: {}", issue)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn regions(ram: u64) -> Vec<MemoryRegion> {
+        vec![
+            MemoryRegion::new("FLASH", 0x0800_0000, 64 * 1024),
+            MemoryRegion::new("RAM", 0x2000_0000, ram),
+        ]
+    }
+
+    #[test]
+    fn test_copy_down() {
+        let elf = ElfFile::open("tests/bin/firmware.arm").unwrap();
+        let map = elf.memory_map(&regions(8 * 1024));
+        let copied: Vec<_> = map.copy_downs().collect();
+        assert_eq!(copied.len(), 1);
+        assert_eq!(copied[0].name, ".data");
+        assert_eq!((copied[0].vma, copied[0].lma), (0x2000_0000, 0x0800_0078));
+        let bss = map.sections.iter().find(|s| s.name == ".bss").unwrap();
+        assert!(bss.nobits && !bss.is_copy_down());
+        assert_eq!(map.segments.iter().filter(|s| s.is_copy_down()).count(), 1);
+        assert!(map.overlays.is_empty());
+        assert!(map.issues.is_empty(), "{:?}", map.issues);
+        // Flash holds the code, constants and .data initializers, RAM .data and .bss
+        assert_eq!(map.usage[0].used, 0x90);
+        assert_eq!(map.usage[1].used, 0x118);
+
+        // A RAM region too small for .bss, and no region for the flash contents
+        let map = elf.memory_map(&regions(0x100)[1..]);
+        assert!(map.issues.contains(&MapIssue::Overflow {
+            segment: 4,
+            by: LoadAddress::Virtual,
+            region: "RAM".to_string(),
+            excess: 0x18,
+        }));
+        assert!(map.issues.contains(&MapIssue::Unmapped {
+            segment: 3,
+            by: LoadAddress::Physical,
+            range: 0x0800_0078..0x0800_0090,
+        }));
+        assert_eq!(map.usage[0].used, 0x100);
+    }
+
+    #[test]
+    fn test_overlays() {
+        let elf = ElfFile::open("tests/bin/overlay.arm").unwrap();
+        let map = elf.memory_map(&regions(8 * 1024));
+        assert_eq!(
+            map.overlays,
+            [Overlay {
+                vma: 0x2000_0000..0x2000_0006,
+                sections: vec![".ov_a".to_string(), ".ov_b".to_string()],
+            }]
+        );
+        let lmas: Vec<u64> = map.sections.iter().map(|s| s.lma).collect();
+        assert_eq!(lmas, [0x0800_0000, 0x0800_0100, 0x0800_0104, 0x0800_0200]);
+        assert!(map.issues.is_empty(), "{:?}", map.issues);
+        assert!(map.to_string().contains(".ov_b"));
+
+        // Loading both overlay members to the same flash address is a mistake
+        let mut elf = elf;
+        elf.program_headers[2].p_paddr = 0x0800_0100;
+        let map = elf.memory_map(&[]);
+        assert!(map.overlays.is_empty());
+        assert!(map.issues.contains(&MapIssue::LmaOverlap {
+            first: 1,
+            second: 2
+        }));
+        assert!(map.issues.contains(&MapIssue::VmaOverlap {
+            sections: vec![".ov_a".to_string(), ".ov_b".to_string()]
+        }));
+
+        let elf = ElfFile::open("/bin/ls").unwrap();
+        let map = elf.memory_map(&[]);
+        assert!(map.overlays.is_empty() && map.issues.is_empty());
+        assert_eq!(map.copy_downs().count(), 0);
+    }
+}
@@ -0,0 +1,114 @@
+use crate::elf_file::ElfFile;
+use crate::elf_types::{PT_NOTE, SHT_NOTE};
+
+pub const SIZEOF_NHDR: usize = 12;
+
+pub const NT_GNU_ABI_TAG: u32 = 1;
+pub const NT_GNU_HWCAP: u32 = 2;
+pub const NT_GNU_BUILD_ID: u32 = 3;
+pub const NT_GNU_GOLD_VERSION: u32 = 4;
+pub const NT_GNU_PROPERTY_TYPE_0: u32 = 5;
+
+/// An entry of a `SHT_NOTE` section or `PT_NOTE` segment
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Note {
+    /// Owner name without the terminating NUL, e.g. `GNU`
+    pub name: String,
+    pub n_type: u32,
+    pub desc: Vec<u8>,
+}
+
+impl ElfFile {
+    /// Parses the notes in `data`, padding name and descriptor to `align` (4 or 8) bytes.
+    /// Stops at the first truncated entry.
+    pub fn parse_notes(&self, data: &[u8], align: u64) -> Vec<Note> {
+        let align = if align == 8 { 8 } else { 4 };
+        let pad = |n: usize| n.checked_add(align - 1).map(|n| n & !(align - 1));
+        let mut notes = Vec::new();
+        let mut pos = 0;
+        while let (Some(namesz), Some(descsz), Some(n_type)) = (
+            self.read_u32(data, pos),
+            self.read_u32(data, pos + 4),
+            self.read_u32(data, pos + 8),
+        ) {
+            let name_start = pos + SIZEOF_NHDR;
+            let Some(desc_start) = pad(name_start + namesz as usize) else {
+                break;
+            };
+            let Some(end) = desc_start.checked_add(descsz as usize) else {
+                break;
+            };
+            let (Some(name), Some(desc)) = (
+                data.get(name_start..name_start + namesz as usize),
+                data.get(desc_start..end),
+            ) else {
+                break;
+            };
+            let name = name.strip_suffix(&[0]).unwrap_or(name);
+            notes.push(Note {
+                name: String::from_utf8_lossy(name).into_owned(),
+                n_type,
+                desc: desc.to_vec(),
+            });
+            match pad(end) {
+                Some(next) if next > pos => pos = next,
+                _ => break,
+            }
+        }
+        notes
+    }
+
+    /// Returns all notes of the `SHT_NOTE` sections, or of the `PT_NOTE` segments for
+    /// files without section headers
+    pub fn notes(&self) -> Vec<Note> {
+        let from_sections: Vec<Note> = self
+            .section_headers
+            .iter()
+            .filter(|sh| sh.sh_type == SHT_NOTE)
+            .filter_map(|sh| Some(self.parse_notes(self.section_data(sh)?, sh.sh_addralign)))
+            .flatten()
+            .collect();
+        if !self.section_headers.is_empty() {
+            return from_sections;
+        }
+        self.program_headers
+            .iter()
+            .filter(|ph| ph.p_type == PT_NOTE)
+            .filter_map(|ph| Some(self.parse_notes(self.segment_data(ph)?, ph.p_align)))
+            .flatten()
+            .collect()
+    }
+
+    /// Returns the `NT_GNU_BUILD_ID` descriptor
+    pub fn build_id(&self) -> Option<Vec<u8>> {
+        self.notes()
+            .into_iter()
+            .find(|n| n.name == "GNU" && n.n_type == NT_GNU_BUILD_ID)
+            .map(|n| n.desc)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_id() {
+        let elf = ElfFile::open("tests/bin/split").unwrap();
+        let id = elf.build_id().unwrap();
+        assert_eq!(id.len(), 20);
+        assert!(elf
+            .notes()
+            .iter()
+            .any(|n| n.name == "GNU" && n.n_type == NT_GNU_ABI_TAG));
+
+        // Without section headers the notes are found through PT_NOTE
+        let mut data = elf.data().to_vec();
+        let shoff_at = if elf.is_64() { 0x28 } else { 0x20 };
+        data[shoff_at..shoff_at + 8].fill(0);
+        data[0x3c..0x40].fill(0);
+        let elf = ElfFile::parse(data).unwrap();
+        assert!(elf.section_headers.is_empty());
+        assert_eq!(elf.build_id(), Some(id));
+    }
+}
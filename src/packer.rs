@@ -0,0 +1,544 @@
+use core::fmt;
+use std::{error::Error, path::Path};
+
+use crate::elf_file::ElfFile;
+use crate::elf_flags::{SectionFlags, SegmentFlags};
+use crate::elf_types::{SegmentType, PT_LOAD, SHT_NOBITS, SHT_NULL};
+
+/// Entropy in bits per byte from which an executable region is considered compressed or
+/// encrypted. Machine code rarely exceeds 6.5.
+pub const HIGH_ENTROPY: f64 = 7.2;
+/// Regions smaller than this cannot reach a meaningful entropy and are never flagged
+pub const MIN_ENTROPY_SIZE: u64 = 512;
+
+/// `l_magic` of UPX's `l_info` and the pack header, as stored in little endian files
+pub const UPX_MAGIC: [u8; 4] = *b"UPX!";
+pub const SIZEOF_L_INFO: usize = 12;
+pub const SIZEOF_P_INFO: usize = 12;
+pub const SIZEOF_B_INFO: usize = 12;
+/// Size of the pack header UPX appends to the end of the file (format version 10 and up)
+pub const SIZEOF_PACK_HEADER: usize = 32;
+
+/// Byte strings left in the loader stubs of known ELF packers
+const SIGNATURES: &[(&str, &[u8])] = &[
+    (
+        "UPX",
+        b"$Info: This file is packed with the UPX executable packer",
+    ),
+    ("UPX", b"$Id: UPX "),
+    ("Burneye", b"TEEE burneye"),
+];
+
+/// Returns the Shannon entropy of `data` in bits per byte, `0.0` for empty input
+pub fn shannon_entropy(data: &[u8]) -> f64 {
+    let mut counts = [0u64; 256];
+    for &b in data {
+        counts[b as usize] += 1;
+    }
+    let len = data.len() as f64;
+    counts
+        .iter()
+        .filter(|&&c| c != 0)
+        .map(|&c| {
+            let p = c as f64 / len;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+/// Entropy of a segment or section
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegionEntropy {
+    /// Section name, or segment type and index such as `PT_LOAD[0]`
+    pub name: String,
+    pub offset: u64,
+    pub size: u64,
+    pub entropy: f64,
+    pub executable: bool,
+}
+
+impl RegionEntropy {
+    /// Returns `true` for executable regions large enough and random enough to be packed
+    pub fn is_high_entropy_code(&self) -> bool {
+        self.executable && self.size >= MIN_ENTROPY_SIZE && self.entropy >= HIGH_ENTROPY
+    }
+}
+
+/// UPX's trailing `PackHeader`, little endian apart from `overlay_offset`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct UpxPackHeader {
+    pub offset: u64,
+    pub version: u8,
+    pub format: u8,
+    pub method: u8,
+    pub level: u8,
+    pub u_adler: u32,
+    pub c_adler: u32,
+    pub u_len: u32,
+    pub c_len: u32,
+    pub u_file_size: u32,
+    pub filter: u8,
+    pub filter_cto: u8,
+    /// File offset of the `p_info` header, stored right after the pack header
+    pub overlay_offset: u32,
+}
+
+/// The `l_info` and `p_info` headers preceding UPX's compressed blocks
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct UpxInfo {
+    /// File offset of `l_info`, the first `b_info` follows `p_info` 24 bytes later
+    pub offset: u64,
+    pub l_checksum: u32,
+    /// `false` if `l_magic` was overwritten, a common trick to make `upx -d` refuse the file
+    pub magic_intact: bool,
+    /// Size of the loader stub
+    pub l_lsize: u16,
+    pub l_version: u8,
+    pub l_format: u8,
+    pub p_progid: u32,
+    /// Size of the original file, zeroed by some tampered samples
+    pub p_filesize: u32,
+    pub p_blocksize: u32,
+    pub pack_header: Option<UpxPackHeader>,
+}
+
+impl UpxInfo {
+    /// Returns the file offset of the first `b_info` block header
+    pub fn blocks_offset(&self) -> u64 {
+        self.offset + (SIZEOF_L_INFO + SIZEOF_P_INFO) as u64
+    }
+}
+
+/// A single observation pointing at a packed or protected file
+#[derive(Debug, Clone, PartialEq)]
+pub enum PackerIndicator {
+    /// An executable region looks compressed or encrypted
+    HighEntropyCode {
+        region: String,
+        entropy: f64,
+    },
+    /// An executable `PT_LOAD` occupies much more memory than file space, i.e. it is
+    /// filled at run time by a decompressor
+    ExpandingSegment {
+        index: usize,
+        file_size: u64,
+        mem_size: u64,
+    },
+    NoSectionHeaders,
+    /// A string identifying a packer's loader stub
+    Signature {
+        packer: &'static str,
+        offset: u64,
+    },
+    /// UPX `l_info`/`p_info` headers
+    UpxHeaders {
+        offset: u64,
+        magic_intact: bool,
+    },
+}
+
+impl PackerIndicator {
+    /// Returns `true` for indicators that identify a packer, as opposed to heuristics
+    pub fn is_conclusive(&self) -> bool {
+        matches!(
+            self,
+            PackerIndicator::Signature { .. } | PackerIndicator::UpxHeaders { .. }
+        )
+    }
+}
+
+impl fmt::Display for PackerIndicator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PackerIndicator::HighEntropyCode { region, entropy } => {
+                write!(f, "executable {} has high entropy ({:.2})", region, entropy)
+            }
+            PackerIndicator::ExpandingSegment {
+                index,
+                file_size,
+                mem_size,
+            } => write!(
+                f,
+                "executable segment {} maps {:#x} file bytes into {:#x} bytes of memory",
+                index, file_size, mem_size
+            ),
+            PackerIndicator::NoSectionHeaders => f.write_str("no section headers"),
+            PackerIndicator::Signature { packer, offset } => {
+                write!(f, "{} signature at {:#x}", packer, offset)
+            }
+            PackerIndicator::UpxHeaders {
+                offset,
+                magic_intact: true,
+            } => write!(f, "UPX l_info/p_info headers at {:#x}", offset),
+            PackerIndicator::UpxHeaders { offset, .. } => write!(
+                f,
+                "UPX l_info/p_info headers with overwritten magic at {:#x}",
+                offset
+            ),
+        }
+    }
+}
+
+/// First-pass verdict of [`ElfFile::packer_report`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PackVerdict {
+    NotPacked,
+    /// A single heuristic matched, e.g. a file stripped with `sstrip`
+    Suspicious,
+    Packed,
+}
+
+impl fmt::Display for PackVerdict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            PackVerdict::NotPacked => "not packed",
+            PackVerdict::Suspicious => "suspicious",
+            PackVerdict::Packed => "packed",
+        })
+    }
+}
+
+/// Entropy statistics and packer indicators of a file, see [`ElfFile::packer_report`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct PackerReport {
+    /// Entropy of the whole file
+    pub entropy: f64,
+    pub segments: Vec<RegionEntropy>,
+    pub sections: Vec<RegionEntropy>,
+    pub indicators: Vec<PackerIndicator>,
+    pub upx: Option<UpxInfo>,
+    pub verdict: PackVerdict,
+}
+
+impl PackerReport {
+    pub fn is_packed(&self) -> bool {
+        self.verdict == PackVerdict::Packed
+    }
+}
+
+impl fmt::Display for PackerReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "verdict: {} (file entropy {:.2})",
+            self.verdict, self.entropy
+        )?;
+        for (title, regions) in [("segments", &self.segments), ("sections", &self.sections)] {
+            if regions.is_empty() {
+                continue;
+            }
+            writeln!(f, "{}:", title)?;
+            for r in regions {
+                writeln!(
+                    f,
+                    "  {:<20} {:#10x} {:#10x} {:.2}{}",
+                    r.name,
+                    r.offset,
+                    r.size,
+                    r.entropy,
+                    if r.executable { " X" } else { "" }
+                )?;
+            }
+        }
+        for indicator in &self.indicators {
+            writeln!(f, "[!] {}", indicator)?;
+        }
+        Ok(())
+    }
+}
+
+/// Attempts to parse the ELF binary at the given path and checks it for signs of packing.
+/// The **caller** is responsible for handling the return value properly.
+pub fn detect_packer<P: AsRef<Path>>(elf_path: P) -> Result<PackerReport, Box<dyn Error>> {
+    Ok(ElfFile::open(elf_path)?.packer_report())
+}
+
+impl ElfFile {
+    /// Computes the entropy of every segment and section and collects packer indicators:
+    /// high entropy code, executable segments expanded at run time, missing section
+    /// headers, packer signatures and UPX headers, including ones with overwritten magic.
+    pub fn packer_report(&self) -> PackerReport {
+        let segments: Vec<RegionEntropy> = self
+            .program_headers
+            .iter()
+            .enumerate()
+            .filter(|(_, ph)| ph.p_filesz != 0)
+            .filter_map(|(i, ph)| {
+                let name = SegmentType::from_raw(ph.p_type, self.machine());
+                Some(RegionEntropy {
+                    name: format!("{}[{}]", name, i),
+                    offset: ph.p_offset,
+                    size: ph.p_filesz,
+                    entropy: shannon_entropy(self.segment_data(ph)?),
+                    executable: ph.flags().contains(SegmentFlags::X),
+                })
+            })
+            .collect();
+        let sections: Vec<RegionEntropy> = self
+            .section_headers
+            .iter()
+            .filter(|sh| sh.sh_type != SHT_NULL && sh.sh_type != SHT_NOBITS && sh.sh_size != 0)
+            .filter_map(|sh| {
+                Some(RegionEntropy {
+                    name: self.section_name(sh).to_string(),
+                    offset: sh.sh_offset,
+                    size: sh.sh_size,
+                    entropy: shannon_entropy(self.section_data(sh)?),
+                    executable: sh.flags().contains(SectionFlags::EXECINSTR),
+                })
+            })
+            .collect();
+
+        let mut indicators: Vec<PackerIndicator> = segments
+            .iter()
+            .chain(&sections)
+            .filter(|r| r.is_high_entropy_code())
+            .map(|r| PackerIndicator::HighEntropyCode {
+                region: r.name.clone(),
+                entropy: r.entropy,
+            })
+            .collect();
+        for (index, ph) in self.program_headers.iter().enumerate() {
+            let executable = ph.flags().contains(SegmentFlags::X);
+            if ph.p_type == PT_LOAD && executable && ph.p_memsz / 2 > ph.p_filesz {
+                indicators.push(PackerIndicator::ExpandingSegment {
+                    index,
+                    file_size: ph.p_filesz,
+                    mem_size: ph.p_memsz,
+                });
+            }
+        }
+        if self.section_headers.is_empty() {
+            indicators.push(PackerIndicator::NoSectionHeaders);
+        }
+        for &(packer, signature) in SIGNATURES {
+            if let Some(pos) = find(self.data(), signature) {
+                indicators.push(PackerIndicator::Signature {
+                    packer,
+                    offset: pos as u64,
+                });
+            }
+        }
+        let upx = self.upx_info();
+        if let Some(upx) = upx {
+            indicators.push(PackerIndicator::UpxHeaders {
+                offset: upx.offset,
+                magic_intact: upx.magic_intact,
+            });
+        }
+
+        let mut heuristics: Vec<std::mem::Discriminant<PackerIndicator>> = indicators
+            .iter()
+            .filter(|i| !i.is_conclusive())
+            .map(std::mem::discriminant)
+            .collect();
+        heuristics.dedup();
+        let verdict =
+            if indicators.iter().any(PackerIndicator::is_conclusive) || heuristics.len() > 1 {
+                PackVerdict::Packed
+            } else if heuristics.len() == 1 {
+                PackVerdict::Suspicious
+            } else {
+                PackVerdict::NotPacked
+            };
+        PackerReport {
+            entropy: shannon_entropy(self.data()),
+            segments,
+            sections,
+            indicators,
+            upx,
+            verdict,
+        }
+    }
+
+    /// Locates UPX's `l_info`/`p_info` headers. Candidates are the position named by the
+    /// trailing pack header, every `UPX!` marker and the end of the program header table,
+    /// where UPX places them. A candidate is accepted if the first `b_info` after it is
+    /// plausible, so headers with overwritten magic are found as well.
+    pub fn upx_info(&self) -> Option<UpxInfo> {
+        let data = self.data();
+        let pack_header = self.upx_pack_header();
+        let mut candidates = Vec::new();
+        if let Some(offset) =
+            pack_header.and_then(|ph| ph.overlay_offset.checked_sub(SIZEOF_L_INFO as u32))
+        {
+            candidates.push(offset as u64);
+        }
+        let mut pos = 0;
+        while let Some(found) = data.get(pos..).and_then(|d| find(d, &UPX_MAGIC)) {
+            if let Some(offset) = (pos + found).checked_sub(4) {
+                candidates.push(offset as u64);
+            }
+            pos += found + 1;
+        }
+        let phdrs_end = (self.phnum() as u64)
+            .checked_mul(self.header.e_phentsize as u64)
+            .and_then(|n| n.checked_add(self.header.e_phoff));
+        candidates.extend(phdrs_end);
+
+        candidates.into_iter().find_map(|offset| {
+            let hdr = self.bytes_at(
+                offset,
+                (SIZEOF_L_INFO + SIZEOF_P_INFO + SIZEOF_B_INFO) as u64,
+            )?;
+            let magic: [u8; 4] = hdr[4..8].try_into().ok()?;
+            let info = UpxInfo {
+                offset,
+                l_checksum: self.read_u32(hdr, 0)?,
+                magic_intact: is_upx_magic(magic),
+                l_lsize: self.read_u16(hdr, 8)?,
+                l_version: hdr[10],
+                l_format: hdr[11],
+                p_progid: self.read_u32(hdr, 12)?,
+                p_filesize: self.read_u32(hdr, 16)?,
+                p_blocksize: self.read_u32(hdr, 20)?,
+                pack_header,
+            };
+            let sz_unc = self.read_u32(hdr, 24)?;
+            let sz_cpr = self.read_u32(hdr, 28)?;
+            let method = hdr[32];
+            let plausible = sz_unc != 0
+                && sz_cpr != 0
+                && sz_cpr <= sz_unc
+                && (sz_cpr == sz_unc || is_upx_method(method))
+                && (info.p_blocksize == 0 || sz_unc <= info.p_blocksize)
+                && offset + 36 + sz_cpr as u64 <= data.len() as u64;
+            plausible.then_some(info)
+        })
+    }
+
+    /// Finds UPX's pack header in the last bytes of the file. The `b_info` end marker
+    /// also reads `UPX!`, so a match only counts if its fields are consistent.
+    fn upx_pack_header(&self) -> Option<UpxPackHeader> {
+        let data = self.data();
+        let tail = data.len().saturating_sub(512);
+        let mut end = data.len();
+        while let Some(found) = rfind(&data[tail..end], &UPX_MAGIC) {
+            let pos = tail + found;
+            end = pos + UPX_MAGIC.len() - 1;
+            let Some(raw) = data.get(pos..pos + SIZEOF_PACK_HEADER + 4) else {
+                continue;
+            };
+            // UPX stores the pack header little endian whatever the target
+            let u32_at = |off: usize| u32::from_le_bytes(raw[off..off + 4].try_into().unwrap());
+            let header = UpxPackHeader {
+                offset: pos as u64,
+                version: raw[4],
+                format: raw[5],
+                method: raw[6],
+                level: raw[7],
+                u_adler: u32_at(8),
+                c_adler: u32_at(12),
+                u_len: u32_at(16),
+                c_len: u32_at(20),
+                u_file_size: u32_at(24),
+                filter: raw[28],
+                filter_cto: raw[29],
+                overlay_offset: self.read_u32(raw, SIZEOF_PACK_HEADER)?,
+            };
+            if (10..=14).contains(&header.version)
+                && is_upx_method(header.method)
+                && header.u_len != 0
+                && (header.overlay_offset as usize) < pos
+            {
+                return Some(header);
+            }
+        }
+        None
+    }
+}
+
+fn is_upx_magic(magic: [u8; 4]) -> bool {
+    u32::from_le_bytes(magic) == u32::from_le_bytes(UPX_MAGIC)
+        || u32::from_be_bytes(magic) == u32::from_le_bytes(UPX_MAGIC)
+}
+
+/// NRV2B/NRV2D/NRV2E in their LE32, 8 and LE16 flavours, and LZMA
+fn is_upx_method(method: u8) -> bool {
+    (2..=10).contains(&method) || method == 14
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+fn rfind(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).rposition(|w| w == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shannon_entropy() {
+        assert_eq!(shannon_entropy(&[]), 0.0);
+        assert_eq!(shannon_entropy(&[0x41; 100]), 0.0);
+        let all: Vec<u8> = (0..=255).collect();
+        assert!((shannon_entropy(&all) - 8.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_unpacked_samples() {
+        for path in ["tests/bin/dd.armel", "tests/bin/objdump.mips"] {
+            let report = detect_packer(path).unwrap();
+            assert_eq!(report.verdict, PackVerdict::NotPacked, "{}", path);
+            assert!(report.upx.is_none());
+            assert!(report
+                .sections
+                .iter()
+                .any(|s| s.name == ".text" && s.executable));
+        }
+    }
+
+    #[test]
+    fn test_upx_samples() {
+        let report = detect_packer("tests/bin/upx.arm").unwrap();
+        assert!(report.is_packed());
+        let upx = report.upx.unwrap();
+        assert!(upx.magic_intact);
+        assert_eq!(
+            (upx.offset, upx.p_filesize, upx.p_blocksize),
+            (0x74, 1724, 0x4000)
+        );
+        let pack_header = upx.pack_header.unwrap();
+        assert_eq!((pack_header.method, pack_header.u_file_size), (3, 1724));
+        assert!(report
+            .indicators
+            .contains(&PackerIndicator::NoSectionHeaders));
+
+        // Overwritten magic and sizes: only the structure gives it away
+        let report = detect_packer("tests/bin/upx.tampered.arm").unwrap();
+        assert!(report.is_packed());
+        let upx = report.upx.unwrap();
+        assert!(!upx.magic_intact && upx.pack_header.is_none());
+        assert_eq!((upx.offset, upx.p_filesize), (0x74, 0));
+
+        let report = detect_packer("tests/bin/upx.x86_64").unwrap();
+        assert!(report.indicators.iter().any(|i| matches!(
+            i,
+            PackerIndicator::HighEntropyCode { region, .. } if region == "PT_LOAD[0]"
+        )));
+        assert!(report
+            .indicators
+            .iter()
+            .any(|i| matches!(i, PackerIndicator::ExpandingSegment { index: 0, .. })));
+
+        // Big endian targets keep the pack header little endian
+        let upx = detect_packer("tests/bin/upx.mips").unwrap().upx.unwrap();
+        let pack_header = upx.pack_header.unwrap();
+        assert!(pack_header.format >= 128);
+        assert_eq!((pack_header.u_file_size, upx.p_filesize), (1924, 1924));
+        assert_eq!(pack_header.u_len, 1924);
+    }
+
+    #[test]
+    fn test_huge_phoff() {
+        let mut elf = ElfFile::open("tests/bin/upx.nrv2b.arm").unwrap();
+        elf.header.e_phoff = u64::MAX;
+        let report = elf.packer_report();
+        assert_eq!(report.upx.unwrap().offset, 0x74);
+        assert!(elf.upx_blocks().is_ok());
+        assert!(elf.upx_unpack().is_ok());
+    }
+}
@@ -0,0 +1,391 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::dynamic::{DT_MIPS_GOTSYM, DT_MIPS_LOCAL_GOTNO, DT_PLTGOT};
+use crate::elf_file::ElfFile;
+use crate::elf_types::{Machine, SHT_DYNSYM, SHT_REL, SHT_RELA};
+use crate::relocations::*;
+use crate::symbols::STT_FUNC;
+
+/// Whether an [`ImportSlot`] is a PLT stub or the GOT entry the stub jumps through
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ImportKind {
+    Plt,
+    Got,
+}
+
+/// A PLT stub or GOT slot mapped to the imported symbol it serves
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportSlot {
+    pub address: u64,
+    pub kind: ImportKind,
+    pub name: String,
+    /// Address of the GOT slot, equal to `address` for [`ImportKind::Got`]
+    pub got: u64,
+}
+
+impl fmt::Display for ImportSlot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let suffix = match self.kind {
+            ImportKind::Plt => "plt",
+            ImportKind::Got => "got",
+        };
+        write!(f, "{:#x} {}@{}", self.address, self.name, suffix)
+    }
+}
+
+impl ElfFile {
+    /// Maps the GOT slots and PLT stubs of a dynamically linked file to their imported
+    /// symbols, sorted by address.
+    ///
+    /// GOT slots come from the `JUMP_SLOT` and `GLOB_DAT` relocations, or from the
+    /// `DT_MIPS_GOTSYM` split of the global GOT on MIPS. PLT stubs are matched to their
+    /// slot by decoding the indirect jump of `.plt`, `.plt.sec` and `.plt.got` on x86-64,
+    /// i386, AArch64, ARM and MIPS. MIPS lazy binding stubs are taken from the values of
+    /// undefined dynamic function symbols.
+    pub fn import_slots(&self) -> Vec<ImportSlot> {
+        let got = self.got_symbols();
+        let mut slots: Vec<ImportSlot> = got
+            .iter()
+            .map(|(&address, name)| ImportSlot {
+                address,
+                kind: ImportKind::Got,
+                name: name.clone(),
+                got: address,
+            })
+            .collect();
+
+        for (address, slot) in self.plt_targets() {
+            if let Some(name) = got.get(&slot) {
+                slots.push(ImportSlot {
+                    address,
+                    kind: ImportKind::Plt,
+                    name: name.clone(),
+                    got: slot,
+                });
+            }
+        }
+
+        if self.is_mips() {
+            let by_name: HashMap<&str, u64> =
+                slots.iter().map(|s| (s.name.as_str(), s.got)).collect();
+            let stubs: Vec<ImportSlot> = self
+                .dynsym()
+                .into_iter()
+                .filter(|s| s.sym_type() == STT_FUNC && !s.is_defined() && s.value != 0)
+                .map(|s| ImportSlot {
+                    address: s.value,
+                    kind: ImportKind::Plt,
+                    got: by_name.get(s.name.as_str()).copied().unwrap_or_default(),
+                    name: s.name,
+                })
+                .collect();
+            for stub in stubs {
+                if !slots
+                    .iter()
+                    .any(|s| s.kind == ImportKind::Plt && s.address == stub.address)
+                {
+                    slots.push(stub);
+                }
+            }
+        }
+
+        slots.sort_by_key(|s| (s.address, s.kind));
+        slots
+    }
+
+    fn is_mips(&self) -> bool {
+        matches!(
+            self.header.machine(),
+            Ok(Machine::Mips | Machine::MipsRs3Le)
+        )
+    }
+
+    /// Returns the imported symbol name of every GOT slot
+    fn got_symbols(&self) -> HashMap<u64, String> {
+        let (glob_dat, jump_slot) = match self.header.machine() {
+            Ok(Machine::X86_64) => (R_X86_64_GLOB_DAT, R_X86_64_JUMP_SLOT),
+            Ok(Machine::I386) => (R_386_GLOB_DAT, R_386_JMP_SLOT),
+            Ok(Machine::Arm) => (R_ARM_GLOB_DAT, R_ARM_JUMP_SLOT),
+            Ok(Machine::Aarch64) => (R_AARCH64_GLOB_DAT, R_AARCH64_JUMP_SLOT),
+            Ok(Machine::Mips | Machine::MipsRs3Le) => (R_MIPS_GLOB_DAT, R_MIPS_JUMP_SLOT),
+            _ => return HashMap::new(),
+        };
+
+        let mut got = HashMap::new();
+        for (index, sh) in self.section_headers.iter().enumerate() {
+            if sh.sh_type != SHT_REL && sh.sh_type != SHT_RELA {
+                continue;
+            }
+            let symbols = self.symbols(sh.sh_link as usize);
+            for r in self.relocations(index) {
+                if r.r_type != glob_dat && r.r_type != jump_slot {
+                    continue;
+                }
+                if let Some(sym) = symbols.get(r.sym as usize).filter(|s| !s.name.is_empty()) {
+                    got.insert(r.offset, sym.name.clone());
+                }
+            }
+        }
+
+        if self.is_mips() {
+            // The global part of the MIPS GOT mirrors .dynsym from DT_MIPS_GOTSYM onwards
+            let pltgot = self.dynamic_value(DT_PLTGOT);
+            let local = self.dynamic_value(DT_MIPS_LOCAL_GOTNO);
+            let gotsym = self.dynamic_value(DT_MIPS_GOTSYM);
+            let dynsym = self
+                .section_headers
+                .iter()
+                .position(|sh| sh.sh_type == SHT_DYNSYM)
+                .map_or_else(Vec::new, |i| self.symbols(i));
+            if let (Some(pltgot), Some(local), Some(gotsym)) = (pltgot, local, gotsym) {
+                let addr_size = self.addr_size() as u64;
+                for (i, sym) in dynsym.iter().enumerate().skip(gotsym as usize) {
+                    let Some(offset) = local
+                        .checked_add(i as u64 - gotsym)
+                        .and_then(|index| index.checked_mul(addr_size))
+                    else {
+                        break;
+                    };
+                    got.entry(pltgot.wrapping_add(offset))
+                        .or_insert_with(|| sym.name.clone());
+                }
+            }
+        }
+        got
+    }
+
+    /// Decodes the PLT stubs into `(stub address, GOT slot)` pairs
+    pub(crate) fn plt_targets(&self) -> Vec<(u64, u64)> {
+        let mut targets = Vec::new();
+        match self.header.machine() {
+            Ok(Machine::X86_64 | Machine::I386) => {
+                let got_base = self.dynamic_value(DT_PLTGOT).unwrap_or_default();
+                for stub in self.plt_stubs() {
+                    let Some(code) = self
+                        .vaddr_to_offset(stub.start)
+                        .and_then(|off| self.bytes_at(off, stub.end - stub.start))
+                    else {
+                        continue;
+                    };
+                    if let Some(slot) = self.x86_jump_slot(code, stub.start, got_base) {
+                        targets.push((stub.start, slot));
+                    }
+                }
+            }
+            Ok(machine) => {
+                for sh in &self.section_headers {
+                    if !self.section_name(sh).starts_with(".plt") {
+                        continue;
+                    }
+                    let Some(code) = self.section_data(sh) else {
+                        continue;
+                    };
+                    let words: Vec<u32> = (0..code.len() / 4)
+                        .filter_map(|i| self.read_u32(code, i * 4))
+                        .collect();
+                    for i in 0..words.len() {
+                        let pc = sh.sh_addr.wrapping_add(i as u64 * 4);
+                        let slot = match machine {
+                            Machine::Aarch64 => aarch64_jump_slot(&words[i..], pc),
+                            Machine::Arm => arm_jump_slot(&words[i..], pc),
+                            Machine::Mips | Machine::MipsRs3Le => mips_jump_slot(&words[i..]),
+                            _ => return targets,
+                        };
+                        if let Some(slot) = slot {
+                            // A BTI landing pad belongs to the stub
+                            let bti = machine == Machine::Aarch64
+                                && i > 0
+                                && words[i - 1] == AARCH64_BTI_C;
+                            targets.push((if bti { pc.wrapping_sub(4) } else { pc }, slot));
+                        }
+                    }
+                }
+            }
+            Err(_) => {}
+        }
+        targets
+    }
+
+    /// Decodes `[endbr] [bnd] jmp *slot` at the start of an x86 PLT stub
+    fn x86_jump_slot(&self, code: &[u8], pc: u64, got_base: u64) -> Option<u64> {
+        let mut i = 0;
+        if code.starts_with(&[0xf3, 0x0f, 0x1e]) {
+            i += 4;
+        }
+        if code.get(i) == Some(&0xf2) {
+            i += 1;
+        }
+        let disp = self.read_u32(code, i + 2)?;
+        let next = pc.wrapping_add(i as u64 + 6);
+        match (code.get(i..i + 2)?, self.is_64()) {
+            // jmp *disp32(%rip)
+            ([0xff, 0x25], true) => Some(next.wrapping_add_signed(disp as i32 as i64)),
+            // jmp *abs32
+            ([0xff, 0x25], false) => Some(disp as u64),
+            // jmp *disp32(%ebx) with %ebx pointing to the GOT
+            ([0xff, 0xa3], false) => {
+                Some(got_base.wrapping_add_signed(disp as i32 as i64) & 0xffff_ffff)
+            }
+            _ => None,
+        }
+    }
+}
+
+const AARCH64_BTI_C: u32 = 0xd503_245f;
+
+/// Decodes `adrp x16, page; ldr x17, [x16, #off]`
+fn aarch64_jump_slot(words: &[u32], pc: u64) -> Option<u64> {
+    let (&adrp, &ldr) = (words.first()?, words.get(1)?);
+    if adrp & 0x9f00_001f != 0x9000_0010 || ldr & 0xffc0_03ff != 0xf940_0211 {
+        return None;
+    }
+    let imm = (((adrp >> 5) & 0x7_ffff) << 2 | (adrp >> 29) & 0x3) as i64;
+    // Sign extend the 21-bit page offset
+    let imm = (imm << 43) >> 43;
+    let page = (pc & !0xfff).wrapping_add_signed(imm << 12);
+    Some(page.wrapping_add(((ldr >> 10) & 0xfff) as u64 * 8))
+}
+
+/// Decodes `add ip, pc, #a; add ip, ip, #b; ldr pc, [ip, #c]!`
+fn arm_jump_slot(words: &[u32], pc: u64) -> Option<u64> {
+    let (&add_pc, &add_ip, &ldr) = (words.first()?, words.get(1)?, words.get(2)?);
+    if add_pc & 0xffff_f000 != 0xe28f_c000
+        || add_ip & 0xffff_f000 != 0xe28c_c000
+        || ldr & 0xffff_f000 != 0xe5bc_f000
+    {
+        return None;
+    }
+    let rotated = |insn: u32| (insn & 0xff).rotate_right(2 * ((insn >> 8) & 0xf)) as u64;
+    let offset = 8 + rotated(add_pc) + rotated(add_ip) + (ldr & 0xfff) as u64;
+    Some(pc.wrapping_add(offset) & 0xffff_ffff)
+}
+
+/// Decodes `lui t7, %hi(slot); lw/ld t9, %lo(slot)(t7)`
+fn mips_jump_slot(words: &[u32]) -> Option<u64> {
+    let (&lui, &load) = (words.first()?, words.get(1)?);
+    if lui >> 16 != 0x3c0f || (load >> 16 != 0x8df9 && load >> 16 != 0xddf9) {
+        return None;
+    }
+    let hi = (lui & 0xffff) << 16;
+    Some(hi.wrapping_add(load as u16 as i16 as i32 as u32) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(elf: &ElfFile, kind: ImportKind) -> Vec<String> {
+        elf.import_slots()
+            .into_iter()
+            .filter(|s| s.kind == kind)
+            .map(|s| s.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_x64_plt_sec_and_plt_got() {
+        let elf = ElfFile::open("tests/bin/plt.x86_64").unwrap();
+        let plt_sec = elf.section_by_name(".plt.sec").unwrap().sh_addr;
+        let plt_got = elf.section_by_name(".plt.got").unwrap().sh_addr;
+        assert_eq!(
+            names(&elf, ImportKind::Plt),
+            [
+                format!("{:#x} puts@plt", plt_got),
+                format!("{:#x} abort@plt", plt_sec),
+                format!("{:#x} exit@plt", plt_sec + 16),
+            ]
+        );
+        assert_eq!(names(&elf, ImportKind::Got).len(), 3);
+    }
+
+    #[test]
+    fn test_x64_lazy_plt() {
+        let elf = ElfFile::open("/bin/ls").unwrap();
+        let slots = elf.import_slots();
+        let malloc = slots
+            .iter()
+            .find(|s| s.kind == ImportKind::Plt && s.name == "malloc")
+            .unwrap();
+        assert!(slots
+            .iter()
+            .any(|s| s.kind == ImportKind::Got && s.address == malloc.got && s.name == "malloc"));
+        let funcs = elf.recover_functions();
+        let f = funcs
+            .iter()
+            .find(|f| f.address.start == malloc.address)
+            .unwrap();
+        assert_eq!(f.name, "malloc@plt");
+    }
+
+    #[test]
+    fn test_i386_pic_plt() {
+        let elf = ElfFile::open("tests/bin/plt.i386").unwrap();
+        let plt = elf.section_by_name(".plt").unwrap().sh_addr;
+        assert_eq!(
+            names(&elf, ImportKind::Plt),
+            [
+                format!("{:#x} puts@plt", plt + 16),
+                format!("{:#x} exit@plt", plt + 32),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_aarch64_arm_plt() {
+        for (path, header, entry) in [
+            ("tests/bin/plt.aarch64", 32, 16),
+            ("tests/bin/plt.arm", 32, 16),
+        ] {
+            let elf = ElfFile::open(path).unwrap();
+            let plt = elf.section_by_name(".plt").unwrap().sh_addr;
+            assert_eq!(
+                names(&elf, ImportKind::Plt),
+                [
+                    format!("{:#x} puts@plt", plt + header),
+                    format!("{:#x} exit@plt", plt + header + entry),
+                ],
+                "{}",
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn test_plt_at_end_of_address_space() {
+        let elf = ElfFile::open("tests/bin/plt.aarch64").unwrap();
+        let plt = elf
+            .section_headers
+            .iter()
+            .position(|sh| elf.section_name(sh) == ".plt")
+            .unwrap();
+        let mut data = elf.data().to_vec();
+        let sh_addr = elf.header.e_shoff as usize + plt * elf.header.e_shentsize as usize + 16;
+        data[sh_addr..sh_addr + 8].copy_from_slice(&0xffff_ffff_ffff_fff0u64.to_le_bytes());
+        // The stubs no longer point into the GOT, the GOT slots themselves remain
+        let slots = ElfFile::parse(data).unwrap().import_slots();
+        assert!(!slots.is_empty() && slots.iter().all(|s| s.kind == ImportKind::Got));
+    }
+
+    #[test]
+    fn test_mips_gotsym() {
+        let elf = ElfFile::open("tests/bin/plt.mips").unwrap();
+        let got = elf.dynamic_value(DT_PLTGOT).unwrap();
+        let local = elf.dynamic_value(DT_MIPS_LOCAL_GOTNO).unwrap();
+        let slots = elf.import_slots();
+        let puts = slots.iter().find(|s| s.name == "puts").unwrap();
+        assert_eq!(puts.kind, ImportKind::Got);
+        assert!(puts.address >= got + local * 4);
+        assert!(slots.iter().any(|s| s.name == "exit"));
+
+        let elf = ElfFile::open("tests/bin/pltexe.mips").unwrap();
+        let plt = elf.section_by_name(".plt").unwrap().sh_addr;
+        assert_eq!(
+            names(&elf, ImportKind::Plt),
+            [
+                format!("{:#x} puts@plt", plt + 32),
+                format!("{:#x} exit@plt", plt + 48),
+            ]
+        );
+    }
+}
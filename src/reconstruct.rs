@@ -0,0 +1,1024 @@
+use std::collections::HashMap;
+use std::{error::Error, path::Path};
+
+use crate::dynamic::*;
+use crate::elf_file::ElfFile;
+use crate::elf_flags::{SectionFlags, SegmentFlags, SHF_MIPS_GPREL};
+use crate::elf_types::*;
+use crate::elf_writer::ElfWriter;
+use crate::notes::{NT_GNU_ABI_TAG, NT_GNU_BUILD_ID, NT_GNU_PROPERTY_TYPE_0, SIZEOF_NHDR};
+use crate::symbols::STB_LOCAL;
+use crate::versions::{SIZEOF_VERDEF, SIZEOF_VERNEED};
+use crate::{ProgramHeader64, SectionHeader64, SIZEOF_SHDR32, SIZEOF_SHDR64};
+
+const SHT_ARM_EXIDX: u32 = 0x7000_0001;
+const SHT_MIPS_REGINFO: u32 = 0x7000_0006;
+const SHT_MIPS_ABIFLAGS: u32 = 0x7000_002a;
+const PT_ARM_EXIDX: u32 = 0x7000_0001;
+const PT_MIPS_REGINFO: u32 = 0x7000_0000;
+const PT_MIPS_ABIFLAGS: u32 = 0x7000_0003;
+
+/// Uncovered ranges of a segment smaller than this are treated as alignment padding
+const MIN_FILL_SIZE: u64 = 16;
+
+/// A section header synthesized by [`ElfFile::reconstruct_sections`]
+#[derive(Debug, Clone)]
+pub struct SyntheticSection {
+    pub name: String,
+    /// `sh_name` is 0 until the table is written, `sh_link` and `sh_info` refer to the
+    /// indices of the reconstructed table
+    pub header: SectionHeader64,
+}
+
+/// A section before its links are resolved to indices
+struct Draft {
+    name: String,
+    sh_type: u32,
+    flags: SectionFlags,
+    addr: u64,
+    size: u64,
+    align: u64,
+    entsize: u64,
+    link: Option<&'static str>,
+    info: u32,
+    info_link: Option<&'static str>,
+}
+
+impl Draft {
+    fn new(name: &str, sh_type: u32, flags: SectionFlags, addr: u64, size: u64) -> Draft {
+        Draft {
+            name: name.to_string(),
+            sh_type,
+            flags,
+            addr,
+            size,
+            align: 1,
+            entsize: 0,
+            link: None,
+            info: 0,
+            info_link: None,
+        }
+    }
+
+    fn align(mut self, align: u64) -> Draft {
+        self.align = align;
+        self
+    }
+
+    fn entsize(mut self, entsize: u64) -> Draft {
+        self.entsize = entsize;
+        self
+    }
+
+    fn link(mut self, link: &'static str) -> Draft {
+        self.link = Some(link);
+        self
+    }
+
+    fn end(&self) -> u64 {
+        self.addr.saturating_add(self.size)
+    }
+}
+
+/// Attempts to parse the ELF binary at the given path and returns it with a section header
+/// table synthesized from its program headers and dynamic entries.
+/// The **caller** is responsible for handling the return value properly.
+pub fn rebuild_section_headers<P: AsRef<Path>>(elf_path: P) -> Result<Vec<u8>, Box<dyn Error>> {
+    let elf = ElfFile::open(elf_path)?;
+    elf.with_section_headers(&elf.reconstruct_sections())
+}
+
+impl ElfFile {
+    /// Synthesizes a section header table for files without one, using only the program
+    /// headers and the `PT_DYNAMIC` entries. Sizes the dynamic table does not record are
+    /// derived from the data: symbol counts from the hash tables, `.eh_frame` from its
+    /// length fields and version tables by walking their chains. The largest remaining
+    /// range of each `PT_LOAD` becomes `.text`, `.rodata` or `.data`.
+    ///
+    /// The first entry is the null section. Existing section headers are ignored.
+    pub fn reconstruct_sections(&self) -> Vec<SyntheticSection> {
+        let mut drafts = self.segment_sections();
+        drafts.extend(self.dynamic_sections());
+        drafts.retain(|d| d.size != 0 && self.load_offset(d.addr).is_some());
+        let mut seen = Vec::new();
+        drafts.retain(|d| {
+            let key = (d.name.clone(), d.addr);
+            let fresh = !seen.contains(&key);
+            seen.push(key);
+            fresh
+        });
+        drafts.extend(self.fill_sections(&drafts));
+        drafts.sort_by_key(|d| (d.addr, !d.flags.contains(SectionFlags::TLS)));
+
+        let mut index = HashMap::new();
+        for (i, d) in drafts.iter().enumerate() {
+            index.entry(d.name.clone()).or_insert(i as u32 + 1);
+        }
+        let resolve = |name: Option<&str>| name.and_then(|n| index.get(n).copied());
+        let mut sections = vec![SyntheticSection {
+            name: String::new(),
+            header: SectionHeader64::default(),
+        }];
+        for d in &drafts {
+            let offset = self.load_offset(d.addr).unwrap_or_default();
+            sections.push(SyntheticSection {
+                name: d.name.clone(),
+                header: SectionHeader64 {
+                    sh_name: 0,
+                    sh_type: d.sh_type,
+                    sh_flags: d.flags.bits(),
+                    sh_addr: d.addr,
+                    sh_offset: offset,
+                    sh_size: d.size,
+                    sh_link: resolve(d.link).unwrap_or_default(),
+                    sh_info: resolve(d.info_link).unwrap_or(d.info),
+                    sh_addralign: d.align,
+                    sh_entsize: d.entsize,
+                },
+            });
+        }
+        sections
+    }
+
+    /// Returns a copy of the file with `sections` and a `.shstrtab` appended as its section
+    /// header table, e.g. to load a section-less binary into tools that depend on sections
+    pub fn with_section_headers(
+        &self,
+        sections: &[SyntheticSection],
+    ) -> Result<Vec<u8>, Box<dyn Error>> {
+        let count = sections.len() + 1;
+        if count >= SHN_LORESERVE as usize {
+            return Err("too many sections for e_shnum".into());
+        }
+        let mut shstrtab = vec![0u8];
+        let mut headers = Vec::with_capacity(count);
+        for s in sections {
+            let mut sh = s.header;
+            sh.sh_name = if s.name.is_empty() {
+                0
+            } else {
+                push_name(&mut shstrtab, &s.name)
+            };
+            headers.push(sh);
+        }
+        let name = push_name(&mut shstrtab, ".shstrtab");
+
+        let mut out = self.data().to_vec();
+        headers.push(SectionHeader64 {
+            sh_name: name,
+            sh_type: SHT_STRTAB,
+            sh_offset: out.len() as u64,
+            sh_size: shstrtab.len() as u64,
+            sh_addralign: 1,
+            ..Default::default()
+        });
+        out.extend(&shstrtab);
+        out.resize(out.len().next_multiple_of(self.addr_size()), 0);
+
+        let w = ElfWriter::new(self.is_64(), self.is_big_endian());
+        let mut header = self.header;
+        header.e_shoff = out.len() as u64;
+        header.e_shnum = count as u16;
+        header.e_shstrndx = (count - 1) as u16;
+        header.e_shentsize = if self.is_64() {
+            SIZEOF_SHDR64
+        } else {
+            SIZEOF_SHDR32
+        } as u16;
+        for sh in &headers {
+            out.extend(w.section_header(sh));
+        }
+        let raw = w.header(&header);
+        out[..raw.len()].copy_from_slice(&raw);
+        Ok(out)
+    }
+
+    /// Returns the file offset of a virtual address inside a `PT_LOAD`, including the
+    /// zero filled tail where `.bss` like sections live
+    fn load_offset(&self, addr: u64) -> Option<u64> {
+        self.program_headers
+            .iter()
+            .filter(|ph| ph.p_type == PT_LOAD)
+            .find(|ph| addr >= ph.p_vaddr && addr - ph.p_vaddr < ph.p_memsz.max(1))
+            .and_then(|ph| (addr - ph.p_vaddr).checked_add(ph.p_offset))
+    }
+
+    /// Returns the end of the file backed part of the `PT_LOAD` containing `addr`
+    fn load_end(&self, addr: u64) -> Option<u64> {
+        self.program_headers
+            .iter()
+            .filter(|ph| ph.p_type == PT_LOAD)
+            .find(|ph| addr >= ph.p_vaddr && addr - ph.p_vaddr < ph.p_memsz)
+            .and_then(|ph| ph.p_vaddr.checked_add(ph.p_filesz))
+    }
+
+    /// Returns the flags of the `PT_LOAD` containing `addr`
+    fn load_flags(&self, addr: u64) -> SegmentFlags {
+        self.program_headers
+            .iter()
+            .filter(|ph| ph.p_type == PT_LOAD)
+            .find(|ph| addr >= ph.p_vaddr && addr - ph.p_vaddr < ph.p_memsz)
+            .map_or(SegmentFlags::default(), |ph| ph.flags())
+    }
+
+    /// `SHF_ALLOC`, plus `SHF_WRITE` if the section lives in a writable segment
+    fn data_flags(&self, addr: u64) -> SectionFlags {
+        if self.load_flags(addr).contains(SegmentFlags::W) {
+            SectionFlags::ALLOC | SectionFlags::WRITE
+        } else {
+            SectionFlags::ALLOC
+        }
+    }
+
+    /// Sections that correspond to a whole program header
+    fn segment_sections(&self) -> Vec<Draft> {
+        let mut drafts = Vec::new();
+        let machine = Machine::try_from(self.machine()).ok();
+        for ph in &self.program_headers {
+            let (addr, size) = (ph.p_vaddr, ph.p_filesz);
+            match (ph.p_type, machine) {
+                (PT_INTERP, _) => drafts.push(Draft::new(
+                    ".interp",
+                    SHT_PROGBITS,
+                    SectionFlags::ALLOC,
+                    addr,
+                    size,
+                )),
+                (PT_NOTE, _) => drafts.extend(self.note_sections(ph)),
+                (PT_DYNAMIC, _) => drafts.push(
+                    Draft::new(".dynamic", SHT_DYNAMIC, self.data_flags(addr), addr, size)
+                        .align(self.addr_size() as u64)
+                        .entsize(2 * self.addr_size() as u64)
+                        .link(".dynstr"),
+                ),
+                (PT_GNU_EH_FRAME, _) => {
+                    drafts.push(
+                        Draft::new(
+                            ".eh_frame_hdr",
+                            SHT_PROGBITS,
+                            SectionFlags::ALLOC,
+                            addr,
+                            size,
+                        )
+                        .align(4),
+                    );
+                    if let Some(d) = self.eh_frame_section() {
+                        drafts.push(d);
+                    }
+                }
+                (PT_TLS, _) => {
+                    let flags = SectionFlags::ALLOC | SectionFlags::WRITE | SectionFlags::TLS;
+                    drafts.push(
+                        Draft::new(".tdata", SHT_PROGBITS, flags, addr, size).align(ph.p_align),
+                    );
+                    if let Some(end) = addr.checked_add(size) {
+                        drafts.push(
+                            Draft::new(
+                                ".tbss",
+                                SHT_NOBITS,
+                                flags,
+                                end,
+                                ph.p_memsz.saturating_sub(size),
+                            )
+                            .align(ph.p_align),
+                        );
+                    }
+                }
+                (PT_ARM_EXIDX, Some(Machine::Arm)) => drafts.push(
+                    Draft::new(
+                        ".ARM.exidx",
+                        SHT_ARM_EXIDX,
+                        SectionFlags::ALLOC | SectionFlags::LINK_ORDER,
+                        addr,
+                        size,
+                    )
+                    .align(4)
+                    .link(".text"),
+                ),
+                (PT_MIPS_REGINFO, Some(Machine::Mips | Machine::MipsRs3Le)) => drafts.push(
+                    Draft::new(
+                        ".reginfo",
+                        SHT_MIPS_REGINFO,
+                        SectionFlags::ALLOC,
+                        addr,
+                        size,
+                    )
+                    .align(4)
+                    .entsize(24),
+                ),
+                (PT_MIPS_ABIFLAGS, Some(Machine::Mips | Machine::MipsRs3Le)) => drafts.push(
+                    Draft::new(
+                        ".MIPS.abiflags",
+                        SHT_MIPS_ABIFLAGS,
+                        SectionFlags::ALLOC,
+                        addr,
+                        size,
+                    )
+                    .align(8)
+                    .entsize(24),
+                ),
+                _ => {}
+            }
+        }
+        drafts
+    }
+
+    /// Splits a `PT_NOTE` segment into one section per note, named like the linker does
+    fn note_sections(&self, ph: &ProgramHeader64) -> Vec<Draft> {
+        let Some(data) = self.segment_data(ph) else {
+            return Vec::new();
+        };
+        let align = if ph.p_align == 8 { 8 } else { 4 };
+        let pad = |n: u64| n.next_multiple_of(align);
+        let mut drafts = Vec::new();
+        let mut pos = 0u64;
+        while let (Some(namesz), Some(descsz), Some(n_type)) = (
+            self.read_u32(data, pos as usize),
+            self.read_u32(data, pos as usize + 4),
+            self.read_u32(data, pos as usize + 8),
+        ) {
+            let name_start = pos as usize + SIZEOF_NHDR;
+            let owner = data.get(name_start..name_start + namesz as usize);
+            let size = pad(pad(SIZEOF_NHDR as u64 + namesz as u64) + descsz as u64);
+            let name = match (owner, n_type) {
+                (Some(b"GNU\0"), NT_GNU_BUILD_ID) => ".note.gnu.build-id",
+                (Some(b"GNU\0"), NT_GNU_ABI_TAG) => ".note.ABI-tag",
+                (Some(b"GNU\0"), NT_GNU_PROPERTY_TYPE_0) => ".note.gnu.property",
+                _ => ".note",
+            };
+            let Some(addr) = ph.p_vaddr.checked_add(pos) else {
+                break;
+            };
+            if pos + size > data.len() as u64 {
+                break;
+            }
+            drafts.push(Draft::new(name, SHT_NOTE, SectionFlags::ALLOC, addr, size).align(align));
+            pos += size;
+        }
+        drafts
+    }
+
+    /// Finds `.eh_frame` through `.eh_frame_hdr` and walks its entries up to the zero
+    /// terminator or the end of the segment
+    fn eh_frame_section(&self) -> Option<Draft> {
+        let addr = self.eh_frame_hdr().ok()?.eh_frame_ptr;
+        let offset = self.vaddr_to_offset(addr)?;
+        let ph = self
+            .program_headers
+            .iter()
+            .filter(|ph| ph.p_type == PT_LOAD)
+            .find(|ph| offset >= ph.p_offset && offset - ph.p_offset < ph.p_filesz)?;
+        let data = self.bytes_at(offset, ph.p_filesz - (offset - ph.p_offset))?;
+        let mut pos = 0usize;
+        while let Some(len) = self.read_u32(data, pos) {
+            let next = match len {
+                0 => {
+                    pos += 4;
+                    break;
+                }
+                0xffff_ffff => self
+                    .read_u64(data, pos + 4)
+                    .and_then(|l| (pos as u64 + 12).checked_add(l)),
+                len => Some(pos as u64 + 4 + len as u64),
+            };
+            match next {
+                Some(next) if next <= data.len() as u64 => pos = next as usize,
+                _ => break,
+            }
+        }
+        let flags = self.data_flags(addr);
+        Some(
+            Draft::new(".eh_frame", SHT_PROGBITS, flags, addr, pos as u64)
+                .align(self.addr_size() as u64),
+        )
+    }
+
+    /// Sections described by `PT_DYNAMIC` entries
+    fn dynamic_sections(&self) -> Vec<Draft> {
+        let dynamic = self.dynamic();
+        if dynamic.is_empty() {
+            return Vec::new();
+        }
+        let value = |tag: i64| dynamic.iter().find(|d| d.d_tag == tag).map(|d| d.d_val);
+        let addr_size = self.addr_size() as u64;
+        let mut drafts = Vec::new();
+
+        if let (Some(addr), Some(size)) = (value(DT_STRTAB), value(DT_STRSZ)) {
+            drafts.push(Draft::new(
+                ".dynstr",
+                SHT_STRTAB,
+                SectionFlags::ALLOC,
+                addr,
+                size,
+            ));
+        }
+        let syment = value(DT_SYMENT).unwrap_or(if self.is_64() { 24 } else { 16 });
+        let count = self.dynsym_count(&value);
+        let sized = |count: Option<u64>, entsize: u64| count?.checked_mul(entsize);
+        if let (Some(addr), Some(count), Some(size)) =
+            (value(DT_SYMTAB), count, sized(count, syment))
+        {
+            let mut d = Draft::new(".dynsym", SHT_DYNSYM, SectionFlags::ALLOC, addr, size)
+                .align(addr_size)
+                .entsize(syment)
+                .link(".dynstr");
+            d.info = self.first_global(addr, count, syment);
+            drafts.push(d);
+        }
+        if let (Some(addr), Some(count)) = (value(DT_HASH), count) {
+            let nbucket = self.read_at(addr, 0).unwrap_or_default();
+            if let Some(size) = sized((2 + nbucket).checked_add(count), 4) {
+                drafts.push(
+                    Draft::new(".hash", SHT_HASH, SectionFlags::ALLOC, addr, size)
+                        .align(addr_size)
+                        .entsize(4)
+                        .link(".dynsym"),
+                );
+            }
+        }
+        if let (Some(addr), Some(count)) = (value(DT_GNU_HASH), count) {
+            if let Some(size) = self.gnu_hash_size(addr, count) {
+                drafts.push(
+                    Draft::new(".gnu.hash", SHT_GNU_HASH, SectionFlags::ALLOC, addr, size)
+                        .align(addr_size)
+                        .link(".dynsym"),
+                );
+            }
+        }
+        if let (Some(addr), Some(size)) = (value(DT_VERSYM), sized(count, 2)) {
+            drafts.push(
+                Draft::new(
+                    ".gnu.version",
+                    SHT_GNU_VERSYM,
+                    SectionFlags::ALLOC,
+                    addr,
+                    size,
+                )
+                .align(2)
+                .entsize(2)
+                .link(".dynsym"),
+            );
+        }
+        for (name, sh_type, tag, num, entry, aux_size) in [
+            (
+                ".gnu.version_d",
+                SHT_GNU_VERDEF,
+                DT_VERDEF,
+                DT_VERDEFNUM,
+                SIZEOF_VERDEF,
+                8,
+            ),
+            (
+                ".gnu.version_r",
+                SHT_GNU_VERNEED,
+                DT_VERNEED,
+                DT_VERNEEDNUM,
+                SIZEOF_VERNEED,
+                16,
+            ),
+        ] {
+            let (Some(addr), Some(num)) = (value(tag), value(num)) else {
+                continue;
+            };
+            let size = self.version_chain_size(addr, num, entry as u64, aux_size, sh_type);
+            let mut d = Draft::new(name, sh_type, SectionFlags::ALLOC, addr, size)
+                .align(addr_size)
+                .link(".dynstr");
+            d.info = num as u32;
+            drafts.push(d);
+        }
+
+        let jmprel = value(DT_JMPREL);
+        let is_rela = value(DT_PLTREL).map_or(value(DT_RELA).is_some(), |t| t == DT_RELA as u64);
+        for (name, sh_type, tag, size_tag, ent_tag, default_ent) in [
+            (
+                ".rela.dyn",
+                SHT_RELA,
+                DT_RELA,
+                DT_RELASZ,
+                DT_RELAENT,
+                3 * addr_size,
+            ),
+            (
+                ".rel.dyn",
+                SHT_REL,
+                DT_REL,
+                DT_RELSZ,
+                DT_RELENT,
+                2 * addr_size,
+            ),
+            (
+                ".relr.dyn",
+                SHT_RELR,
+                DT_RELR,
+                DT_RELRSZ,
+                DT_RELRENT,
+                addr_size,
+            ),
+        ] {
+            let (Some(addr), Some(mut size)) = (value(tag), value(size_tag)) else {
+                continue;
+            };
+            // Some linkers let DT_RELASZ cover the PLT relocations that follow
+            if let Some(plt) = jmprel.filter(|&j| j > addr && j - addr < size) {
+                size = plt - addr;
+            }
+            let mut d = Draft::new(name, sh_type, SectionFlags::ALLOC, addr, size)
+                .align(addr_size)
+                .entsize(value(ent_tag).unwrap_or(default_ent));
+            if sh_type != SHT_RELR {
+                d = d.link(".dynsym");
+            }
+            drafts.push(d);
+        }
+        if let (Some(addr), Some(size)) = (jmprel, value(DT_PLTRELSZ)) {
+            let (name, sh_type, entsize) = if is_rela {
+                (".rela.plt", SHT_RELA, 3 * addr_size)
+            } else {
+                (".rel.plt", SHT_REL, 2 * addr_size)
+            };
+            let mut d = Draft::new(
+                name,
+                sh_type,
+                SectionFlags::ALLOC | SectionFlags::INFO_LINK,
+                addr,
+                size,
+            )
+            .align(addr_size)
+            .entsize(entsize)
+            .link(".dynsym");
+            d.info_link = Some(".got.plt");
+            drafts.push(d);
+        }
+
+        for (name, sh_type, tag, size_tag) in [
+            (
+                ".preinit_array",
+                SHT_PREINIT_ARRAY,
+                DT_PREINIT_ARRAY,
+                DT_PREINIT_ARRAYSZ,
+            ),
+            (
+                ".init_array",
+                SHT_INIT_ARRAY,
+                DT_INIT_ARRAY,
+                DT_INIT_ARRAYSZ,
+            ),
+            (
+                ".fini_array",
+                SHT_FINI_ARRAY,
+                DT_FINI_ARRAY,
+                DT_FINI_ARRAYSZ,
+            ),
+        ] {
+            if let (Some(addr), Some(size)) = (value(tag), value(size_tag)) {
+                drafts.push(
+                    Draft::new(
+                        name,
+                        sh_type,
+                        SectionFlags::ALLOC | SectionFlags::WRITE,
+                        addr,
+                        size,
+                    )
+                    .align(addr_size)
+                    .entsize(addr_size),
+                );
+            }
+        }
+        drafts.extend(self.got_sections(&value));
+        drafts
+    }
+
+    /// `.got` and `.got.plt`. MIPS records both layouts in dynamic entries, elsewhere
+    /// `.got.plt` holds three reserved words plus one per PLT relocation and `.got` is
+    /// the rest of the `PT_GNU_RELRO` range after `.dynamic`.
+    fn got_sections(&self, value: &dyn Fn(i64) -> Option<u64>) -> Vec<Draft> {
+        let addr_size = self.addr_size() as u64;
+        let machine = Machine::try_from(self.machine()).ok();
+        let wa = SectionFlags::ALLOC | SectionFlags::WRITE;
+        let mut drafts = Vec::new();
+        if let Some(Machine::Mips | Machine::MipsRs3Le) = machine {
+            if let (Some(addr), Some(local), Some(symtabno), Some(gotsym)) = (
+                value(DT_PLTGOT),
+                value(DT_MIPS_LOCAL_GOTNO),
+                value(DT_MIPS_SYMTABNO),
+                value(DT_MIPS_GOTSYM),
+            ) {
+                let size = local
+                    .checked_add(symtabno)
+                    .and_then(|n| n.saturating_sub(gotsym).checked_mul(addr_size));
+                if let Some(size) = size {
+                    let flags = wa | SectionFlags::from_bits_retain(SHF_MIPS_GPREL);
+                    drafts.push(Draft::new(".got", SHT_PROGBITS, flags, addr, size).align(16));
+                }
+            }
+            // The PLT GOT starts with two words reserved for the dynamic linker
+            if let (Some(addr), Some(size)) = (value(DT_MIPS_PLTGOT), value(DT_PLTRELSZ)) {
+                let slots = size / (2 * addr_size);
+                drafts.push(
+                    Draft::new(".got.plt", SHT_PROGBITS, wa, addr, (2 + slots) * addr_size)
+                        .align(addr_size),
+                );
+            }
+            return drafts;
+        }
+        let mut got_plt = None;
+        if let (
+            Some(addr),
+            Some(Machine::I386 | Machine::X86_64 | Machine::Arm | Machine::Aarch64),
+        ) = (value(DT_PLTGOT), machine)
+        {
+            let relent = if value(DT_PLTREL) == Some(DT_RELA as u64) {
+                3 * addr_size
+            } else {
+                2 * addr_size
+            };
+            let slots = value(DT_PLTRELSZ).map_or(0, |s| s / relent);
+            got_plt = Some(addr);
+            drafts.push(
+                Draft::new(".got.plt", SHT_PROGBITS, wa, addr, (3 + slots) * addr_size)
+                    .align(addr_size)
+                    .entsize(addr_size),
+            );
+        }
+        let dynamic = self
+            .program_headers
+            .iter()
+            .find(|ph| ph.p_type == PT_DYNAMIC);
+        let relro = self
+            .program_headers
+            .iter()
+            .find(|ph| ph.p_type == PT_GNU_RELRO);
+        let bounds = dynamic.zip(relro).and_then(|(dynamic, relro)| {
+            let start = dynamic
+                .p_vaddr
+                .checked_add(dynamic.p_memsz)?
+                .checked_next_multiple_of(addr_size)?;
+            Some((
+                dynamic,
+                relro,
+                start,
+                relro.p_vaddr.checked_add(relro.p_filesz)?,
+            ))
+        });
+        if let Some((dynamic, relro, start, relro_end)) = bounds {
+            // lld pads PT_GNU_RELRO to a page boundary, only file backed bytes can be `.got`
+            let mut end = relro_end.min(self.load_end(dynamic.p_vaddr).unwrap_or_default());
+            if let Some(got_plt) = got_plt.filter(|&g| g >= start) {
+                end = end.min(got_plt);
+            }
+            if dynamic.p_vaddr >= relro.p_vaddr && end > start && end - start < 0x10000 {
+                drafts.push(
+                    Draft::new(".got", SHT_PROGBITS, wa, start, end - start)
+                        .align(addr_size)
+                        .entsize(addr_size),
+                );
+            }
+        }
+        drafts
+    }
+
+    /// Covers the largest remaining range of each `PT_LOAD` with `.text`, `.rodata` or
+    /// `.data`, and its zero filled tail with `.bss`
+    fn fill_sections(&self, known: &[Draft]) -> Vec<Draft> {
+        let headers_end = self
+            .header
+            .e_phoff
+            .saturating_add(self.phnum() as u64 * self.header.e_phentsize as u64);
+        let mut drafts = Vec::new();
+        for ph in self
+            .program_headers
+            .iter()
+            .filter(|ph| ph.p_type == PT_LOAD)
+        {
+            let flags = ph.flags();
+            let start = if ph.p_offset == 0 {
+                ph.p_vaddr.checked_add(headers_end)
+            } else {
+                Some(ph.p_vaddr)
+            };
+            let (Some(start), Some(end), Some(mem_end)) = (
+                start,
+                ph.p_vaddr.checked_add(ph.p_filesz),
+                ph.p_vaddr.checked_add(ph.p_memsz),
+            ) else {
+                continue;
+            };
+            let mut covered: Vec<(u64, u64)> = known
+                .iter()
+                .filter(|d| d.sh_type != SHT_NOBITS && d.addr < end && d.end() > start)
+                .map(|d| (d.addr, d.end()))
+                .collect();
+            covered.sort();
+            let mut best = (0, 0);
+            let mut pos = start;
+            for (s, e) in covered.into_iter().chain([(end, end)]) {
+                if s > pos && s - pos > best.1 - best.0 {
+                    best = (pos, s);
+                }
+                pos = pos.max(e);
+            }
+            if best.1 - best.0 >= MIN_FILL_SIZE {
+                let draft = if flags.contains(SegmentFlags::X) {
+                    Draft::new(
+                        ".text",
+                        SHT_PROGBITS,
+                        SectionFlags::ALLOC | SectionFlags::EXECINSTR,
+                        best.0,
+                        best.1 - best.0,
+                    )
+                } else if flags.contains(SegmentFlags::W) {
+                    Draft::new(
+                        ".data",
+                        SHT_PROGBITS,
+                        SectionFlags::ALLOC | SectionFlags::WRITE,
+                        best.0,
+                        best.1 - best.0,
+                    )
+                } else {
+                    Draft::new(
+                        ".rodata",
+                        SHT_PROGBITS,
+                        SectionFlags::ALLOC,
+                        best.0,
+                        best.1 - best.0,
+                    )
+                };
+                drafts.push(draft.align(16));
+            }
+            let relro_padding = self.program_headers.iter().any(|r| {
+                r.p_type == PT_GNU_RELRO
+                    && r.p_vaddr <= end
+                    && r.p_vaddr.saturating_add(r.p_memsz) >= mem_end
+            });
+            if ph.p_memsz > ph.p_filesz && flags.contains(SegmentFlags::W) && !relro_padding {
+                drafts.push(
+                    Draft::new(
+                        ".bss",
+                        SHT_NOBITS,
+                        SectionFlags::ALLOC | SectionFlags::WRITE,
+                        end,
+                        ph.p_memsz - ph.p_filesz,
+                    )
+                    .align(16),
+                );
+            }
+        }
+        drafts
+    }
+
+    /// Reads a `u32` at a virtual address
+    fn read_at(&self, addr: u64, delta: u64) -> Option<u64> {
+        let offset = self.vaddr_to_offset(addr.checked_add(delta)?)?;
+        self.read_u32(self.bytes_at(offset, 4)?, 0).map(u64::from)
+    }
+
+    /// Number of `.dynsym` entries, from `DT_MIPS_SYMTABNO`, `DT_HASH` or `DT_GNU_HASH`
+    fn dynsym_count(&self, value: &dyn Fn(i64) -> Option<u64>) -> Option<u64> {
+        if let Some(count) = value(DT_MIPS_SYMTABNO) {
+            return Some(count);
+        }
+        if let Some(addr) = value(DT_HASH) {
+            return self.read_at(addr, 4);
+        }
+        let addr = value(DT_GNU_HASH)?;
+        let nbuckets = self.read_at(addr, 0)?;
+        let symoffset = self.read_at(addr, 4)?;
+        let bloom_size = self.read_at(addr, 8)?;
+        let buckets = addr.checked_add(16 + bloom_size * self.addr_size() as u64)?;
+        let chains = buckets.checked_add(nbuckets * 4)?;
+        let mut last = (0..nbuckets)
+            .filter_map(|i| self.read_at(buckets, i * 4))
+            .max()
+            .unwrap_or_default();
+        if last < symoffset {
+            return Some(symoffset);
+        }
+        // Walk the last chain to the entry with the stop bit set
+        while self.read_at(chains, (last - symoffset) * 4)? & 1 == 0 {
+            last += 1;
+        }
+        Some(last + 1)
+    }
+
+    fn gnu_hash_size(&self, addr: u64, count: u64) -> Option<u64> {
+        let nbuckets = self.read_at(addr, 0)?;
+        let symoffset = self.read_at(addr, 4)?;
+        let bloom_size = self.read_at(addr, 8)?;
+        count
+            .saturating_sub(symoffset)
+            .checked_mul(4)?
+            .checked_add(16 + bloom_size * self.addr_size() as u64 + nbuckets * 4)
+    }
+
+    /// Index of the first non-local `.dynsym` entry, stored in the section's `sh_info`.
+    /// Only the entries inside the file backed part of the containing `PT_LOAD` are read.
+    fn first_global(&self, addr: u64, count: u64, syment: u64) -> u32 {
+        let info_offset = if self.is_64() { 4 } else { 12 };
+        let readable = self
+            .load_end(addr)
+            .map_or(0, |end| end.saturating_sub(addr) / syment.max(1));
+        (0..count.min(readable))
+            .find(|i| {
+                let bind = addr
+                    .checked_add(i * syment + info_offset)
+                    .and_then(|at| self.vaddr_to_offset(at))
+                    .and_then(|o| self.bytes_at(o, 1))
+                    .map_or(0, |b| b[0] >> 4);
+                bind != STB_LOCAL
+            })
+            .unwrap_or(count) as u32
+    }
+
+    /// Walks `num` verdef or verneed entries and returns the end of the furthest auxiliary
+    /// entry relative to `addr`
+    fn version_chain_size(
+        &self,
+        addr: u64,
+        num: u64,
+        entry: u64,
+        aux_size: u64,
+        sh_type: u32,
+    ) -> u64 {
+        // vd_cnt/vn_cnt, vd_aux/vn_aux and vd_next/vn_next, and the vda_next/vna_next field
+        let (cnt, aux, next, aux_next) = if sh_type == SHT_GNU_VERDEF {
+            (6, 12, 16, 4)
+        } else {
+            (2, 8, 12, 12)
+        };
+        let read_u16 = |at: u64| {
+            let offset = self.vaddr_to_offset(at)?;
+            self.read_u16(self.bytes_at(offset, 2)?, 0).map(u64::from)
+        };
+        let mut end = 0;
+        let mut pos = addr;
+        for _ in 0..num {
+            end = end.max((pos - addr).saturating_add(entry));
+            let (Some(n), Some(a), Some(nx)) = (
+                pos.checked_add(cnt).and_then(read_u16),
+                self.read_at(pos, aux),
+                self.read_at(pos, next),
+            ) else {
+                break;
+            };
+            let Some(mut aux_pos) = pos.checked_add(a) else {
+                break;
+            };
+            for _ in 0..n {
+                end = end.max((aux_pos - addr).saturating_add(aux_size));
+                match self.read_at(aux_pos, aux_next) {
+                    Some(0) | None => break,
+                    Some(step) => match aux_pos.checked_add(step) {
+                        Some(next) => aux_pos = next,
+                        None => break,
+                    },
+                }
+            }
+            match pos.checked_add(nx) {
+                Some(next) if nx != 0 => pos = next,
+                _ => break,
+            }
+        }
+        end
+    }
+}
+
+fn push_name(table: &mut Vec<u8>, name: &str) -> u32 {
+    let offset = table.len() as u32;
+    table.extend(name.as_bytes());
+    table.push(0);
+    offset
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Clears the section header fields of the ELF header
+    fn strip_section_headers(elf: &ElfFile) -> ElfFile {
+        let mut header = elf.header;
+        header.e_shoff = 0;
+        header.e_shnum = 0;
+        header.e_shstrndx = 0;
+        let raw = ElfWriter::new(elf.is_64(), elf.is_big_endian()).header(&header);
+        let mut data = elf.data().to_vec();
+        data[..raw.len()].copy_from_slice(&raw);
+        ElfFile::parse(data).unwrap()
+    }
+
+    #[test]
+    fn test_reconstruct_matches_linker() {
+        for path in [
+            "tests/bin/plt.x86_64",
+            "tests/bin/plt.i386",
+            "tests/bin/plt.arm",
+            "tests/bin/plt.aarch64",
+            "tests/bin/pltexe.mips",
+            "tests/bin/libdemo.v2.so",
+        ] {
+            let orig = ElfFile::open(path).unwrap();
+            let bare = strip_section_headers(&orig);
+            assert!(bare.section_headers.is_empty());
+            let sections = bare.reconstruct_sections();
+            for name in [
+                ".dynsym",
+                ".dynstr",
+                ".dynamic",
+                ".gnu.hash",
+                ".hash",
+                ".gnu.version",
+                ".gnu.version_d",
+                ".gnu.version_r",
+                ".rela.dyn",
+                ".rela.plt",
+                ".rel.plt",
+                ".eh_frame",
+                ".note.gnu.build-id",
+            ] {
+                let (Some(want), Some(got)) = (
+                    orig.section_by_name(name),
+                    sections.iter().find(|s| s.name == name),
+                ) else {
+                    continue;
+                };
+                let got = &got.header;
+                assert_eq!(
+                    (
+                        got.sh_type,
+                        got.sh_addr,
+                        got.sh_offset,
+                        got.sh_size,
+                        got.sh_link != 0,
+                    ),
+                    (
+                        want.sh_type,
+                        want.sh_addr,
+                        want.sh_offset,
+                        want.sh_size,
+                        want.sh_link != 0,
+                    ),
+                    "{} {}",
+                    path,
+                    name
+                );
+                let name_of = |i: u32| orig.section_name(&orig.section_headers[i as usize]);
+                assert_eq!(sections[got.sh_link as usize].name, name_of(want.sh_link));
+                if got.sh_flags & SectionFlags::INFO_LINK.bits() != 0 {
+                    assert_eq!(sections[got.sh_info as usize].name, name_of(want.sh_info));
+                } else {
+                    assert_eq!(got.sh_info, want.sh_info, "{} {}", path, name);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_reconstruct_overflow() {
+        let bare = strip_section_headers(&ElfFile::open("tests/bin/plt.x86_64").unwrap());
+        let dynamic = bare
+            .program_headers
+            .iter()
+            .find(|ph| ph.p_type == PT_DYNAMIC)
+            .unwrap()
+            .p_offset as usize;
+        let mut data = bare.data().to_vec();
+        for entry in data[dynamic..].chunks_exact_mut(16).take(17) {
+            let tag = i64::from_le_bytes(entry[..8].try_into().unwrap());
+            if tag == DT_NEEDED {
+                entry[..8].copy_from_slice(&DT_MIPS_SYMTABNO.to_le_bytes());
+            }
+            if matches!(tag, DT_NEEDED | DT_RELASZ | DT_VERNEEDNUM) {
+                entry[8..].copy_from_slice(&u64::MAX.to_le_bytes());
+            }
+        }
+        let sections = ElfFile::parse(data).unwrap().reconstruct_sections();
+        let find = |name: &str| sections.iter().find(|s| s.name == name);
+        // The symbol count overflows every size derived from it
+        assert!(find(".dynsym").is_none() && find(".gnu.version").is_none());
+        assert_eq!(find(".rela.dyn").unwrap().header.sh_size, 0x18);
+        assert!(find(".gnu.version_r").is_some() && find(".dynamic").is_some());
+    }
+
+    #[test]
+    fn test_write_back() {
+        let bare = strip_section_headers(&ElfFile::open("tests/bin/libdemo.v2.so").unwrap());
+        let rebuilt = ElfFile::parse(
+            bare.with_section_headers(&bare.reconstruct_sections())
+                .unwrap(),
+        )
+        .unwrap();
+        assert!(rebuilt
+            .validate()
+            .iter()
+            .all(|f| f.severity != crate::Severity::Error));
+        assert_eq!(
+            rebuilt.section_name(&rebuilt.section_headers[rebuilt.shstrndx()]),
+            ".shstrtab"
+        );
+        assert_eq!(rebuilt.soname().as_deref(), Some("libdemo.so.1"));
+        let names: Vec<String> = rebuilt.dynsym().into_iter().map(|s| s.name).collect();
+        assert!(names.contains(&"api_add".to_string()));
+        assert_eq!(rebuilt.dynsym_versions().len(), names.len());
+        assert!(!rebuilt.version_definitions().is_empty());
+    }
+}
@@ -0,0 +1,241 @@
+use crate::elf_file::ElfFile;
+use crate::elf_types::{Machine, SHT_REL, SHT_RELA};
+
+pub const SIZEOF_REL32: usize = 8;
+pub const SIZEOF_RELA32: usize = 12;
+pub const SIZEOF_REL64: usize = 16;
+pub const SIZEOF_RELA64: usize = 24;
+
+pub const R_X86_64_NONE: u32 = 0;
+pub const R_X86_64_64: u32 = 1;
+pub const R_X86_64_PC32: u32 = 2;
+pub const R_X86_64_PLT32: u32 = 4;
+pub const R_X86_64_COPY: u32 = 5;
+pub const R_X86_64_GLOB_DAT: u32 = 6;
+pub const R_X86_64_JUMP_SLOT: u32 = 7;
+pub const R_X86_64_RELATIVE: u32 = 8;
+pub const R_X86_64_IRELATIVE: u32 = 37;
+
+pub const R_386_NONE: u32 = 0;
+pub const R_386_32: u32 = 1;
+pub const R_386_PC32: u32 = 2;
+pub const R_386_COPY: u32 = 5;
+pub const R_386_GLOB_DAT: u32 = 6;
+pub const R_386_JMP_SLOT: u32 = 7;
+pub const R_386_RELATIVE: u32 = 8;
+pub const R_386_IRELATIVE: u32 = 42;
+
+pub const R_ARM_NONE: u32 = 0;
+pub const R_ARM_ABS32: u32 = 2;
+pub const R_ARM_COPY: u32 = 20;
+pub const R_ARM_GLOB_DAT: u32 = 21;
+pub const R_ARM_JUMP_SLOT: u32 = 22;
+pub const R_ARM_RELATIVE: u32 = 23;
+pub const R_ARM_IRELATIVE: u32 = 160;
+
+pub const R_AARCH64_NONE: u32 = 0;
+pub const R_AARCH64_ABS64: u32 = 257;
+pub const R_AARCH64_COPY: u32 = 1024;
+pub const R_AARCH64_GLOB_DAT: u32 = 1025;
+pub const R_AARCH64_JUMP_SLOT: u32 = 1026;
+pub const R_AARCH64_RELATIVE: u32 = 1027;
+pub const R_AARCH64_IRELATIVE: u32 = 1032;
+
+pub const R_MIPS_NONE: u32 = 0;
+pub const R_MIPS_32: u32 = 2;
+pub const R_MIPS_REL32: u32 = 3;
+pub const R_MIPS_GLOB_DAT: u32 = 51;
+pub const R_MIPS_COPY: u32 = 126;
+pub const R_MIPS_JUMP_SLOT: u32 = 127;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Rel32 {
+    pub r_offset: u32,
+    pub r_info: u32,
+}
+
+unsafe impl plain::Plain for Rel32 {}
+
+impl Rel32 {
+    fn fix_rel(rel: &mut Rel32, bit: u8) -> &mut Rel32 {
+        if bit == 2 {
+            rel.r_offset = rel.r_offset.to_be();
+            rel.r_info = rel.r_info.to_be();
+        }
+        rel
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Rela32 {
+    pub r_offset: u32,
+    pub r_info: u32,
+    pub r_addend: i32,
+}
+
+unsafe impl plain::Plain for Rela32 {}
+
+impl Rela32 {
+    fn fix_rela(rel: &mut Rela32, bit: u8) -> &mut Rela32 {
+        if bit == 2 {
+            rel.r_offset = rel.r_offset.to_be();
+            rel.r_info = rel.r_info.to_be();
+            rel.r_addend = rel.r_addend.to_be();
+        }
+        rel
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Rel64 {
+    pub r_offset: u64,
+    pub r_info: u64,
+}
+
+unsafe impl plain::Plain for Rel64 {}
+
+impl Rel64 {
+    fn fix_rel(rel: &mut Rel64, bit: u8) -> &mut Rel64 {
+        if bit == 2 {
+            rel.r_offset = rel.r_offset.to_be();
+            rel.r_info = rel.r_info.to_be();
+        }
+        rel
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Rela64 {
+    pub r_offset: u64,
+    pub r_info: u64,
+    pub r_addend: i64,
+}
+
+unsafe impl plain::Plain for Rela64 {}
+
+impl Rela64 {
+    fn fix_rela(rel: &mut Rela64, bit: u8) -> &mut Rela64 {
+        if bit == 2 {
+            rel.r_offset = rel.r_offset.to_be();
+            rel.r_info = rel.r_info.to_be();
+            rel.r_addend = rel.r_addend.to_be();
+        }
+        rel
+    }
+}
+
+/// A relocation entry with its `r_info` split into symbol index and type.
+///
+/// `addend` is `None` for `SHT_REL` entries whose addend is stored at the relocated place.
+/// For MIPS64 only the first of the three packed relocation types is reported.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Relocation {
+    pub offset: u64,
+    pub sym: u32,
+    pub r_type: u32,
+    pub addend: Option<i64>,
+}
+
+impl ElfFile {
+    /// Parses the relocation table stored in section `index` (`SHT_REL` or `SHT_RELA`).
+    ///
+    /// Returns an empty list if the section is not a relocation table or lies outside the
+    /// file. The symbol indices refer to the symbol table linked through `sh_link`.
+    pub fn relocations(&self, index: usize) -> Vec<Relocation> {
+        let Some(sh) = self.section_headers.get(index) else {
+            return Vec::new();
+        };
+        let rela = match sh.sh_type {
+            SHT_RELA => true,
+            SHT_REL => false,
+            _ => return Vec::new(),
+        };
+        let Some(data) = self.section_data(sh) else {
+            return Vec::new();
+        };
+
+        let bit = self.header.e_ident[0x5];
+        let min_size = match (self.is_64(), rela) {
+            (true, true) => SIZEOF_RELA64,
+            (true, false) => SIZEOF_REL64,
+            (false, true) => SIZEOF_RELA32,
+            (false, false) => SIZEOF_REL32,
+        };
+        let entsize = match sh.sh_entsize as usize {
+            0 => min_size,
+            n => n.max(min_size),
+        };
+        let mips64 = self.is_64() && self.header.machine() == Ok(Machine::Mips);
+        data.chunks_exact(entsize)
+            .map(|raw| {
+                let (offset, info, addend) = match (self.is_64(), rela) {
+                    (true, true) => {
+                        let mut r = Rela64::default();
+                        plain::copy_from_bytes(&mut r, &raw[..min_size]).unwrap_or_default();
+                        let r = *Rela64::fix_rela(&mut r, bit);
+                        (r.r_offset, r.r_info, r.r_addend)
+                    }
+                    (true, false) => {
+                        let mut r = Rel64::default();
+                        plain::copy_from_bytes(&mut r, &raw[..min_size]).unwrap_or_default();
+                        let r = *Rel64::fix_rel(&mut r, bit);
+                        (r.r_offset, r.r_info, 0)
+                    }
+                    (false, true) => {
+                        let mut r = Rela32::default();
+                        plain::copy_from_bytes(&mut r, &raw[..min_size]).unwrap_or_default();
+                        let r = *Rela32::fix_rela(&mut r, bit);
+                        (r.r_offset as u64, r.r_info as u64, r.r_addend as i64)
+                    }
+                    (false, false) => {
+                        let mut r = Rel32::default();
+                        plain::copy_from_bytes(&mut r, &raw[..min_size]).unwrap_or_default();
+                        let r = *Rel32::fix_rel(&mut r, bit);
+                        (r.r_offset as u64, r.r_info as u64, 0)
+                    }
+                };
+                let (sym, r_type) = match (self.is_64(), mips64) {
+                    // MIPS64 stores a 32-bit symbol followed by four single byte fields
+                    (true, true) if !self.is_big_endian() => (info as u32, (info >> 56) as u32),
+                    (true, true) => ((info >> 32) as u32, (info & 0xff) as u32),
+                    (true, false) => ((info >> 32) as u32, info as u32),
+                    (false, _) => ((info >> 8) as u32, (info & 0xff) as u32),
+                };
+                Relocation {
+                    offset,
+                    sym,
+                    r_type,
+                    addend: rela.then_some(addend),
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_x64_rela() {
+        let elf = ElfFile::open("/bin/ls").unwrap();
+        let index = elf
+            .section_headers
+            .iter()
+            .position(|sh| elf.section_name(sh) == ".rela.plt")
+            .unwrap();
+        let relocs = elf.relocations(index);
+        assert_eq!(
+            relocs.len() as u64,
+            elf.section_headers[index].sh_size / SIZEOF_RELA64 as u64
+        );
+        let dynsym = elf.dynsym();
+        assert!(relocs.iter().all(|r| r.r_type == R_X86_64_JUMP_SLOT
+            && r.addend == Some(0)
+            && !dynsym[r.sym as usize].name.is_empty()));
+    }
+}
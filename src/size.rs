@@ -0,0 +1,342 @@
+use std::collections::HashSet;
+use std::fmt::Write;
+
+use crate::elf_file::ElfFile;
+use crate::elf_flags::{SectionFlags, SegmentFlags};
+use crate::elf_types::{
+    PT_LOAD, SHN_COMMON, SHT_NOBITS, SHT_NULL, SHT_REL, SHT_RELA, SHT_SYMTAB, SHT_SYMTAB_SHNDX,
+};
+use crate::symbols::{STT_FILE, STT_SECTION};
+use crate::SectionHeader64;
+
+/// Column header of [`SizeReport::berkeley_row`], as printed by `size`
+pub const BERKELEY_HEADER: &str = "   text\t   data\t    bss\t    dec\t    hex\tfilename";
+
+/// The Berkeley `size` bucket memory is accounted to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SizeClass {
+    /// Code and read-only data, stored in flash on embedded targets
+    Text,
+    /// Initialized writable data, stored in flash and copied to RAM
+    Data,
+    /// Zero initialized data, only taking up RAM
+    Bss,
+}
+
+impl SizeClass {
+    /// Classifies an allocated section like binutils does, `None` for non-alloc sections
+    pub fn of_section(sh: &SectionHeader64) -> Option<SizeClass> {
+        let flags = sh.flags();
+        if !flags.contains(SectionFlags::ALLOC) {
+            None
+        } else if flags.contains(SectionFlags::EXECINSTR) || !flags.contains(SectionFlags::WRITE) {
+            Some(SizeClass::Text)
+        } else if sh.sh_type != SHT_NOBITS {
+            Some(SizeClass::Data)
+        } else {
+            Some(SizeClass::Bss)
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SizeClass::Text => "text",
+            SizeClass::Data => "data",
+            SizeClass::Bss => "bss",
+        }
+    }
+}
+
+/// A row of the SysV (`size -A`) layout
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SizeEntry {
+    pub name: String,
+    pub size: u64,
+    pub addr: u64,
+}
+
+/// A sized symbol and the bucket its section belongs to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolSize {
+    pub name: String,
+    pub addr: u64,
+    pub size: u64,
+    pub class: SizeClass,
+}
+
+/// Memory footprint of an image, see [`ElfFile::size_report`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SizeReport {
+    pub text: u64,
+    pub data: u64,
+    pub bss: u64,
+    /// `true` if the totals were taken from `PT_LOAD` segments because the file has no
+    /// section headers
+    pub from_segments: bool,
+    /// Sections, or `loadN` entries for segments, as listed by `size -A`
+    pub entries: Vec<SizeEntry>,
+    /// Sized symbols of allocated sections, largest first
+    pub symbols: Vec<SymbolSize>,
+}
+
+impl SizeReport {
+    pub fn total(&self) -> u64 {
+        self.text.saturating_add(self.data).saturating_add(self.bss)
+    }
+
+    /// Formats the totals like a line of `size` output, following [`BERKELEY_HEADER`]
+    pub fn berkeley_row(&self, filename: &str) -> String {
+        format!(
+            "{:>7}\t{:>7}\t{:>7}\t{:>7}\t{:>7x}\t{}",
+            self.text,
+            self.data,
+            self.bss,
+            self.total(),
+            self.total(),
+            filename
+        )
+    }
+
+    /// Formats the report like `size filename`
+    pub fn berkeley(&self, filename: &str) -> String {
+        format!("{}\n{}\n", BERKELEY_HEADER, self.berkeley_row(filename))
+    }
+
+    /// Formats the report like `size -A filename`
+    pub fn sysv(&self, filename: &str) -> String {
+        let width = |it: &mut dyn Iterator<Item = usize>, min: usize| it.fold(min, usize::max);
+        let total = (self.entries.iter().map(|e| e.size)).fold(0, u64::saturating_add);
+        let name_w = width(&mut self.entries.iter().map(|e| e.name.len()), 7);
+        let size_w = width(
+            &mut self
+                .entries
+                .iter()
+                .map(|e| e.size.to_string().len())
+                .chain([total.to_string().len()]),
+            4,
+        );
+        let addr_w = width(
+            &mut self.entries.iter().map(|e| e.addr.to_string().len()),
+            4,
+        );
+
+        let mut s = format!("{}  :\n", filename);
+        let _ = writeln!(
+            s,
+            "{:<name_w$}   {:>size_w$}   {:>addr_w$}",
+            "section", "size", "addr"
+        );
+        for e in &self.entries {
+            let _ = writeln!(
+                s,
+                "{:<name_w$}   {:>size_w$}   {:>addr_w$}",
+                e.name, e.size, e.addr
+            );
+        }
+        let _ = write!(s, "{:<name_w$}   {:>size_w$}\n\n\n", "Total", total);
+        s
+    }
+
+    /// Lists the symbols with their share of the total, largest first
+    pub fn symbol_breakdown(&self) -> String {
+        let total = self.total().max(1) as f64;
+        let mut s = String::new();
+        for sym in &self.symbols {
+            let _ = writeln!(
+                s,
+                "{:>10} {:>6.2}% {:<5} {:#010x} {}",
+                sym.size,
+                sym.size as f64 * 100.0 / total,
+                sym.class.as_str(),
+                sym.addr,
+                sym.name
+            );
+        }
+        s
+    }
+}
+
+impl ElfFile {
+    /// Computes the text, data and bss totals of `size` from the allocated sections, or from
+    /// the `PT_LOAD` segments if the file has no section headers
+    pub fn size_report(&self) -> SizeReport {
+        let mut report = SizeReport::default();
+        if self.section_headers.is_empty() {
+            report.from_segments = true;
+            for (i, ph) in self
+                .program_headers
+                .iter()
+                .filter(|ph| ph.p_type == PT_LOAD)
+                .enumerate()
+            {
+                if ph.flags().contains(SegmentFlags::W) {
+                    report.data = report.data.saturating_add(ph.p_filesz);
+                } else {
+                    report.text = report.text.saturating_add(ph.p_filesz);
+                }
+                report.bss = (report.bss).saturating_add(ph.p_memsz.saturating_sub(ph.p_filesz));
+                report.entries.push(SizeEntry {
+                    name: format!("load{}", i),
+                    size: ph.p_memsz,
+                    addr: ph.p_vaddr,
+                });
+            }
+            return report;
+        }
+
+        let symtab_strtab = self
+            .section_headers
+            .iter()
+            .find(|sh| sh.sh_type == SHT_SYMTAB)
+            .map(|sh| sh.sh_link as usize);
+        for (i, sh) in self.section_headers.iter().enumerate() {
+            match SizeClass::of_section(sh) {
+                Some(SizeClass::Text) => report.text = report.text.saturating_add(sh.sh_size),
+                Some(SizeClass::Data) => report.data = report.data.saturating_add(sh.sh_size),
+                Some(SizeClass::Bss) => report.bss = report.bss.saturating_add(sh.sh_size),
+                None => {}
+            }
+            // Symbol tables, string tables and relocations of other sections are not
+            // sections to binutils
+            let is_reloc = (sh.sh_type == SHT_REL || sh.sh_type == SHT_RELA)
+                && !sh.flags().contains(SectionFlags::ALLOC);
+            if matches!(sh.sh_type, SHT_NULL | SHT_SYMTAB | SHT_SYMTAB_SHNDX)
+                || is_reloc
+                || i == self.shstrndx()
+                || Some(i) == symtab_strtab
+            {
+                continue;
+            }
+            report.entries.push(SizeEntry {
+                name: self.section_name(sh).to_string(),
+                size: sh.sh_size,
+                addr: sh.sh_addr,
+            });
+        }
+        report.symbols = self.symbol_sizes();
+        report
+    }
+
+    /// Returns the sized symbols of `.symtab`, or `.dynsym` if there is none, largest first.
+    /// Aliases sharing address and size are counted once.
+    fn symbol_sizes(&self) -> Vec<SymbolSize> {
+        let mut symbols = self.symtab();
+        if symbols.is_empty() {
+            symbols = self.dynsym();
+        }
+        let mut seen = HashSet::new();
+        let mut sizes: Vec<SymbolSize> = symbols
+            .into_iter()
+            .filter(|sym| sym.size != 0 && !matches!(sym.sym_type(), STT_SECTION | STT_FILE))
+            .filter_map(|sym| {
+                let class = if sym.shndx == SHN_COMMON as u32 {
+                    SizeClass::Bss
+                } else if sym.is_defined() {
+                    SizeClass::of_section(self.section_headers.get(sym.shndx as usize)?)?
+                } else {
+                    return None;
+                };
+                Some(SymbolSize {
+                    name: sym.name,
+                    addr: sym.value,
+                    size: sym.size,
+                    class,
+                })
+            })
+            .filter(|sym| seen.insert((sym.addr, sym.size, sym.class)))
+            .collect();
+        sizes.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.name.cmp(&b.name)));
+        sizes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_binutils() {
+        let elf = ElfFile::open("tests/bin/firmware.arm").unwrap();
+        let report = elf.size_report();
+        assert_eq!((report.text, report.data, report.bss), (120, 24, 256));
+        assert_eq!(
+            report.berkeley("tests/bin/firmware.arm"),
+            "   text\t   data\t    bss\t    dec\t    hex\tfilename\n    \
+             120\t     24\t    256\t    400\t    190\ttests/bin/firmware.arm\n"
+        );
+        assert_eq!(
+            report.sysv("fw"),
+            "fw  :\n\
+             section       size        addr\n\
+             .isr_vector     16   134217728\n\
+             .text           88   134217744\n\
+             .rodata         16   134217832\n\
+             .data           24   536870912\n\
+             .bss           256   536870936\n\
+             .comment        94           0\n\
+             Total          494\n\n\n"
+        );
+        let names: Vec<&str> = report.symbols.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names[0], "buffer");
+        assert_eq!(report.symbols[0].class, SizeClass::Bss);
+
+        for (path, sizes) in [
+            ("tests/bin/dd.armel", (574584, 2144, 7456)),
+            ("tests/bin/objdump.mips", (1327684, 256820, 37468)),
+        ] {
+            let report = ElfFile::open(path).unwrap().size_report();
+            assert_eq!((report.text, report.data, report.bss), sizes, "{}", path);
+        }
+        let report = ElfFile::open("tests/bin/debug.o").unwrap().size_report();
+        let names: Vec<&str> = report.entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names[..4], [".text", ".data", ".bss", ".debug_info"]);
+        assert_eq!(report.entries.iter().map(|e| e.size).sum::<u64>(), 649);
+    }
+
+    #[test]
+    fn test_segment_fallback() {
+        let elf = ElfFile::open("tests/bin/firmware.arm").unwrap();
+        let mut data = elf.data().to_vec();
+        data[0x20..0x24].fill(0);
+        data[0x30..0x34].fill(0);
+        let elf = ElfFile::parse(data).unwrap();
+        let report = elf.size_report();
+        assert!(report.from_segments);
+        assert_eq!((report.text, report.data, report.bss), (120, 24, 256));
+        assert_eq!(report.entries.len(), 5);
+        assert!(report.symbols.is_empty());
+    }
+
+    #[test]
+    fn test_oversized_sizes() {
+        let orig = ElfFile::open("tests/bin/dwarf2").unwrap();
+        let bss = orig
+            .section_headers
+            .iter()
+            .position(|sh| orig.section_name(sh) == ".bss")
+            .unwrap();
+        let mut data = orig.data().to_vec();
+        let size = orig.header.e_shoff as usize + bss * 64 + 32;
+        data[size..size + 8].copy_from_slice(&u64::MAX.to_le_bytes());
+        let report = ElfFile::parse(data).unwrap().size_report();
+        assert_eq!(report.bss, u64::MAX);
+        assert_eq!(report.total(), u64::MAX);
+        assert!(report.berkeley("f").contains("ffffffffffffffff"));
+        assert!(report.sysv("f").ends_with(&format!("{}\n\n\n", u64::MAX)));
+
+        // Every loadable segment claims the whole address space
+        let mut data = orig.data().to_vec();
+        data[0x28..0x30].fill(0);
+        data[0x3c..0x3e].fill(0);
+        for (i, ph) in orig.program_headers.iter().enumerate() {
+            if ph.p_type == PT_LOAD {
+                let filesz = orig.header.e_phoff as usize + i * 56 + 32;
+                data[filesz..filesz + 16].fill(0xff);
+            }
+        }
+        let report = ElfFile::parse(data).unwrap().size_report();
+        assert!(report.from_segments);
+        assert_eq!((report.text, report.data), (u64::MAX, u64::MAX));
+        assert_eq!(report.total(), u64::MAX);
+    }
+}
@@ -0,0 +1,398 @@
+use core::fmt;
+use std::ops::Range;
+
+use crate::elf_file::ElfFile;
+use crate::elf_flags::{SectionFlags, SegmentFlags};
+use crate::elf_types::{PT_LOAD, SHT_NOBITS, SHT_NULL};
+
+/// Character encodings recognized by [`ElfFile::strings`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum StringEncoding {
+    /// Printable 7-bit characters and tab, like `strings`
+    Ascii,
+    /// Printable characters of any valid UTF-8 sequence, a superset of `Ascii`
+    Utf8,
+    /// Printable characters below U+3000 in UTF-16 little endian, a superset of
+    /// `strings -e l`
+    Utf16Le,
+    /// Printable characters below U+3000 in UTF-16 big endian, a superset of
+    /// `strings -e b`
+    Utf16Be,
+}
+
+impl fmt::Display for StringEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            StringEncoding::Ascii => "ascii",
+            StringEncoding::Utf8 => "utf-8",
+            StringEncoding::Utf16Le => "utf-16le",
+            StringEncoding::Utf16Be => "utf-16be",
+        })
+    }
+}
+
+/// The parts of a file [`ElfFile::strings`] scans
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StringScope {
+    /// The named sections, or all allocated sections with contents if the list is empty
+    Sections(Vec<String>),
+    /// Allocated sections that are neither writable nor executable, e.g. `.rodata`
+    ReadOnlyData,
+    /// `PT_LOAD` segments that have none of the given flags, e.g. `SegmentFlags::X` for
+    /// non-executable ranges
+    Segments { without: SegmentFlags },
+    /// Every byte of the file
+    File,
+}
+
+/// Parameters for [`ElfFile::strings`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StringsOptions {
+    /// Minimum number of characters of a reported string
+    pub min_len: usize,
+    pub encodings: Vec<StringEncoding>,
+    pub scope: StringScope,
+}
+
+impl Default for StringsOptions {
+    fn default() -> StringsOptions {
+        StringsOptions {
+            min_len: 4,
+            encodings: vec![StringEncoding::Ascii],
+            scope: StringScope::Sections(Vec::new()),
+        }
+    }
+}
+
+/// A string found in the file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FoundString {
+    pub offset: u64,
+    /// Address the string is loaded at, `None` outside of `PT_LOAD` segments
+    pub vaddr: Option<u64>,
+    /// Name of the section containing the string
+    pub section: Option<String>,
+    pub encoding: StringEncoding,
+    pub text: String,
+}
+
+impl fmt::Display for FoundString {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let vaddr = self.vaddr.map_or("-".to_string(), |v| format!("{:#x}", v));
+        write!(
+            f,
+            "{:#10x} {:>18} {:<16} {}",
+            self.offset,
+            vaddr,
+            self.section.as_deref().unwrap_or("-"),
+            self.text
+        )
+    }
+}
+
+impl ElfFile {
+    /// Extracts printable strings of at least `min_len` characters from the parts of the
+    /// file selected by `opts.scope`, sorted by file offset. Each hit is annotated with its
+    /// virtual address and containing section.
+    pub fn strings(&self, opts: &StringsOptions) -> Vec<FoundString> {
+        let mut found = Vec::new();
+        for range in self.string_ranges(&opts.scope) {
+            let Some(data) = self.bytes_at(range.start, range.end - range.start) else {
+                continue;
+            };
+            for &encoding in &opts.encodings {
+                for (start, text) in scan(data, encoding, opts.min_len.max(1)) {
+                    let offset = range.start + start as u64;
+                    found.push(FoundString {
+                        offset,
+                        vaddr: self.offset_to_vaddr(offset),
+                        section: self.section_at_offset(offset),
+                        encoding,
+                        text,
+                    });
+                }
+            }
+        }
+        found.sort_by_key(|s| (s.offset, s.encoding));
+        // Overlapping scopes such as segments sharing a page report a string once
+        found.dedup_by(|a, b| a.offset == b.offset && a.encoding == b.encoding);
+        found
+    }
+
+    /// Returns the file ranges selected by `scope`
+    fn string_ranges(&self, scope: &StringScope) -> Vec<Range<u64>> {
+        let section_ranges = |keep: &dyn Fn(&str, SectionFlags) -> bool| -> Vec<Range<u64>> {
+            self.section_headers
+                .iter()
+                .filter(|sh| sh.sh_type != SHT_NULL && sh.sh_type != SHT_NOBITS)
+                .filter(|sh| keep(self.section_name(sh), sh.flags()))
+                .map(|sh| sh.sh_offset..sh.sh_offset.saturating_add(sh.sh_size))
+                .collect()
+        };
+        match scope {
+            StringScope::Sections(names) if names.is_empty() => {
+                section_ranges(&|_, flags| flags.contains(SectionFlags::ALLOC))
+            }
+            StringScope::Sections(names) => {
+                section_ranges(&|name, _| names.iter().any(|n| n == name))
+            }
+            StringScope::ReadOnlyData => section_ranges(&|_, flags| {
+                flags.contains(SectionFlags::ALLOC)
+                    && !flags.intersects(SectionFlags::WRITE | SectionFlags::EXECINSTR)
+            }),
+            StringScope::Segments { without } => self
+                .program_headers
+                .iter()
+                .filter(|ph| ph.p_type == PT_LOAD && !ph.flags().intersects(*without))
+                .map(|ph| ph.p_offset..ph.p_offset.saturating_add(ph.p_filesz))
+                .collect(),
+            StringScope::File => std::iter::once(0..self.data().len() as u64).collect(),
+        }
+    }
+
+    fn section_at_offset(&self, offset: u64) -> Option<String> {
+        self.section_headers
+            .iter()
+            .filter(|sh| sh.sh_type != SHT_NULL && sh.sh_type != SHT_NOBITS)
+            .find(|sh| offset >= sh.sh_offset && offset - sh.sh_offset < sh.sh_size)
+            .map(|sh| self.section_name(sh).to_string())
+    }
+}
+
+fn is_printable_ascii(b: u8) -> bool {
+    b == b'\t' || (0x20..0x7f).contains(&b)
+}
+
+/// Returns `(offset, text)` of every run of at least `min_len` printable characters
+fn scan(data: &[u8], encoding: StringEncoding, min_len: usize) -> Vec<(usize, String)> {
+    match encoding {
+        StringEncoding::Ascii => scan_ascii(data, min_len),
+        StringEncoding::Utf8 => scan_utf8(data, min_len),
+        StringEncoding::Utf16Le => scan_utf16(data, min_len, u16::from_le_bytes),
+        StringEncoding::Utf16Be => scan_utf16(data, min_len, u16::from_be_bytes),
+    }
+}
+
+fn scan_ascii(data: &[u8], min_len: usize) -> Vec<(usize, String)> {
+    let mut found = Vec::new();
+    let mut start = 0;
+    for (i, &b) in data.iter().chain([0u8].iter()).enumerate() {
+        if !is_printable_ascii(b) {
+            if i - start >= min_len {
+                found.push((start, String::from_utf8_lossy(&data[start..i]).into_owned()));
+            }
+            start = i + 1;
+        }
+    }
+    found
+}
+
+fn scan_utf8(data: &[u8], min_len: usize) -> Vec<(usize, String)> {
+    let mut found = Vec::new();
+    let mut run = String::new();
+    let mut chars = 0;
+    let mut start = 0;
+    let mut i = 0;
+    while i <= data.len() {
+        let width = match data.get(i) {
+            Some(&b) if b < 0x80 => 1,
+            Some(&b) if b >> 5 == 0x6 => 2,
+            Some(&b) if b >> 4 == 0xe => 3,
+            Some(&b) if b >> 3 == 0x1e => 4,
+            _ => 0,
+        };
+        let c = data
+            .get(i..i + width)
+            .and_then(|bytes| std::str::from_utf8(bytes).ok())
+            .and_then(|s| s.chars().next())
+            .filter(|&c| c == '\t' || !c.is_control());
+        match c {
+            Some(c) => {
+                if run.is_empty() {
+                    start = i;
+                }
+                run.push(c);
+                chars += 1;
+                i += width;
+            }
+            None => {
+                if chars >= min_len {
+                    found.push((start, std::mem::take(&mut run)));
+                }
+                run.clear();
+                chars = 0;
+                i += 1;
+            }
+        }
+    }
+    found
+}
+
+/// Printable characters of the alphabetic scripts. CJK and everything above it is left
+/// out, as that is what pairs of ASCII bytes decode to.
+fn is_printable_utf16(c: char) -> bool {
+    (c == '\t' || !c.is_control()) && (c as u32) < 0x3000
+}
+
+fn scan_utf16(data: &[u8], min_len: usize, unit: fn([u8; 2]) -> u16) -> Vec<(usize, String)> {
+    let mut found = Vec::new();
+    for alignment in 0..2 {
+        let mut run = String::new();
+        let mut chars = 0;
+        let mut start = alignment;
+        let mut pos = alignment;
+        let units = data.get(alignment..).unwrap_or_default().chunks_exact(2);
+        let decoded = char::decode_utf16(units.map(|p| unit([p[0], p[1]])));
+        for c in decoded.map(|c| Some(c.ok())).chain([None]) {
+            // Unpaired surrogates take up one unit
+            let width = c.map_or(0, |c| c.map_or(2, |c| c.len_utf16() * 2));
+            match c.flatten().filter(|&c| is_printable_utf16(c)) {
+                Some(c) => {
+                    if run.is_empty() {
+                        start = pos;
+                    }
+                    run.push(c);
+                    chars += 1;
+                }
+                None => {
+                    if chars >= min_len {
+                        found.push((start, std::mem::take(&mut run)));
+                    }
+                    run.clear();
+                    chars = 0;
+                }
+            }
+            pos += width;
+        }
+    }
+    found.sort_by_key(|&(offset, _)| offset);
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::firmware::{wrap_raw_binary, RawImageOptions};
+
+    #[test]
+    fn test_firmware_strings() {
+        let elf = ElfFile::open("tests/bin/firmware.arm").unwrap();
+        let found = elf.strings(&StringsOptions::default());
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].text, "firmware v1.2");
+        assert_eq!(found[0].offset, 0x1a8);
+        assert_eq!(found[0].vaddr, Some(0x0800_0068));
+        assert_eq!(found[0].section.as_deref(), Some(".rodata"));
+
+        let opts = StringsOptions {
+            scope: StringScope::Sections(vec![".comment".to_string()]),
+            ..Default::default()
+        };
+        let found = elf.strings(&opts);
+        assert!(found[0].text.starts_with("Linker: LLD"));
+        assert_eq!(found[0].vaddr, None);
+        let opts = StringsOptions {
+            scope: StringScope::Segments {
+                without: SegmentFlags::X | SegmentFlags::W,
+            },
+            ..Default::default()
+        };
+        assert_eq!(elf.strings(&opts).len(), 1);
+        let opts = StringsOptions {
+            scope: StringScope::File,
+            ..Default::default()
+        };
+        assert!(elf.strings(&opts).iter().any(|s| s.text == ".shstrtab"));
+    }
+
+    #[test]
+    fn test_encodings() {
+        let mut blob = b"\x01plain\x00gr\xc3\xbc\xc3\x9fe\x00\xff".to_vec();
+        blob.extend("wide".encode_utf16().flat_map(|u| u.to_le_bytes()));
+        blob.extend([0, 0, 0]);
+        blob.extend("WIDE".encode_utf16().flat_map(|u| u.to_be_bytes()));
+        blob.extend([0xff, 0xff]);
+        let opts = RawImageOptions {
+            load_address: 0x1000,
+            section_name: ".rodata".to_string(),
+            ..Default::default()
+        };
+        let elf = ElfFile::parse(wrap_raw_binary(&blob, &opts)).unwrap();
+        let base = elf.section_by_name(".rodata").unwrap().sh_offset;
+        let opts = StringsOptions {
+            encodings: vec![
+                StringEncoding::Utf8,
+                StringEncoding::Utf16Le,
+                StringEncoding::Utf16Be,
+            ],
+            ..Default::default()
+        };
+        let hits = elf.strings(&opts);
+        let hits: Vec<_> = hits
+            .iter()
+            .map(|s| (s.offset - base, s.vaddr, s.encoding, s.text.as_str()))
+            .collect();
+        assert_eq!(
+            hits,
+            [
+                (1, Some(0x1001), StringEncoding::Utf8, "plain"),
+                (7, Some(0x1007), StringEncoding::Utf8, "grüße"),
+                (16, Some(0x1010), StringEncoding::Utf16Le, "wide"),
+                (27, Some(0x101b), StringEncoding::Utf16Be, "WIDE"),
+            ]
+        );
+        let ascii = elf.strings(&StringsOptions::default());
+        let texts: Vec<&str> = ascii.iter().map(|s| s.text.as_str()).collect();
+        assert_eq!(texts, ["plain"]);
+    }
+
+    #[test]
+    fn test_utf16_non_ascii() {
+        let mut blob = vec![0xff, 0xff];
+        blob.extend("größe".encode_utf16().flat_map(|u| u.to_le_bytes()));
+        blob.extend([0, 0]);
+        blob.extend("Привет".encode_utf16().flat_map(|u| u.to_le_bytes()));
+        blob.extend([0, 0]);
+        // An unpaired surrogate ends a run
+        blob.extend([0x00, 0xd8]);
+        blob.extend("ünï".encode_utf16().flat_map(|u| u.to_le_bytes()));
+        let hits = scan(&blob, StringEncoding::Utf16Le, 4);
+        assert_eq!(hits, [(2, "größe".to_string()), (14, "Привет".to_string())]);
+        let hits = scan(&blob, StringEncoding::Utf16Le, 3);
+        assert_eq!(hits.last().unwrap(), &(30, "ünï".to_string()));
+    }
+
+    #[test]
+    fn test_overlapping_ranges() {
+        let opts = RawImageOptions {
+            load_address: 0x1000,
+            section_name: ".rodata".to_string(),
+            ..Default::default()
+        };
+        let mut elf = ElfFile::parse(wrap_raw_binary(b"\x01plain\x00", &opts)).unwrap();
+        let rodata = *elf.section_by_name(".rodata").unwrap();
+        elf.section_headers.push(rodata);
+        let opts = StringsOptions {
+            encodings: vec![StringEncoding::Ascii, StringEncoding::Utf8],
+            ..Default::default()
+        };
+        let encodings: Vec<_> = elf.strings(&opts).iter().map(|s| s.encoding).collect();
+        assert_eq!(encodings, [StringEncoding::Ascii, StringEncoding::Utf8]);
+    }
+
+    #[test]
+    fn test_vaddr_overflow() {
+        let orig = ElfFile::open("tests/bin/dwarf2").unwrap();
+        let mut data = orig.data().to_vec();
+        for (i, ph) in orig.program_headers.iter().enumerate() {
+            if ph.p_type == PT_LOAD {
+                let vaddr = orig.header.e_phoff as usize + i * 56 + 16;
+                data[vaddr..vaddr + 8].fill(0xff);
+            }
+        }
+        let elf = ElfFile::parse(data).unwrap();
+        let found = elf.strings(&StringsOptions::default());
+        assert!(!found.is_empty());
+        assert!(found.iter().all(|s| s.vaddr.is_none()));
+    }
+}
@@ -0,0 +1,505 @@
+use std::{error::Error, path::Path};
+
+use crate::elf_file::ElfFile;
+use crate::elf_flags::SectionFlags;
+use crate::elf_types::*;
+use crate::elf_writer::ElfWriter;
+use crate::SectionHeader64;
+
+/// Selects the sections [`ElfFile::strip`] removes. The default matches a plain `strip`
+#[derive(Debug, Clone)]
+pub struct StripOptions {
+    /// `.symtab` and its string table
+    pub symbols: bool,
+    /// `.debug_*`, `.zdebug_*`, `.stab*`, `.gdb_index` and `.gnu.debuglto_*`
+    pub debug: bool,
+    pub comment: bool,
+    /// Note sections to remove by name, e.g. `.note.gnu.build-id`
+    pub notes: Vec<String>,
+    /// Further sections to remove by name
+    pub sections: Vec<String>,
+}
+
+impl Default for StripOptions {
+    fn default() -> StripOptions {
+        StripOptions {
+            symbols: true,
+            debug: true,
+            comment: false,
+            notes: Vec::new(),
+            sections: Vec::new(),
+        }
+    }
+}
+
+/// Returns true for sections `strip --strip-debug` removes
+pub fn is_debug_section(name: &str) -> bool {
+    name.starts_with(".debug")
+        || name.starts_with(".zdebug")
+        || name.starts_with(".gnu.debuglto_")
+        || name.starts_with(".stab")
+        || name == ".gdb_index"
+}
+
+/// Attempts to parse the ELF binary at the given path and returns a stripped copy.
+/// The **caller** is responsible for handling the return value properly.
+pub fn strip<P: AsRef<Path>>(elf_path: P, opts: &StripOptions) -> Result<Vec<u8>, Box<dyn Error>> {
+    ElfFile::open(elf_path)?.strip(opts)
+}
+
+impl ElfFile {
+    /// Returns a copy of the file without the sections selected by `opts`, together with
+    /// the relocation sections, string tables and extended index tables that only served
+    /// them. Remaining sections are renumbered, `sh_link`, `sh_info`, symbol section
+    /// indices, group members and `e_shstrndx` follow, and `.shstrtab` is rebuilt.
+    ///
+    /// Contents mapped by program headers keep their offsets, so removing an allocated
+    /// section only drops its header. Everything else is packed after the last segment
+    /// and the section header table is written last. The output only depends on the
+    /// input, so stripping is reproducible. Files where nothing matches are returned
+    /// unchanged.
+    ///
+    /// Symbols in `.symtab` defined in a removed section become `SHN_ABS`. It is an error
+    /// to remove a section that a kept section or a `.dynsym` entry still refers to.
+    pub fn strip(&self, opts: &StripOptions) -> Result<Vec<u8>, Box<dyn Error>> {
+        let removed = self.strip_set(opts)?;
+        if !removed.contains(&true) {
+            return Ok(self.data().to_vec());
+        }
+        let mut map = vec![None; removed.len()];
+        let mut count = 0u32;
+        for (i, r) in removed.iter().enumerate() {
+            if !r {
+                map[i] = Some(count);
+                count += 1;
+            }
+        }
+
+        // Bytes mapped by segments cannot move
+        let phdrs_end = (self.phnum() as u64)
+            .checked_mul(self.header.e_phentsize as u64)
+            .and_then(|n| n.checked_add(self.header.e_phoff))
+            .ok_or("program header table lies outside the file")?;
+        let mut fixed_end = (self.header.e_ehsize as u64).max(phdrs_end);
+        for ph in &self.program_headers {
+            fixed_end = fixed_end.max(ph.p_offset.saturating_add(ph.p_filesz));
+        }
+        let fixed_end = fixed_end.min(self.data().len() as u64);
+        let shstrndx = self.shstrndx();
+        let mut out = self.data()[..fixed_end as usize].to_vec();
+
+        let mut shstrtab = vec![0u8];
+        let mut headers: Vec<(usize, SectionHeader64)> = Vec::new();
+        for (i, sh) in self.section_headers.iter().enumerate() {
+            if removed[i] {
+                continue;
+            }
+            let mut sh = *sh;
+            let name = self.section_name(&self.section_headers[i]);
+            sh.sh_name = if i == 0 || name.is_empty() {
+                0
+            } else {
+                let offset = shstrtab.len() as u32;
+                shstrtab.extend(name.as_bytes());
+                shstrtab.push(0);
+                offset
+            };
+            headers.push((i, sh));
+        }
+
+        // Pack the movable sections in their original order
+        let mut order: Vec<usize> = (0..headers.len()).collect();
+        order.sort_by_key(|&k| headers[k].1.sh_offset);
+        for k in order {
+            let (i, sh) = &mut headers[k];
+            let in_place = sh.sh_offset.saturating_add(section_file_size(sh)) <= fixed_end;
+            if *i == 0 || (in_place && *i != shstrndx) {
+                continue;
+            }
+            let align = sh.sh_addralign.max(1);
+            if !align.is_power_of_two() || align > self.data().len() as u64 {
+                return Err(format!("section {} has invalid alignment {:#x}", *i, align).into());
+            }
+            let start = out
+                .len()
+                .checked_next_multiple_of(align as usize)
+                .ok_or_else(|| format!("section {} has invalid alignment {:#x}", *i, align))?;
+            out.resize(start, 0);
+            sh.sh_offset = out.len() as u64;
+            if *i == shstrndx {
+                sh.sh_size = shstrtab.len() as u64;
+                out.extend(&shstrtab);
+            } else if sh.sh_type != SHT_NOBITS {
+                let data = self
+                    .bytes_at(self.section_headers[*i].sh_offset, sh.sh_size)
+                    .ok_or_else(|| format!("section {} is out of bounds", *i))?;
+                out.extend(data);
+            }
+        }
+
+        let remap = |index: u32| map.get(index as usize).copied().flatten();
+        for (i, sh) in &mut headers {
+            if *i == 0 {
+                continue;
+            }
+            sh.sh_link = remap(sh.sh_link).unwrap_or_default();
+            let info_is_index = matches!(sh.sh_type, SHT_REL | SHT_RELA)
+                || sh.flags().contains(SectionFlags::INFO_LINK);
+            if info_is_index {
+                sh.sh_info = remap(sh.sh_info).unwrap_or_default();
+            }
+        }
+        for (i, sh) in &mut headers {
+            let name = self.section_name(&self.section_headers[*i]);
+            match sh.sh_type {
+                SHT_SYMTAB | SHT_DYNSYM => self.remap_symbols(&mut out, sh, &map, name)?,
+                SHT_SYMTAB_SHNDX => {
+                    self.remap_words(&mut out, sh, 0, &map, false)?;
+                }
+                // Removed members leave the group
+                SHT_GROUP => sh.sh_size = self.remap_words(&mut out, sh, 4, &map, true)?,
+                _ => {}
+            }
+        }
+
+        let w = ElfWriter::new(self.is_64(), self.is_big_endian());
+        let mut header = self.header;
+        let new_shstrndx = remap(shstrndx as u32).unwrap_or_default();
+        let (sh0_size, sh0_link);
+        (header.e_shnum, sh0_size) = if count >= SHN_LORESERVE as u32 {
+            (0, count as u64)
+        } else {
+            (count as u16, 0)
+        };
+        (header.e_shstrndx, sh0_link) = if new_shstrndx >= SHN_LORESERVE as u32 {
+            (SHN_XINDEX, new_shstrndx)
+        } else {
+            (new_shstrndx as u16, 0)
+        };
+        headers[0].1.sh_size = sh0_size;
+        headers[0].1.sh_link = sh0_link;
+        out.resize(out.len().next_multiple_of(self.addr_size()), 0);
+        header.e_shoff = out.len() as u64;
+        for (_, sh) in &headers {
+            out.extend(w.section_header(sh));
+        }
+        let raw = w.header(&header);
+        out[..raw.len()].copy_from_slice(&raw);
+        Ok(out)
+    }
+
+    /// Marks the sections to remove, following what only served removed sections
+    fn strip_set(&self, opts: &StripOptions) -> Result<Vec<bool>, Box<dyn Error>> {
+        let shstrndx = self.shstrndx();
+        let keep = |i: usize| i == 0 || i == shstrndx;
+        let mut removed: Vec<bool> = self
+            .section_headers
+            .iter()
+            .enumerate()
+            .map(|(i, sh)| {
+                let name = self.section_name(sh);
+                !keep(i)
+                    && ((opts.symbols && sh.sh_type == SHT_SYMTAB)
+                        || (opts.debug && is_debug_section(name))
+                        || (opts.comment && name == ".comment")
+                        || (sh.sh_type == SHT_NOTE && opts.notes.iter().any(|n| n == name))
+                        || opts.sections.iter().any(|n| n == name))
+            })
+            .collect();
+        let is_removed = |removed: &[bool], index: u32| removed.get(index as usize) == Some(&true);
+
+        loop {
+            let mut changed = false;
+            for (i, sh) in self.section_headers.iter().enumerate() {
+                if removed[i] || keep(i) || sh.flags().contains(SectionFlags::ALLOC) {
+                    continue;
+                }
+                let orphan = match sh.sh_type {
+                    SHT_REL | SHT_RELA => is_removed(&removed, sh.sh_info),
+                    SHT_SYMTAB_SHNDX => is_removed(&removed, sh.sh_link),
+                    SHT_STRTAB => {
+                        let users = || {
+                            self.section_headers
+                                .iter()
+                                .enumerate()
+                                .filter(|(_, other)| other.sh_link as usize == i)
+                        };
+                        users().any(|(j, _)| removed[j]) && users().all(|(j, _)| removed[j])
+                    }
+                    _ => false,
+                };
+                if orphan {
+                    removed[i] = true;
+                    changed = true;
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+
+        for (i, sh) in self.section_headers.iter().enumerate() {
+            if !removed[i] && i != 0 && is_removed(&removed, sh.sh_link) {
+                return Err(format!(
+                    "section {} links to removed section {}",
+                    self.section_name(sh),
+                    self.section_name(&self.section_headers[sh.sh_link as usize])
+                )
+                .into());
+            }
+        }
+        Ok(removed)
+    }
+
+    /// Rewrites `st_shndx` of the symbol table written at `sh.sh_offset` in `out`
+    fn remap_symbols(
+        &self,
+        out: &mut [u8],
+        sh: &SectionHeader64,
+        map: &[Option<u32>],
+        name: &str,
+    ) -> Result<(), Box<dyn Error>> {
+        let (entsize, field) = if self.is_64() { (24, 6) } else { (16, 14) };
+        let entsize = if sh.sh_entsize == 0 {
+            entsize
+        } else {
+            sh.sh_entsize as usize
+        };
+        for k in 0..(sh.sh_size as usize / entsize) {
+            let at = sh.sh_offset as usize + k * entsize + field;
+            let Some(shndx) = out.get(at..at + 2).and_then(|b| self.read_u16(b, 0)) else {
+                break;
+            };
+            if shndx == SHN_UNDEF || shndx >= SHN_LORESERVE {
+                continue;
+            }
+            let value = match map.get(shndx as usize) {
+                Some(Some(new)) if *new < SHN_LORESERVE as u32 => *new as u16,
+                Some(Some(_)) => SHN_XINDEX,
+                _ if sh.sh_type == SHT_DYNSYM => {
+                    return Err(format!("{} entry {} refers to a removed section", name, k).into())
+                }
+                _ => SHN_ABS,
+            };
+            self.write_u16(out, at, value);
+        }
+        Ok(())
+    }
+
+    /// Rewrites section indices stored as 32-bit words, `skip` bytes into the section, and
+    /// returns the new section size. Indices of removed sections are dropped if
+    /// `drop_removed` is set, else they are left as is
+    fn remap_words(
+        &self,
+        out: &mut [u8],
+        sh: &SectionHeader64,
+        skip: usize,
+        map: &[Option<u32>],
+        drop_removed: bool,
+    ) -> Result<u64, Box<dyn Error>> {
+        let out_of_bounds = || format!("section at {:#x} is out of bounds", sh.sh_offset);
+        let start = usize::try_from(sh.sh_offset).map_err(|_| out_of_bounds())?;
+        let end = usize::try_from(sh.sh_size)
+            .ok()
+            .and_then(|size| start.checked_add(size))
+            .filter(|&end| end <= out.len())
+            .ok_or_else(out_of_bounds)?;
+        let mut write = start.saturating_add(skip);
+        let mut dropped = 0;
+        for at in (write..end).step_by(4) {
+            let Some(index) = out[..end].get(at..at + 4).and_then(|b| self.read_u32(b, 0)) else {
+                break;
+            };
+            let value = match map.get(index as usize) {
+                _ if index == 0 => Some(0),
+                Some(Some(new)) => Some(*new),
+                _ if drop_removed => None,
+                _ => Some(index),
+            };
+            match value {
+                Some(value) => {
+                    self.write_u32(out, write, value);
+                    write += 4;
+                }
+                None => dropped += 4,
+            }
+        }
+        if dropped != 0 {
+            out[write..write + dropped].fill(0);
+        }
+        Ok(sh.sh_size - dropped as u64)
+    }
+
+    fn write_u16(&self, out: &mut [u8], at: usize, value: u16) {
+        let bytes = if self.is_big_endian() {
+            value.to_be_bytes()
+        } else {
+            value.to_le_bytes()
+        };
+        out[at..at + 2].copy_from_slice(&bytes);
+    }
+
+    fn write_u32(&self, out: &mut [u8], at: usize, value: u32) {
+        let bytes = if self.is_big_endian() {
+            value.to_be_bytes()
+        } else {
+            value.to_le_bytes()
+        };
+        out[at..at + 4].copy_from_slice(&bytes);
+    }
+}
+
+/// Number of bytes a section occupies in the file
+fn section_file_size(sh: &SectionHeader64) -> u64 {
+    if sh.sh_type == SHT_NOBITS {
+        0
+    } else {
+        sh.sh_size
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(elf: &ElfFile) -> Vec<String> {
+        elf.section_headers
+            .iter()
+            .map(|sh| elf.section_name(sh).to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_strip_arm() {
+        let orig = ElfFile::open("tests/bin/dd.armel").unwrap();
+        let opts = StripOptions {
+            comment: true,
+            ..Default::default()
+        };
+        let data = orig.strip(&opts).unwrap();
+        assert!(data.len() < orig.data().len());
+        let elf = ElfFile::parse(data).unwrap();
+        let names = names(&elf);
+        for name in [".symtab", ".strtab", ".comment"] {
+            assert!(!names.iter().any(|n| n == name), "{}", name);
+        }
+        assert_eq!(names.len(), orig.section_headers.len() - 3);
+        assert!(elf
+            .validate()
+            .iter()
+            .all(|f| f.severity != crate::Severity::Error));
+        // Loaded contents past the ELF header are untouched and links follow the renumbering
+        let exidx = elf.section_by_name(".ARM.exidx").unwrap();
+        assert_eq!(
+            elf.section_name(&elf.section_headers[exidx.sh_link as usize]),
+            ".text"
+        );
+        let ehsize = orig.header.e_ehsize as usize;
+        for ph in &orig.program_headers {
+            let skip = if ph.p_offset == 0 { ehsize } else { 0 };
+            let contents = |elf: &ElfFile| {
+                elf.segment_data(ph)
+                    .unwrap()
+                    .get(skip..)
+                    .map(<[u8]>::to_vec)
+            };
+            assert_eq!(contents(&orig), contents(&elf));
+        }
+    }
+
+    #[test]
+    fn test_strip_mips_debug() {
+        let orig = ElfFile::open("tests/bin/objdump.mips").unwrap();
+        let opts = StripOptions {
+            symbols: false,
+            ..Default::default()
+        };
+        let elf = ElfFile::parse(orig.strip(&opts).unwrap()).unwrap();
+        assert!(!names(&elf).iter().any(|n| is_debug_section(n)));
+        assert!(elf.data().len() < orig.data().len());
+        // The symbol table survives with its section indices renumbered
+        let main = |elf: &ElfFile| elf.symtab().into_iter().find(|s| s.name == "main").unwrap();
+        let (before, after) = (main(&orig), main(&elf));
+        assert_eq!(before.value, after.value);
+        assert_eq!(
+            elf.section_name(&elf.section_headers[after.shndx as usize]),
+            ".text"
+        );
+        // Stripping is reproducible and idempotent
+        assert_eq!(elf.data(), orig.strip(&opts).unwrap().as_slice());
+        assert_eq!(elf.strip(&opts).unwrap().as_slice(), elf.data());
+    }
+
+    #[test]
+    fn test_strip_relocatable() {
+        let orig = ElfFile::open("tests/bin/debug.o").unwrap();
+        let opts = StripOptions {
+            symbols: false,
+            notes: vec![".note.GNU-stack".into()],
+            ..Default::default()
+        };
+        let elf = ElfFile::parse(orig.strip(&opts).unwrap()).unwrap();
+        let names = names(&elf);
+        assert!(!names.iter().any(|n| n.contains("debug")));
+        // `.note.GNU-stack` is PROGBITS, not a note
+        assert!(names.iter().any(|n| n == ".note.GNU-stack"));
+        let rela = elf.section_by_name(".rela.text").unwrap();
+        assert_eq!(
+            elf.section_name(&elf.section_headers[rela.sh_info as usize]),
+            ".text"
+        );
+        assert_eq!(
+            elf.section_name(&elf.section_headers[rela.sh_link as usize]),
+            ".symtab"
+        );
+        let text = |elf: &ElfFile| {
+            elf.section_data(elf.section_by_name(".text").unwrap())
+                .unwrap()
+                .to_vec()
+        };
+        assert_eq!(text(&orig), text(&elf));
+
+        // Relocations still need the symbol table
+        assert!(orig.strip(&StripOptions::default()).is_err());
+    }
+
+    #[test]
+    fn test_strip_group_member() {
+        let orig = ElfFile::open("tests/bin/group.o").unwrap();
+        let opts = StripOptions {
+            symbols: false,
+            sections: vec![".data.grp".into()],
+            ..Default::default()
+        };
+        let elf = ElfFile::parse(orig.strip(&opts).unwrap()).unwrap();
+        let group = elf.section_by_name(".group").unwrap();
+        assert_eq!(group.sh_size, 12);
+        let words: Vec<u32> = elf
+            .section_data(group)
+            .unwrap()
+            .chunks(4)
+            .map(|w| u32::from_le_bytes(w.try_into().unwrap()))
+            .collect();
+        let members: Vec<&str> = words[1..]
+            .iter()
+            .map(|&i| elf.section_name(&elf.section_headers[i as usize]))
+            .collect();
+        assert_eq!(words[0], 1);
+        assert_eq!(members, [".text.grp", ".rodata.grp"]);
+    }
+
+    #[test]
+    fn test_strip_bad_alignment() {
+        let data = std::fs::read("tests/bin/dwarf2").unwrap();
+        let shoff = ElfFile::parse(data.clone()).unwrap().header.e_shoff as usize;
+        // `.shstrtab` is always rewritten at the end of the file
+        let addralign = shoff + 36 * 64 + 48;
+        for align in [0x7fff_ffff_ffff_f000, u64::MAX, 3] {
+            let mut data = data.clone();
+            data[addralign..addralign + 8].copy_from_slice(&align.to_le_bytes());
+            let elf = ElfFile::parse(data).unwrap();
+            let err = elf.strip(&StripOptions::default()).unwrap_err();
+            assert!(err.to_string().contains("invalid alignment"), "{}", err);
+        }
+    }
+}
@@ -0,0 +1,327 @@
+use crate::elf_file::ElfFile;
+use crate::elf_types::{
+    SHN_LORESERVE, SHN_UNDEF, SHN_XINDEX, SHT_DYNSYM, SHT_SYMTAB, SHT_SYMTAB_SHNDX,
+};
+use crate::{cstr_at, SectionHeader64};
+
+pub const SIZEOF_SYM32: usize = 16;
+pub const SIZEOF_SYM64: usize = 24;
+
+pub const STB_LOCAL: u8 = 0;
+pub const STB_GLOBAL: u8 = 1;
+pub const STB_WEAK: u8 = 2;
+pub const STB_GNU_UNIQUE: u8 = 10;
+
+pub const STT_NOTYPE: u8 = 0;
+pub const STT_OBJECT: u8 = 1;
+pub const STT_FUNC: u8 = 2;
+pub const STT_SECTION: u8 = 3;
+pub const STT_FILE: u8 = 4;
+pub const STT_COMMON: u8 = 5;
+pub const STT_TLS: u8 = 6;
+pub const STT_GNU_IFUNC: u8 = 10;
+
+pub const STV_DEFAULT: u8 = 0;
+pub const STV_INTERNAL: u8 = 1;
+pub const STV_HIDDEN: u8 = 2;
+pub const STV_PROTECTED: u8 = 3;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Sym32 {
+    pub st_name: u32,
+    pub st_value: u32,
+    pub st_size: u32,
+    pub st_info: u8,
+    pub st_other: u8,
+    pub st_shndx: u16,
+}
+
+unsafe impl plain::Plain for Sym32 {}
+
+impl Sym32 {
+    fn fix_sym(sym: &mut Sym32, bit: u8) -> &mut Sym32 {
+        if bit == 2 {
+            sym.st_name = sym.st_name.to_be();
+            sym.st_value = sym.st_value.to_be();
+            sym.st_size = sym.st_size.to_be();
+            sym.st_shndx = sym.st_shndx.to_be();
+        }
+        sym
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Sym64 {
+    pub st_name: u32,
+    pub st_info: u8,
+    pub st_other: u8,
+    pub st_shndx: u16,
+    pub st_value: u64,
+    pub st_size: u64,
+}
+
+unsafe impl plain::Plain for Sym64 {}
+
+impl Sym64 {
+    fn fix_sym(sym: &mut Sym64, bit: u8) -> &mut Sym64 {
+        if bit == 2 {
+            sym.st_name = sym.st_name.to_be();
+            sym.st_shndx = sym.st_shndx.to_be();
+            sym.st_value = sym.st_value.to_be();
+            sym.st_size = sym.st_size.to_be();
+        }
+        sym
+    }
+}
+
+impl From<Sym32> for Sym64 {
+    fn from(s: Sym32) -> Sym64 {
+        Sym64 {
+            st_name: s.st_name,
+            st_info: s.st_info,
+            st_other: s.st_other,
+            st_shndx: s.st_shndx,
+            st_value: s.st_value as u64,
+            st_size: s.st_size as u64,
+        }
+    }
+}
+
+/// A symbol table entry with its name resolved and its section index widened.
+///
+/// `shndx` holds the real section index: entries that use the `SHN_XINDEX` escape are
+/// resolved through the associated `SHT_SYMTAB_SHNDX` section, or get 0 if there is none.
+/// Reserved indices such as `SHN_ABS` or `SHN_COMMON` are kept as is.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Symbol {
+    pub name: String,
+    pub value: u64,
+    pub size: u64,
+    pub info: u8,
+    pub other: u8,
+    pub shndx: u32,
+    /// The `st_shndx` field as stored in the entry
+    pub raw_shndx: u16,
+}
+
+impl Symbol {
+    /// Returns the binding (`STB_*`) of the symbol
+    pub fn bind(&self) -> u8 {
+        self.info >> 4
+    }
+
+    /// Returns the type (`STT_*`) of the symbol
+    pub fn sym_type(&self) -> u8 {
+        self.info & 0xf
+    }
+
+    /// Returns the visibility (`STV_*`) of the symbol
+    pub fn visibility(&self) -> u8 {
+        self.other & 0x3
+    }
+
+    /// Returns `true` if the symbol refers to a regular section of the file
+    pub fn is_defined(&self) -> bool {
+        match self.raw_shndx {
+            SHN_UNDEF => false,
+            // Any resolved extended index is a section, even one above SHN_LORESERVE
+            SHN_XINDEX => self.shndx != 0,
+            n => n < SHN_LORESERVE,
+        }
+    }
+}
+
+impl ElfFile {
+    /// Parses the symbol table stored in section `index` (`SHT_SYMTAB` or `SHT_DYNSYM`).
+    ///
+    /// Returns an empty list if the section is not a symbol table or lies outside the file.
+    /// Entries that are cut off by the end of the section are dropped.
+    pub fn symbols(&self, index: usize) -> Vec<Symbol> {
+        let Some(sh) = self.section_headers.get(index) else {
+            return Vec::new();
+        };
+        if sh.sh_type != SHT_SYMTAB && sh.sh_type != SHT_DYNSYM {
+            return Vec::new();
+        }
+        let Some(data) = self.section_data(sh) else {
+            return Vec::new();
+        };
+        let strtab = self
+            .section_headers
+            .get(sh.sh_link as usize)
+            .and_then(|s| self.section_data(s))
+            .unwrap_or_default();
+        let shndx_table = self.symtab_shndx(index);
+
+        let bit = self.header.e_ident[0x5];
+        let min_size = if self.is_64() {
+            SIZEOF_SYM64
+        } else {
+            SIZEOF_SYM32
+        };
+        let entsize = match sh.sh_entsize as usize {
+            0 => min_size,
+            n => n.max(min_size),
+        };
+        data.chunks_exact(entsize)
+            .enumerate()
+            .map(|(i, raw)| {
+                let sym = if self.is_64() {
+                    let mut s = Sym64::default();
+                    plain::copy_from_bytes(&mut s, &raw[..SIZEOF_SYM64]).unwrap_or_default();
+                    *Sym64::fix_sym(&mut s, bit)
+                } else {
+                    let mut s = Sym32::default();
+                    plain::copy_from_bytes(&mut s, &raw[..SIZEOF_SYM32]).unwrap_or_default();
+                    Sym64::from(*Sym32::fix_sym(&mut s, bit))
+                };
+                let shndx = if sym.st_shndx == SHN_XINDEX {
+                    shndx_table
+                        .and_then(|t| self.read_u32(t, i * 4))
+                        .unwrap_or_default()
+                } else {
+                    sym.st_shndx as u32
+                };
+                Symbol {
+                    name: cstr_at(strtab, sym.st_name as usize).to_string(),
+                    value: sym.st_value,
+                    size: sym.st_size,
+                    info: sym.st_info,
+                    other: sym.st_other,
+                    shndx,
+                    raw_shndx: sym.st_shndx,
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the entries of the `.symtab` section, if any
+    pub fn symtab(&self) -> Vec<Symbol> {
+        self.first_of_type(SHT_SYMTAB)
+            .map_or_else(Vec::new, |i| self.symbols(i))
+    }
+
+    /// Returns the entries of the `.dynsym` section, if any
+    pub fn dynsym(&self) -> Vec<Symbol> {
+        self.first_of_type(SHT_DYNSYM)
+            .map_or_else(Vec::new, |i| self.symbols(i))
+    }
+
+    fn first_of_type(&self, sh_type: u32) -> Option<usize> {
+        self.section_headers
+            .iter()
+            .position(|sh| sh.sh_type == sh_type)
+    }
+
+    /// Returns the contents of the `SHT_SYMTAB_SHNDX` section associated with symbol table `index`
+    fn symtab_shndx(&self, index: usize) -> Option<&[u8]> {
+        self.section_headers
+            .iter()
+            .find(|sh: &&SectionHeader64| {
+                sh.sh_type == SHT_SYMTAB_SHNDX && sh.sh_link as usize == index
+            })
+            .and_then(|sh| self.section_data(sh))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_arm_symtab() {
+        let elf = ElfFile::open("tests/bin/dd.armel").unwrap();
+        let syms = elf.symtab();
+        assert!(!syms.is_empty());
+        assert_eq!(syms[0], Symbol::default());
+        let main = syms.iter().find(|s| s.name == "main").unwrap();
+        assert_eq!(main.sym_type(), STT_FUNC);
+        assert_eq!(main.bind(), STB_GLOBAL);
+        assert!(main.is_defined());
+        assert_eq!(
+            elf.section_name(&elf.section_headers[main.shndx as usize]),
+            ".text"
+        );
+    }
+
+    #[test]
+    fn test_mips_big_endian_symtab() {
+        let elf = ElfFile::open("tests/bin/objdump.mips").unwrap();
+        let syms = elf.symtab();
+        let malloc = syms.iter().find(|s| s.name == "malloc").unwrap();
+        assert_eq!(malloc.value, 0x503550);
+        assert_eq!(malloc.size, 2532);
+        assert_eq!(malloc.shndx, 4);
+        assert!(elf.dynsym().is_empty());
+    }
+
+    #[test]
+    fn test_x64_dynsym() {
+        let elf = ElfFile::open("/bin/ls").unwrap();
+        let syms = elf.dynsym();
+        assert!(syms.iter().any(|s| s.name == "malloc" && !s.is_defined()));
+    }
+
+    #[test]
+    fn test_symtab_shndx_escape() {
+        let mut data = std::fs::read("tests/bin/dd.armel").unwrap();
+        let elf = ElfFile::parse(data.clone()).unwrap();
+        let symtab_idx = elf.first_of_type(SHT_SYMTAB).unwrap();
+        let symtab = elf.section_headers[symtab_idx];
+        let expected = elf.symtab();
+        let (target, orig) = expected
+            .iter()
+            .enumerate()
+            .find(|(_, s)| s.name == "main")
+            .map(|(i, s)| (i, s.shndx))
+            .unwrap();
+
+        // Replace main's st_shndx with the escape and move the real index to an
+        // extended index table appended to the file
+        let sym_off = symtab.sh_offset as usize + target * SIZEOF_SYM32;
+        data[sym_off + 14..sym_off + 16].copy_from_slice(&SHN_XINDEX.to_le_bytes());
+        let table_off = data.len();
+        let mut table = vec![0u8; expected.len() * 4];
+        table[target * 4..target * 4 + 4].copy_from_slice(&orig.to_le_bytes());
+        data.extend_from_slice(&table);
+
+        // Repurpose .comment as the SHT_SYMTAB_SHNDX section
+        let comment = elf
+            .section_headers
+            .iter()
+            .position(|sh| elf.section_name(sh) == ".comment")
+            .unwrap();
+        let sh_off = elf.header.e_shoff as usize + comment * elf.header.e_shentsize as usize;
+        data[sh_off + 4..sh_off + 8].copy_from_slice(&SHT_SYMTAB_SHNDX.to_le_bytes());
+        data[sh_off + 16..sh_off + 20].copy_from_slice(&(table_off as u32).to_le_bytes());
+        data[sh_off + 20..sh_off + 24].copy_from_slice(&(table.len() as u32).to_le_bytes());
+        data[sh_off + 24..sh_off + 28].copy_from_slice(&(symtab_idx as u32).to_le_bytes());
+
+        let patched = ElfFile::parse(data).unwrap();
+        let mut syms = patched.symtab();
+        let main = &mut syms[target];
+        assert_eq!((main.raw_shndx, main.shndx), (SHN_XINDEX, orig));
+        assert!(main.is_defined());
+        main.raw_shndx = orig as u16;
+        assert_eq!(syms, expected);
+    }
+
+    #[test]
+    fn test_is_defined() {
+        let sym = |raw_shndx: u16, shndx: u32| Symbol {
+            raw_shndx,
+            shndx,
+            ..Default::default()
+        };
+        assert!(!sym(SHN_UNDEF, 0).is_defined());
+        assert!(sym(5, 5).is_defined());
+        assert!(!sym(crate::elf_types::SHN_ABS, 0xfff1).is_defined());
+        // Extended indices in the reserved range are still sections
+        assert!(sym(SHN_XINDEX, 0xff05).is_defined());
+        assert!(sym(SHN_XINDEX, 0x1_0000).is_defined());
+        // An escape without SHT_SYMTAB_SHNDX resolves to nothing
+        assert!(!sym(SHN_XINDEX, 0).is_defined());
+    }
+}
@@ -0,0 +1,538 @@
+use std::{error::Error, path::Path};
+
+use crate::elf_file::ElfFile;
+use crate::elf_types::PT_LOAD;
+use crate::packer::SIZEOF_B_INFO;
+
+pub const M_NRV2B_LE32: u8 = 2;
+pub const M_NRV2B_8: u8 = 3;
+pub const M_NRV2B_LE16: u8 = 4;
+pub const M_NRV2D_LE32: u8 = 5;
+pub const M_NRV2D_8: u8 = 6;
+pub const M_NRV2D_LE16: u8 = 7;
+pub const M_NRV2E_LE32: u8 = 8;
+pub const M_NRV2E_8: u8 = 9;
+pub const M_NRV2E_LE16: u8 = 10;
+pub const M_LZMA: u8 = 14;
+
+/// Upper bound for the sum of the blocks' uncompressed sizes
+const MAX_UNPACKED_SIZE: u64 = 1 << 30;
+
+/// A `b_info` block header and the location of its compressed data
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BlockInfo {
+    /// File offset of the compressed data following the header
+    pub offset: u64,
+    pub sz_unc: u32,
+    /// Equal to `sz_unc` for blocks UPX stored uncompressed
+    pub sz_cpr: u32,
+    pub method: u8,
+    /// Id of the filter applied before compression, 0 for none
+    pub filter: u8,
+    pub cto8: u8,
+}
+
+impl BlockInfo {
+    pub fn is_stored(&self) -> bool {
+        self.sz_cpr == self.sz_unc
+    }
+}
+
+/// Attempts to parse the UPX packed ELF binary at the given path and returns the original file.
+/// The **caller** is responsible for handling the return value properly.
+pub fn upx_unpack<P: AsRef<Path>>(elf_path: P) -> Result<Vec<u8>, Box<dyn Error>> {
+    ElfFile::open(elf_path)?.upx_unpack()
+}
+
+impl ElfFile {
+    /// Returns the `b_info` blocks following UPX's `p_info` header, up to the end marker
+    pub fn upx_blocks(&self) -> Result<Vec<BlockInfo>, Box<dyn Error>> {
+        let info = self.upx_info().ok_or("no UPX headers found")?;
+        let mut blocks = Vec::new();
+        let mut total = 0u64;
+        let mut pos = info.blocks_offset();
+        loop {
+            let hdr = self
+                .bytes_at(pos, SIZEOF_B_INFO as u64)
+                .ok_or_else(|| format!("truncated UPX block header at {:#x}", pos))?;
+            let sz_unc = self.read_u32(hdr, 0).unwrap_or_default();
+            if sz_unc == 0 {
+                return Ok(blocks);
+            }
+            let block = BlockInfo {
+                offset: pos + SIZEOF_B_INFO as u64,
+                sz_unc,
+                sz_cpr: self.read_u32(hdr, 4).unwrap_or_default(),
+                method: hdr[8],
+                filter: hdr[9],
+                cto8: hdr[10],
+            };
+            if block.sz_cpr == 0 || block.sz_cpr > block.sz_unc {
+                return Err(format!("corrupt UPX block header at {:#x}", pos).into());
+            }
+            total += block.sz_unc as u64;
+            if total > MAX_UNPACKED_SIZE {
+                return Err(format!(
+                    "UPX blocks unpack to more than {:#x} bytes",
+                    MAX_UNPACKED_SIZE
+                )
+                .into());
+            }
+            pos = block.offset + block.sz_cpr as u64;
+            blocks.push(block);
+        }
+    }
+
+    /// Restores the original file from a UPX packed Linux executable.
+    ///
+    /// UPX compresses the ELF and program headers first, then the contents of every
+    /// `PT_LOAD` in header order, the gaps between them and finally whatever follows the
+    /// last one. The original headers say where each piece belongs. Only the block
+    /// headers are trusted, so files with overwritten magic, `p_filesize` or pack header
+    /// are unpacked as well. The call/jump and ARM `BL` filters are undone after
+    /// decompression, any other filter is rejected.
+    pub fn upx_unpack(&self) -> Result<Vec<u8>, Box<dyn Error>> {
+        let info = self.upx_info().ok_or("no UPX headers found")?;
+        let mut stream = Vec::new();
+        for (i, block) in self.upx_blocks()?.into_iter().enumerate() {
+            let src = self
+                .bytes_at(block.offset, block.sz_cpr as u64)
+                .ok_or_else(|| format!("truncated UPX block at {:#x}", block.offset))?;
+            if block.is_stored() {
+                stream.extend_from_slice(src);
+                continue;
+            }
+            let mut data = upx_decompress(block.method, src, block.sz_unc as usize)
+                .map_err(|e| format!("UPX block at {:#x}: {}", block.offset, e))?;
+            // Like the stub, never unfilter the ELF and program headers
+            if i > 0 {
+                upx_unfilter(block.filter, block.cto8, &mut data)?;
+            }
+            stream.extend(data);
+        }
+
+        let orig = ElfFile::parse(stream.clone())
+            .map_err(|e| format!("unpacked ELF header is invalid: {}", e))?;
+        let headers_end = (orig.program_headers.len() as u64)
+            .checked_mul(orig.header.e_phentsize as u64)
+            .and_then(|size| size.checked_add(orig.header.e_phoff))
+            .filter(|&end| end <= stream.len() as u64);
+        let Some(headers_end) = headers_end.filter(|_| orig.program_headers.len() == orig.phnum())
+        else {
+            return Err("unpacked program headers are truncated".into());
+        };
+        let loads: Vec<(u64, u64)> = orig
+            .program_headers
+            .iter()
+            .filter(|ph| ph.p_type == PT_LOAD)
+            .map(|ph| (ph.p_offset, ph.p_filesz))
+            .collect();
+        let mut extents = vec![(0, headers_end)];
+        for (k, &(offset, size)) in loads.iter().enumerate() {
+            // The headers were already stored at the start of the first segment
+            if k == 0 && offset == 0 {
+                extents.push((headers_end, size.saturating_sub(headers_end)));
+            } else {
+                extents.push((offset, size));
+            }
+        }
+        for pair in loads.windows(2) {
+            let end = pair[0]
+                .0
+                .checked_add(pair[0].1)
+                .ok_or("unpacked segment sizes overflow")?;
+            if pair[1].0 > end {
+                extents.push((end, pair[1].0 - end));
+            }
+        }
+
+        // The blocks hold every byte of the original, which bounds where pieces may go
+        // and the size recorded in the headers
+        let limit = stream.len();
+        let file_size = match info.p_filesize {
+            0 => info.pack_header.map_or(0, |ph| ph.u_file_size as usize),
+            size => size as usize,
+        }
+        .min(limit);
+        let mut out = Vec::new();
+        let mut pos = 0usize;
+        for (offset, size) in extents {
+            let (offset, size) = (offset as usize, size as usize);
+            if offset.checked_add(size).is_none_or(|end| end > limit) {
+                return Err(
+                    format!("unpacked segment at {:#x} is outside the file", offset).into(),
+                );
+            }
+            let piece = stream
+                .get(pos..pos + size)
+                .ok_or("UPX blocks end before the original segments")?;
+            write_at(&mut out, offset, piece);
+            pos += size;
+        }
+        let rest_offset = loads
+            .iter()
+            .map(|&(o, s)| o.saturating_add(s))
+            .max()
+            .unwrap_or_default();
+        let rest = &stream[pos..];
+        if rest_offset.saturating_add(rest.len() as u64) > limit as u64 {
+            return Err(format!("unpacked data at {:#x} is outside the file", rest_offset).into());
+        }
+        write_at(&mut out, rest_offset as usize, rest);
+        if file_size != 0 {
+            out.resize(file_size, 0);
+        }
+        Ok(out)
+    }
+}
+
+fn write_at(out: &mut Vec<u8>, offset: usize, data: &[u8]) {
+    if out.len() < offset + data.len() {
+        out.resize(offset + data.len(), 0);
+    }
+    out[offset..offset + data.len()].copy_from_slice(data);
+}
+
+/// Preallocates for a typical compression ratio rather than trusting `sz_unc`
+fn capacity_hint(src: &[u8], sz_unc: usize) -> usize {
+    sz_unc.min(src.len().saturating_mul(8))
+}
+
+fn add(a: u32, b: u32) -> Result<u32, Box<dyn Error>> {
+    a.checked_add(b).ok_or_else(|| "corrupt NRV stream".into())
+}
+
+/// Undoes the filter UPX applied to a block before compressing it. Offsets are relative
+/// to the start of the block, as in the runtime stub.
+pub fn upx_unfilter(filter: u8, cto8: u8, buf: &mut [u8]) -> Result<(), Box<dyn Error>> {
+    match filter {
+        0 => {}
+        0x26 | 0x46 => unfilter_calls(buf, cto8, false),
+        0x49 => unfilter_calls(buf, cto8, true),
+        0x50 => unfilter_arm_bl(buf),
+        f => return Err(format!("unsupported UPX filter {:#x}", f).into()),
+    }
+    Ok(())
+}
+
+/// `call`/`jmp` (and with `jcc` the `0f 8x` conditional jumps) whose displacement
+/// starts with `cto8` hold a big endian offset from the start of the block
+fn unfilter_calls(buf: &mut [u8], cto8: u8, jcc: bool) {
+    let cto = (cto8 as u32) << 24;
+    let mut last_call = 0;
+    let mut ic = 0;
+    while ic + 5 < buf.len() {
+        let op = buf[ic];
+        let is_jump = op == 0xe8
+            || op == 0xe9
+            || (jcc && ic > 0 && ic != last_call && buf[ic - 1] == 0x0f && op & 0xf0 == 0x80);
+        if is_jump && buf[ic + 1] == cto8 {
+            let target =
+                u32::from_be_bytes(buf[ic + 1..ic + 5].try_into().unwrap()).wrapping_sub(cto);
+            let rel = target.wrapping_sub(ic as u32 + 1);
+            buf[ic + 1..ic + 5].copy_from_slice(&rel.to_le_bytes());
+            ic += 4;
+            last_call = ic + 1;
+        }
+        ic += 1;
+    }
+}
+
+/// Little endian ARM `BL` instructions hold a big endian word offset from the start
+/// of the block
+fn unfilter_arm_bl(buf: &mut [u8]) {
+    for ic in (0..buf.len().saturating_sub(3)).step_by(4) {
+        if buf[ic + 3] == 0xeb {
+            let target = u32::from_be_bytes([0, buf[ic], buf[ic + 1], buf[ic + 2]]);
+            let rel = target.wrapping_sub(ic as u32 / 4);
+            buf[ic..ic + 3].copy_from_slice(&rel.to_le_bytes()[..3]);
+        }
+    }
+}
+
+/// Decompresses a single UPX block of `sz_unc` bytes
+pub fn upx_decompress(method: u8, src: &[u8], sz_unc: usize) -> Result<Vec<u8>, Box<dyn Error>> {
+    match method {
+        M_NRV2B_LE32 => nrv_decompress(Nrv::B, 4, src, sz_unc),
+        M_NRV2B_8 => nrv_decompress(Nrv::B, 1, src, sz_unc),
+        M_NRV2B_LE16 => nrv_decompress(Nrv::B, 2, src, sz_unc),
+        M_NRV2D_LE32 => nrv_decompress(Nrv::D, 4, src, sz_unc),
+        M_NRV2D_8 => nrv_decompress(Nrv::D, 1, src, sz_unc),
+        M_NRV2D_LE16 => nrv_decompress(Nrv::D, 2, src, sz_unc),
+        M_NRV2E_LE32 => nrv_decompress(Nrv::E, 4, src, sz_unc),
+        M_NRV2E_8 => nrv_decompress(Nrv::E, 1, src, sz_unc),
+        M_NRV2E_LE16 => nrv_decompress(Nrv::E, 2, src, sz_unc),
+        M_LZMA => lzma_decompress(src, sz_unc),
+        m => Err(format!("unsupported UPX method {}", m).into()),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Nrv {
+    B,
+    D,
+    E,
+}
+
+/// Reads the flag bits NRV interleaves with literal bytes, most significant bit first
+/// from little endian words of `width` bytes
+struct BitReader<'a> {
+    src: &'a [u8],
+    pos: usize,
+    width: usize,
+    bits: u32,
+    count: usize,
+}
+
+impl BitReader<'_> {
+    fn bit(&mut self) -> Result<u32, Box<dyn Error>> {
+        if self.count == 0 {
+            let word = self
+                .src
+                .get(self.pos..self.pos + self.width)
+                .ok_or("compressed data is truncated")?;
+            self.bits = word.iter().rev().fold(0, |acc, &b| (acc << 8) | b as u32);
+            self.pos += self.width;
+            self.count = self.width * 8;
+        }
+        self.count -= 1;
+        Ok((self.bits >> self.count) & 1)
+    }
+
+    fn byte(&mut self) -> Result<u8, Box<dyn Error>> {
+        let b = *self
+            .src
+            .get(self.pos)
+            .ok_or("compressed data is truncated")?;
+        self.pos += 1;
+        Ok(b)
+    }
+
+    /// Reads an Elias gamma style number of at least 2
+    fn gamma(&mut self) -> Result<u32, Box<dyn Error>> {
+        let mut v = 1u32;
+        loop {
+            v = v.checked_mul(2).ok_or("corrupt NRV stream")? + self.bit()?;
+            if self.bit()? == 1 {
+                return Ok(v);
+            }
+        }
+    }
+}
+
+/// A port of UCL's `nrv2b`, `nrv2d` and `nrv2e` decompressors
+fn nrv_decompress(
+    nrv: Nrv,
+    width: usize,
+    src: &[u8],
+    sz_unc: usize,
+) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut r = BitReader {
+        src,
+        pos: 0,
+        width,
+        bits: 0,
+        count: 0,
+    };
+    let mut out: Vec<u8> = Vec::with_capacity(capacity_hint(src, sz_unc));
+    let mut last_off = 1u32;
+    loop {
+        while r.bit()? == 1 {
+            if out.len() == sz_unc {
+                return Err("corrupt NRV stream".into());
+            }
+            out.push(r.byte()?);
+        }
+        let mut off = match nrv {
+            Nrv::B => r.gamma()?,
+            Nrv::D | Nrv::E => {
+                let mut off = 1u32;
+                loop {
+                    off = off * 2 + r.bit()?;
+                    if r.bit()? == 1 {
+                        break off;
+                    }
+                    off = (off - 1) * 2 + r.bit()?;
+                    if off > 0x0100_0002 {
+                        return Err("corrupt NRV stream".into());
+                    }
+                }
+            }
+        };
+        let mut len = 0u32;
+        if off == 2 {
+            off = last_off;
+            if nrv != Nrv::B {
+                len = r.bit()?;
+            }
+        } else {
+            if off > 0x0100_0002 {
+                return Err("corrupt NRV stream".into());
+            }
+            off = (off - 3) * 256 + r.byte()? as u32;
+            if off == u32::MAX {
+                break;
+            }
+            if nrv != Nrv::B {
+                len = (off ^ u32::MAX) & 1;
+                off >>= 1;
+            }
+            off += 1;
+            last_off = off;
+        }
+        let len = match nrv {
+            Nrv::B => {
+                let len = r.bit()? * 2 + r.bit()?;
+                let len = if len == 0 { add(r.gamma()?, 2)? } else { len };
+                add(len, (off > 0xd00) as u32)?
+            }
+            Nrv::D => {
+                let len = len * 2 + r.bit()?;
+                let len = if len == 0 { add(r.gamma()?, 2)? } else { len };
+                add(len, (off > 0x500) as u32)?
+            }
+            Nrv::E => {
+                let len = if len != 0 {
+                    1 + r.bit()?
+                } else if r.bit()? == 1 {
+                    3 + r.bit()?
+                } else {
+                    add(r.gamma()?, 3)?
+                };
+                add(len, (off > 0x500) as u32)?
+            }
+        };
+        let (off, len) = (off as usize, len as usize + 1);
+        if off == 0 || off > out.len() || out.len().checked_add(len).is_none_or(|end| end > sz_unc)
+        {
+            return Err("corrupt NRV stream".into());
+        }
+        for _ in 0..len {
+            out.push(out[out.len() - off]);
+        }
+    }
+    if out.len() != sz_unc {
+        return Err(format!("decompressed {} bytes, expected {}", out.len(), sz_unc).into());
+    }
+    Ok(out)
+}
+
+/// UPX replaces the LZMA header by two bytes: `(lc + lp) << 3 | pb` and `lp << 4 | lc`
+#[cfg(feature = "compression")]
+fn lzma_decompress(src: &[u8], sz_unc: usize) -> Result<Vec<u8>, Box<dyn Error>> {
+    use lzma_rs::decompress::raw::{LzmaDecoder, LzmaParams, LzmaProperties};
+
+    let [b0, b1, ref stream @ ..] = *src else {
+        return Err("LZMA header is truncated".into());
+    };
+    let props = LzmaProperties {
+        lc: (b1 & 0xf) as u32,
+        lp: (b1 >> 4) as u32,
+        pb: (b0 & 7) as u32,
+    };
+    if props.lc > 8 || props.lp > 4 || props.pb > 4 || (b0 >> 3) as u32 != props.lc + props.lp {
+        return Err("invalid LZMA properties".into());
+    }
+    let params = LzmaParams::new(props, (sz_unc as u32).max(4096), Some(sz_unc as u64));
+    let mut out = Vec::with_capacity(capacity_hint(src, sz_unc));
+    LzmaDecoder::new(params, None)?.decompress(&mut &stream[..], &mut out)?;
+    Ok(out)
+}
+
+#[cfg(not(feature = "compression"))]
+fn lzma_decompress(_src: &[u8], _sz_unc: usize) -> Result<Vec<u8>, Box<dyn Error>> {
+    Err("LZMA support requires the compression feature".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_upx_unpack() {
+        for (packed, orig, method) in [
+            ("upx.nrv2b.arm", "plt.arm", M_NRV2B_8),
+            ("upx.aarch64", "plt.aarch64", M_NRV2D_8),
+            ("upx.mips", "pltexe.mips", M_NRV2E_LE32),
+            #[cfg(feature = "compression")]
+            ("upx.lzma.x86_64", "dwarf5", M_LZMA),
+        ] {
+            let elf = ElfFile::open(format!("tests/bin/{}", packed)).unwrap();
+            assert!(elf.upx_blocks().unwrap().iter().all(|b| b.method == method));
+            let unpacked = elf.upx_unpack().unwrap();
+            assert!(unpacked == std::fs::read(format!("tests/bin/{}", orig)).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_upx_unpack_filtered() {
+        for (packed, orig, filter) in [
+            ("upx.filtered.x86_64", "dwarf5", 0x49),
+            ("upx.filtered.arm", "plt.arm", 0x50),
+        ] {
+            let elf = ElfFile::open(format!("tests/bin/{}", packed)).unwrap();
+            let blocks = elf.upx_blocks().unwrap();
+            let block = *blocks.iter().find(|b| b.filter == filter).unwrap();
+            let pack_header = elf.upx_info().unwrap().pack_header.unwrap();
+            assert_eq!(
+                (pack_header.filter, pack_header.filter_cto),
+                (filter, block.cto8)
+            );
+            let unpacked = elf.upx_unpack().unwrap();
+            assert!(unpacked == std::fs::read(format!("tests/bin/{}", orig)).unwrap());
+
+            // The filter changed the block's contents
+            let src = elf.bytes_at(block.offset, block.sz_cpr as u64).unwrap();
+            let mut data = upx_decompress(block.method, src, block.sz_unc as usize).unwrap();
+            let filtered = data.clone();
+            upx_unfilter(filter, block.cto8, &mut data).unwrap();
+            assert_ne!(data, filtered);
+
+            let mut data = elf.data().to_vec();
+            data[block.offset as usize - 3] = 0x42;
+            let err = ElfFile::parse(data).unwrap().upx_unpack().unwrap_err();
+            assert_eq!(err.to_string(), "unsupported UPX filter 0x42");
+        }
+    }
+
+    #[test]
+    fn test_nrv_corrupt() {
+        // A match at the repeated offset whose gamma coded length is u32::MAX
+        let bits = format!("00100{}11", "10".repeat(30));
+        let src: Vec<u8> = bits
+            .as_bytes()
+            .chunks(8)
+            .map(|c| {
+                c.iter()
+                    .enumerate()
+                    .fold(0, |acc, (i, b)| acc | (b - b'0') << (7 - i))
+            })
+            .collect();
+        assert_eq!(src.len(), 9);
+        assert!(upx_decompress(M_NRV2B_8, &src, 16).is_err());
+        assert!(upx_decompress(M_NRV2E_8, &src, 16).is_err());
+        // Literals beyond the announced size
+        assert!(upx_decompress(M_NRV2B_8, &[0xff, 1, 2, 3, 4, 5, 6, 7, 8], 4).is_err());
+    }
+
+    #[test]
+    fn test_upx_unpack_tampered() {
+        let unpacked = upx_unpack("tests/bin/upx.nrv2b.tampered.arm").unwrap();
+        let elf = ElfFile::parse(unpacked).unwrap();
+        assert_eq!(elf.data(), std::fs::read("tests/bin/plt.arm").unwrap());
+        assert!(upx_unpack("tests/bin/plt.arm").is_err());
+
+        // A bogus size in the pack header is bounded by the blocks' contents
+        let elf = ElfFile::open("tests/bin/upx.mips").unwrap();
+        let (info, pack_header) = (
+            elf.upx_info().unwrap(),
+            elf.upx_info().unwrap().pack_header.unwrap(),
+        );
+        let mut data = elf.data().to_vec();
+        data[info.offset as usize + 16..][..4].fill(0);
+        data[pack_header.offset as usize + 24..][..4]
+            .copy_from_slice(&0x8407_0000u32.to_le_bytes());
+        let unpacked = ElfFile::parse(data).unwrap().upx_unpack().unwrap();
+        assert_eq!(unpacked, std::fs::read("tests/bin/pltexe.mips").unwrap());
+    }
+}
@@ -0,0 +1,653 @@
+use core::fmt;
+use std::{error::Error, path::Path};
+
+use crate::elf_file::{ElfFile, ELFCLASS32, ELFCLASS64, ELFDATA2LSB, ELFDATA2MSB};
+use crate::elf_flags::{SectionFlags, SegmentFlags};
+use crate::elf_types::*;
+use crate::{
+    ProgramHeader64, SectionHeader64, SIZEOF_EHDR32, SIZEOF_EHDR64, SIZEOF_PHDR32, SIZEOF_PHDR64,
+    SIZEOF_SHDR32, SIZEOF_SHDR64,
+};
+
+/// How much a finding deviates from what a loader or toolchain would accept
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// Unusual but harmless
+    Info,
+    /// Breaks tooling or contradicts the specification, but the kernel may still run it
+    Warning,
+    /// Structurally broken, or deliberately crafted to mislead analysis
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Info => "INFO",
+            Severity::Warning => "WARNING",
+            Severity::Error => "ERROR",
+        })
+    }
+}
+
+/// The class of anomaly a [`Finding`] describes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FindingKind {
+    BadIdent,
+    BadVersion,
+    UnknownType,
+    UnknownMachine,
+    EhsizeMismatch,
+    PhentsizeMismatch,
+    ShentsizeMismatch,
+    ProgramHeadersOutOfBounds,
+    SectionHeadersOutOfBounds,
+    SegmentOutOfBounds,
+    SectionOutOfBounds,
+    FileszExceedsMemsz,
+    BadAlignment,
+    MisalignedSegment,
+    UnsortedLoadSegments,
+    OverlappingLoadSegments,
+    EntryOutsideExecutableSegment,
+    MultipleInterp,
+    MultiplePhdr,
+    MultipleDynamic,
+    SegmentOrder,
+    BadInterp,
+    BadShstrndx,
+    BadSectionLink,
+    SectionOutsideSegments,
+    SectionSegmentMismatch,
+    ExecutableSectionNotExecutable,
+}
+
+/// A single structural anomaly found by [`validate`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    pub severity: Severity,
+    pub kind: FindingKind,
+    pub message: String,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[{}] {:?}: {}", self.severity, self.kind, self.message)
+    }
+}
+
+struct Report(Vec<Finding>);
+
+impl Report {
+    fn push(&mut self, severity: Severity, kind: FindingKind, message: String) {
+        self.0.push(Finding {
+            severity,
+            kind,
+            message,
+        });
+    }
+}
+
+/// Attempts to parse the ELF binary at the given path and returns all structural anomalies.
+/// The **caller** is responsible for handling the return value properly.
+pub fn validate<P: AsRef<Path>>(elf_path: P) -> Result<Vec<Finding>, Box<dyn Error>> {
+    Ok(ElfFile::open(elf_path)?.validate())
+}
+
+impl ElfFile {
+    /// Checks the header tables against each other and against the file size.
+    /// Nothing is trusted: every finding carries a severity and a readable message.
+    pub fn validate(&self) -> Vec<Finding> {
+        let mut r = Report(Vec::new());
+        check_header(self, &mut r);
+        check_tables(self, &mut r);
+        check_segments(self, &mut r);
+        check_sections(self, &mut r);
+        r.0
+    }
+}
+
+fn check_header(elf: &ElfFile, r: &mut Report) {
+    let h = &elf.header;
+    let class = h.e_ident[0x4];
+    if class != ELFCLASS32 && class != ELFCLASS64 {
+        r.push(
+            Severity::Error,
+            FindingKind::BadIdent,
+            format!("invalid EI_CLASS {}", class),
+        );
+    }
+    let data = h.e_ident[0x5];
+    if data != ELFDATA2LSB && data != ELFDATA2MSB {
+        r.push(
+            Severity::Error,
+            FindingKind::BadIdent,
+            format!("invalid EI_DATA {}", data),
+        );
+    }
+    if h.e_ident[0x6] != 1 || h.e_version != 1 {
+        r.push(
+            Severity::Warning,
+            FindingKind::BadVersion,
+            format!(
+                "EI_VERSION is {} and e_version is {}, expected 1",
+                h.e_ident[0x6], h.e_version
+            ),
+        );
+    }
+    if ElfType::try_from(h.e_type).is_err() {
+        r.push(
+            Severity::Warning,
+            FindingKind::UnknownType,
+            format!("unknown e_type 0x{:x}", h.e_type),
+        );
+    }
+    if Machine::try_from(h.e_machine).is_err() {
+        r.push(
+            Severity::Info,
+            FindingKind::UnknownMachine,
+            format!("unknown e_machine 0x{:x}", h.e_machine),
+        );
+    }
+
+    let (ehsize, phentsize, shentsize) = if elf.is_64() {
+        (SIZEOF_EHDR64, SIZEOF_PHDR64, SIZEOF_SHDR64)
+    } else {
+        (SIZEOF_EHDR32, SIZEOF_PHDR32, SIZEOF_SHDR32)
+    };
+    if h.e_ehsize as usize != ehsize {
+        r.push(
+            Severity::Warning,
+            FindingKind::EhsizeMismatch,
+            format!(
+                "e_ehsize is {}, expected {} for this class",
+                h.e_ehsize, ehsize
+            ),
+        );
+    }
+    if elf.phnum() > 0 && h.e_phentsize as usize != phentsize {
+        r.push(
+            Severity::Error,
+            FindingKind::PhentsizeMismatch,
+            format!(
+                "e_phentsize is {}, expected {} for this class",
+                h.e_phentsize, phentsize
+            ),
+        );
+    }
+    if elf.shnum() > 0 && h.e_shentsize as usize != shentsize {
+        r.push(
+            Severity::Warning,
+            FindingKind::ShentsizeMismatch,
+            format!(
+                "e_shentsize is {}, expected {} for this class",
+                h.e_shentsize, shentsize
+            ),
+        );
+    }
+}
+
+fn check_tables(elf: &ElfFile, r: &mut Report) {
+    let h = &elf.header;
+    let len = elf.data().len() as u64;
+    if elf.program_headers.len() < elf.phnum() {
+        r.push(
+            Severity::Error,
+            FindingKind::ProgramHeadersOutOfBounds,
+            format!(
+                "program header table at 0x{:x} with {} entries extends past EOF (0x{:x}), only {} readable",
+                h.e_phoff,
+                elf.phnum(),
+                len,
+                elf.program_headers.len()
+            ),
+        );
+    }
+    if elf.section_headers.len() < elf.shnum() {
+        r.push(
+            Severity::Warning,
+            FindingKind::SectionHeadersOutOfBounds,
+            format!(
+                "section header table at 0x{:x} with {} entries extends past EOF (0x{:x}), only {} readable",
+                h.e_shoff,
+                elf.shnum(),
+                len,
+                elf.section_headers.len()
+            ),
+        );
+    }
+    if elf.shnum() == 0 {
+        if h.e_shstrndx != SHN_UNDEF {
+            r.push(
+                Severity::Warning,
+                FindingKind::BadShstrndx,
+                format!(
+                    "e_shstrndx is {} but there are no section headers",
+                    h.e_shstrndx
+                ),
+            );
+        }
+        return;
+    }
+    if h.e_shstrndx >= SHN_LORESERVE && h.e_shstrndx != SHN_XINDEX {
+        r.push(
+            Severity::Error,
+            FindingKind::BadShstrndx,
+            format!("e_shstrndx 0x{:x} is a reserved index", h.e_shstrndx),
+        );
+        return;
+    }
+    let idx = elf.shstrndx();
+    match elf.section_headers.get(idx) {
+        None => r.push(
+            Severity::Error,
+            FindingKind::BadShstrndx,
+            format!(
+                "section header string table index {} is out of range ({} sections)",
+                idx,
+                elf.shnum()
+            ),
+        ),
+        Some(sh) if sh.sh_type != SHT_STRTAB => r.push(
+            Severity::Error,
+            FindingKind::BadShstrndx,
+            format!(
+                "section header string table index {} points to a section of type 0x{:x} instead of STRTAB",
+                idx, sh.sh_type
+            ),
+        ),
+        Some(sh) if elf.section_data(sh).is_none() => r.push(
+            Severity::Error,
+            FindingKind::BadShstrndx,
+            format!(
+                "section header string table (section {}) extends past EOF",
+                idx
+            ),
+        ),
+        _ => {}
+    }
+}
+
+fn is_load(ph: &ProgramHeader64) -> bool {
+    ph.p_type == PT_LOAD
+}
+
+fn check_segments(elf: &ElfFile, r: &mut Report) {
+    let machine = elf.machine();
+    let len = elf.data().len() as u64;
+    let (mut interp, mut phdr, mut dynamic) = (0, 0, 0);
+    let mut seen_load = false;
+    let mut last_load_vaddr = None;
+
+    for (i, ph) in elf.program_headers.iter().enumerate() {
+        let name = ph.segment_type(machine);
+        if ph
+            .p_offset
+            .checked_add(ph.p_filesz)
+            .is_none_or(|end| end > len)
+        {
+            r.push(
+                if is_load(ph) {
+                    Severity::Error
+                } else {
+                    Severity::Warning
+                },
+                FindingKind::SegmentOutOfBounds,
+                format!(
+                    "segment {} ({}) covers 0x{:x}..0x{:x} which extends past EOF (0x{:x})",
+                    i,
+                    name,
+                    ph.p_offset,
+                    ph.p_offset.saturating_add(ph.p_filesz),
+                    len
+                ),
+            );
+        }
+        if ph.p_align > 1 && !ph.p_align.is_power_of_two() {
+            r.push(
+                Severity::Warning,
+                FindingKind::BadAlignment,
+                format!(
+                    "segment {} ({}) has p_align 0x{:x} which is not a power of two",
+                    i, name, ph.p_align
+                ),
+            );
+        }
+        match ph.p_type {
+            PT_LOAD => {
+                if ph.p_filesz > ph.p_memsz {
+                    r.push(
+                        Severity::Error,
+                        FindingKind::FileszExceedsMemsz,
+                        format!(
+                            "segment {} (PT_LOAD) has p_filesz 0x{:x} > p_memsz 0x{:x}",
+                            i, ph.p_filesz, ph.p_memsz
+                        ),
+                    );
+                }
+                if ph.p_align > 1 && ph.p_vaddr % ph.p_align != ph.p_offset % ph.p_align {
+                    r.push(
+                        Severity::Error,
+                        FindingKind::MisalignedSegment,
+                        format!(
+                            "segment {} (PT_LOAD) p_vaddr 0x{:x} and p_offset 0x{:x} are not congruent modulo p_align 0x{:x}",
+                            i, ph.p_vaddr, ph.p_offset, ph.p_align
+                        ),
+                    );
+                }
+                if last_load_vaddr.is_some_and(|v| ph.p_vaddr < v) {
+                    r.push(
+                        Severity::Warning,
+                        FindingKind::UnsortedLoadSegments,
+                        format!("segment {} (PT_LOAD) is not sorted by p_vaddr", i),
+                    );
+                }
+                last_load_vaddr = Some(ph.p_vaddr);
+                seen_load = true;
+            }
+            PT_INTERP => {
+                interp += 1;
+                if seen_load {
+                    r.push(
+                        Severity::Warning,
+                        FindingKind::SegmentOrder,
+                        format!("segment {} (PT_INTERP) follows a PT_LOAD segment", i),
+                    );
+                }
+                match elf.segment_data(ph) {
+                    Some(d) if d.last() == Some(&0) => {}
+                    Some(_) => r.push(
+                        Severity::Warning,
+                        FindingKind::BadInterp,
+                        format!("segment {} (PT_INTERP) is not NUL terminated", i),
+                    ),
+                    None => {}
+                }
+            }
+            PT_PHDR => {
+                phdr += 1;
+                if seen_load {
+                    r.push(
+                        Severity::Warning,
+                        FindingKind::SegmentOrder,
+                        format!("segment {} (PT_PHDR) follows a PT_LOAD segment", i),
+                    );
+                }
+            }
+            PT_DYNAMIC => dynamic += 1,
+            _ => {}
+        }
+    }
+    if interp > 1 {
+        r.push(
+            Severity::Error,
+            FindingKind::MultipleInterp,
+            format!("{} PT_INTERP segments", interp),
+        );
+    }
+    if phdr > 1 {
+        r.push(
+            Severity::Error,
+            FindingKind::MultiplePhdr,
+            format!("{} PT_PHDR segments", phdr),
+        );
+    }
+    if dynamic > 1 {
+        r.push(
+            Severity::Warning,
+            FindingKind::MultipleDynamic,
+            format!("{} PT_DYNAMIC segments", dynamic),
+        );
+    }
+
+    let loads: Vec<(usize, &ProgramHeader64)> = elf
+        .program_headers
+        .iter()
+        .enumerate()
+        .filter(|(_, ph)| is_load(ph) && ph.p_memsz > 0)
+        .collect();
+    for (n, (i, a)) in loads.iter().enumerate() {
+        for (j, b) in loads.iter().skip(n + 1) {
+            let a_end = a.p_vaddr.saturating_add(a.p_memsz);
+            let b_end = b.p_vaddr.saturating_add(b.p_memsz);
+            if a.p_vaddr < b_end && b.p_vaddr < a_end {
+                r.push(
+                    Severity::Error,
+                    FindingKind::OverlappingLoadSegments,
+                    format!(
+                        "PT_LOAD segments {} (0x{:x}..0x{:x}) and {} (0x{:x}..0x{:x}) overlap",
+                        i, a.p_vaddr, a_end, j, b.p_vaddr, b_end
+                    ),
+                );
+            }
+        }
+    }
+
+    let entry = elf.header.e_entry;
+    let runnable = matches!(
+        ElfType::try_from(elf.header.e_type),
+        Ok(ElfType::Exec | ElfType::Dyn)
+    );
+    if runnable && entry != 0 && !loads.is_empty() {
+        let in_exec = loads.iter().any(|(_, ph)| {
+            ph.flags().contains(SegmentFlags::X)
+                && entry >= ph.p_vaddr
+                && entry - ph.p_vaddr < ph.p_memsz
+        });
+        if !in_exec {
+            r.push(
+                Severity::Error,
+                FindingKind::EntryOutsideExecutableSegment,
+                format!(
+                    "entry point 0x{:x} is not inside an executable PT_LOAD segment",
+                    entry
+                ),
+            );
+        }
+    }
+}
+
+fn check_sections(elf: &ElfFile, r: &mut Report) {
+    let len = elf.data().len() as u64;
+    let shnum = elf.section_headers.len() as u32;
+    let loads: Vec<&ProgramHeader64> = elf
+        .program_headers
+        .iter()
+        .filter(|ph| is_load(ph))
+        .collect();
+
+    for (i, sh) in elf.section_headers.iter().enumerate().skip(1) {
+        let name = elf.section_name(sh);
+        if sh.sh_type != SHT_NOBITS
+            && sh.sh_type != SHT_NULL
+            && sh
+                .sh_offset
+                .checked_add(sh.sh_size)
+                .is_none_or(|end| end > len)
+        {
+            r.push(
+                Severity::Warning,
+                FindingKind::SectionOutOfBounds,
+                format!(
+                    "section {} ({}) covers 0x{:x}..0x{:x} which extends past EOF (0x{:x})",
+                    i,
+                    name,
+                    sh.sh_offset,
+                    sh.sh_offset.saturating_add(sh.sh_size),
+                    len
+                ),
+            );
+        }
+        if sh.sh_addralign > 1 && !sh.sh_addralign.is_power_of_two() {
+            r.push(
+                Severity::Warning,
+                FindingKind::BadAlignment,
+                format!(
+                    "section {} ({}) has sh_addralign 0x{:x} which is not a power of two",
+                    i, name, sh.sh_addralign
+                ),
+            );
+        }
+        if sh.sh_link >= shnum && sh.sh_link != SHN_XINDEX as u32 {
+            r.push(
+                Severity::Warning,
+                FindingKind::BadSectionLink,
+                format!(
+                    "section {} ({}) has sh_link {} out of range",
+                    i, name, sh.sh_link
+                ),
+            );
+        }
+        check_section_mapping(&loads, i, name, sh, r);
+    }
+}
+
+fn check_section_mapping(
+    loads: &[&ProgramHeader64],
+    i: usize,
+    name: &str,
+    sh: &SectionHeader64,
+    r: &mut Report,
+) {
+    let flags = sh.flags();
+    if !flags.contains(SectionFlags::ALLOC) || sh.sh_size == 0 || loads.is_empty() {
+        return;
+    }
+    // .tbss occupies no address space outside of the TLS template
+    if flags.contains(SectionFlags::TLS) && sh.sh_type == SHT_NOBITS {
+        return;
+    }
+    let end = sh.sh_addr.saturating_add(sh.sh_size);
+    let seg = loads
+        .iter()
+        .find(|ph| sh.sh_addr >= ph.p_vaddr && end <= ph.p_vaddr.saturating_add(ph.p_memsz));
+    let ph = match seg {
+        Some(ph) => ph,
+        None => {
+            r.push(
+                Severity::Warning,
+                FindingKind::SectionOutsideSegments,
+                format!(
+                    "allocated section {} ({}) at 0x{:x}..0x{:x} is not covered by any PT_LOAD segment",
+                    i, name, sh.sh_addr, end
+                ),
+            );
+            return;
+        }
+    };
+    if sh.sh_type != SHT_NOBITS && sh.sh_addr - ph.p_vaddr < ph.p_filesz {
+        match ph.p_offset.checked_add(sh.sh_addr - ph.p_vaddr) {
+            Some(expected) if sh.sh_offset == expected => {}
+            Some(expected) => r.push(
+                Severity::Warning,
+                FindingKind::SectionSegmentMismatch,
+                format!(
+                    "section {} ({}) has sh_offset 0x{:x} but its segment maps address 0x{:x} from offset 0x{:x}",
+                    i, name, sh.sh_offset, sh.sh_addr, expected
+                ),
+            ),
+            None => r.push(
+                Severity::Warning,
+                FindingKind::SectionSegmentMismatch,
+                format!(
+                    "section {} ({}) at 0x{:x} is mapped by a segment whose file offset overflows",
+                    i, name, sh.sh_addr
+                ),
+            ),
+        }
+    }
+    if flags.contains(SectionFlags::EXECINSTR) && !ph.flags().contains(SegmentFlags::X) {
+        r.push(
+            Severity::Warning,
+            FindingKind::ExecutableSectionNotExecutable,
+            format!(
+                "executable section {} ({}) is mapped by a non-executable segment",
+                i, name
+            ),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn kinds(findings: &[Finding]) -> Vec<FindingKind> {
+        findings.iter().map(|f| f.kind).collect()
+    }
+
+    #[test]
+    fn test_clean_binaries_have_no_errors() {
+        for path in ["tests/bin/dd.armel", "tests/bin/objdump.mips", "/bin/ls"] {
+            let findings = validate(path).unwrap();
+            assert!(
+                findings.iter().all(|f| f.severity < Severity::Error),
+                "{}: {:?}",
+                path,
+                findings
+            );
+        }
+    }
+
+    #[test]
+    fn test_corrupted_header_fields() {
+        let mut data = fs::read("tests/bin/objdump.mips").unwrap();
+        // e_ehsize, e_phentsize and e_shstrndx (big endian ELF32)
+        data[0x28..0x2a].copy_from_slice(&64u16.to_be_bytes());
+        data[0x2a..0x2c].copy_from_slice(&56u16.to_be_bytes());
+        data[0x32..0x34].copy_from_slice(&500u16.to_be_bytes());
+        let findings = ElfFile::parse(data).unwrap().validate();
+        let kinds = kinds(&findings);
+        assert!(kinds.contains(&FindingKind::EhsizeMismatch));
+        assert!(kinds.contains(&FindingKind::PhentsizeMismatch));
+        assert!(kinds.contains(&FindingKind::BadShstrndx));
+    }
+
+    #[test]
+    fn test_corrupted_segments() {
+        let mut elf = ElfFile::open("tests/bin/dd.armel").unwrap();
+        let load = elf
+            .program_headers
+            .iter()
+            .position(|ph| ph.p_type == PT_LOAD)
+            .unwrap();
+        let text = elf.program_headers[load];
+        elf.program_headers[load].p_filesz = text.p_memsz + 0x10;
+        let mut copy = text;
+        copy.p_vaddr += 0x1001;
+        elf.program_headers.push(copy);
+        elf.header.e_entry = 0x10;
+        let kinds = kinds(&elf.validate());
+        assert!(kinds.contains(&FindingKind::FileszExceedsMemsz));
+        assert!(kinds.contains(&FindingKind::OverlappingLoadSegments));
+        assert!(kinds.contains(&FindingKind::MisalignedSegment));
+        assert!(kinds.contains(&FindingKind::EntryOutsideExecutableSegment));
+    }
+
+    #[test]
+    fn test_truncated_tables() {
+        let data = fs::read("tests/bin/dd.armel").unwrap();
+        let findings = ElfFile::parse(data[..0x1000].to_vec()).unwrap().validate();
+        let kinds = kinds(&findings);
+        assert!(kinds.contains(&FindingKind::SectionHeadersOutOfBounds));
+        assert!(kinds.contains(&FindingKind::SegmentOutOfBounds));
+    }
+
+    #[test]
+    fn test_segment_offset_overflow() {
+        let mut elf = ElfFile::open("tests/bin/dwarf2").unwrap();
+        for ph in elf
+            .program_headers
+            .iter_mut()
+            .filter(|ph| ph.p_type == PT_LOAD)
+        {
+            ph.p_offset = u64::MAX;
+        }
+        let findings = elf.validate();
+        assert!(findings
+            .iter()
+            .any(|f| f.kind == FindingKind::SectionSegmentMismatch
+                && f.message.contains("overflows")));
+    }
+}
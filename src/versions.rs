@@ -0,0 +1,256 @@
+use crate::cstr_at;
+use crate::elf_file::ElfFile;
+use crate::elf_types::{SHT_GNU_VERDEF, SHT_GNU_VERNEED, SHT_GNU_VERSYM};
+use crate::SectionHeader64;
+
+/// `.gnu.version` index of local symbols
+pub const VER_NDX_LOCAL: u16 = 0;
+/// `.gnu.version` index of unversioned global symbols
+pub const VER_NDX_GLOBAL: u16 = 1;
+/// Marks a non-default version (`sym@VER` instead of `sym@@VER`)
+pub const VERSYM_HIDDEN: u16 = 0x8000;
+
+/// The version definition naming the file itself
+pub const VER_FLG_BASE: u16 = 0x1;
+pub const VER_FLG_WEAK: u16 = 0x2;
+
+pub const SIZEOF_VERDEF: usize = 20;
+pub const SIZEOF_VERDAUX: usize = 8;
+pub const SIZEOF_VERNEED: usize = 16;
+pub const SIZEOF_VERNAUX: usize = 16;
+
+/// An `Elf_Verdef` entry of `.gnu.version_d`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionDef {
+    pub index: u16,
+    pub flags: u16,
+    pub hash: u32,
+    pub name: String,
+    /// Versions this one inherits from
+    pub parents: Vec<String>,
+}
+
+/// An `Elf_Vernaux` entry: a version the file requires from a library
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionNeedAux {
+    pub name: String,
+    pub index: u16,
+    pub flags: u16,
+    pub hash: u32,
+}
+
+/// An `Elf_Verneed` entry of `.gnu.version_r`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionNeed {
+    pub file: String,
+    pub versions: Vec<VersionNeedAux>,
+}
+
+/// The version a dynamic symbol is bound to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolVersion {
+    pub name: String,
+    /// `true` for the default version of a definition, printed as `sym@@VER`
+    pub default: bool,
+    /// Library the version is required from, `None` for definitions
+    pub file: Option<String>,
+}
+
+impl ElfFile {
+    /// Returns the `.gnu.version` entries, one for each `.dynsym` symbol
+    pub fn versym(&self) -> Vec<u16> {
+        let Some(data) = self.version_section(SHT_GNU_VERSYM).map(|(_, data)| data) else {
+            return Vec::new();
+        };
+        (0..data.len() / 2)
+            .filter_map(|i| self.read_u16(data, i * 2))
+            .collect()
+    }
+
+    /// Parses the version definitions of `.gnu.version_d`
+    pub fn version_definitions(&self) -> Vec<VersionDef> {
+        let Some((sh, data)) = self.version_section(SHT_GNU_VERDEF) else {
+            return Vec::new();
+        };
+        let strtab = self.version_strtab(sh);
+        let mut defs = Vec::new();
+        let mut pos = 0usize;
+        // sh_info holds the entry count, the data length bounds corrupted chains
+        for _ in 0..sh.sh_info.min((data.len() / SIZEOF_VERDEF) as u32) {
+            let (Some(flags), Some(index), Some(cnt), Some(hash), Some(aux), Some(next)) = (
+                self.read_u16(data, pos + 2),
+                self.read_u16(data, pos + 4),
+                self.read_u16(data, pos + 6),
+                self.read_u32(data, pos + 8),
+                self.read_u32(data, pos + 12),
+                self.read_u32(data, pos + 16),
+            ) else {
+                break;
+            };
+            let mut names = Vec::new();
+            let mut aux_pos = pos + aux as usize;
+            for _ in 0..cnt {
+                let (Some(name), Some(aux_next)) = (
+                    self.read_u32(data, aux_pos),
+                    self.read_u32(data, aux_pos + 4),
+                ) else {
+                    break;
+                };
+                names.push(cstr_at(strtab, name as usize).to_string());
+                if aux_next == 0 {
+                    break;
+                }
+                aux_pos += aux_next as usize;
+            }
+            let mut names = names.into_iter();
+            defs.push(VersionDef {
+                index,
+                flags,
+                hash,
+                name: names.next().unwrap_or_default(),
+                parents: names.collect(),
+            });
+            if next == 0 {
+                break;
+            }
+            pos += next as usize;
+        }
+        defs
+    }
+
+    /// Parses the version requirements of `.gnu.version_r`
+    pub fn version_needs(&self) -> Vec<VersionNeed> {
+        let Some((sh, data)) = self.version_section(SHT_GNU_VERNEED) else {
+            return Vec::new();
+        };
+        let strtab = self.version_strtab(sh);
+        let mut needs = Vec::new();
+        let mut pos = 0usize;
+        for _ in 0..sh.sh_info.min((data.len() / SIZEOF_VERNEED) as u32) {
+            let (Some(cnt), Some(file), Some(aux), Some(next)) = (
+                self.read_u16(data, pos + 2),
+                self.read_u32(data, pos + 4),
+                self.read_u32(data, pos + 8),
+                self.read_u32(data, pos + 12),
+            ) else {
+                break;
+            };
+            let mut versions = Vec::new();
+            let mut aux_pos = pos + aux as usize;
+            for _ in 0..cnt {
+                let (Some(hash), Some(flags), Some(index), Some(name), Some(aux_next)) = (
+                    self.read_u32(data, aux_pos),
+                    self.read_u16(data, aux_pos + 4),
+                    self.read_u16(data, aux_pos + 6),
+                    self.read_u32(data, aux_pos + 8),
+                    self.read_u32(data, aux_pos + 12),
+                ) else {
+                    break;
+                };
+                versions.push(VersionNeedAux {
+                    name: cstr_at(strtab, name as usize).to_string(),
+                    index,
+                    flags,
+                    hash,
+                });
+                if aux_next == 0 {
+                    break;
+                }
+                aux_pos += aux_next as usize;
+            }
+            needs.push(VersionNeed {
+                file: cstr_at(strtab, file as usize).to_string(),
+                versions,
+            });
+            if next == 0 {
+                break;
+            }
+            pos += next as usize;
+        }
+        needs
+    }
+
+    /// Resolves the version of every `.dynsym` entry. Local and unversioned symbols, and
+    /// all symbols of files without `.gnu.version`, yield `None`.
+    pub fn dynsym_versions(&self) -> Vec<Option<SymbolVersion>> {
+        let defs = self.version_definitions();
+        let needs = self.version_needs();
+        self.versym()
+            .into_iter()
+            .map(|v| {
+                let index = v & !VERSYM_HIDDEN;
+                if index == VER_NDX_LOCAL || index == VER_NDX_GLOBAL {
+                    return None;
+                }
+                if let Some(def) = defs.iter().find(|d| d.index == index) {
+                    return Some(SymbolVersion {
+                        name: def.name.clone(),
+                        default: v & VERSYM_HIDDEN == 0,
+                        file: None,
+                    });
+                }
+                needs.iter().find_map(|need| {
+                    need.versions
+                        .iter()
+                        .find(|aux| aux.index == index)
+                        .map(|aux| SymbolVersion {
+                            name: aux.name.clone(),
+                            default: false,
+                            file: Some(need.file.clone()),
+                        })
+                })
+            })
+            .collect()
+    }
+
+    fn version_section(&self, sh_type: u32) -> Option<(&SectionHeader64, &[u8])> {
+        let sh = self
+            .section_headers
+            .iter()
+            .find(|sh| sh.sh_type == sh_type)?;
+        Some((sh, self.section_data(sh)?))
+    }
+
+    fn version_strtab(&self, sh: &SectionHeader64) -> &[u8] {
+        self.section_headers
+            .get(sh.sh_link as usize)
+            .and_then(|s| self.section_data(s))
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_symbol_versions() {
+        let elf = ElfFile::open("tests/bin/libdemo.v2.so").unwrap();
+        let defs = elf.version_definitions();
+        let names: Vec<&str> = defs.iter().map(|d| d.name.as_str()).collect();
+        assert_eq!(names, ["libdemo.so.1", "LIBDEMO_1.0", "LIBDEMO_2.0"]);
+        assert_eq!(defs[0].flags, VER_FLG_BASE);
+        assert_eq!(defs[2].parents, ["LIBDEMO_1.0"]);
+        let needs = elf.version_needs();
+        assert_eq!(needs.len(), 1);
+        assert_eq!(needs[0].file, "libm.so.6");
+        assert_eq!(needs[0].versions[0].name, "GLIBC_2.2.5");
+
+        let versions = elf.dynsym_versions();
+        let symbols = elf.dynsym();
+        assert_eq!(versions.len(), symbols.len());
+        let printed: Vec<String> = symbols
+            .iter()
+            .zip(&versions)
+            .filter_map(|(sym, v)| {
+                let v = v.as_ref()?;
+                let at = if v.default { "@@" } else { "@" };
+                Some(format!("{}{}{}", sym.name, at, v.name))
+            })
+            .collect();
+        assert!(printed.contains(&"cos@GLIBC_2.2.5".to_string()));
+        assert!(printed.contains(&"api_add@@LIBDEMO_2.0".to_string()));
+        assert!(printed.contains(&"api_add@LIBDEMO_1.0".to_string()));
+        assert!(versions[0].is_none());
+    }
+}
@@ -0,0 +1,222 @@
+#!/usr/bin/env python3
+"""Minimal UPX layout packer that builds the tests/bin/upx.* fixtures.
+
+It writes UPX's l_info, p_info, b_info and pack headers around real NRV2B/D/E or
+LZMA blocks, so the files unpack without a upx binary. The packer detection
+samples upx.arm, upx.tampered.arm and upx.x86_64 are not built here. Output is
+deterministic:
+
+    python3 tests/upx_pack.py tests/bin/plt.arm tests/bin/upx.nrv2b.arm 3 23
+    python3 tests/upx_pack.py tests/bin/plt.arm tests/bin/upx.nrv2b.tampered.arm 3 23 tamper
+    python3 tests/upx_pack.py tests/bin/plt.aarch64 tests/bin/upx.aarch64 6 25
+    python3 tests/upx_pack.py tests/bin/pltexe.mips tests/bin/upx.mips 8 137
+    python3 tests/upx_pack.py tests/bin/dwarf5 tests/bin/upx.lzma.x86_64 14 22
+    python3 tests/upx_pack.py tests/bin/dwarf5 tests/bin/upx.filtered.x86_64 8 22 0x49
+    python3 tests/upx_pack.py tests/bin/plt.arm tests/bin/upx.filtered.arm 3 23 0x50
+
+Arguments: input, output, compression method, UPX format id and optionally
+"tamper" to clear the magic, p_filesize, p_blocksize and pack header, or a
+filter id (0x26, 0x46, 0x49 or 0x50) to apply to the executable segments.
+"""
+import lzma, struct, sys, zlib, random
+
+M_NRV2B_LE32, M_NRV2B_8, M_NRV2D_8, M_NRV2E_LE32, M_LZMA = 2, 3, 6, 8, 14
+
+class Bits:
+    def __init__(self, word):
+        self.out = bytearray(); self.word = word; self.pos = None; self.n = 0; self.acc = 0
+    def bit(self, b):
+        if self.n == 0:
+            self.pos = len(self.out); self.out += bytes(self.word)
+        self.acc = (self.acc << 1) | b; self.n += 1
+        if self.n == self.word * 8: self.flush()
+    def flush(self):
+        if self.n == 0: return
+        v = self.acc << (self.word * 8 - self.n)
+        if self.word == 1: self.out[self.pos] = v
+        else: self.out[self.pos:self.pos + 4] = struct.pack('<I', v)
+        self.n = 0; self.acc = 0
+    def byte(self, b): self.out.append(b)
+
+def gamma(bw, v):  # v >= 2
+    bits = bin(v)[3:]
+    for i, c in enumerate(bits):
+        bw.bit(int(c)); bw.bit(1 if i == len(bits) - 1 else 0)
+
+def gamma2_bits(v):
+    if v < 4: return [v & 1]
+    return gamma2_bits(v // 4 + 1) + [0, (v % 4) >> 1, v % 4 & 1]
+
+def gamma2(bw, v):
+    for b in gamma2_bits(v): bw.bit(b)
+    bw.bit(1)
+
+def matches(data):
+    heads = {}; i = 0
+    while i < len(data):
+        best = (0, 0)
+        if i + 3 <= len(data):
+            key = data[i:i + 3]
+            for p in reversed(heads.get(key, [])[-64:]):
+                if i - p > 0xffff: break
+                l = 0
+                while i + l < len(data) and data[p + l] == data[i + l] and l < 0x1000: l += 1
+                if l > best[0]: best = (l, i - p)
+        if best[0] >= 3:
+            for k in range(i, i + best[0]): heads.setdefault(data[k:k + 3], []).append(k)
+            yield ('m', best[1], best[0]); i += best[0]
+        else:
+            heads.setdefault(data[i:i + 3], []).append(i)
+            yield ('l', data[i]); i += 1
+
+def nrv(data, variant, word):
+    bw = Bits(word); last = 1
+    for t in matches(data):
+        if t[0] == 'l':
+            bw.bit(1); bw.byte(t[1]); continue
+        _, d, L = t
+        bw.bit(0)
+        far = d > (0xd00 if variant == 'b' else 0x500)
+        mm = L - 1 - far
+        if variant == 'b':
+            if d == last: gamma(bw, 2)
+            else: gamma(bw, ((d - 1) >> 8) + 3); bw.byte((d - 1) & 0xff)
+            if mm < 4: bw.bit(mm >> 1); bw.bit(mm & 1)
+            else: bw.bit(0); bw.bit(0); gamma(bw, mm - 2)
+        else:
+            if variant == 'd': lenbit = (mm >> 1) if mm < 4 else 0
+            else: lenbit = 1 if mm <= 2 else 0
+            if d == last: gamma2(bw, 2); bw.bit(lenbit)
+            else:
+                x = ((d - 1) << 1) | (1 - lenbit)
+                gamma2(bw, (x >> 8) + 3); bw.byte(x & 0xff)
+            if variant == 'd':
+                if mm < 4: bw.bit(mm & 1)
+                else: bw.bit(0); gamma(bw, mm - 2)
+            else:
+                if mm <= 2: bw.bit(mm - 1)
+                elif mm <= 4: bw.bit(1); bw.bit(mm - 3)
+                else: bw.bit(0); gamma(bw, mm - 3)
+        last = d
+    bw.bit(0)
+    if variant == 'b': gamma(bw, 0x1000002)
+    else: gamma2(bw, 0x1000002)
+    bw.byte(0xff); bw.flush()
+    return bytes(bw.out)
+
+def call_trick(buf, jcc):
+    """UPX's cto32/ctoj32 filters: e8/e9 (and 0f 8x for jcc) targets inside the
+    block become big endian absolute offsets tagged with a cto8 byte"""
+    def run(b, cto8, apply):
+        last = 0; ic = 0; conflict = False
+        while ic + 5 < len(b):
+            op = b[ic]
+            if op in (0xe8, 0xe9) or (jcc and ic > 0 and ic != last and b[ic - 1] == 0x0f and 0x80 <= op <= 0x8f):
+                jc = (struct.unpack_from('<I', b, ic + 1)[0] + ic + 1) & 0xffffffff
+                if jc < len(b):
+                    if apply: struct.pack_into('>I', b, ic + 1, jc + (cto8 << 24))
+                    ic += 4; last = ic + 1
+                elif b[ic + 1] == cto8:
+                    conflict = True
+            ic += 1
+        return conflict
+    cto8 = next(c for c in range(0x80, 0x180) if not run(bytearray(buf), c & 0xff, False)) & 0xff
+    b = bytearray(buf); run(b, cto8, True)
+    return bytes(b), cto8
+
+def arm_bl(buf):
+    """UPX's 0x50 filter: BL offsets become big endian absolute word offsets"""
+    b = bytearray(buf)
+    for ic in range(0, len(b) - 3, 4):
+        if b[ic + 3] == 0xeb:
+            jc = (b[ic] | b[ic + 1] << 8 | b[ic + 2] << 16) + ic // 4 & 0xffffff
+            b[ic:ic + 3] = bytes([jc >> 16, jc >> 8 & 0xff, jc & 0xff])
+    return bytes(b), 0
+
+def apply_filter(data, ftid):
+    if ftid in (0x26, 0x46, 0x49): return call_trick(data, (ftid & 0xf) >= 9)
+    if ftid == 0x50: return arm_bl(data)
+    raise ValueError(hex(ftid))
+
+def compress(data, method):
+    if method == M_LZMA:
+        lc, lp, pb = 3, 0, 2
+        raw = lzma.compress(data, format=lzma.FORMAT_RAW, filters=[
+            {'id': lzma.FILTER_LZMA1, 'lc': lc, 'lp': lp, 'pb': pb, 'dict_size': 1 << 20}])
+        return bytes([((lc + lp) << 3) | pb, (lp << 4) | lc]) + raw
+    variant = {M_NRV2B_LE32: 'b', M_NRV2B_8: 'b', M_NRV2D_8: 'd', M_NRV2E_LE32: 'e'}[method]
+    return nrv(data, variant, 4 if method in (M_NRV2B_LE32, M_NRV2E_LE32) else 1)
+
+def main(src, dst, method, fmt, tamper=False, ftid=0, blocksize=0x4000):
+    data = open(src, 'rb').read()
+    is64 = data[4] == 2; en = '>' if data[5] == 2 else '<'
+    te32 = lambda v: struct.pack(en + 'I', v)
+    if is64:
+        phoff, = struct.unpack_from(en + 'Q', data, 0x20); phentsize, phnum = struct.unpack_from(en + 'HH', data, 0x36)
+        phdrs = [struct.unpack_from(en + 'IIQQQQQQ', data, phoff + i * phentsize) for i in range(phnum)]
+        loads = [(p[2], p[5]) for p in phdrs if p[0] == 1]; ehsize = 64
+        execs = [(p[2], p[5]) for p in phdrs if p[0] == 1 and p[1] & 1]
+    else:
+        phoff, = struct.unpack_from(en + 'I', data, 0x1c); phentsize, phnum = struct.unpack_from(en + 'HH', data, 0x2a)
+        phdrs = [struct.unpack_from(en + 'IIIIIIII', data, phoff + i * phentsize) for i in range(phnum)]
+        loads = [(p[1], p[4]) for p in phdrs if p[0] == 1]; ehsize = 52
+        execs = [(p[1], p[4]) for p in phdrs if p[0] == 1 and p[6] & 1]
+    hdr = phoff + phnum * phentsize
+    extents = [(0, hdr)]
+    for k, (off, size) in enumerate(loads):
+        if k == 0 and off == 0: off, size = hdr, size - hdr
+        extents.append((off, size))
+    for k in range(len(loads) - 1):
+        end = loads[k][0] + loads[k][1]
+        if loads[k + 1][0] > end: extents.append((end, loads[k + 1][0] - end))
+    hi = max(o + s for o, s in loads)
+    if len(data) > hi: extents.append((hi, len(data) - hi))
+
+    body = bytearray(); u_len = 0; hdr_filter = bytes([0, 0])
+    for k, (off, size) in enumerate(extents):
+        # The headers are never filtered
+        filtered = ftid and k > 0 and any(o <= off < o + s for o, s in execs)
+        for b in range(off, off + size, blocksize):
+            chunk = data[b:min(b + blocksize, off + size)]
+            f, cto8 = apply_filter(chunk, ftid) if filtered else (chunk, 0)
+            c = compress(f, method)
+            if len(c) >= len(chunk): c, f_id, cto8 = chunk, 0, 0
+            else: f_id = ftid if filtered else 0
+            body += te32(len(chunk)) + te32(len(c)) + bytes([method, f_id, cto8, 0]) + c
+            u_len += len(chunk)
+            if f_id: hdr_filter = bytes([f_id, cto8])
+    body += te32(0) + te32(0x21585055)
+
+    nph = 2; phsz = 56 if is64 else 32
+    linfo_off = ehsize + nph * phsz
+    stub = bytes(random.Random(1).randrange(256) for _ in range(0x180))
+    linfo = te32(0) + (b'\0\0\0\0' if tamper else b'UPX!') + struct.pack(en + 'H', len(stub)) + bytes([13, fmt])
+    pinfo = te32(0) + te32(0 if tamper else len(data)) + te32(0 if tamper else blocksize)
+    payload = linfo + pinfo + bytes(body) + stub
+    packhdr = (b'\0\0\0\0' if tamper else b'UPX!') + bytes([13, fmt, method, 8])
+    packhdr += struct.pack('<IIIII', zlib.adler32(data), zlib.adler32(bytes(body)), u_len, len(body), len(data))
+    packhdr += hdr_filter + bytes([0, 0]) + te32(linfo_off + 12)
+    total = linfo_off + len(payload) + len(packhdr)
+    base = 0x10000 if not is64 else 0x400000
+    span = max(p[3] + p[6] for p in phdrs if p[0] == 1) if is64 else max(p[2] + p[5] for p in phdrs if p[0] == 1)
+    memsz = (span - base + 0xfff & ~0xfff) + total if span > base else total * 4
+    if is64:
+        eh = bytearray(data[:64]); struct.pack_into(en + 'Q', eh, 0x18, base + linfo_off + len(payload) - len(stub))
+        struct.pack_into(en + 'QQ', eh, 0x20, 64, 0); struct.pack_into(en + 'HHHH', eh, 0x36, phsz, nph, 64, 0)
+        struct.pack_into(en + 'H', eh, 0x3e, 0)
+        ph = struct.pack(en + 'IIQQQQQQ', 1, 7, 0, base, base, total, memsz, 0x1000)
+        ph += struct.pack(en + 'IIQQQQQQ', 1, 6, 0, base + memsz + 0x1000, base + memsz + 0x1000, 0, 0x1000, 0x1000)
+    else:
+        eh = bytearray(data[:52]); struct.pack_into(en + 'I', eh, 0x18, base + linfo_off + len(payload) - len(stub))
+        struct.pack_into(en + 'II', eh, 0x1c, 52, 0); struct.pack_into(en + 'HHHH', eh, 0x2a, phsz, nph, 40, 0)
+        struct.pack_into(en + 'H', eh, 0x32, 0)
+        ph = struct.pack(en + 'IIIIIIII', 1, 0, base, base, total, memsz, 7, 0x1000)
+        ph += struct.pack(en + 'IIIIIIII', 1, 0, base + memsz + 0x1000, base + memsz + 0x1000, 0, 0x1000, 6, 0x1000)
+    out = bytes(eh) + ph + payload + packhdr
+    assert len(out) == total
+    open(dst, 'wb').write(out)
+
+if __name__ == '__main__':
+    extra = sys.argv[5] if len(sys.argv) > 5 else ''
+    main(sys.argv[1], sys.argv[2], int(sys.argv[3]), int(sys.argv[4]), extra == 'tamper',
+         int(extra, 16) if extra.startswith('0x') else 0)